@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Once, OnceLock};
+
+use imgui::{sys, Context};
+use tracing::warn;
+
+/// Space before each allocation for its size and owner, keeping the allocation itself aligned
+/// as malloc's would be
+const HEADER: usize = 16;
+const ALIGN: usize = 16;
+
+static ALLOCATOR: OnceLock<&'static (dyn GlobalAlloc + Sync)> = OnceLock::new();
+static HOOKS: Once = Once::new();
+
+/// Routes imgui's allocations through `allocator` instead of the system allocator, e.g. to keep
+/// them within a plugin's memory budget.
+///
+/// imgui's allocator is shared by every context in the process, or plugin as each links its own
+/// imgui, so this must be called before the first `System` is built. Returns false if it's too
+/// late.
+pub fn set_allocator(allocator: &'static (dyn GlobalAlloc + Sync)) -> bool {
+    ALLOCATOR.set(allocator).is_ok()
+}
+
+/// Creates a context whose allocations are counted by the returned `ContextMemory`, installing
+/// the allocator hooks first if need be. Used by Systems.
+///
+/// The hooks can't free memory imgui allocated before they were installed, so they're only
+/// installed if no context exists yet, i.e. when the first `System` is built before any other
+/// context. Otherwise nothing is counted or routed through `set_allocator`'s allocator.
+#[must_use]
+pub fn create_context() -> (Context, ContextMemory) {
+    HOOKS.call_once(|| unsafe {
+        if sys::igGetCurrentContext().is_null() {
+            sys::igSetAllocatorFunctions(Some(alloc), Some(free), ptr::null_mut());
+        } else {
+            warn!("An imgui context already exists, so imgui's memory won't be tracked");
+        }
+    });
+    let imgui = Context::create();
+    let memory = ContextMemory::default();
+    // looked up on every allocation, so cached in the context rather than found in a list
+    unsafe {
+        (*sys::igGetIO()).UserData = ptr::from_ref(memory.inner).cast_mut().cast();
+    }
+    (imgui, memory)
+}
+
+fn allocator() -> &'static dyn GlobalAlloc {
+    *ALLOCATOR.get_or_init(|| &System)
+}
+
+/// The owner of the current context, if it was created by `create_context`. Null while a
+/// context is being created, when it isn't current yet.
+unsafe fn current_owner() -> *const Inner {
+    if sys::igGetCurrentContext().is_null() {
+        return ptr::null();
+    }
+    (*sys::igGetIO()).UserData.cast_const().cast()
+}
+
+// the header holds the size then the owner, each well within its 8 bytes
+#[allow(clippy::cast_ptr_alignment)]
+unsafe extern "C" fn alloc(size: usize, _user_data: *mut c_void) -> *mut c_void {
+    let Ok(layout) = Layout::from_size_align(size + HEADER, ALIGN) else {
+        return ptr::null_mut();
+    };
+    let header = allocator().alloc(layout);
+    if header.is_null() {
+        return ptr::null_mut();
+    }
+    let owner = current_owner();
+    if let Some(owner) = owner.as_ref() {
+        owner.allocated(size);
+    }
+    header.cast::<usize>().write(size);
+    header.cast::<*const Inner>().add(1).write(owner);
+    header.add(HEADER).cast()
+}
+
+#[allow(clippy::cast_ptr_alignment)]
+unsafe extern "C" fn free(ptr: *mut c_void, _user_data: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    let header = ptr.cast::<u8>().sub(HEADER);
+    let size = header.cast::<usize>().read();
+    let owner = header.cast::<*const Inner>().add(1).read();
+    if let Some(owner) = owner.as_ref() {
+        owner.freed(size);
+    }
+    allocator().dealloc(
+        header,
+        Layout::from_size_align_unchecked(size + HEADER, ALIGN),
+    );
+}
+
+/// The memory imgui has allocated for one context, i.e. one window. Cloning gives another handle.
+///
+/// The budget is advisory, as imgui can't cope with a failed allocation: exceeding it logs a
+/// warning, once, and sets `is_over_budget`.
+///
+/// The counters are never freed, as allocations may outlive every handle, but are only a few
+/// dozen bytes per context.
+#[derive(Clone)]
+pub struct ContextMemory {
+    inner: &'static Inner,
+}
+
+impl Default for ContextMemory {
+    fn default() -> Self {
+        ContextMemory {
+            inner: Box::leak(Box::default()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    allocations: AtomicU64,
+    live_bytes: AtomicUsize,
+    peak_bytes: AtomicUsize,
+    /// zero for no budget
+    budget: AtomicUsize,
+    warned: AtomicBool,
+}
+
+impl Inner {
+    fn allocated(&self, size: usize) {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        let live = self.live_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_bytes.fetch_max(live, Ordering::Relaxed);
+        let budget = self.budget.load(Ordering::Relaxed);
+        if budget > 0 && live > budget && !self.warned.swap(true, Ordering::Relaxed) {
+            warn!("imgui is using {live} bytes, over its budget of {budget}");
+        }
+    }
+
+    fn freed(&self, size: usize) {
+        self.live_bytes.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+impl ContextMemory {
+    /// Allocations since the context was created
+    #[must_use]
+    pub fn allocations(&self) -> u64 {
+        self.inner.allocations.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn live_bytes(&self) -> usize {
+        self.inner.live_bytes.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn peak_bytes(&self) -> usize {
+        self.inner.peak_bytes.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn budget(&self) -> Option<usize> {
+        Some(self.inner.budget.load(Ordering::Relaxed)).filter(|budget| *budget > 0)
+    }
+
+    pub fn set_budget(&self, budget: Option<usize>) {
+        self.inner
+            .budget
+            .store(budget.unwrap_or_default(), Ordering::Relaxed);
+        self.inner.warned.store(false, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_over_budget(&self) -> bool {
+        self.budget()
+            .is_some_and(|budget| self.live_bytes() > budget)
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use imgui::Ui;
+
+use crate::events::{Event, InputState};
+use crate::App;
+
+/// An `App` made of layers, e.g. a main UI with a debug overlay and a modal dialog above it.
+/// Cloning gives another handle to the same stack, so layers can keep one to push or pop
+/// layers at runtime.
+///
+/// Layers are drawn bottom first, so higher layers appear on top, and are offered events top
+/// first until one consumes them. Every layer is updated, and the stack is ready once all its
+/// layers are. Pushes and pops take effect before the next update or event.
+#[derive(Clone, Default)]
+pub struct AppStack {
+    layers: Rc<RefCell<Vec<Box<dyn App>>>>,
+    pending: Rc<RefCell<Vec<Change>>>,
+}
+
+enum Change {
+    Push(Box<dyn App>),
+    Pop,
+}
+
+impl AppStack {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `layer` above the existing layers
+    #[must_use]
+    pub fn with(self, layer: impl App + 'static) -> Self {
+        self.layers.borrow_mut().push(Box::new(layer));
+        self
+    }
+
+    /// Adds `layer` to the top of the stack
+    pub fn push(&self, layer: impl App + 'static) {
+        self.pending
+            .borrow_mut()
+            .push(Change::Push(Box::new(layer)));
+    }
+
+    /// Removes the top layer, calling its `on_exit`
+    pub fn pop(&self) {
+        self.pending.borrow_mut().push(Change::Pop);
+    }
+
+    fn apply_changes(&self) {
+        let changes = std::mem::take(&mut *self.pending.borrow_mut());
+        for change in changes {
+            match change {
+                Change::Push(layer) => self.layers.borrow_mut().push(layer),
+                Change::Pop => {
+                    // off the stack first, so its on_exit can use the stack
+                    let layer = self.layers.borrow_mut().pop();
+                    if let Some(mut layer) = layer {
+                        layer.on_exit();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl App for AppStack {
+    fn draw_ui(&self, ui: &Ui) {
+        for layer in self.layers.borrow().iter() {
+            layer.draw_ui(ui);
+        }
+    }
+
+    fn ready(&self) -> bool {
+        self.layers.borrow().iter().all(|layer| layer.ready())
+    }
+
+    fn wants_redraw(&self) -> bool {
+        self.layers
+            .borrow()
+            .iter()
+            .any(|layer| layer.wants_redraw())
+    }
+
+    fn handle_event(&mut self, event: Event, input: &InputState) -> bool {
+        self.apply_changes();
+        self.layers
+            .borrow_mut()
+            .iter_mut()
+            .rev()
+            .any(|layer| layer.handle_event(event.clone(), input))
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.apply_changes();
+        for layer in self.layers.borrow_mut().iter_mut() {
+            layer.update(dt);
+        }
+    }
+
+    fn on_close_requested(&mut self) -> bool {
+        self.layers
+            .borrow_mut()
+            .iter_mut()
+            .rev()
+            .all(|layer| layer.on_close_requested())
+    }
+
+    fn on_exit(&mut self) {
+        for layer in self.layers.borrow_mut().iter_mut().rev() {
+            layer.on_exit();
+        }
+    }
+
+    fn render_background(&mut self) {
+        for layer in self.layers.borrow_mut().iter_mut() {
+            layer.render_background();
+        }
+    }
+
+    fn render_foreground(&mut self) {
+        for layer in self.layers.borrow_mut().iter_mut() {
+            layer.render_foreground();
+        }
+    }
+}
@@ -0,0 +1,407 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+//! Building blocks for hosting an `App` on platforms other than GLFW and X-Plane (SDL2, the
+//! browser, other simulators).
+//!
+//! A backend translates its native input into `Event`s and passes them to `dispatch_event`, then
+//! once per frame calls `FrameDriver::frame`, which prepares imgui through the
+//! `PlatformBackend`, draws the `App` into a window filling the display and hands the result to
+//! the `RendererBackend`. The bundled Systems run their frames through the same `FrameDriver`,
+//! adding their own chrome with `FrameHooks`.
+//!
+//! An app which runs both as an X-Plane plugin and as a desktop tool keeps its `App` in a crate
+//! depending only on `imgui-support`, with thin plugin and binary crates creating the matching
+//! `System`. Features which only exist in one environment are checked at runtime through
+//! `Backend::current` and `Backend::capabilities` rather than with `cfg`.
+
+use std::sync::Mutex;
+
+use image::{ImageError, RgbaImage};
+use imgui::{Context, DrawData, Io, Key, MouseButton, TextureId, Ui, WindowFlags};
+
+use crate::events::{self, Action, Event, InputState, Modifiers};
+use crate::loading::LoadingScreen;
+use crate::metrics::RenderStats;
+use crate::tasks::Tasks;
+use crate::{begin_host_window, App};
+
+static CURRENT: Mutex<Option<Registration>> = Mutex::new(None);
+
+#[derive(Clone, Copy)]
+struct Registration {
+    backend: Backend,
+    capabilities: Capabilities,
+    create_texture: fn(&RgbaImage) -> Result<TextureId, ImageError>,
+    delete_texture: fn(TextureId),
+}
+
+/// The environment hosting the app
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    Standalone,
+    XPlane,
+    /// A canvas in a web page, see `web::init` (with the `wasm` feature)
+    Web,
+    /// A third-party backend built on `FrameDriver`
+    Custom,
+}
+
+/// What the current backend supports
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    /// `App::draw_ui` creates its own windows, which can dock together
+    pub docking: bool,
+    /// `AppMenu`s are drawn in a menu bar in the window
+    pub menu_bar: bool,
+    /// `AppMenu`s are added to the host's plugin menu
+    pub plugin_menu: bool,
+    /// Named commands can be registered for users to bind to keys and buttons
+    pub commands: bool,
+    /// `Hotkeys` work while the window doesn't have keyboard focus
+    pub global_hotkeys: bool,
+    /// `Event::FileDrop` is sent
+    pub file_drop: bool,
+    /// Consuming `Event::CloseRequested` keeps the window open
+    pub close_veto: bool,
+    /// Windows can be popped out into their own OS window
+    pub pop_out: bool,
+    /// Windows can be shown in VR
+    pub vr: bool,
+}
+
+impl Backend {
+    /// The backend of the most recently created `System`, or `None` before one exists
+    #[must_use]
+    pub fn current() -> Option<Backend> {
+        current().map(|registration| registration.backend)
+    }
+
+    /// What the current backend supports; nothing before a `System` exists
+    #[must_use]
+    pub fn capabilities() -> Capabilities {
+        current()
+            .map(|registration| registration.capabilities)
+            .unwrap_or_default()
+    }
+
+    /// Called by each `System` as it's created. Custom backends should call it before their
+    /// first frame. `create_texture` and `delete_texture` back the module's functions of the
+    /// same names.
+    pub fn register(
+        backend: Backend,
+        capabilities: Capabilities,
+        create_texture: fn(&RgbaImage) -> Result<TextureId, ImageError>,
+        delete_texture: fn(TextureId),
+    ) {
+        *CURRENT.lock().unwrap() = Some(Registration {
+            backend,
+            capabilities,
+            create_texture,
+            delete_texture,
+        });
+    }
+}
+
+fn current() -> Option<Registration> {
+    *CURRENT.lock().unwrap()
+}
+
+/// Creates a texture through whichever backend is current, for apps shared between backends
+///
+/// # Errors
+///
+/// Returns `ImageError` if the image could not be loaded.
+///
+/// # Panics
+///
+/// Panics if no `System` has been created.
+pub fn create_texture(image: &RgbaImage) -> Result<TextureId, ImageError> {
+    let registration = current().expect("No backend has been registered");
+    (registration.create_texture)(image)
+}
+
+/// Deletes a texture from `create_texture` through whichever backend is current
+///
+/// # Panics
+///
+/// Panics if no `System` has been created.
+pub fn delete_texture(texture_id: TextureId) {
+    let registration = current().expect("No backend has been registered");
+    (registration.delete_texture)(texture_id);
+}
+
+pub trait PlatformBackend {
+    /// Updates `io` before a frame: display size and scale, delta time, clipboard etc.
+    fn prepare_frame(&mut self, io: &mut Io);
+
+    /// Feeds an event the `App` didn't consume to imgui
+    fn handle_event(&mut self, io: &mut Io, event: Event);
+}
+
+pub trait RendererBackend {
+    /// Draws a finished frame. Texture IDs in `draw_data` are those the backend handed out when
+    /// creating the font atlas and app textures.
+    fn render(&mut self, draw_data: &DrawData) -> RenderStats;
+}
+
+/// Offers `event` to the app, passing it on to imgui if the app doesn't consume it. `input` is
+/// usually `FrameDriver::input`.
+pub fn dispatch_event<P: PlatformBackend>(
+    app: &mut dyn App,
+    platform: &mut P,
+    io: &mut Io,
+    event: Event,
+    input: &InputState,
+) {
+    if !app.handle_event(event.clone(), input) {
+        platform.handle_event(io, event);
+    }
+}
+
+/// Passes `event` to imgui as input, for `PlatformBackend::handle_event`. Cursor positions must
+/// already be in imgui's coordinates.
+pub fn send_input(io: &mut Io, event: Event) {
+    match event {
+        Event::Key(key, ch, action, modifiers) => {
+            let pressed = action == Action::Press;
+            if let Some(key) = key {
+                io.add_key_event(key, pressed);
+            }
+
+            let Modifiers {
+                control,
+                option,
+                shift,
+                super_key,
+            } = modifiers;
+
+            if pressed && !control && !option && !super_key && ch != '\u{7f}' {
+                io.add_input_character(ch);
+            }
+
+            io.add_key_event(Key::ModCtrl, control);
+            io.add_key_event(Key::ModAlt, option);
+            io.add_key_event(Key::ModShift, shift);
+            io.add_key_event(Key::ModSuper, super_key);
+        }
+        Event::CursorPos(x, y) => {
+            io.add_mouse_pos_event([x, y]);
+        }
+        Event::Scroll(x, y) => {
+            io.add_mouse_wheel_event([x, y]);
+        }
+        Event::MouseButton(button, action) => {
+            let button = match button {
+                events::MouseButton::Left => MouseButton::Left,
+                events::MouseButton::Right => MouseButton::Right,
+            };
+            io.add_mouse_button_event(button, action != Action::Release);
+        }
+        Event::LongPress(..)
+        | Event::FileDrop(_)
+        | Event::Focus(_)
+        | Event::Resize(..)
+        | Event::SizeClass(_)
+        | Event::CloseRequested
+        | Event::GraphicsReset
+        | Event::Menu(_)
+        | Event::Sim(_)
+        | Event::WindowChanged(_)
+        | Event::User(_) => {}
+    }
+}
+
+/// Runs frames the same way the bundled backends do, including the task progress bar and an
+/// optional loading screen.
+#[derive(Default)]
+pub struct FrameDriver {
+    tasks: Tasks,
+    loading_screen: Option<LoadingScreen>,
+    input: InputState,
+    window_padding: [f32; 2],
+}
+
+/// Where `FrameDriver` draws the app, in a window without decoration or background
+pub struct HostWindow<'a> {
+    pub title: &'a str,
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    /// Around the app's contents
+    pub padding: [f32; 2],
+}
+
+/// The parts of a frame which differ between backends, called by `FrameDriver::frame_with` in the
+/// order they're declared
+pub trait FrameHooks {
+    /// Whether the app is ready, ending the loading screen
+    fn ready(&self) -> bool;
+
+    /// Draws anything the host window sits below, e.g. a menu bar, and returns the host window.
+    /// `None` leaves the app to create its own windows, as with docking. While `loading`, the
+    /// loading screen is drawn in the host window in place of the app.
+    fn host_window(
+        &mut self,
+        ui: &Ui,
+        display_size: [f32; 2],
+        loading: bool,
+    ) -> Option<HostWindow<'_>>;
+
+    /// Draws the app, in the host window if there is one
+    fn draw_app(&mut self, ui: &Ui);
+
+    /// Draws over the app and task progress, e.g. overlays, modals and window chrome
+    fn draw_overlays(&mut self, _ui: &Ui, _loading: bool) {}
+
+    /// Called once the frame is built, before imgui renders it, e.g. to clear the background
+    fn before_render(&mut self) {}
+
+    /// Sees the frame's draw data before the renderer draws it
+    fn inspect(&mut self, _draw_data: &DrawData) {}
+}
+
+/// The hooks of a backend which only draws the app, filling the display
+struct AppOnly<'a> {
+    app: &'a dyn App,
+    padding: [f32; 2],
+}
+
+impl FrameHooks for AppOnly<'_> {
+    fn ready(&self) -> bool {
+        self.app.ready()
+    }
+
+    fn host_window(
+        &mut self,
+        _ui: &Ui,
+        display_size: [f32; 2],
+        _loading: bool,
+    ) -> Option<HostWindow<'_>> {
+        Some(HostWindow {
+            title: "ImGui Window",
+            position: [0.0, 0.0],
+            size: display_size,
+            padding: self.padding,
+        })
+    }
+
+    fn draw_app(&mut self, ui: &Ui) {
+        self.app.draw_ui(ui);
+    }
+}
+
+impl FrameDriver {
+    #[must_use]
+    pub fn new(tasks: Tasks, loading_screen: Option<LoadingScreen>) -> Self {
+        FrameDriver {
+            tasks,
+            loading_screen,
+            input: InputState::default(),
+            window_padding: [0.0, 0.0],
+        }
+    }
+
+    #[must_use]
+    pub fn tasks(&self) -> &Tasks {
+        &self.tasks
+    }
+
+    /// Whether the loading screen is still shown
+    #[must_use]
+    pub fn is_loading(&self) -> bool {
+        self.loading_screen.is_some()
+    }
+
+    /// The padding around the app's contents in `frame`, zero by default
+    pub fn set_window_padding(&mut self, padding: [f32; 2]) {
+        self.window_padding = padding;
+    }
+
+    /// imgui's input state as of the last frame, for `dispatch_event`
+    #[must_use]
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Runs a frame of `app` filling the display
+    pub fn frame<P: PlatformBackend, R: RendererBackend>(
+        &mut self,
+        imgui: &mut Context,
+        platform: &mut P,
+        renderer: &mut R,
+        app: &dyn App,
+    ) -> RenderStats {
+        let mut hooks = AppOnly {
+            app,
+            padding: self.window_padding,
+        };
+        self.frame_with(imgui, platform, renderer, &mut hooks)
+    }
+
+    /// Runs a frame, with `hooks` deciding where the app goes and what's drawn around it
+    pub fn frame_with<P: PlatformBackend, R: RendererBackend>(
+        &mut self,
+        imgui: &mut Context,
+        platform: &mut P,
+        renderer: &mut R,
+        hooks: &mut dyn FrameHooks,
+    ) -> RenderStats {
+        platform.prepare_frame(imgui.io_mut());
+        let display_size = imgui.io().display_size;
+
+        if self.loading_screen.is_some() && hooks.ready() {
+            self.loading_screen = None;
+        }
+        let loading = self.loading_screen.is_some();
+
+        let ui = imgui.new_frame();
+        let flags = WindowFlags::NO_BACKGROUND | WindowFlags::NO_DECORATION;
+        let host = hooks.host_window(ui, display_size, loading);
+        if let Some(loading_screen) = &self.loading_screen {
+            let host = host.unwrap_or(HostWindow {
+                title: "ImGui Window",
+                position: [0.0, 0.0],
+                size: display_size,
+                padding: self.window_padding,
+            });
+            if let Some(_window) = begin_host_window(
+                ui,
+                host.title,
+                host.position,
+                host.size,
+                host.padding,
+                flags,
+            ) {
+                loading_screen.draw(ui, &self.tasks);
+            }
+        } else {
+            match host {
+                Some(host) => {
+                    if let Some(_window) = begin_host_window(
+                        ui,
+                        host.title,
+                        host.position,
+                        host.size,
+                        host.padding,
+                        flags | WindowFlags::NO_INPUTS,
+                    ) {
+                        hooks.draw_app(ui);
+                    }
+                }
+                None => hooks.draw_app(ui),
+            }
+            self.tasks.draw(ui);
+        }
+        hooks.draw_overlays(ui, loading);
+        self.input = InputState::capture(ui);
+
+        hooks.before_render();
+        let draw_data = imgui.render();
+        hooks.inspect(draw_data);
+        renderer.render(draw_data)
+    }
+}
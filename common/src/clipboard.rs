@@ -0,0 +1,115 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::fmt::Write;
+
+use imgui::Ui;
+
+/// Copied text in several flavours: plain text, tab separated values for spreadsheets and HTML
+/// for documents.
+///
+/// The GLFW and X-Plane clipboards only hold text, so `copy` places the TSV flavour when there is
+/// one, which spreadsheets split into cells. Apps with their own clipboard integration can place
+/// every flavour. In X-Plane, imgui's clipboard only reaches the system clipboard on Windows.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RichCopy {
+    pub text: String,
+    pub tsv: Option<String>,
+    pub html: Option<String>,
+}
+
+impl RichCopy {
+    #[must_use]
+    pub fn text(text: &str) -> Self {
+        RichCopy {
+            text: String::from(text),
+            tsv: None,
+            html: Some(format!("<pre>{}</pre>", escape_html(text))),
+        }
+    }
+
+    /// A table with a header row, e.g. from a flight plan or a log. The text flavour pads the
+    /// columns to line up in a monospaced font.
+    #[must_use]
+    pub fn table<S: AsRef<str>>(headers: &[&str], rows: &[Vec<S>]) -> Self {
+        let rows: Vec<Vec<&str>> = std::iter::once(headers.to_vec())
+            .chain(
+                rows.iter()
+                    .map(|row| row.iter().map(AsRef::as_ref).collect()),
+            )
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let text = rows
+            .iter()
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:width$}"))
+                    .collect();
+                String::from(cells.join("  ").trim_end())
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let tsv = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.replace(['\t', '\n', '\r'], " "))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut html = String::from("<table>");
+        for (index, row) in rows.iter().enumerate() {
+            let tag = if index == 0 { "th" } else { "td" };
+            html.push_str("<tr>");
+            for cell in row {
+                let _ = write!(html, "<{tag}>{}</{tag}>", escape_html(cell));
+            }
+            html.push_str("</tr>");
+        }
+        html.push_str("</table>");
+
+        RichCopy {
+            text,
+            tsv: Some(tsv),
+            html: Some(html),
+        }
+    }
+
+    /// The flavour for a clipboard which only holds text
+    #[must_use]
+    pub fn best_text(&self) -> &str {
+        self.tsv.as_deref().unwrap_or(&self.text)
+    }
+
+    /// Places `best_text` on imgui's clipboard, which Systems connect to the system clipboard
+    /// where they can
+    pub fn copy(&self, ui: &Ui) {
+        ui.set_clipboard_text(self.best_text());
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
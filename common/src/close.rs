@@ -0,0 +1,35 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Closes the System's window from the App, e.g. once unsaved changes are dealt with after
+/// `App::on_close_requested` kept it open. Cloning gives another handle to the same window.
+///
+/// The request is carried out after the current frame, without asking the App again. X-Plane
+/// windows are hidden rather than closed.
+#[derive(Clone, Debug, Default)]
+pub struct CloseHandle {
+    requested: Rc<Cell<bool>>,
+}
+
+impl CloseHandle {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request_close(&self) {
+        self.requested.set(true);
+    }
+
+    /// Whether a close was requested since the last call, for the System
+    #[must_use]
+    pub fn take(&self) -> bool {
+        self.requested.replace(false)
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use imgui::Ui;
+
+/// A message with a row of buttons, e.g. to confirm discarding changes. Shown with
+/// `System::show_modal`, which calls `on_result` with the index of the button pressed.
+///
+/// Standalone shows it as an imgui modal popup; X-Plane shows it in its own window on the modal
+/// layer, which dims the sim behind it.
+pub struct ModalDialog {
+    title: String,
+    body: String,
+    buttons: Vec<String>,
+    on_result: Option<Box<dyn FnOnce(usize)>>,
+}
+
+impl ModalDialog {
+    #[must_use]
+    pub fn new(
+        title: &str,
+        body: &str,
+        buttons: &[&str],
+        on_result: impl FnOnce(usize) + 'static,
+    ) -> Self {
+        ModalDialog {
+            title: String::from(title),
+            body: String::from(body),
+            buttons: buttons.iter().map(|button| String::from(*button)).collect(),
+            on_result: Some(Box::new(on_result)),
+        }
+    }
+
+    #[must_use]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    #[must_use]
+    pub fn buttons(&self) -> &[String] {
+        &self.buttons
+    }
+
+    /// Calls `on_result` with the index of the button pressed, if it hasn't been called already
+    pub fn finish(&mut self, index: usize) {
+        if let Some(on_result) = self.on_result.take() {
+            on_result(index);
+        }
+    }
+
+    /// Whether a button has been pressed
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.on_result.is_none()
+    }
+
+    /// Draws the body and buttons into the current window, calling `on_result` if a button is
+    /// pressed. Used by Systems.
+    pub fn draw_contents(&mut self, ui: &Ui) {
+        ui.text_wrapped(&self.body);
+        ui.spacing();
+        let mut pressed = None;
+        for (index, label) in self.buttons.iter().enumerate() {
+            if index > 0 {
+                ui.same_line();
+            }
+            if ui.button(label) {
+                pressed = Some(index);
+            }
+        }
+        if let Some(index) = pressed {
+            self.finish(index);
+        }
+    }
+
+    /// Draws the dialog as an imgui modal popup, closing it once a button is pressed. Used by
+    /// Systems.
+    pub fn draw_popup(&mut self, ui: &Ui) {
+        let title = self.title.clone();
+        ui.open_popup(&title);
+        ui.modal_popup_config(&title)
+            .always_auto_resize(true)
+            .build(|| {
+                self.draw_contents(ui);
+                if self.is_finished() {
+                    ui.close_current_popup();
+                }
+            });
+    }
+}
+
+/// Dialogs waiting for the System to show them, so the app can show one while the System is
+/// running it. Clone it to give the app a handle.
+#[derive(Clone, Default)]
+pub struct Modals {
+    queue: Rc<RefCell<Vec<ModalDialog>>>,
+}
+
+impl Modals {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a dialog, as `System::show_modal` does
+    pub fn show(
+        &self,
+        title: &str,
+        body: &str,
+        buttons: &[&str],
+        on_result: impl FnOnce(usize) + 'static,
+    ) {
+        self.queue
+            .borrow_mut()
+            .push(ModalDialog::new(title, body, buttons, on_result));
+    }
+
+    /// The dialogs queued since the last call, oldest first. Used by Systems.
+    #[must_use]
+    pub fn take(&self) -> Vec<ModalDialog> {
+        self.queue.take()
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use imgui::{DrawCmd, DrawCmdParams, DrawData, DrawIdx};
+use serde::{Deserialize, Serialize};
+
+/// A copy of a frame's `DrawData` which can be kept, sent to another thread or serialized, for
+/// drawing the UI somewhere other than the System's own window (a compositor, a remote display).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OwnedDrawData {
+    pub display_pos: [f32; 2],
+    pub display_size: [f32; 2],
+    pub framebuffer_scale: [f32; 2],
+    pub draw_lists: Vec<OwnedDrawList>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OwnedDrawList {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<DrawIdx>,
+    pub commands: Vec<DrawCommand>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Vertex {
+    pub pos: [f32; 2],
+    pub uv: [f32; 2],
+    /// RGBA
+    pub col: [u8; 4],
+}
+
+/// Draws `count` indices starting at `index_offset`, adding `vertex_offset` to each
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DrawCommand {
+    /// Left, top, right, bottom in display coordinates
+    pub clip_rect: [f32; 4],
+    /// The `TextureId` the backend gave out, e.g. a GL texture name
+    pub texture_id: usize,
+    pub vertex_offset: usize,
+    pub index_offset: usize,
+    pub count: usize,
+}
+
+impl From<&DrawData> for OwnedDrawData {
+    fn from(draw_data: &DrawData) -> Self {
+        let draw_lists = draw_data
+            .draw_lists()
+            .map(|draw_list| OwnedDrawList {
+                vertices: draw_list
+                    .vtx_buffer()
+                    .iter()
+                    .map(|vertex| Vertex {
+                        pos: vertex.pos,
+                        uv: vertex.uv,
+                        col: vertex.col,
+                    })
+                    .collect(),
+                indices: draw_list.idx_buffer().to_vec(),
+                // render state resets and callbacks only make sense to the renderer in this process
+                commands: draw_list
+                    .commands()
+                    .filter_map(|command| match command {
+                        DrawCmd::Elements {
+                            count,
+                            cmd_params:
+                                DrawCmdParams {
+                                    clip_rect,
+                                    texture_id,
+                                    vtx_offset,
+                                    idx_offset,
+                                },
+                        } => Some(DrawCommand {
+                            clip_rect,
+                            texture_id: texture_id.id(),
+                            vertex_offset: vtx_offset,
+                            index_offset: idx_offset,
+                            count,
+                        }),
+                        _ => None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        OwnedDrawData {
+            display_pos: draw_data.display_pos,
+            display_size: draw_data.display_size,
+            framebuffer_scale: draw_data.framebuffer_scale,
+            draw_lists,
+        }
+    }
+}
+
+/// Holds the most recent frame's draw data once enabled on the `SystemBuilder`. Cloning gives
+/// another handle to the same frame, e.g. for a thread streaming the UI elsewhere.
+#[derive(Clone, Default)]
+pub struct DrawDataExport {
+    latest: Arc<Mutex<Option<OwnedDrawData>>>,
+}
+
+impl DrawDataExport {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of the latest frame, or `None` before the first
+    #[must_use]
+    pub fn latest(&self) -> Option<OwnedDrawData> {
+        self.lock().clone()
+    }
+
+    /// Takes the latest frame, so each is only handled once
+    #[must_use]
+    pub fn take(&self) -> Option<OwnedDrawData> {
+        self.lock().take()
+    }
+
+    /// Replaces the latest frame. Used by Systems after rendering.
+    pub fn publish(&self, draw_data: &DrawData) {
+        *self.lock() = Some(OwnedDrawData::from(draw_data));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<OwnedDrawData>> {
+        self.latest.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -4,14 +4,248 @@
  * All rights reserved.
  */
 
-use imgui::Key;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use imgui::{Key, Ui, WindowHoveredFlags};
+
+use crate::geometry::Rect;
+use crate::layout::SizeClass;
 
 #[derive(Clone, Debug)]
 pub enum Event {
     MouseButton(MouseButton, Action),
-    CursorPos(i32, i32),
-    Scroll(i32, i32),
+    /// The left button or a finger was held still for the `LongPress` duration, at the given
+    /// position in the same coordinates as `CursorPos`. Off unless the System's `set_long_press`
+    /// is given a `LongPress`. The press still reaches imgui; release follows as usual.
+    LongPress(f32, f32),
+    /// The cursor position, with sub-pixel precision where available. Standalone gives it in the
+    /// window's pixels from its top left corner, as imgui does; X-Plane gives global desktop
+    /// boxels with y up, which the System translates for imgui.
+    CursorPos(f32, f32),
+    /// Horizontal and vertical scroll in imgui's conventions: positive is left and up, one unit
+    /// per wheel click, with fractions from trackpads and smooth-scrolling mice
+    Scroll(f32, f32),
     Key(Option<Key>, char, Action, Modifiers),
+    /// Files dropped onto the window (standalone only)
+    FileDrop(Vec<PathBuf>),
+    /// The window gained (`true`) or lost (`false`) keyboard focus
+    Focus(bool),
+    /// The window was resized to the given width and height
+    Resize(u32, u32),
+    /// The window's width crossed one of the `Breakpoints` given to the `SystemBuilder`
+    SizeClass(SizeClass),
+    /// The user asked to close the window. Consuming the event vetoes the close (standalone, and
+    /// self-decorated X-Plane windows; X-Plane hides others without asking)
+    CloseRequested,
+    /// The GL context was reset and its textures lost. The font texture and textures from
+    /// `create_texture` have been uploaded again, keeping their ids; GL objects the app made
+    /// itself must be created again.
+    GraphicsReset,
+    /// An item with the given id was chosen from an `AppMenu`
+    Menu(String),
+    /// A message from X-Plane, forwarded by `System::handle_message` (X-Plane only)
+    Sim(SimEvent),
+    /// The window moved, was resized, popped out or in, moved into or out of VR, or was shown or
+    /// hidden, checked every sim frame (X-Plane only)
+    WindowChanged(WindowState),
+    /// Text from outside the UI, e.g. a QR code read by the standalone `camera` feature's scanner
+    User(String),
+}
+
+impl Event {
+    /// The cursor position rounded to whole pixels, for code written when positions were `i32`
+    #[deprecated(note = "match `Event::CursorPos`, whose position is `f32`")]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn cursor_pos_i32(&self) -> Option<(i32, i32)> {
+        match *self {
+            Event::CursorPos(x, y) => Some((x.round() as i32, y.round() as i32)),
+            _ => None,
+        }
+    }
+
+    /// The scroll rounded to whole wheel clicks, for code written when scrolling was `i32`
+    #[deprecated(note = "match `Event::Scroll`, whose amounts are `f32`")]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn scroll_i32(&self) -> Option<(i32, i32)> {
+        match *self {
+            Event::Scroll(x, y) => Some((x.round() as i32, y.round() as i32)),
+            _ => None,
+        }
+    }
+}
+
+/// Where and how an X-Plane window is shown, e.g. to save with the app's layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowState {
+    /// In boxels for a floating window, OS pixels for a popped out one, and from `(0, 0)` for
+    /// one in VR
+    pub geometry: Rect,
+    pub popped_out: bool,
+    pub in_vr: bool,
+    pub visible: bool,
+}
+
+/// What imgui made of the input as of the last frame, passed with each event so apps can leave
+/// input imgui wants alone, e.g. only moving a camera when the mouse isn't over the UI
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputState {
+    pub want_capture_mouse: bool,
+    pub want_capture_keyboard: bool,
+    /// A text field has keyboard focus
+    pub want_text_input: bool,
+    pub mouse_pos: [f32; 2],
+    /// The mouse is over one of imgui's windows
+    pub hovered: bool,
+}
+
+impl InputState {
+    /// Takes a snapshot at the end of a frame. Used by Systems.
+    #[must_use]
+    pub fn capture(ui: &Ui) -> Self {
+        let io = ui.io();
+        InputState {
+            want_capture_mouse: io.want_capture_mouse,
+            want_capture_keyboard: io.want_capture_keyboard,
+            want_text_input: io.want_text_input,
+            mouse_pos: io.mouse_pos,
+            hovered: ui.is_window_hovered_with_flags(WindowHoveredFlags::ANY_WINDOW),
+        }
+    }
+}
+
+/// Holds cursor moves and scrolls so that each run of them between other events is passed on as
+/// at most one of each: the last position and the summed scroll. Used by Systems to spare apps
+/// the hundreds of moves a fast mouse can send each frame.
+#[derive(Debug, Default)]
+pub struct MotionCoalescer {
+    cursor: Option<(f32, f32)>,
+    scroll: Option<(f32, f32)>,
+}
+
+impl MotionCoalescer {
+    /// Holds `event` if it's a cursor move or scroll, returning whether it was held
+    pub fn hold(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::CursorPos(x, y) => {
+                self.cursor = Some((x, y));
+                true
+            }
+            Event::Scroll(x, y) => {
+                let (held_x, held_y) = self.scroll.unwrap_or_default();
+                self.scroll = Some((held_x + x, held_y + y));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Takes the held events, the move before the scroll so it scrolls what's under the cursor
+    pub fn flush(&mut self) -> Vec<Event> {
+        let cursor = self.cursor.take().map(|(x, y)| Event::CursorPos(x, y));
+        let scroll = self.scroll.take().map(|(x, y)| Event::Scroll(x, y));
+        cursor.into_iter().chain(scroll).collect()
+    }
+}
+
+/// How long the left button or a finger must be held still to count as a long press
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LongPress {
+    pub duration: Duration,
+    /// How far in pixels the cursor may move before the press no longer counts
+    pub tolerance: f32,
+}
+
+impl Default for LongPress {
+    fn default() -> Self {
+        LongPress {
+            duration: Duration::from_millis(500),
+            tolerance: 8.0,
+        }
+    }
+}
+
+/// Watches the events passed to the app for long presses, giving at most one per press. Used by
+/// Systems.
+#[derive(Debug, Default)]
+pub struct LongPressDetector {
+    config: Option<LongPress>,
+    cursor: (f32, f32),
+    /// When and where the left button went down, until it goes up, moves or long-presses
+    press: Option<(Instant, (f32, f32))>,
+}
+
+impl LongPressDetector {
+    #[must_use]
+    pub fn config(&self) -> Option<LongPress> {
+        self.config
+    }
+
+    /// Turns detection on with `config`, or off if `None`, forgetting any press under way
+    pub fn set_config(&mut self, config: Option<LongPress>) {
+        self.config = config;
+        self.press = None;
+    }
+
+    /// Follows the cursor and left button
+    pub fn observe(&mut self, event: &Event) {
+        let Some(config) = self.config else {
+            return;
+        };
+        match *event {
+            Event::CursorPos(x, y) => {
+                self.cursor = (x, y);
+                if let Some((_, (press_x, press_y))) = self.press {
+                    if (x - press_x).hypot(y - press_y) > config.tolerance {
+                        self.press = None;
+                    }
+                }
+            }
+            Event::MouseButton(MouseButton::Left, Action::Press) => {
+                self.press = Some((Instant::now(), self.cursor));
+            }
+            Event::MouseButton(MouseButton::Left, Action::Release) | Event::Focus(false) => {
+                self.press = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether a press is being held, so frames should keep coming to catch its end
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.press.is_some()
+    }
+
+    /// The long press, once the button has been held for long enough
+    pub fn poll(&mut self) -> Option<Event> {
+        let duration = self.config?.duration;
+        let (start, (x, y)) = self.press?;
+        if start.elapsed() < duration {
+            return None;
+        }
+        self.press = None;
+        Some(Event::LongPress(x, y))
+    }
+}
+
+/// Plugin messages sent by X-Plane. Aircraft are identified by index, 0 being the user's.
+#[derive(Clone, Debug)]
+pub enum SimEvent {
+    PlaneCrashed,
+    PlaneLoaded(i32),
+    PlaneUnloaded(i32),
+    LiveryLoaded(i32),
+    AirportLoaded,
+    SceneryLoaded,
+    AirplaneCountChanged,
+    WillWritePrefs,
+    EnteredVr,
+    ExitingVr,
+    ReleasePlanes,
 }
 
 #[derive(Clone, Debug)]
@@ -26,9 +260,13 @@ pub enum Action {
     Release,
 }
 
-#[derive(Clone, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Modifiers {
     pub control: bool,
     pub option: bool,
     pub shift: bool,
+    /// Cmd on macOS, the Windows key elsewhere (standalone only; X-Plane can't tell Cmd from
+    /// Ctrl, so reports both as `control`)
+    pub super_key: bool,
 }
@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use imgui::{Context, Key, StyleColor};
+
+/// Moves keyboard focus between widgets on the App's behalf, e.g. from a cockpit button box
+/// with no keyboard attached. Cloning gives another handle to the same focus, so one can be
+/// moved into a `Hotkeys` callback or kept by the App.
+///
+/// Requests are fed to imgui as Tab, Shift+Tab and Space presses before the `System`'s next
+/// frame, so they reach every widget imgui can focus. Activating needs keyboard navigation
+/// enabled with `Navigation`.
+#[derive(Clone, Default)]
+pub struct Focus {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    requests: Vec<FocusRequest>,
+    ring: Option<FocusRing>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FocusRequest {
+    Next,
+    Previous,
+    /// Presses the focused widget, as if it were clicked
+    Activate,
+}
+
+/// How the focused widget is highlighted
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FocusRing {
+    pub color: [f32; 4],
+}
+
+impl Default for FocusRing {
+    /// A bright amber, visible against the default dark style and in a dim cockpit
+    fn default() -> Self {
+        FocusRing {
+            color: [1.0, 0.75, 0.0, 1.0],
+        }
+    }
+}
+
+impl Focus {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn focus_next(&self) {
+        self.request(FocusRequest::Next);
+    }
+
+    pub fn focus_prev(&self) {
+        self.request(FocusRequest::Previous);
+    }
+
+    pub fn activate(&self) {
+        self.request(FocusRequest::Activate);
+    }
+
+    /// Highlights the focused widget with `ring`, or imgui's style if `None`
+    pub fn set_ring(&self, ring: Option<FocusRing>) {
+        self.lock().ring = ring;
+    }
+
+    #[must_use]
+    pub fn ring(&self) -> Option<FocusRing> {
+        self.lock().ring
+    }
+
+    /// Feeds requests made since the last call to imgui and applies the focus ring. Used by
+    /// Systems before each frame.
+    pub fn apply(&self, imgui: &mut Context) {
+        let (requests, ring) = {
+            let mut inner = self.lock();
+            (std::mem::take(&mut inner.requests), inner.ring)
+        };
+        if let Some(ring) = ring {
+            imgui.style_mut()[StyleColor::NavHighlight] = ring.color;
+        }
+
+        // imgui trickles queued input, so each press and release lands in its own frame
+        let io = imgui.io_mut();
+        for request in requests {
+            let (key, shift) = match request {
+                FocusRequest::Next => (Key::Tab, false),
+                FocusRequest::Previous => (Key::Tab, true),
+                FocusRequest::Activate => (Key::Space, false),
+            };
+            if shift {
+                io.add_key_event(Key::ModShift, true);
+            }
+            io.add_key_event(key, true);
+            io.add_key_event(key, false);
+            if shift {
+                io.add_key_event(Key::ModShift, false);
+            }
+        }
+    }
+
+    fn request(&self, request: FocusRequest) {
+        self.lock().requests.push(request);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,256 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use gl21 as gl;
+use imgui::internal::RawCast;
+use imgui::{sys, Context, FontAtlas, TextureId};
+
+use crate::metrics;
+use crate::renderer_common::{add_font_styles, FontStyles, DEFAULT_FONT_SIZE};
+
+/// Rows of the atlas uploaded each frame, keeping each upload well under a millisecond
+const ROWS_PER_FRAME: i32 = 64;
+
+/// Changes the bundled fonts' size and styles at runtime without stalling a frame. Cloning gives
+/// another handle, so the App can keep one, e.g. for a text size setting.
+///
+/// The `System` builds the new atlas on a worker thread, uploads it over several frames and
+/// then switches to it between frames. `FontId`s and `Io::font_default` refer to the old fonts,
+/// so they're invalid after the switch; the default font becomes the first new one.
+#[derive(Clone, Default)]
+pub struct Fonts {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    request: Option<(f32, FontStyles)>,
+    rebuilding: bool,
+    /// The size and styles last asked for, before scaling
+    size_pixels: f32,
+    styles: FontStyles,
+    scale: f32,
+}
+
+impl Fonts {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuilds the fonts at `size_pixels`, times the UI scale, with `styles`, replacing any
+    /// rebuild not yet started
+    pub fn set(&self, size_pixels: f32, styles: FontStyles) {
+        let mut inner = self.lock();
+        inner.request = Some((size_pixels * inner.scale, styles.clone()));
+        inner.size_pixels = size_pixels;
+        inner.styles = styles;
+    }
+
+    /// Rebuilds the fonts at their size times `scale`, so scaled text stays sharp. Used by
+    /// Systems; apps call `System::set_ui_scale`.
+    pub fn set_scale(&self, scale: f32) {
+        let mut inner = self.lock();
+        if (scale - inner.scale).abs() > f32::EPSILON {
+            inner.scale = scale;
+            inner.request = Some((inner.size_pixels * scale, inner.styles.clone()));
+        }
+    }
+
+    /// Whether a rebuild has been requested or is under way
+    #[must_use]
+    pub fn is_rebuilding(&self) -> bool {
+        let inner = self.lock();
+        inner.rebuilding || inner.request.is_some()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Inner {
+            request: None,
+            rebuilding: false,
+            size_pixels: DEFAULT_FONT_SIZE,
+            styles: FontStyles::default(),
+            scale: 1.0,
+        }
+    }
+}
+
+/// An atlas outside any context, owned by whichever thread is working on it
+struct Atlas(*mut sys::ImFontAtlas);
+
+// The atlas is only used by one thread at a time and refers to nothing thread-local
+unsafe impl Send for Atlas {}
+
+impl Atlas {
+    fn fonts(&mut self) -> &mut FontAtlas {
+        unsafe { FontAtlas::from_raw_mut(&mut *self.0) }
+    }
+}
+
+impl Drop for Atlas {
+    fn drop(&mut self) {
+        unsafe { sys::ImFontAtlas_destroy(self.0) }
+    }
+}
+
+enum Stage {
+    Idle,
+    Building(JoinHandle<Atlas>),
+    Uploading {
+        atlas: Atlas,
+        texture: u32,
+        next_row: i32,
+    },
+}
+
+/// Carries out `Fonts` requests one step per frame. Used by Systems.
+pub struct FontRebuilder {
+    fonts: Fonts,
+    stage: Stage,
+}
+
+impl FontRebuilder {
+    #[must_use]
+    pub fn new(fonts: Fonts) -> Self {
+        FontRebuilder {
+            fonts,
+            stage: Stage::Idle,
+        }
+    }
+
+    #[must_use]
+    pub fn fonts(&self) -> &Fonts {
+        &self.fonts
+    }
+
+    /// Advances any rebuild, outside a frame. `create_texture` generates and binds a texture;
+    /// `bind_texture` binds an existing one. Returns the new font texture once imgui has
+    /// switched to it, leaving the caller to delete the old one.
+    pub fn step(
+        &mut self,
+        imgui: &mut Context,
+        create_texture: fn() -> u32,
+        bind_texture: fn(u32),
+    ) -> Option<u32> {
+        match std::mem::replace(&mut self.stage, Stage::Idle) {
+            Stage::Idle => {
+                let mut inner = self.fonts.lock();
+                if let Some((size_pixels, styles)) = inner.request.take() {
+                    inner.rebuilding = true;
+                    self.stage =
+                        Stage::Building(thread::spawn(move || build(size_pixels, &styles)));
+                }
+                None
+            }
+            Stage::Building(worker) if !worker.is_finished() => {
+                self.stage = Stage::Building(worker);
+                None
+            }
+            Stage::Building(worker) => {
+                let Ok(mut atlas) = worker.join() else {
+                    self.fonts.lock().rebuilding = false;
+                    return None;
+                };
+                let texture = create_texture();
+                let texture_data = atlas.fonts().build_rgba32_texture();
+                metrics::track_texture(texture, texture_data.width, texture_data.height);
+                #[allow(clippy::cast_possible_wrap)]
+                unsafe {
+                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+                    gl::TexImage2D(
+                        gl::TEXTURE_2D,
+                        0,
+                        gl::RGBA as _,
+                        texture_data.width as _,
+                        texture_data.height as _,
+                        0,
+                        gl::RGBA,
+                        gl::UNSIGNED_BYTE,
+                        ptr::null(),
+                    );
+                }
+                self.stage = Stage::Uploading {
+                    atlas,
+                    texture,
+                    next_row: 0,
+                };
+                None
+            }
+            Stage::Uploading {
+                mut atlas,
+                texture,
+                next_row,
+            } => {
+                bind_texture(texture);
+                let texture_data = atlas.fonts().build_rgba32_texture();
+                #[allow(clippy::cast_possible_wrap)]
+                let (width, height) = (texture_data.width as i32, texture_data.height as i32);
+                let rows = ROWS_PER_FRAME.min(height - next_row);
+                #[allow(clippy::cast_sign_loss)]
+                let offset = (next_row * width * 4) as usize;
+                unsafe {
+                    gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+                    gl::TexSubImage2D(
+                        gl::TEXTURE_2D,
+                        0,
+                        0,
+                        next_row,
+                        width,
+                        rows,
+                        gl::RGBA,
+                        gl::UNSIGNED_BYTE,
+                        texture_data.data[offset..].as_ptr().cast::<c_void>(),
+                    );
+                }
+                if next_row + rows < height {
+                    self.stage = Stage::Uploading {
+                        atlas,
+                        texture,
+                        next_row: next_row + rows,
+                    };
+                    return None;
+                }
+                atlas.fonts().tex_id = TextureId::new(texture as usize);
+                swap_atlas(imgui, &atlas);
+                self.fonts.lock().rebuilding = false;
+                Some(texture)
+            }
+        }
+    }
+}
+
+fn build(size_pixels: f32, styles: &FontStyles) -> Atlas {
+    let mut atlas = Atlas(unsafe { sys::ImFontAtlas_ImFontAtlas() });
+    add_font_styles(atlas.fonts(), size_pixels, styles);
+    let _ = atlas.fonts().build_rgba32_texture();
+    atlas
+}
+
+/// Moves the built fonts into the context's atlas and the old ones into `atlas`, to be freed
+/// when it's dropped
+fn swap_atlas(imgui: &mut Context, atlas: &Atlas) {
+    unsafe {
+        let current: *mut sys::ImFontAtlas = imgui.fonts().raw_mut();
+        ptr::swap(current, atlas.0);
+        // fonts point back at the atlas which built them, which has just changed address
+        let fonts = &(*current).Fonts;
+        for i in 0..usize::try_from(fonts.Size).unwrap_or_default() {
+            (**fonts.Data.add(i)).ContainerAtlas = current;
+        }
+        imgui.io_mut().raw_mut().FontDefault = ptr::null_mut();
+    }
+}
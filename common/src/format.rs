@@ -0,0 +1,242 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::Cell;
+use std::env;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{Settings, SettingsError};
+
+thread_local! {
+    static CURRENT: Cell<Locale> = Cell::new(Locale::default());
+}
+
+/// The order the parts of a date are written in
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DateOrder {
+    /// e.g. 2023-10-31
+    #[default]
+    YearMonthDay,
+    /// e.g. 31/10/2023
+    DayMonthYear,
+    /// e.g. 10/31/2023
+    MonthDayYear,
+}
+
+/// How numbers and dates are written for the user. Each System has one, taken from the OS
+/// unless set, which the widgets and `current` use while it draws.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Locale {
+    pub decimal_separator: char,
+    /// Put between each group of three digits before the decimal separator, if anything
+    pub group_separator: Option<char>,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+}
+
+impl Default for Locale {
+    /// ISO 8601 dates and numbers with a decimal point and no grouping, as when no locale is
+    /// known
+    fn default() -> Self {
+        Locale {
+            decimal_separator: '.',
+            group_separator: None,
+            date_order: DateOrder::YearMonthDay,
+            date_separator: '-',
+        }
+    }
+}
+
+impl Locale {
+    const SETTINGS_KEY: &'static str = "locale";
+
+    /// The conventions for a language tag such as `de_DE.UTF-8` or `en-GB`, or the default for
+    /// languages not known here
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default();
+        let mut parts = tag.split(['_', '-']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+
+        let (decimal_separator, group_separator) = match (language.as_str(), region.as_str()) {
+            ("de", "CH") => ('.', Some('\'')),
+            ("es", "MX") | ("en" | "ja" | "ko" | "zh" | "th" | "he", _) => ('.', Some(',')),
+            ("de" | "es" | "it" | "nl" | "pt" | "da" | "el" | "tr" | "id", _) => (',', Some('.')),
+            ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu", _) => {
+                (',', Some('\u{a0}'))
+            }
+            _ => return Locale::default(),
+        };
+        let (date_order, date_separator) = match (language.as_str(), region.as_str()) {
+            ("en", "US" | "") => (DateOrder::MonthDayYear, '/'),
+            ("ja" | "ko" | "zh" | "sv" | "lt", _) => (DateOrder::YearMonthDay, '-'),
+            ("hu", _) => (DateOrder::YearMonthDay, '.'),
+            ("de" | "ru" | "pl" | "cs" | "sk" | "nb" | "no" | "fi" | "uk" | "tr" | "da", _) => {
+                (DateOrder::DayMonthYear, '.')
+            }
+            ("nl", _) => (DateOrder::DayMonthYear, '-'),
+            _ => (DateOrder::DayMonthYear, '/'),
+        };
+        Locale {
+            decimal_separator,
+            group_separator,
+            date_order,
+            date_separator,
+        }
+    }
+
+    /// The user's locale as the OS reports it: the `LC_ALL`, `LC_NUMERIC` or `LANG` environment
+    /// variables, then on macOS and Windows the system preference. The default if none is set.
+    #[must_use]
+    pub fn from_os() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|tag| !tag.is_empty() && tag != "C" && tag != "POSIX")
+            .or_else(os_preference)
+            .map_or_else(Locale::default, |tag| Locale::from_tag(&tag))
+    }
+
+    /// Reads the locale the user chose from `settings`, falling back to the OS's
+    #[must_use]
+    pub fn load(settings: &Settings) -> Self {
+        settings
+            .get(Self::SETTINGS_KEY)
+            .unwrap_or_else(Locale::from_os)
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the locale could not be serialized.
+    pub fn store(&self, settings: &mut Settings) -> Result<(), SettingsError> {
+        settings.set(Self::SETTINGS_KEY, self)
+    }
+
+    /// `value` with `precision` decimal places, its digits grouped if `grouped`. Leave grouping
+    /// out of text the user will edit.
+    #[must_use]
+    pub fn format_number(&self, value: f64, precision: usize, grouped: bool) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{:.*}", precision, value.abs());
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut formatted = String::new();
+        if value.is_sign_negative() && text.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+            formatted.push('-');
+        }
+        for (index, digit) in whole.chars().enumerate() {
+            let remaining = whole.len() - index;
+            if index > 0 && remaining.is_multiple_of(3) && grouped {
+                formatted.extend(self.group_separator);
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push(self.decimal_separator);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// Reads a number written in this locale, with or without grouping. A decimal point is
+    /// accepted too where it can't be mistaken for a group separator.
+    #[must_use]
+    pub fn parse_number(&self, text: &str) -> Option<f64> {
+        let text: String = text
+            .trim()
+            .chars()
+            .filter(|&c| Some(c) != self.group_separator && !c.is_whitespace())
+            .map(|c| if c == self.decimal_separator { '.' } else { c })
+            .collect();
+        text.parse().ok()
+    }
+
+    /// A date in this locale's order, with the year in full
+    #[must_use]
+    pub fn format_date(&self, year: i32, month: u32, day: u32) -> String {
+        let separator = self.date_separator;
+        match self.date_order {
+            DateOrder::YearMonthDay => format!("{year:04}{separator}{month:02}{separator}{day:02}"),
+            DateOrder::DayMonthYear => format!("{day:02}{separator}{month:02}{separator}{year:04}"),
+            DateOrder::MonthDayYear => format!("{month:02}{separator}{day:02}{separator}{year:04}"),
+        }
+    }
+
+    /// Reads a date in this locale's order as year, month and day. Any of `/`, `-` and `.` may
+    /// separate the parts.
+    #[must_use]
+    pub fn parse_date(&self, text: &str) -> Option<(i32, u32, u32)> {
+        let parts: Vec<&str> = text.trim().split(['/', '-', '.']).collect();
+        let [a, b, c] = parts[..] else {
+            return None;
+        };
+        let (year, month, day) = match self.date_order {
+            DateOrder::YearMonthDay => (a, b, c),
+            DateOrder::DayMonthYear => (c, b, a),
+            DateOrder::MonthDayYear => (c, a, b),
+        };
+        let (year, month, day) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+        Some((year, month, day))
+    }
+}
+
+/// The locale of the System drawing on this thread, for formatting in the app's own widgets
+#[must_use]
+pub fn current() -> Locale {
+    CURRENT.with(Cell::get)
+}
+
+/// Makes `locale` the one widgets use until it's next set. Used by Systems before drawing.
+pub fn set_current(locale: Locale) {
+    CURRENT.with(|current| current.set(locale));
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn os_preference() -> Option<String> {
+    let output = std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleLocale"])
+        .output()
+        .ok()?;
+    let tag = String::from_utf8(output.stdout).ok()?;
+    Some(String::from(tag.trim())).filter(|tag| !tag.is_empty())
+}
+
+#[cfg(target_os = "windows")]
+fn os_preference() -> Option<String> {
+    const LOCALE_NAME_MAX_LENGTH: usize = 85;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetUserDefaultLocaleName(name: *mut u16, length: i32) -> i32;
+    }
+    let mut name = [0u16; LOCALE_NAME_MAX_LENGTH];
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let length = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    // counting the terminating NUL, or 0 if it failed
+    let length = usize::try_from(length).ok()?.checked_sub(1)?;
+    String::from_utf16(&name[..length])
+        .ok()
+        .filter(|tag| !tag.is_empty())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn os_preference() -> Option<String> {
+    None
+}
@@ -0,0 +1,219 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// Mean earth radius in nautical miles
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatLon {
+    /// Degrees, positive north
+    pub lat: f64,
+    /// Degrees, positive east
+    pub lon: f64,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CoordFormat {
+    /// `51.47750 -0.46139`
+    Decimal,
+    /// `N51°28.65' W000°27.68'`
+    #[default]
+    DegreesMinutes,
+    /// `N51°28'39" W000°27'41"`
+    DegreesMinutesSeconds,
+}
+
+impl LatLon {
+    /// Returns `None` if either component is out of range.
+    #[must_use]
+    pub fn new(lat: f64, lon: f64) -> Option<Self> {
+        ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon))
+            .then_some(LatLon { lat, lon })
+    }
+
+    #[must_use]
+    pub fn format(&self, format: CoordFormat) -> String {
+        match format {
+            CoordFormat::Decimal => format!("{:.5} {:.5}", self.lat, self.lon),
+            CoordFormat::DegreesMinutes | CoordFormat::DegreesMinutesSeconds => {
+                let lat = format_angle(self.lat, ('N', 'S'), 2, format);
+                let lon = format_angle(self.lon, ('E', 'W'), 3, format);
+                format!("{lat} {lon}")
+            }
+        }
+    }
+
+    /// Parses a coordinate pair in any of the `CoordFormat`s.
+    ///
+    /// Hemisphere letters may precede or follow each component, degree, minute and second
+    /// symbols are optional, and components may be separated by spaces or a comma.
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut components: Vec<Component> = Vec::new();
+        let mut current = Component::default();
+
+        for token in tokenize(text) {
+            match token {
+                Token::Number(value) => {
+                    if current.values.len() == 3 {
+                        return None;
+                    }
+                    current.values.push(value);
+                }
+                Token::Hemisphere(hemisphere) => {
+                    if current.values.is_empty() {
+                        if current.hemisphere.replace(hemisphere).is_some() {
+                            return None;
+                        }
+                    } else if current.hemisphere.is_none() {
+                        current.hemisphere = Some(hemisphere);
+                        components.push(std::mem::take(&mut current));
+                    } else {
+                        components.push(std::mem::take(&mut current));
+                        current.hemisphere = Some(hemisphere);
+                    }
+                }
+                Token::Separator => {
+                    if !current.values.is_empty() {
+                        components.push(std::mem::take(&mut current));
+                    }
+                }
+            }
+        }
+        if !current.values.is_empty() {
+            components.push(current);
+        }
+
+        // Plain "lat lon" decimal pairs have no separators between components
+        if components.len() == 1 && components[0].hemisphere.is_none() {
+            if let [lat, lon] = components[0].values[..] {
+                return LatLon::new(lat, lon);
+            }
+        }
+
+        match &components[..] {
+            [lat, lon] => {
+                let lat = lat.degrees(('N', 'S'))?;
+                let lon = lon.degrees(('E', 'W'))?;
+                LatLon::new(lat, lon)
+            }
+            _ => None,
+        }
+    }
+
+    /// Great circle distance in nautical miles
+    #[must_use]
+    pub fn distance_nm(&self, other: &LatLon) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_NM * a.sqrt().asin()
+    }
+
+    /// Initial true bearing towards `other`, in degrees 0..360
+    #[must_use]
+    pub fn bearing_to(&self, other: &LatLon) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lon = (other.lon - self.lon).to_radians();
+        let y = d_lon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+}
+
+fn format_angle(
+    value: f64,
+    hemispheres: (char, char),
+    width: usize,
+    format: CoordFormat,
+) -> String {
+    let hemisphere = if value < 0.0 {
+        hemispheres.1
+    } else {
+        hemispheres.0
+    };
+    let value = value.abs();
+    // round to the displayed precision first so we never show 60 minutes or seconds
+    if format == CoordFormat::DegreesMinutesSeconds {
+        let total = (value * 3600.0).round();
+        let (d, m, s) = (
+            (total / 3600.0).trunc(),
+            (total / 60.0).trunc() % 60.0,
+            total % 60.0,
+        );
+        format!("{hemisphere}{d:0width$}°{m:02}'{s:02}\"")
+    } else {
+        let total = (value * 6000.0).round() / 100.0;
+        let (d, m) = ((total / 60.0).trunc(), total % 60.0);
+        format!("{hemisphere}{d:0width$}°{m:05.2}'")
+    }
+}
+
+#[derive(Default)]
+struct Component {
+    hemisphere: Option<char>,
+    values: Vec<f64>,
+}
+
+impl Component {
+    fn degrees(&self, hemispheres: (char, char)) -> Option<f64> {
+        let (first, rest) = self.values.split_first()?;
+        let mut magnitude = first.abs();
+        for (value, divisor) in rest.iter().zip([60.0, 3600.0]) {
+            if !(0.0..60.0).contains(value) {
+                return None;
+            }
+            magnitude += value / divisor;
+        }
+        let negative = match self.hemisphere {
+            Some(h) if h == hemispheres.0 => false,
+            Some(h) if h == hemispheres.1 => true,
+            Some(_) => return None,
+            None => *first < 0.0,
+        };
+        Some(if negative { -magnitude } else { magnitude })
+    }
+}
+
+enum Token {
+    Number(f64),
+    Hemisphere(char),
+    Separator,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+    let flush = |number: &mut String, tokens: &mut Vec<Token>| -> bool {
+        if number.is_empty() {
+            return true;
+        }
+        let parsed = number.parse().ok().map(Token::Number);
+        number.clear();
+        parsed.map(|token| tokens.push(token)).is_some()
+    };
+    for c in text.chars() {
+        if c.is_ascii_digit() || c == '.' || (c == '-' && number.is_empty()) {
+            number.push(c);
+            continue;
+        }
+        if !flush(&mut number, &mut tokens) {
+            return Vec::new();
+        }
+        match c.to_ascii_uppercase() {
+            h @ ('N' | 'S' | 'E' | 'W') => tokens.push(Token::Hemisphere(h)),
+            ',' | ';' | '/' => tokens.push(Token::Separator),
+            _ => {}
+        }
+    }
+    if !flush(&mut number, &mut tokens) {
+        return Vec::new();
+    }
+    tokens
+}
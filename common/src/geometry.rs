@@ -6,7 +6,7 @@
 use mint::Vector2;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Rect {
     pub left: i32,
     pub top: i32,
@@ -50,3 +50,69 @@ impl From<Rect> for [f32; 2] {
         [value.width() as f32, value.height() as f32]
     }
 }
+
+/// A corner or the centre of the screen, which a `Placement` is measured from
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Centre,
+}
+
+/// Where a window starts on the screen, worked out by the System once it knows the screen's
+/// bounds: the main monitor's when standalone, X-Plane's global desktop in the sim.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placement {
+    /// `x` pixels right of and `y` pixels down from the screen's top left, as taken by
+    /// `SystemBuilder::new`
+    Absolute { x: i32, y: i32 },
+    /// `x` and `y` pixels in from the anchor's edges, or right and down from the centre
+    Anchored { anchor: Anchor, x: i32, y: i32 },
+    /// As `Anchored`, with the offsets as percentages of the screen's width and height
+    Percent { anchor: Anchor, x: f32, y: f32 },
+}
+
+impl Placement {
+    /// A `width` by `height` window placed on `screen`, in the screen's coordinates, whichever
+    /// way up they are
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap,
+        clippy::cast_precision_loss
+    )]
+    pub fn rect(&self, screen: &Rect, width: u32, height: u32) -> Rect {
+        let (anchor, x, y) = match *self {
+            Placement::Absolute { x, y } => (Anchor::TopLeft, x, y),
+            Placement::Anchored { anchor, x, y } => (anchor, x, y),
+            Placement::Percent { anchor, x, y } => (
+                anchor,
+                (screen.width() as f32 * x / 100.0).round() as i32,
+                (screen.height() as f32 * y / 100.0).round() as i32,
+            ),
+        };
+        let (width, height) = (width as i32, height as i32);
+        let spare_width = screen.width() as i32 - width;
+        let spare_height = screen.height() as i32 - height;
+        // Distances right and down from the screen's top left
+        let (across, down) = match anchor {
+            Anchor::TopLeft => (x, y),
+            Anchor::TopRight => (spare_width - x, y),
+            Anchor::BottomLeft => (x, spare_height - y),
+            Anchor::BottomRight => (spare_width - x, spare_height - y),
+            Anchor::Centre => (spare_width / 2 + x, spare_height / 2 + y),
+        };
+        let left = screen.left + across;
+        // X-Plane's y axis points up, GLFW's down
+        if screen.top > screen.bottom {
+            let top = screen.top - down;
+            Rect::new(left, top, left + width, top - height)
+        } else {
+            let top = screen.top + down;
+            Rect::new(left, top, left + width, top + height)
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use imgui::Key;
+
+use crate::events::{Event, Modifiers};
+
+/// A key and the modifiers which must be held with it, e.g. Ctrl+Shift+M
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chord {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl Chord {
+    #[must_use]
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Chord { key, modifiers }
+    }
+
+    /// Parses chords like `Ctrl+Shift+M` or `Alt+F5`. Modifiers are `Ctrl`, `Shift`, `Alt` (or
+    /// `Option`) and `Cmd` (or `Super`); the key is any imgui key name, with digits accepted
+    /// as-is. Returns `None` if any part isn't recognised.
+    #[must_use]
+    pub fn parse(chord: &str) -> Option<Chord> {
+        let mut modifiers = Modifiers::default();
+        let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+        let key = parse_key(parts.pop()?)?;
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.control = true,
+                "shift" => modifiers.shift = true,
+                "alt" | "option" => modifiers.option = true,
+                "cmd" | "super" => modifiers.super_key = true,
+                _ => return None,
+            }
+        }
+        Some(Chord { key, modifiers })
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Modifiers {
+            control,
+            option,
+            shift,
+            super_key,
+        } = self.modifiers;
+        for (held, name) in [
+            (control, "Ctrl+"),
+            (shift, "Shift+"),
+            (option, "Alt+"),
+            (super_key, "Cmd+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        let key = format!("{:?}", self.key);
+        f.write_str(key.strip_prefix("Alpha").unwrap_or(&key))
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let name = if name.len() == 1 && name.as_bytes()[0].is_ascii_digit() {
+        format!("Alpha{name}")
+    } else {
+        String::from(name)
+    };
+    Key::VARIANTS
+        .into_iter()
+        .find(|key| format!("{key:?}").eq_ignore_ascii_case(&name))
+}
+
+/// What happens when a hotkey is pressed
+pub enum HotkeyAction {
+    Callback(Box<dyn FnMut()>),
+    /// Passes the event to `App::handle_event`
+    Event(Event),
+}
+
+impl HotkeyAction {
+    /// Runs a callback, or returns the event to send to the app
+    pub fn trigger(&mut self) -> Option<Event> {
+        match self {
+            HotkeyAction::Callback(callback) => {
+                callback();
+                None
+            }
+            HotkeyAction::Event(event) => Some(event.clone()),
+        }
+    }
+}
+
+pub struct Hotkey {
+    pub chord: Chord,
+    pub description: String,
+    pub action: HotkeyAction,
+}
+
+/// Keyboard shortcuts which work whether or not the app's window has keyboard focus.
+///
+/// X-Plane registers them as XPLM hot keys, which users can remap in the keyboard settings.
+/// Standalone matches them against key presses before the App or imgui sees them, as GLFW has
+/// no system-wide shortcuts.
+#[derive(Default)]
+pub struct Hotkeys {
+    hotkeys: Vec<Hotkey>,
+}
+
+impl Hotkeys {
+    #[must_use]
+    pub fn new() -> Self {
+        Hotkeys::default()
+    }
+
+    /// Calls `callback` when `chord` is pressed
+    #[must_use]
+    pub fn on(mut self, chord: Chord, description: &str, callback: impl FnMut() + 'static) -> Self {
+        self.add(
+            chord,
+            description,
+            HotkeyAction::Callback(Box::new(callback)),
+        );
+        self
+    }
+
+    /// Sends `event` to the app when `chord` is pressed
+    #[must_use]
+    pub fn send(mut self, chord: Chord, description: &str, event: Event) -> Self {
+        self.add(chord, description, HotkeyAction::Event(event));
+        self
+    }
+
+    fn add(&mut self, chord: Chord, description: &str, action: HotkeyAction) {
+        self.hotkeys.push(Hotkey {
+            chord,
+            description: String::from(description),
+            action,
+        });
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hotkeys.is_empty()
+    }
+
+    /// The action of the first hotkey for `key` pressed with exactly `modifiers`
+    pub fn matching(&mut self, key: Key, modifiers: &Modifiers) -> Option<&mut HotkeyAction> {
+        self.hotkeys
+            .iter_mut()
+            .find(|hotkey| hotkey.chord.key == key && hotkey.chord.modifiers == *modifiers)
+            .map(|hotkey| &mut hotkey.action)
+    }
+
+    #[must_use]
+    pub fn into_hotkeys(self) -> Vec<Hotkey> {
+        self.hotkeys
+    }
+}
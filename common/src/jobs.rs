@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use imgui::{ProgressBar, Ui};
+
+use crate::tasks::{TaskHandle, Tasks};
+
+const PROGRESS_POPUP: &str = "Working##chunked_job";
+
+/// How long jobs may run each frame before yielding, well inside X-Plane's frame time
+pub const FRAME_BUDGET: Duration = Duration::from_millis(4);
+
+/// Returned by each chunk of a `ChunkedJobs` job
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Step {
+    /// There is more to do; the job is called again, this frame if time allows
+    Continue,
+    Done,
+}
+
+/// Long operations which must run on the UI or sim thread, e.g. because they read datarefs,
+/// split into chunks run a few milliseconds at a time so the sim keeps drawing. Cloning gives
+/// another handle to the same jobs.
+///
+/// While a job runs, the `System` shows a modal progress dialog with a button to cancel it.
+/// Jobs report progress and check for cancellation through their `TaskHandle`, and also appear
+/// in the `Tasks` progress bar.
+#[derive(Clone, Default)]
+pub struct ChunkedJobs {
+    tasks: Tasks,
+    jobs: Rc<RefCell<Vec<Job>>>,
+}
+
+struct Job {
+    handle: TaskHandle,
+    step: Box<dyn FnMut(&TaskHandle) -> Step>,
+}
+
+impl ChunkedJobs {
+    /// Jobs are registered with `tasks`
+    #[must_use]
+    pub fn new(tasks: Tasks) -> Self {
+        ChunkedJobs {
+            tasks,
+            jobs: Rc::default(),
+        }
+    }
+
+    /// Queues `step`, which is called repeatedly until it returns `Step::Done` or the job is
+    /// cancelled. Each call should do a small amount of work, e.g. one file or one batch of
+    /// datarefs.
+    pub fn run(&self, name: &str, step: impl FnMut(&TaskHandle) -> Step + 'static) {
+        self.jobs.borrow_mut().push(Job {
+            handle: self.tasks.start(name),
+            step: Box::new(step),
+        });
+    }
+
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        !self.jobs.borrow().is_empty()
+    }
+
+    /// Runs chunks of the queued jobs, oldest first, until `budget` has elapsed. Used by Systems.
+    pub fn run_for(&self, budget: Duration) {
+        // jobs may queue more jobs, so none are borrowed while they run
+        let mut jobs = std::mem::take(&mut *self.jobs.borrow_mut());
+        let start = Instant::now();
+        while let Some(job) = jobs.first_mut() {
+            if job.handle.is_cancelled() || (job.step)(&job.handle) == Step::Done {
+                jobs.remove(0);
+            }
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        let mut queued = self.jobs.borrow_mut();
+        jobs.append(&mut queued);
+        *queued = jobs;
+    }
+
+    /// Draws the progress dialog while any job is running, closing it once they finish
+    pub fn draw(&self, ui: &Ui) {
+        let jobs = self.jobs.borrow();
+        if !jobs.is_empty() {
+            ui.open_popup(PROGRESS_POPUP);
+        }
+        ui.modal_popup_config(PROGRESS_POPUP)
+            .always_auto_resize(true)
+            .movable(false)
+            .build(|| {
+                let Some(job) = jobs.first() else {
+                    ui.close_current_popup();
+                    return;
+                };
+                let (label, progress) = job.handle.label_and_progress();
+                ui.text(label);
+                #[allow(clippy::cast_possible_truncation)]
+                let fraction = progress.unwrap_or_else(|| ui.time().fract() as f32);
+                ProgressBar::new(fraction).size([300.0, 0.0]).build(ui);
+                if jobs.len() > 1 {
+                    ui.text_disabled(format!("{} more queued", jobs.len() - 1));
+                }
+                if ui.button("Cancel") {
+                    job.handle.cancellation().cancel();
+                }
+            });
+    }
+}
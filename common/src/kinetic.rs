@@ -0,0 +1,209 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use imgui::{Io, StyleColor, Ui};
+
+/// How far back scrolls count towards the velocity
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+/// Seconds for the momentum to fall to about a third
+const TIME_CONSTANT: f32 = 0.325;
+/// Wheel clicks a second below which the momentum stops
+const MIN_SPEED: f32 = 0.5;
+/// Speed at which the overscroll glow is brightest
+const GLOW_SPEED: f32 = 20.0;
+/// Depth of the overscroll glow in pixels
+const GLOW_DEPTH: f32 = 24.0;
+
+/// Kinetic scrolling for touch and trackpad users: scrolling carries on after the fingers lift,
+/// slowing to a stop. Off by default. Cloning gives another handle.
+///
+/// Systems hold the scrolls they would pass to imgui and feed them in once a frame along with the
+/// momentum. Apps can call `draw_overscroll` in scrolled windows to show a glow at an edge
+/// scrolled past.
+#[derive(Clone, Default)]
+pub struct KineticScroll {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    enabled: bool,
+    /// Scrolls held since the last frame
+    pending: [f32; 2],
+    /// Recent scrolls, for measuring their speed
+    samples: VecDeque<(Instant, [f32; 2])>,
+    /// Wheel clicks a second
+    velocity: [f32; 2],
+}
+
+impl KineticScroll {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.inner.borrow().enabled
+    }
+
+    /// Turning it off stops any momentum at once
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut inner = self.inner.borrow_mut();
+        inner.enabled = enabled;
+        if !enabled {
+            inner.samples.clear();
+            inner.velocity = [0.0, 0.0];
+        }
+    }
+
+    /// Whether scrolling is still carrying on, so frames should keep coming
+    #[must_use]
+    pub fn is_moving(&self) -> bool {
+        let inner = self.inner.borrow();
+        inner.velocity != [0.0, 0.0] || inner.pending != [0.0, 0.0]
+    }
+
+    /// Holds a scroll for the next frame, returning false if kinetic scrolling is off and the
+    /// caller should pass it to imgui as usual. Used by Systems.
+    #[must_use]
+    pub fn hold(&self, x: f32, y: f32) -> bool {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.enabled {
+            return false;
+        }
+        inner.pending[0] += x;
+        inner.pending[1] += y;
+        inner.samples.push_back((Instant::now(), [x, y]));
+        true
+    }
+
+    /// Passes imgui the scrolls held since the last frame, or the momentum once they stop. Call
+    /// after setting the frame's delta time. Used by Systems.
+    pub fn apply(&self, io: &mut Io) {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.enabled {
+            return;
+        }
+        let now = Instant::now();
+        while inner
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > VELOCITY_WINDOW)
+        {
+            inner.samples.pop_front();
+        }
+
+        let dt = io.delta_time;
+        let scroll = if inner.pending == [0.0, 0.0] {
+            let decay = (-dt / TIME_CONSTANT).exp();
+            inner.velocity = inner.velocity.map(|speed| {
+                let speed = speed * decay;
+                if speed.abs() < MIN_SPEED {
+                    0.0
+                } else {
+                    speed
+                }
+            });
+            inner.velocity.map(|speed| speed * dt)
+        } else {
+            let window = VELOCITY_WINDOW.as_secs_f32();
+            let total = inner.samples.iter().fold([0.0, 0.0], |total, (_, [x, y])| {
+                [total[0] + x, total[1] + y]
+            });
+            inner.velocity = total.map(|distance| distance / window);
+            std::mem::take(&mut inner.pending)
+        };
+        if scroll != [0.0, 0.0] {
+            io.add_mouse_wheel_event(scroll);
+        }
+    }
+
+    /// Draws a glow along each edge of the current window which is being scrolled past, fading
+    /// as the scroll slows. Call at the end of a scrolled window or child's contents.
+    pub fn draw_overscroll(&self, ui: &Ui) {
+        let [vx, vy] = self.inner.borrow().velocity;
+        if [vx, vy] == [0.0, 0.0] || !ui.is_window_hovered() {
+            return;
+        }
+        let [left, top] = ui.window_pos();
+        let [width, height] = ui.window_size();
+        let (right, bottom) = (left + width, top + height);
+
+        // Positive scrolls move towards the top and left
+        let edges = [
+            (vy > 0.0 && ui.scroll_y() <= 0.0, vy, Edge::Top),
+            (
+                vy < 0.0 && ui.scroll_y() >= ui.scroll_max_y(),
+                vy,
+                Edge::Bottom,
+            ),
+            (vx > 0.0 && ui.scroll_x() <= 0.0, vx, Edge::Left),
+            (
+                vx < 0.0 && ui.scroll_x() >= ui.scroll_max_x(),
+                vx,
+                Edge::Right,
+            ),
+        ];
+        let draw_list = ui.get_window_draw_list();
+        let [red, green, blue, alpha] = ui.style_color(StyleColor::ScrollbarGrabActive);
+        for (past, speed, edge) in edges {
+            if !past {
+                continue;
+            }
+            let strength = (speed.abs() / GLOW_SPEED).min(1.0);
+            let bright = [red, green, blue, alpha * strength];
+            let clear = [red, green, blue, 0.0];
+            match edge {
+                Edge::Top => draw_list.add_rect_filled_multicolor(
+                    [left, top],
+                    [right, top + GLOW_DEPTH],
+                    bright,
+                    bright,
+                    clear,
+                    clear,
+                ),
+                Edge::Bottom => draw_list.add_rect_filled_multicolor(
+                    [left, bottom - GLOW_DEPTH],
+                    [right, bottom],
+                    clear,
+                    clear,
+                    bright,
+                    bright,
+                ),
+                Edge::Left => draw_list.add_rect_filled_multicolor(
+                    [left, top],
+                    [left + GLOW_DEPTH, bottom],
+                    bright,
+                    clear,
+                    clear,
+                    bright,
+                ),
+                Edge::Right => draw_list.add_rect_filled_multicolor(
+                    [right - GLOW_DEPTH, top],
+                    [right, bottom],
+                    clear,
+                    bright,
+                    bright,
+                    clear,
+                ),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
@@ -0,0 +1,217 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::Ui;
+
+/// How much of a row's width, or a column's height, a cell takes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    /// A fixed number of pixels
+    Fixed(f32),
+    /// A share of the space left over by fixed cells and gaps, in proportion to its weight
+    Flex(f32),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Axis {
+    Row,
+    Column,
+}
+
+enum Content<'a> {
+    Draw(Box<dyn FnOnce(&Ui) + 'a>),
+    Stack(Stack<'a>),
+}
+
+/// Cells laid out side by side in a row, or top to bottom in a column, sized from the space
+/// available when drawn so the layout follows the window as it's resized. Each cell is a child
+/// window, so its contents are clipped to it and can use the usual imgui calls. Nest stacks for
+/// more complex panels, e.g. a sidebar beside a column of flexible panes.
+#[must_use]
+pub struct Stack<'a> {
+    id: &'a str,
+    axis: Axis,
+    gap: Option<f32>,
+    extent: Option<f32>,
+    cells: Vec<(Size, Content<'a>)>,
+}
+
+impl<'a> Stack<'a> {
+    /// Cells side by side, left to right. `id` distinguishes stacks drawn in the same window.
+    pub fn row(id: &'a str) -> Self {
+        Self::new(id, Axis::Row)
+    }
+
+    /// Cells stacked top to bottom. `id` distinguishes stacks drawn in the same window.
+    pub fn column(id: &'a str) -> Self {
+        Self::new(id, Axis::Column)
+    }
+
+    fn new(id: &'a str, axis: Axis) -> Self {
+        Stack {
+            id,
+            axis,
+            gap: None,
+            extent: None,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Space between cells. Defaults to the style's item spacing.
+    pub fn gap(mut self, gap: f32) -> Self {
+        self.gap = Some(gap);
+        self
+    }
+
+    /// A row's height or a column's width. Defaults to all that's available.
+    pub fn extent(mut self, extent: f32) -> Self {
+        self.extent = Some(extent);
+        self
+    }
+
+    /// Adds a cell drawn by `draw`
+    pub fn cell(mut self, size: Size, draw: impl FnOnce(&Ui) + 'a) -> Self {
+        self.cells.push((size, Content::Draw(Box::new(draw))));
+        self
+    }
+
+    /// Adds a cell holding a nested row or column
+    pub fn stack(mut self, size: Size, stack: Stack<'a>) -> Self {
+        self.cells.push((size, Content::Stack(stack)));
+        self
+    }
+
+    /// Draws the cells into the space available at the cursor, leaving the cursor below them
+    pub fn draw(self, ui: &Ui) {
+        let _id = ui.push_id(self.id);
+        let [available_width, available_height] = ui.content_region_avail();
+        let spacing = ui.clone_style().item_spacing;
+        let (length, extent, gap) = match self.axis {
+            Axis::Row => (
+                available_width,
+                self.extent.unwrap_or(available_height),
+                self.gap.unwrap_or(spacing[0]),
+            ),
+            Axis::Column => (
+                available_height,
+                self.extent.unwrap_or(available_width),
+                self.gap.unwrap_or(spacing[1]),
+            ),
+        };
+        let sizes: Vec<Size> = self.cells.iter().map(|(size, _)| *size).collect();
+        let lengths = split(length, gap, &sizes);
+
+        let [x, y] = ui.cursor_pos();
+        let mut offset = 0.0;
+        let cells = self.cells.into_iter().zip(lengths);
+        for (index, ((_, content), cell_length)) in cells.enumerate() {
+            let (position, size) = match self.axis {
+                Axis::Row => ([x + offset, y], [cell_length, extent]),
+                Axis::Column => ([x, y + offset], [extent, cell_length]),
+            };
+            ui.set_cursor_pos(position);
+            ui.child_window(format!("##cell{index}"))
+                .size(size)
+                .build(|| match content {
+                    Content::Draw(draw) => draw(ui),
+                    Content::Stack(stack) => stack.draw(ui),
+                });
+            offset += cell_length + gap;
+        }
+
+        // reserves the stack's space so whatever follows is placed below it
+        ui.set_cursor_pos([x, y]);
+        ui.dummy(match self.axis {
+            Axis::Row => [length, extent],
+            Axis::Column => [extent, length],
+        });
+    }
+}
+
+/// Divides `length` between cells of `sizes` separated by `gap`. Cells are at least a pixel, as
+/// imgui sizes a child window of zero to fill the space left.
+#[allow(clippy::cast_precision_loss)]
+fn split(length: f32, gap: f32, sizes: &[Size]) -> Vec<f32> {
+    let gaps = gap * sizes.len().saturating_sub(1) as f32;
+    let (fixed, weights) = sizes
+        .iter()
+        .fold((0.0, 0.0), |(fixed, weights), size| match size {
+            Size::Fixed(pixels) => (fixed + pixels, weights),
+            Size::Flex(weight) => (fixed, weights + weight),
+        });
+    let remaining = (length - gaps - fixed).max(0.0);
+    sizes
+        .iter()
+        .map(|size| match size {
+            Size::Fixed(pixels) => *pixels,
+            Size::Flex(weight) if weights > 0.0 => remaining * weight / weights,
+            Size::Flex(_) => 0.0,
+        })
+        .map(|length| length.max(1.0))
+        .collect()
+}
+
+/// How wide a window is, from `Breakpoints`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum SizeClass {
+    /// e.g. a single column
+    Compact,
+    Medium,
+    /// e.g. a sidebar beside several columns
+    Expanded,
+}
+
+/// The widths at which a window moves between `SizeClass`es, so panels can switch between
+/// single and multi-column layouts as the window is resized or popped out. Given to the
+/// `SystemBuilder`, which sends `Event::SizeClass` when the class changes, including once the
+/// window first draws.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Breakpoints {
+    medium: f32,
+    expanded: f32,
+    current: Option<SizeClass>,
+}
+
+impl Default for Breakpoints {
+    fn default() -> Self {
+        Self::new(480.0, 960.0)
+    }
+}
+
+impl Breakpoints {
+    /// Windows at least `medium` pixels wide are `Medium`, and at least `expanded` are `Expanded`
+    #[must_use]
+    pub fn new(medium: f32, expanded: f32) -> Self {
+        Breakpoints {
+            medium,
+            expanded: expanded.max(medium),
+            current: None,
+        }
+    }
+
+    #[must_use]
+    pub fn classify(&self, width: f32) -> SizeClass {
+        if width >= self.expanded {
+            SizeClass::Expanded
+        } else if width >= self.medium {
+            SizeClass::Medium
+        } else {
+            SizeClass::Compact
+        }
+    }
+
+    /// The class as of the last `update`
+    #[must_use]
+    pub fn current(&self) -> Option<SizeClass> {
+        self.current
+    }
+
+    /// Classifies `width`, returning the class if it has changed. Used by Systems.
+    pub fn update(&mut self, width: f32) -> Option<SizeClass> {
+        let class = self.classify(width);
+        (self.current.replace(class) != Some(class)).then_some(class)
+    }
+}
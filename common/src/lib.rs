@@ -8,31 +8,160 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::missing_panics_doc)]
 
+use std::collections::BTreeMap;
 use std::ffi::c_void;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
 
 use gl21 as gl;
 use image::{EncodableLayout, ImageError, RgbaImage};
-use imgui::{TextureId, Ui};
+use imgui::{Condition, Context, StyleVar, TextureId, Ui, WindowFlags, WindowToken};
 use tracing::debug;
 
-use crate::events::Event;
+use crate::events::{Event, InputState};
 
+pub mod allocator;
+pub mod app_stack;
+pub mod backend;
+pub mod clipboard;
+pub mod close;
+pub mod dialog;
+pub mod draw_data;
 pub mod events;
+pub mod focus;
+pub mod fonts;
+pub mod format;
+pub mod geo;
 pub mod geometry;
+pub mod hotkeys;
+pub mod jobs;
+pub mod kinetic;
+pub mod layout;
+pub mod loading;
+pub mod menu;
+pub mod metrics;
+pub mod navigation;
+pub mod phases;
+pub mod platform_utils;
+pub mod profiles;
+pub mod recorder;
 pub mod renderer_common;
+pub mod scroll;
+pub mod self_test;
+pub mod settings;
+pub mod smoothing;
+pub mod tasks;
+pub mod theme;
+pub mod units;
+pub mod video;
+#[cfg(feature = "wasm")]
+pub mod web;
+pub mod widgets;
 
 pub trait App {
     fn draw_ui(&self, _ui: &Ui) {}
-    /// return true to consume the event
-    fn handle_event(&mut self, event: Event) -> bool;
+    /// return false while initialising to show the System's loading screen, if it has one
+    fn ready(&self) -> bool {
+        true
+    }
+    /// return true to consume the event. `input` says whether imgui wants the mouse and keyboard.
+    fn handle_event(&mut self, event: Event, input: &InputState) -> bool;
+    /// called every frame with the seconds since the last call, even while the window is hidden
+    fn update(&mut self, _dt: f32) {}
+    /// return true while animating so the next frame is drawn straight away, rather than when
+    /// input arrives. Only Systems which wait for input between frames ask.
+    fn wants_redraw(&self) -> bool {
+        false
+    }
+    /// called with the GL context current just before the UI is rendered, to draw behind it.
+    /// Restore any GL state changed other than the framebuffer's contents.
+    fn render_background(&mut self) {}
+    /// called with the GL context current just after the UI is rendered, to draw over it
+    fn render_foreground(&mut self) {}
+    /// return false to keep the window open when the user asks to close it, e.g. to ask about
+    /// unsaved changes first and close it with the System's `CloseHandle` once they're dealt
+    /// with. X-Plane windows only ask when closed from the System's own title bar, and are
+    /// hidden rather than closed. Not called if the app consumed `Event::CloseRequested`.
+    fn on_close_requested(&mut self) -> bool {
+        true
+    }
+    /// called once when a standalone System is dropped, with the GL context still current, to
+    /// clean up
+    fn on_exit(&mut self) {}
+}
+
+/// Stores imgui's ini file (window positions, collapsed state etc.) at `ini_path`, with the log
+/// file alongside it, or disables both if `None`.
+pub fn set_persistence(imgui: &mut Context, ini_path: Option<&Path>) {
+    imgui.set_ini_filename(ini_path.map(Path::to_path_buf));
+    imgui.set_log_filename(ini_path.map(|path| path.with_extension("log")));
+}
+
+/// Begins the window a System draws the app or loading screen into, at `position` and `size`
+/// with `padding` around its contents. The padding applies to this window alone, leaving the
+/// app's own windows and popups with the style's. Used by Systems and custom backends.
+#[must_use]
+pub fn begin_host_window<'ui>(
+    ui: &'ui Ui,
+    name: &str,
+    position: [f32; 2],
+    size: [f32; 2],
+    padding: [f32; 2],
+    flags: WindowFlags,
+) -> Option<WindowToken<'ui>> {
+    let _padding = ui.push_style_var(StyleVar::WindowPadding(padding));
+    ui.window(name)
+        .position(position, Condition::Always)
+        .size(size, Condition::Always)
+        .flags(flags)
+        .begin()
 }
 
+/// The images of the textures made with `create_texture`, by texture name, so they can be
+/// uploaded again after a context reset
+static MANAGED_TEXTURES: Mutex<BTreeMap<u32, RgbaImage>> = Mutex::new(BTreeMap::new());
+
 /// Use `imgui_support_(standalone|xplane)::create_texture` in preference to this.
 ///
 /// # Errors
 ///
 /// Returns `ImageError` if the image could not be loaded.
 pub fn create_texture(texture_id: u32, image: &RgbaImage) -> Result<TextureId, ImageError> {
+    upload_texture(image);
+    let (width, height) = image.dimensions();
+    metrics::track_texture(texture_id, width, height);
+    lock_managed_textures().insert(texture_id, image.clone());
+    Ok(TextureId::new(texture_id as _))
+}
+
+pub fn deallocate_texture(texture_id: TextureId) {
+    debug!(id = texture_id.id(), "Deallocating texture");
+    #[allow(clippy::cast_possible_truncation)]
+    let texture = texture_id.id() as u32;
+    lock_managed_textures().remove(&texture);
+    renderer_common::delete_texture(texture);
+}
+
+/// Uploads the textures made with `create_texture` again under their old names, so the app's
+/// `TextureId`s stay valid after a context reset. Used by Systems, before they restore the font
+/// texture, so it can't be given one of those names.
+pub fn restore_textures() {
+    for (texture, image) in lock_managed_textures().iter() {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, *texture);
+        }
+        upload_texture(image);
+    }
+}
+
+fn lock_managed_textures() -> std::sync::MutexGuard<'static, BTreeMap<u32, RgbaImage>> {
+    MANAGED_TEXTURES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Uploads `image` to the currently bound texture
+fn upload_texture(image: &RgbaImage) {
     let (width, height) = image.dimensions();
     #[allow(clippy::cast_possible_wrap)]
     unsafe {
@@ -51,12 +180,4 @@ pub fn create_texture(texture_id: u32, image: &RgbaImage) -> Result<TextureId, I
             image.as_bytes().as_ptr().cast::<c_void>(),
         );
     }
-    Ok(TextureId::new(texture_id as _))
-}
-
-pub fn deallocate_texture(texture_id: TextureId) {
-    debug!(id = texture_id.id(), "Deallocating texture");
-    unsafe {
-        gl::DeleteTextures(1, [texture_id.id()].as_ptr().cast());
-    }
 }
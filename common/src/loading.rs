@@ -0,0 +1,69 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::{Image, ProgressBar, TextureId, Ui};
+
+use crate::tasks::Tasks;
+
+const BAR_WIDTH: f32 = 240.0;
+
+/// Shown in place of the App until `App::ready` returns true.
+///
+/// The message and progress bar follow the first running task, if any, so long initialisation
+/// (e.g. loading navdata) should be registered with the System's `Tasks`.
+pub struct LoadingScreen {
+    message: String,
+    logo: Option<(TextureId, [f32; 2])>,
+}
+
+impl LoadingScreen {
+    #[must_use]
+    pub fn new(message: &str) -> Self {
+        LoadingScreen {
+            message: String::from(message),
+            logo: None,
+        }
+    }
+
+    /// Draws `texture` at `size` above the message
+    #[must_use]
+    pub fn logo(mut self, texture: TextureId, size: [f32; 2]) -> Self {
+        self.logo = Some((texture, size));
+        self
+    }
+
+    /// Draws the loading screen centred in the current window
+    pub fn draw(&self, ui: &Ui, tasks: &Tasks) {
+        let (message, progress) = match tasks.current() {
+            Some((status, progress)) => (status, progress),
+            None => (self.message.clone(), None),
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let fraction = progress.unwrap_or_else(|| ui.time().fract() as f32);
+
+        let spacing = ui.clone_style().item_spacing[1];
+        let logo_height = self.logo.map_or(0.0, |(_, size)| size[1] + spacing);
+        let height = logo_height + ui.text_line_height_with_spacing() + ui.frame_height();
+        let [width, window_height] = ui.window_size();
+        let mut y = ((window_height - height) / 2.0).max(0.0);
+
+        if let Some((texture, size)) = self.logo {
+            ui.set_cursor_pos([(width - size[0]) / 2.0, y]);
+            Image::new(texture, size).build(ui);
+            y += logo_height;
+        }
+
+        ui.set_cursor_pos([(width - ui.calc_text_size(&message)[0]) / 2.0, y]);
+        ui.text(&message);
+        y += ui.text_line_height_with_spacing();
+
+        ui.set_cursor_pos([(width - BAR_WIDTH) / 2.0, y]);
+        ProgressBar::new(fraction)
+            .overlay_text("")
+            .size([BAR_WIDTH, 0.0])
+            .build(ui);
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::Ui;
+
+/// A menu declared once for both environments: standalone draws it in an imgui main menu bar,
+/// X-Plane adds it to the plugin's submenu of the Plugins menu.
+///
+/// Choosing an item sends `Event::Menu` with the item's id to the App.
+#[derive(Clone, Debug)]
+pub struct AppMenu {
+    title: String,
+    items: Vec<MenuItem>,
+}
+
+#[derive(Clone, Debug)]
+pub enum MenuItem {
+    Item { label: String, id: String },
+    Separator,
+    Submenu(AppMenu),
+}
+
+impl AppMenu {
+    #[must_use]
+    pub fn new(title: &str) -> Self {
+        AppMenu {
+            title: String::from(title),
+            items: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn item(mut self, label: &str, id: &str) -> Self {
+        self.items.push(MenuItem::Item {
+            label: String::from(label),
+            id: String::from(id),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn separator(mut self) -> Self {
+        self.items.push(MenuItem::Separator);
+        self
+    }
+
+    #[must_use]
+    pub fn submenu(mut self, submenu: AppMenu) -> Self {
+        self.items.push(MenuItem::Submenu(submenu));
+        self
+    }
+
+    #[must_use]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    #[must_use]
+    pub fn items(&self) -> &[MenuItem] {
+        &self.items
+    }
+
+    /// Draws the menu inside a menu bar, returning the id of the item chosen this frame
+    #[must_use]
+    pub fn draw(&self, ui: &Ui) -> Option<String> {
+        let _menu = ui.begin_menu(&self.title)?;
+        let mut chosen = None;
+        for item in &self.items {
+            match item {
+                MenuItem::Item { label, id } => {
+                    if ui.menu_item(label) {
+                        chosen = Some(id.clone());
+                    }
+                }
+                MenuItem::Separator => ui.separator(),
+                MenuItem::Submenu(submenu) => {
+                    if let Some(id) = submenu.draw(ui) {
+                        chosen = Some(id);
+                    }
+                }
+            }
+        }
+        chosen
+    }
+}
@@ -0,0 +1,302 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::BTreeMap;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use imgui::{Condition, DrawData, DrawIdx, DrawVert, Ui, WindowFlags};
+
+/// GL texture names and their approximate size in bytes
+static TEXTURES: Mutex<BTreeMap<u32, usize>> = Mutex::new(BTreeMap::new());
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+const FRAME_TIME_SAMPLES: usize = 120;
+
+/// Records a texture upload of `width` x `height` RGBA pixels, replacing any previous upload to
+/// the same texture
+pub(crate) fn track_texture(texture: u32, width: u32, height: u32) {
+    let bytes = width as usize * height as usize * 4;
+    lock_textures().insert(texture, bytes);
+}
+
+pub(crate) fn untrack_texture(texture: u32) {
+    lock_textures().remove(&texture);
+}
+
+fn lock_textures() -> std::sync::MutexGuard<'static, BTreeMap<u32, usize>> {
+    TEXTURES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A global allocator which counts allocations for `Metrics`, wrapping the system allocator.
+/// Install it in the final binary or plugin crate:
+///
+/// `#[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;`
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn allocated(size: usize) {
+    COUNTING.store(true, Ordering::Relaxed);
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+}
+
+/// Counts from `CountingAllocator`, covering the whole process rather than just this crate
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllocationStats {
+    /// Allocations since the process started
+    pub allocations: u64,
+    /// Allocations during the last frame
+    pub frame_allocations: u64,
+    pub live_bytes: usize,
+    pub peak_bytes: usize,
+}
+
+/// The size of a frame's draw data
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DrawMetrics {
+    pub draw_lists: usize,
+    pub vertices: usize,
+    pub indices: usize,
+    pub commands: usize,
+    pub bytes: usize,
+}
+
+impl From<&DrawData> for DrawMetrics {
+    fn from(draw_data: &DrawData) -> Self {
+        let mut metrics = DrawMetrics::default();
+        for draw_list in draw_data.draw_lists() {
+            metrics.draw_lists += 1;
+            metrics.vertices += draw_list.vtx_buffer().len();
+            metrics.indices += draw_list.idx_buffer().len();
+            metrics.commands += draw_list.commands().count();
+        }
+        metrics.bytes =
+            metrics.vertices * size_of::<DrawVert>() + metrics.indices * size_of::<DrawIdx>();
+        metrics
+    }
+}
+
+/// What the renderer did to draw a frame, e.g. for logging to catch UI performance regressions
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RenderStats {
+    pub draw_lists: usize,
+    pub commands: usize,
+    pub vertices: usize,
+    pub indices: usize,
+    /// Commands skipped because their clip rect was empty or off the display
+    pub scissor_rejects: usize,
+    pub draw_calls: usize,
+    /// Changes of texture between draw calls
+    pub texture_binds: usize,
+}
+
+/// The times between the most recent frames, in seconds
+#[derive(Clone, Copy, Debug)]
+pub struct FrameTimes {
+    samples: [f32; FRAME_TIME_SAMPLES],
+    next: usize,
+    count: usize,
+    last_frame: Option<Instant>,
+}
+
+impl Default for FrameTimes {
+    fn default() -> Self {
+        FrameTimes {
+            samples: [0.0; FRAME_TIME_SAMPLES],
+            next: 0,
+            count: 0,
+            last_frame: None,
+        }
+    }
+}
+
+impl FrameTimes {
+    fn record(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame.replace(now) {
+            self.samples[self.next] = (now - last_frame).as_secs_f32();
+            self.next = (self.next + 1) % FRAME_TIME_SAMPLES;
+            self.count = (self.count + 1).min(FRAME_TIME_SAMPLES);
+        }
+    }
+
+    #[must_use]
+    pub fn average(&self) -> Option<f32> {
+        #[allow(clippy::cast_precision_loss)]
+        (self.count > 0).then(|| self.samples.iter().sum::<f32>() / self.count as f32)
+    }
+
+    #[must_use]
+    pub fn max(&self) -> Option<f32> {
+        (self.count > 0).then(|| self.samples.iter().copied().fold(0.0, f32::max))
+    }
+
+    /// Frames per second, averaged over the recent frames
+    #[must_use]
+    pub fn fps(&self) -> Option<f32> {
+        self.average().map(|average| 1.0 / average)
+    }
+}
+
+/// Memory use as of the last frame, for spotting leaks over long sim sessions
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics {
+    pub draw: DrawMetrics,
+    /// `None` unless `CountingAllocator` is installed
+    pub allocations: Option<AllocationStats>,
+    /// Estimated from the sizes of textures uploaded through this crate, including fonts
+    pub texture_bytes: usize,
+    pub frame_times: FrameTimes,
+}
+
+impl Metrics {
+    /// Updates the metrics after a frame has been rendered. Used by Systems.
+    pub fn update(&mut self, draw_data: &DrawData) {
+        self.draw = DrawMetrics::from(draw_data);
+        self.frame_times.record();
+        self.texture_bytes = lock_textures().values().sum();
+        if COUNTING.load(Ordering::Relaxed) {
+            let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+            let previous = self
+                .allocations
+                .map_or(allocations, |stats| stats.allocations);
+            self.allocations = Some(AllocationStats {
+                allocations,
+                frame_allocations: allocations - previous,
+                live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+                peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+            });
+        }
+    }
+}
+
+/// Draws `Metrics` in a small window in the top right corner of the display
+pub struct MetricsOverlay;
+
+impl MetricsOverlay {
+    pub fn draw(ui: &Ui, metrics: &Metrics) {
+        let [width, _] = ui.io().display_size;
+        ui.window("##metrics")
+            .position([width - 8.0, 8.0], Condition::Always)
+            .position_pivot([1.0, 0.0])
+            .bg_alpha(0.6)
+            .flags(
+                WindowFlags::NO_DECORATION
+                    | WindowFlags::ALWAYS_AUTO_RESIZE
+                    | WindowFlags::NO_INPUTS
+                    | WindowFlags::NO_SAVED_SETTINGS,
+            )
+            .build(|| {
+                let draw = &metrics.draw;
+                ui.text(format!(
+                    "Draw: {} lists, {} vertices, {} indices, {} commands",
+                    draw.draw_lists, draw.vertices, draw.indices, draw.commands
+                ));
+                ui.text(format!("Draw data: {}", format_bytes(draw.bytes)));
+                ui.text(format!("Textures: {}", format_bytes(metrics.texture_bytes)));
+                if let Some(allocations) = &metrics.allocations {
+                    ui.text(format!(
+                        "Heap: {} live, {} peak",
+                        format_bytes(allocations.live_bytes),
+                        format_bytes(allocations.peak_bytes)
+                    ));
+                    ui.text(format!(
+                        "Allocations: {} this frame",
+                        allocations.frame_allocations
+                    ));
+                }
+            });
+    }
+}
+
+/// Draws the frame rate, a graph of recent frame times and what it cost to draw them in a small
+/// window in the top left corner of the display, for diagnosing the UI's cost inside the sim
+pub struct PerfOverlay;
+
+impl PerfOverlay {
+    pub fn draw(ui: &Ui, metrics: &Metrics) {
+        let frame_times = &metrics.frame_times;
+        ui.window("##perf")
+            .position([8.0, 8.0], Condition::Always)
+            .bg_alpha(0.6)
+            .flags(
+                WindowFlags::NO_DECORATION
+                    | WindowFlags::ALWAYS_AUTO_RESIZE
+                    | WindowFlags::NO_INPUTS
+                    | WindowFlags::NO_SAVED_SETTINGS,
+            )
+            .build(|| {
+                if let (Some(fps), Some(average)) = (frame_times.fps(), frame_times.average()) {
+                    ui.text(format!("{fps:.0} FPS ({:.1} ms)", average * 1000.0));
+                }
+                let millis = frame_times.samples.map(|seconds| seconds * 1000.0);
+                let max = frame_times.max().unwrap_or_default() * 1000.0;
+                ui.plot_lines("##frame_times", &millis)
+                    .values_offset(frame_times.next)
+                    .scale_min(0.0)
+                    // a 30 FPS frame fills the graph unless frames take longer
+                    .scale_max(max.max(1000.0 / 30.0))
+                    .overlay_text(format!("{max:.1} ms max"))
+                    .graph_size([240.0, 60.0])
+                    .build();
+                let draw = &metrics.draw;
+                ui.text(format!(
+                    "{} vertices, {} indices",
+                    draw.vertices, draw.indices
+                ));
+                ui.text(format!(
+                    "{} draw calls in {} lists",
+                    draw.commands, draw.draw_lists
+                ));
+                ui.text(format!("Textures: {}", format_bytes(metrics.texture_bytes)));
+            });
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_bytes(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes < KIB {
+        format!("{bytes} B")
+    } else if bytes < KIB * KIB {
+        format!("{:.1} KiB", bytes / KIB)
+    } else {
+        format!("{:.1} MiB", bytes / (KIB * KIB))
+    }
+}
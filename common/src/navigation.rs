@@ -0,0 +1,219 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::internal::RawCast;
+use imgui::{sys, BackendFlags, ConfigFlags, Io, Key, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{Settings, SettingsError};
+
+/// Fraction of stick travel ignored around the centre by default
+const STICK_DEADZONE: f32 = 0.25;
+/// Analog value above which a stick or trigger counts as pressed
+const PRESS_THRESHOLD: f32 = 0.1;
+
+/// Which input devices imgui may use to move focus between widgets
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Navigation {
+    pub keyboard: bool,
+    pub gamepad: bool,
+}
+
+impl Navigation {
+    pub fn apply(self, io: &mut Io) {
+        io.config_flags
+            .set(ConfigFlags::NAV_ENABLE_KEYBOARD, self.keyboard);
+        io.config_flags
+            .set(ConfigFlags::NAV_ENABLE_GAMEPAD, self.gamepad);
+    }
+}
+
+/// A gamepad snapshot in imgui's (Xbox-style) layout
+#[derive(Clone, Debug, Default)]
+pub struct GamepadState {
+    /// Digital buttons currently held, as imgui gamepad keys
+    pub buttons: Vec<Key>,
+    /// Left stick position in -1..1, with y increasing downwards
+    pub left_stick: [f32; 2],
+    /// Right stick position in -1..1, with y increasing downwards
+    pub right_stick: [f32; 2],
+    /// Left trigger in 0..1
+    pub left_trigger: f32,
+    /// Right trigger in 0..1
+    pub right_trigger: f32,
+}
+
+const DIGITAL_KEYS: [Key; 14] = [
+    Key::GamepadStart,
+    Key::GamepadBack,
+    Key::GamepadFaceLeft,
+    Key::GamepadFaceRight,
+    Key::GamepadFaceUp,
+    Key::GamepadFaceDown,
+    Key::GamepadDpadLeft,
+    Key::GamepadDpadRight,
+    Key::GamepadDpadUp,
+    Key::GamepadDpadDown,
+    Key::GamepadL1,
+    Key::GamepadR1,
+    Key::GamepadL3,
+    Key::GamepadR3,
+];
+
+/// How an analog axis's travel is turned into what imgui sees
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisResponse {
+    /// Fraction of travel ignored around the rest position, for sticks which don't centre
+    /// exactly
+    pub deadzone: f32,
+    /// Exponent applied to the travel beyond the deadzone. 1 is linear; higher gives finer
+    /// control near the rest position.
+    pub curve: f32,
+    /// Multiplier applied last, with the result limited to full travel
+    pub sensitivity: f32,
+}
+
+impl AxisResponse {
+    /// Applies the response to `value`, in -1..1 for a stick or 0..1 for a trigger
+    #[must_use]
+    pub fn apply(self, value: f32) -> f32 {
+        let travel = value.abs();
+        if travel <= self.deadzone {
+            return 0.0;
+        }
+        let travel = ((travel - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        (value.signum() * travel.powf(self.curve) * self.sensitivity).clamp(-1.0, 1.0)
+    }
+}
+
+impl Default for AxisResponse {
+    fn default() -> Self {
+        AxisResponse {
+            deadzone: 0.0,
+            curve: 1.0,
+            sensitivity: 1.0,
+        }
+    }
+}
+
+/// The response of each gamepad axis, applied before imgui sees them, so analog control feels
+/// right on different hardware. By default the sticks have a quarter of their travel as a
+/// deadzone and the responses are otherwise linear.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalogTuning {
+    pub left_x: AxisResponse,
+    pub left_y: AxisResponse,
+    pub right_x: AxisResponse,
+    pub right_y: AxisResponse,
+    pub left_trigger: AxisResponse,
+    pub right_trigger: AxisResponse,
+}
+
+impl AnalogTuning {
+    const SETTINGS_KEY: &'static str = "analog_tuning";
+
+    /// Reads the tuning from `settings`, falling back to defaults if none is stored.
+    #[must_use]
+    pub fn load(settings: &Settings) -> Self {
+        settings.get(Self::SETTINGS_KEY).unwrap_or_default()
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the tuning could not be serialized.
+    pub fn store(&self, settings: &mut Settings) -> Result<(), SettingsError> {
+        settings.set(Self::SETTINGS_KEY, self)
+    }
+
+    /// Draws sliders for each axis's response, e.g. in a settings window. Returns true if any
+    /// changed, so the caller can store and apply the tuning.
+    pub fn edit(&mut self, ui: &Ui) -> bool {
+        let mut changed = false;
+        for (label, response) in [
+            ("Left stick X", &mut self.left_x),
+            ("Left stick Y", &mut self.left_y),
+            ("Right stick X", &mut self.right_x),
+            ("Right stick Y", &mut self.right_y),
+            ("Left trigger", &mut self.left_trigger),
+            ("Right trigger", &mut self.right_trigger),
+        ] {
+            let _id = ui.push_id(label);
+            ui.text(label);
+            changed |= ui.slider("Deadzone", 0.0, 0.9, &mut response.deadzone);
+            changed |= ui.slider("Curve", 0.2, 5.0, &mut response.curve);
+            changed |= ui.slider("Sensitivity", 0.1, 5.0, &mut response.sensitivity);
+        }
+        changed
+    }
+
+    fn apply(&self, state: &GamepadState) -> GamepadState {
+        let [lx, ly] = state.left_stick;
+        let [rx, ry] = state.right_stick;
+        GamepadState {
+            buttons: state.buttons.clone(),
+            left_stick: [self.left_x.apply(lx), self.left_y.apply(ly)],
+            right_stick: [self.right_x.apply(rx), self.right_y.apply(ry)],
+            left_trigger: self.left_trigger.apply(state.left_trigger),
+            right_trigger: self.right_trigger.apply(state.right_trigger),
+        }
+    }
+}
+
+impl Default for AnalogTuning {
+    fn default() -> Self {
+        let stick = AxisResponse {
+            deadzone: STICK_DEADZONE,
+            ..AxisResponse::default()
+        };
+        AnalogTuning {
+            left_x: stick,
+            left_y: stick,
+            right_x: stick,
+            right_y: stick,
+            left_trigger: AxisResponse::default(),
+            right_trigger: AxisResponse::default(),
+        }
+    }
+}
+
+/// Feeds a gamepad snapshot to imgui, after applying `tuning` to its axes, or marks the gamepad
+/// as disconnected if `state` is `None`.
+pub fn update_gamepad(io: &mut Io, state: Option<&GamepadState>, tuning: &AnalogTuning) {
+    let Some(state) = state else {
+        io.backend_flags.remove(BackendFlags::HAS_GAMEPAD);
+        return;
+    };
+    io.backend_flags.insert(BackendFlags::HAS_GAMEPAD);
+    let state = tuning.apply(state);
+
+    for key in DIGITAL_KEYS {
+        io.add_key_event(key, state.buttons.contains(&key));
+    }
+
+    add_analog(io, Key::GamepadL2, state.left_trigger);
+    add_analog(io, Key::GamepadR2, state.right_trigger);
+
+    let [lx, ly] = state.left_stick;
+    add_analog(io, Key::GamepadLStickLeft, -lx);
+    add_analog(io, Key::GamepadLStickRight, lx);
+    add_analog(io, Key::GamepadLStickUp, -ly);
+    add_analog(io, Key::GamepadLStickDown, ly);
+
+    let [rx, ry] = state.right_stick;
+    add_analog(io, Key::GamepadRStickLeft, -rx);
+    add_analog(io, Key::GamepadRStickRight, rx);
+    add_analog(io, Key::GamepadRStickUp, -ry);
+    add_analog(io, Key::GamepadRStickDown, ry);
+}
+
+fn add_analog(io: &mut Io, key: Key, value: f32) {
+    let value = value.clamp(0.0, 1.0);
+    unsafe {
+        sys::ImGuiIO_AddKeyAnalogEvent(io.raw_mut(), key as u32, value > PRESS_THRESHOLD, value);
+    }
+}
@@ -0,0 +1,58 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Hook = Box<dyn FnMut()>;
+
+/// The points in each frame where hooks registered with `System::on_phase` run, in order
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum FramePhase {
+    /// After the window's input has been passed to the app and imgui
+    Input,
+    /// After the app and imgui have been updated, before the frame is built
+    Update,
+    /// After `App::draw_ui` and the System's own windows, before imgui finishes the frame
+    Build,
+    /// After imgui's draw data has been rendered, with the GL state the renderer left
+    Render,
+    /// Just before the frame is shown: before the buffers are swapped (standalone) or the draw
+    /// callback returns (X-Plane), e.g. to draw custom GL on top
+    Present,
+}
+
+/// Callbacks run at a `FramePhase` of every frame. Cloning gives another handle.
+///
+/// Hooks run in the order they were added, and can't add further hooks while running.
+#[derive(Clone, Default)]
+pub struct PhaseHooks {
+    hooks: Rc<RefCell<Vec<(FramePhase, Hook)>>>,
+}
+
+impl PhaseHooks {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `hook` at `phase` from the next time that phase is reached
+    pub fn add(&self, phase: FramePhase, hook: impl FnMut() + 'static) {
+        self.hooks.borrow_mut().push((phase, Box::new(hook)));
+    }
+
+    /// Runs the hooks for `phase`. Used by Systems.
+    pub fn run(&self, phase: FramePhase) {
+        for (_, hook) in self
+            .hooks
+            .borrow_mut()
+            .iter_mut()
+            .filter(|(hook_phase, _)| *hook_phase == phase)
+        {
+            hook();
+        }
+    }
+}
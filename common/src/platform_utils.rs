@@ -0,0 +1,40 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::io;
+use std::process::Command;
+use std::thread;
+
+use tracing::warn;
+
+/// Opens `url` with whatever the system uses for its scheme, e.g. the default browser for https.
+/// Returns once the opener has been started, without waiting for it.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the opener could not be started.
+pub fn open_url(url: &str) -> io::Result<()> {
+    // not `cmd /c start`, which would treat characters such as & in the url as its own syntax
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    let mut child = command.arg(url).spawn()?;
+    // waited for in the background, so it doesn't linger as a zombie in a long-running host
+    thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            warn!(error = %e, "Unable to wait for the URL opener");
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::settings::{Settings, SettingsError};
+
+const SETTINGS_KEY: &str = "profiles";
+
+/// Named window layouts such as "VFR", "IFR" or "Debug", stored in a settings file. Cloning gives
+/// another handle to the same profiles, so the App can keep one to switch layouts from its UI.
+///
+/// The App requests saves and switches; the `System` carries them out before its next frame,
+/// capturing whatever its backend can restore (window geometry and visibility, and imgui's
+/// window state where the System owns it).
+#[derive(Clone)]
+pub struct Profiles {
+    path: PathBuf,
+    pending: Arc<Mutex<Option<ProfileRequest>>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProfileRequest {
+    Save(String),
+    Apply(String),
+}
+
+impl Profiles {
+    /// Profiles are stored under the `profiles` key of the settings file at `path`
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Profiles {
+            path: path.into(),
+            pending: Arc::default(),
+        }
+    }
+
+    /// The names of the saved profiles, in alphabetical order
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the settings file could not be read.
+    pub fn names(&self) -> Result<Vec<String>, SettingsError> {
+        Ok(self.read()?.0.into_keys().collect())
+    }
+
+    /// Asks the System to save the current layout as `name`, replacing any profile of that name
+    pub fn save(&self, name: &str) {
+        *self.lock() = Some(ProfileRequest::Save(String::from(name)));
+    }
+
+    /// Asks the System to switch to the layout saved as `name`
+    pub fn apply(&self, name: &str) {
+        *self.lock() = Some(ProfileRequest::Apply(String::from(name)));
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the settings file could not be read or written.
+    pub fn remove(&self, name: &str) -> Result<(), SettingsError> {
+        let (mut profiles, mut settings) = self.read()?;
+        profiles.remove(name);
+        settings.set(SETTINGS_KEY, &profiles)?;
+        settings.save()
+    }
+
+    /// The request made since the last call, if any. Used by Systems.
+    #[must_use]
+    pub fn take_request(&self) -> Option<ProfileRequest> {
+        self.lock().take()
+    }
+
+    /// Stores a System's layout as `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the settings file could not be read or written.
+    pub fn store<T: Serialize>(&self, name: &str, layout: &T) -> Result<(), SettingsError> {
+        let (mut profiles, mut settings) = self.read()?;
+        profiles.insert(String::from(name), serde_json::to_value(layout)?);
+        settings.set(SETTINGS_KEY, &profiles)?;
+        settings.save()
+    }
+
+    /// Loads the layout stored as `name`, or `None` if there isn't one
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the settings file could not be read or the layout was saved by
+    /// a different backend.
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, SettingsError> {
+        match self.read()?.0.remove(name) {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn read(&self) -> Result<(BTreeMap<String, Value>, Settings), SettingsError> {
+        let settings = Settings::load(&self.path)?;
+        let profiles = settings.get(SETTINGS_KEY).unwrap_or_default();
+        Ok((profiles, settings))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<ProfileRequest>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,201 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The slowest and fastest a macro can be replayed, relative to how it was recorded
+const MIN_SPEED: f32 = 0.01;
+const MAX_SPEED: f32 = 100.0;
+
+/// Recorded sequences of UI input which can be replayed by name, e.g. to repeat a long setup
+/// flow. Cloning gives another handle to the same macros, so one can be moved into a `Hotkeys`
+/// callback or kept by the App.
+///
+/// Requests are carried out by the `System` before its next frame. Macros last until the
+/// System is dropped.
+#[derive(Clone, Default)]
+pub struct Macros {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    requests: Vec<MacroRequest>,
+    names: Vec<String>,
+    recording: bool,
+    playing: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MacroRequest {
+    StartRecording,
+    /// Stops recording, keeping the recording under the given name
+    StopRecording(String),
+    /// Replays a macro at the given speed, 2.0 being twice as fast as it was recorded. The speed
+    /// is clamped between 0.01 and 100, and NaN plays it as recorded.
+    Play(String, f32),
+    /// Stops recording without keeping it, or stops playback
+    Cancel,
+}
+
+impl Macros {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_recording(&self) {
+        self.request(MacroRequest::StartRecording);
+    }
+
+    pub fn stop_recording(&self, name: &str) {
+        self.request(MacroRequest::StopRecording(String::from(name)));
+    }
+
+    pub fn play(&self, name: &str, speed: f32) {
+        self.request(MacroRequest::Play(String::from(name), speed));
+    }
+
+    pub fn cancel(&self) {
+        self.request(MacroRequest::Cancel);
+    }
+
+    /// The names of the recorded macros, in alphabetical order
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.lock().names.clone()
+    }
+
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.lock().recording
+    }
+
+    /// The name of the macro being replayed, if any
+    #[must_use]
+    pub fn playing(&self) -> Option<String> {
+        self.lock().playing.clone()
+    }
+
+    fn request(&self, request: MacroRequest) {
+        self.lock().requests.push(request);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Records and replays a backend's input events for `Macros`. Used by Systems, which pass it
+/// every input event and inject the events it returns.
+pub struct MacroRecorder<E> {
+    macros: Macros,
+    recorded: BTreeMap<String, Vec<(Duration, E)>>,
+    recording: Option<(Instant, Vec<(Duration, E)>)>,
+    playback: Option<Playback<E>>,
+}
+
+struct Playback<E> {
+    start: Instant,
+    speed: f32,
+    steps: Vec<(Duration, E)>,
+    next: usize,
+}
+
+impl<E: Clone> MacroRecorder<E> {
+    #[must_use]
+    pub fn new(macros: Macros) -> Self {
+        MacroRecorder {
+            macros,
+            recorded: BTreeMap::new(),
+            recording: None,
+            playback: None,
+        }
+    }
+
+    #[must_use]
+    pub fn macros(&self) -> &Macros {
+        &self.macros
+    }
+
+    /// Adds a user input event to the recording, if there is one. Events injected by playback
+    /// mustn't be passed back in.
+    pub fn record(&mut self, event: &E) {
+        if let Some((start, steps)) = &mut self.recording {
+            steps.push((start.elapsed(), event.clone()));
+        }
+    }
+
+    /// Carries out requests made through `Macros` and returns the events now due for playback
+    #[must_use]
+    pub fn update(&mut self) -> Vec<E> {
+        let requests = std::mem::take(&mut self.macros.lock().requests);
+        for request in requests {
+            self.handle_request(request);
+        }
+
+        let mut due = Vec::new();
+        if let Some(playback) = &mut self.playback {
+            let elapsed = playback.start.elapsed().mul_f32(playback.speed);
+            while let Some((offset, event)) = playback.steps.get(playback.next) {
+                if *offset > elapsed {
+                    break;
+                }
+                due.push(event.clone());
+                playback.next += 1;
+            }
+            if playback.next == playback.steps.len() {
+                self.playback = None;
+            }
+        }
+
+        let mut inner = self.macros.lock();
+        inner.recording = self.recording.is_some();
+        if self.playback.is_none() {
+            inner.playing = None;
+        }
+        due
+    }
+
+    fn handle_request(&mut self, request: MacroRequest) {
+        match request {
+            MacroRequest::StartRecording => {
+                self.playback = None;
+                self.recording = Some((Instant::now(), Vec::new()));
+            }
+            MacroRequest::StopRecording(name) => {
+                if let Some((_, steps)) = self.recording.take() {
+                    self.recorded.insert(name, steps);
+                    self.macros.lock().names = self.recorded.keys().cloned().collect();
+                }
+            }
+            MacroRequest::Play(name, speed) => {
+                if let Some(steps) = self.recorded.get(&name) {
+                    // an unbounded speed would overflow the scaled playback time
+                    let speed = if speed.is_nan() {
+                        1.0
+                    } else {
+                        speed.clamp(MIN_SPEED, MAX_SPEED)
+                    };
+                    self.recording = None;
+                    self.playback = Some(Playback {
+                        start: Instant::now(),
+                        speed,
+                        steps: steps.clone(),
+                        next: 0,
+                    });
+                    self.macros.lock().playing = Some(name);
+                }
+            }
+            MacroRequest::Cancel => {
+                self.recording = None;
+                self.playback = None;
+            }
+        }
+    }
+}
@@ -13,7 +13,9 @@ use imgui::{
     FontGlyphRanges, FontSource, TextureId,
 };
 
+use crate::metrics::{self, RenderStats};
 use crate::renderer_common::berkeley_mono::RANGES;
+use crate::theme::Theme;
 
 mod berkeley_mono {
     pub const REGULAR: &[u8] = include_bytes!("../resources/BerkeleyMono-Regular.ttf");
@@ -25,7 +27,11 @@ mod berkeley_mono {
     ];
 }
 
+/// The size the bundled fonts are first built at, in pixels
+pub const DEFAULT_FONT_SIZE: f32 = 14.0;
+
 #[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug)]
 pub struct FontStyles {
     pub regular: bool,
     pub bold: bool,
@@ -45,15 +51,11 @@ impl Default for FontStyles {
 }
 
 pub fn add_fonts(font_texture: u32, atlas: &mut FontAtlas, size_pixels: f32, styles: &FontStyles) {
-    unsafe {
-        #[allow(clippy::cast_possible_wrap)]
-        {
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
-        }
-        gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
-    }
+    add_font_styles(atlas, size_pixels, styles);
+    upload_font_texture(font_texture, atlas);
+}
 
+pub(crate) fn add_font_styles(atlas: &mut FontAtlas, size_pixels: f32, styles: &FontStyles) {
     if styles.regular {
         add_font(atlas, "Regular", size_pixels, berkeley_mono::REGULAR);
     }
@@ -71,6 +73,19 @@ pub fn add_fonts(font_texture: u32, atlas: &mut FontAtlas, size_pixels: f32, sty
             berkeley_mono::BOLD_ITALIC,
         );
     }
+}
+
+/// Uploads the atlas to the currently bound texture, e.g. to restore it after a context reset
+pub fn upload_font_texture(font_texture: u32, atlas: &mut FontAtlas) {
+    unsafe {
+        #[allow(clippy::cast_possible_wrap)]
+        {
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+        }
+        gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+    }
+
     let texture = atlas.build_rgba32_texture();
 
     #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
@@ -87,9 +102,24 @@ pub fn add_fonts(font_texture: u32, atlas: &mut FontAtlas, size_pixels: f32, sty
             texture.data.as_ptr().cast::<c_void>(),
         );
     }
+    metrics::track_texture(font_texture, texture.width, texture.height);
     atlas.tex_id = TextureId::new(font_texture as usize);
 }
 
+pub fn delete_texture(texture: u32) {
+    metrics::untrack_texture(texture);
+    unsafe {
+        gl::DeleteTextures(1, &texture);
+    }
+}
+
+/// GL 2.1 has no reset notification, so a texture that no longer exists is taken as a sign the
+/// context was lost or reset.
+#[must_use]
+pub fn texture_lost(texture: u32) -> bool {
+    unsafe { gl::IsTexture(texture) == gl::FALSE }
+}
+
 fn add_font(atlas: &mut FontAtlas, name: &str, size_pixels: f32, data: &[u8]) {
     let size_str = size_pixels.to_string();
 
@@ -113,22 +143,26 @@ pub fn configure_imgui(imgui: &mut Context, name: &str) {
         env!("CARGO_PKG_VERSION")
     )));
 
-    {
-        let style = imgui.style_mut();
-        style.window_rounding = 3.0;
-        style.frame_rounding = 2.0;
-    }
+    Theme::default().apply(imgui.style_mut());
 }
 
-pub fn render<F: Fn(usize, [f32; 4], TextureId, &[DrawIdx], usize)>(
+/// Draws each command whose clip rect overlaps the display, calling `bind_texture_fn` only when
+/// the texture changes, and returns what it took
+pub fn render<B: Fn(TextureId), F: Fn(usize, [f32; 4], &[DrawIdx], usize)>(
     draw_data: &DrawData,
+    bind_texture_fn: B,
     draw_element_fn: F,
-) {
+) -> RenderStats {
+    let mut stats = RenderStats::default();
+    let mut bound_texture = None;
     #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
     unsafe {
         for draw_list in draw_data.draw_lists() {
             let vtx_buffer = draw_list.vtx_buffer();
             let idx_buffer = draw_list.idx_buffer();
+            stats.draw_lists += 1;
+            stats.vertices += vtx_buffer.len();
+            stats.indices += idx_buffer.len();
 
             gl::VertexPointer(
                 2,
@@ -163,7 +197,18 @@ pub fn render<F: Fn(usize, [f32; 4], TextureId, &[DrawIdx], usize)>(
                                 ..
                             },
                     } => {
-                        draw_element_fn(count, clip_rect, texture_id, idx_buffer, idx_offset);
+                        stats.commands += 1;
+                        if clipped_out(draw_data, clip_rect) {
+                            stats.scissor_rejects += 1;
+                            continue;
+                        }
+                        if bound_texture != Some(texture_id) {
+                            bind_texture_fn(texture_id);
+                            bound_texture = Some(texture_id);
+                            stats.texture_binds += 1;
+                        }
+                        draw_element_fn(count, clip_rect, idx_buffer, idx_offset);
+                        stats.draw_calls += 1;
                     }
                     DrawCmd::ResetRenderState => {
                         unimplemented!("Haven't implemented DrawCmd::ResetRenderState yet");
@@ -175,6 +220,16 @@ pub fn render<F: Fn(usize, [f32; 4], TextureId, &[DrawIdx], usize)>(
             }
         }
     }
+    stats
+}
+
+/// Whether `clip_rect` is empty or wholly outside the display, so drawing with it would show
+/// nothing
+pub(crate) fn clipped_out(draw_data: &DrawData, [x, y, z, w]: [f32; 4]) -> bool {
+    let [left, top] = draw_data.display_pos;
+    let right = left + draw_data.display_size[0];
+    let bottom = top + draw_data.display_size[1];
+    z <= x || w <= y || x >= right || y >= bottom || z <= left || w <= top
 }
 
 pub fn return_param<T, F>(f: F) -> T
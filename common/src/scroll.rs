@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use imgui::Ui;
+use tracing::warn;
+
+use crate::settings::Settings;
+
+const SETTINGS_KEY: &str = "scroll_positions";
+
+/// How often changed positions are written, matching imgui's ini file
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The scroll positions of named child regions, e.g. a dataref browser or a long checklist,
+/// restored when they're first drawn in a later session. Cloning gives another handle.
+///
+/// With persistence enabled, Systems store them alongside imgui's ini file. Otherwise they're
+/// only kept for the session, e.g. while a window is closed and reopened.
+#[derive(Clone, Default)]
+pub struct ScrollPositions {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    settings: Settings,
+    positions: BTreeMap<String, [f32; 2]>,
+    /// Regions drawn this session, whose saved position has been applied
+    restored: BTreeSet<String>,
+    changed: bool,
+    last_save: Option<Instant>,
+}
+
+impl ScrollPositions {
+    /// Positions kept for the session only
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads positions stored alongside imgui's ini file at `ini_path`, or keeps them for the
+    /// session if `None`. Used by Systems.
+    #[must_use]
+    pub fn alongside(ini_path: Option<&Path>) -> Self {
+        ini_path.map_or_else(Self::new, |path| {
+            Self::load(path.with_extension("scroll.json"))
+        })
+    }
+
+    /// Loads positions from the settings file at `path`, falling back to session-only positions
+    /// if it can't be read
+    #[must_use]
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let settings = Settings::load(path).unwrap_or_else(|e| {
+            warn!(error = %e, "Unable to load scroll positions");
+            Settings::new()
+        });
+        let positions = settings.get(SETTINGS_KEY).unwrap_or_default();
+        ScrollPositions {
+            inner: Rc::new(RefCell::new(Inner {
+                settings,
+                positions,
+                restored: BTreeSet::new(),
+                changed: false,
+                last_save: None,
+            })),
+        }
+    }
+
+    /// Draws `contents` in a child region of `size` named `name`, restoring its scroll position
+    /// the first time it's drawn and remembering it after
+    pub fn child(&self, ui: &Ui, name: &str, size: [f32; 2], contents: impl FnOnce()) {
+        ui.child_window(name).size(size).build(|| {
+            let restore = {
+                let mut inner = self.inner.borrow_mut();
+                if inner.restored.insert(String::from(name)) {
+                    Some(inner.positions.get(name).copied().unwrap_or_default())
+                } else {
+                    None
+                }
+            };
+            if let Some([x, y]) = restore {
+                // takes effect next frame, once the contents have been measured
+                ui.set_scroll_x(x);
+                ui.set_scroll_y(y);
+            }
+            contents();
+            if restore.is_none() {
+                self.inner
+                    .borrow_mut()
+                    .record(name, [ui.scroll_x(), ui.scroll_y()]);
+            }
+        });
+    }
+
+    /// Forgets every position, e.g. from a "reset layout" command
+    pub fn clear(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.positions.clear();
+        inner.changed = true;
+    }
+
+    /// Writes changed positions at most every few seconds. Used by Systems after each frame.
+    pub fn save_if_due(&self) {
+        let mut inner = self.inner.borrow_mut();
+        let recently_saved = inner
+            .last_save
+            .is_some_and(|last_save| last_save.elapsed() < SAVE_INTERVAL);
+        if inner.changed && !recently_saved {
+            inner.save();
+        }
+    }
+}
+
+impl Inner {
+    fn record(&mut self, name: &str, position: [f32; 2]) {
+        if self.positions.get(name) != Some(&position) {
+            self.positions.insert(String::from(name), position);
+            self.changed = true;
+        }
+    }
+
+    fn save(&mut self) {
+        self.changed = false;
+        self.last_save = Some(Instant::now());
+        let result = self
+            .settings
+            .set(SETTINGS_KEY, &self.positions)
+            .and_then(|()| self.settings.save());
+        if let Err(e) = result {
+            warn!(error = %e, "Unable to save scroll positions");
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if self.changed {
+            self.save();
+        }
+    }
+}
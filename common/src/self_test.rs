@@ -0,0 +1,384 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::ffi::c_void;
+use std::fmt::{self, Display, Formatter};
+use std::ptr;
+
+use gl21 as gl;
+use gl21::types::{GLboolean, GLenum, GLint, GLuint};
+use imgui::{sys, Context, FontAtlas};
+
+use crate::renderer_common::texture_lost;
+
+/// Font atlases are built up to this size, so smaller texture limits may cut fonts short
+const MIN_TEXTURE_SIZE: GLint = 2048;
+/// Drawn in the test frame
+const SAMPLE_TEXT: &str = "The quick brown fox 0123456789";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    /// The UI should work, but may look or behave wrongly
+    Warning,
+    /// The UI is likely to be blank, garbled or missing text
+    Error,
+}
+
+/// A problem found by `System::self_test`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// What was checked, e.g. "Texture upload"
+    pub check: &'static str,
+    /// What went wrong and what might put it right
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}: {}", self.severity, self.check, self.message)
+    }
+}
+
+/// Checks the GL context's limits and texture upload, `imgui`'s font atlas and that a frame drawn
+/// with it has something in it, returning any problems found. Needs the GL context current.
+/// Scissoring is checked separately by `check_scissor`, as it draws. Used by Systems.
+pub fn run(imgui: &mut Context) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    drain_errors();
+    check_limits(imgui, &mut diagnostics);
+    check_texture_upload(&mut diagnostics);
+    check_fonts(imgui.fonts(), &mut diagnostics);
+    check_test_frame(imgui, &mut diagnostics);
+    diagnostics
+}
+
+fn check_limits(imgui: &mut Context, diagnostics: &mut Vec<Diagnostic>) {
+    const CHECK: &str = "GL limits";
+    let mut max_texture_size = 0;
+    let mut max_viewport = [0; 2];
+    unsafe {
+        gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size);
+        gl::GetIntegerv(gl::MAX_VIEWPORT_DIMS, max_viewport.as_mut_ptr());
+    }
+
+    let (atlas_width, atlas_height) = atlas_size(imgui.fonts());
+    if atlas_width > max_texture_size || atlas_height > max_texture_size {
+        diagnostics.push(error(
+            CHECK,
+            format!(
+                "The font atlas is {atlas_width}x{atlas_height} but textures can be at most \
+                 {max_texture_size}; use smaller or fewer fonts, or update the graphics driver"
+            ),
+        ));
+    } else if max_texture_size < MIN_TEXTURE_SIZE {
+        diagnostics.push(warning(
+            CHECK,
+            format!(
+                "Textures can be at most {max_texture_size} pixels wide, so larger UI scales \
+                 may lose glyphs; update the graphics driver"
+            ),
+        ));
+    }
+
+    let [width, height] = imgui.io().display_size;
+    #[allow(clippy::cast_precision_loss)]
+    if width > max_viewport[0] as f32 || height > max_viewport[1] as f32 {
+        diagnostics.push(warning(
+            CHECK,
+            format!(
+                "The window is {width}x{height} but can only be drawn up to {}x{}; make it \
+                 smaller",
+                max_viewport[0], max_viewport[1]
+            ),
+        ));
+    }
+}
+
+/// Uploads a tiny texture and reads it back, leaving the pixel store as it was, as X-Plane's
+/// context is shared with other plugins
+fn check_texture_upload(diagnostics: &mut Vec<Diagnostic>) {
+    const CHECK: &str = "Texture upload";
+    const PIXELS: [u8; 16] = [
+        255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 128,
+    ];
+    let mut read_back = [0_u8; 16];
+    let mut texture: GLuint = 0;
+    let mut previous: GLint = 0;
+    let mut unpack_alignment: GLint = 0;
+    let mut unpack_row_length: GLint = 0;
+    let mut pack_alignment: GLint = 0;
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    unsafe {
+        gl::GetIntegerv(gl::TEXTURE_BINDING_2D, &mut previous);
+        gl::GetIntegerv(gl::UNPACK_ALIGNMENT, &mut unpack_alignment);
+        gl::GetIntegerv(gl::UNPACK_ROW_LENGTH, &mut unpack_row_length);
+        gl::GetIntegerv(gl::PACK_ALIGNMENT, &mut pack_alignment);
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as _,
+            2,
+            2,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            PIXELS.as_ptr().cast::<c_void>(),
+        );
+        gl::GetTexImage(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            read_back.as_mut_ptr().cast::<c_void>(),
+        );
+        gl::DeleteTextures(1, &texture);
+        gl::BindTexture(gl::TEXTURE_2D, previous as GLuint);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment);
+        gl::PixelStorei(gl::UNPACK_ROW_LENGTH, unpack_row_length);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, pack_alignment);
+    }
+
+    if let Some(error_code) = take_error() {
+        diagnostics.push(error(
+            CHECK,
+            format!(
+                "Uploading a texture failed with GL error {error_code:#x}; update the graphics \
+                 driver"
+            ),
+        ));
+    } else if read_back != PIXELS {
+        diagnostics.push(error(
+            CHECK,
+            String::from(
+                "A texture read back differently from how it was uploaded, so images and text \
+                 may be garbled; update the graphics driver",
+            ),
+        ));
+    }
+}
+
+/// Clears two pixels of the bound framebuffer at `x`, `y`, the second outside a scissor rect,
+/// and reads them back, returning any problems found. Call it from the System's own drawing,
+/// at pixels of its window which are about to be drawn over, as the framebuffer may be shared.
+/// Used by Systems.
+#[must_use]
+pub fn check_scissor(x: GLint, y: GLint) -> Vec<Diagnostic> {
+    const CHECK: &str = "Scissor";
+    let mut diagnostics = Vec::new();
+    let mut pixels = [0_u8; 8];
+    let mut scissor_box: [GLint; 4] = [0; 4];
+    let mut clear_colour = [0.0; 4];
+    let mut colour_mask: [GLboolean; 4] = [gl::TRUE; 4];
+    drain_errors();
+    unsafe {
+        let scissor_enabled = gl::IsEnabled(gl::SCISSOR_TEST);
+        gl::GetIntegerv(gl::SCISSOR_BOX, scissor_box.as_mut_ptr());
+        gl::GetFloatv(gl::COLOR_CLEAR_VALUE, clear_colour.as_mut_ptr());
+        gl::GetBooleanv(gl::COLOR_WRITEMASK, colour_mask.as_mut_ptr());
+
+        gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::Scissor(x, y, 2, 1);
+        gl::ClearColor(1.0, 0.0, 0.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::Scissor(x, y, 1, 1);
+        gl::ClearColor(0.0, 1.0, 0.0, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::ReadPixels(
+            x,
+            y,
+            2,
+            1,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr().cast::<c_void>(),
+        );
+
+        let [red, green, blue, alpha] = clear_colour;
+        gl::ClearColor(red, green, blue, alpha);
+        let [left, bottom, width, height] = scissor_box;
+        gl::Scissor(left, bottom, width, height);
+        set_enabled(gl::SCISSOR_TEST, scissor_enabled);
+        let [red, green, blue, alpha] = colour_mask;
+        gl::ColorMask(red, green, blue, alpha);
+    }
+
+    let inside_green = pixels[1] > 128 && pixels[0] < 128;
+    let outside_red = pixels[4] > 128 && pixels[5] < 128;
+    if let Some(error_code) = take_error() {
+        diagnostics.push(warning(
+            CHECK,
+            format!("Testing the scissor rect failed with GL error {error_code:#x}"),
+        ));
+    } else if !inside_green || !outside_red {
+        diagnostics.push(error(
+            CHECK,
+            String::from(
+                "Drawing isn't clipped to the scissor rect, so windows may draw over each other; \
+                 update the graphics driver",
+            ),
+        ));
+    }
+    diagnostics
+}
+
+fn check_fonts(atlas: &mut FontAtlas, diagnostics: &mut Vec<Diagnostic>) {
+    const CHECK: &str = "Font atlas";
+    let (width, height) = atlas_size(atlas);
+    if width == 0 || height == 0 {
+        diagnostics.push(error(
+            CHECK,
+            String::from("The font atlas hasn't been built, so no text will show"),
+        ));
+        return;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let texture = atlas.tex_id.id() as u32;
+    if texture == 0 || texture_lost(texture) {
+        diagnostics.push(error(
+            CHECK,
+            String::from(
+                "The font texture is missing, so text will show as blocks; restart the app or \
+                 plugin",
+            ),
+        ));
+    }
+
+    let fonts = atlas.fonts();
+    if fonts.is_empty() {
+        diagnostics.push(error(CHECK, String::from("No fonts are loaded")));
+    }
+    for font in fonts.into_iter().filter_map(|id| atlas.get_font(id)) {
+        let font: *const imgui::Font = font;
+        let font: *mut sys::ImFont = font.cast_mut().cast();
+        let (glyphs, fallback, question_mark) = unsafe {
+            (
+                (*font).Glyphs.Size,
+                (*font).FallbackGlyph,
+                sys::ImFont_FindGlyphNoFallback(font, sys::ImWchar::from(b'?')),
+            )
+        };
+        if glyphs == 0 || fallback.is_null() || question_mark.is_null() {
+            diagnostics.push(error(
+                CHECK,
+                format!(
+                    "A font has {glyphs} glyphs and is missing basic ones, so its text may not \
+                     show; check the font file"
+                ),
+            ));
+        }
+    }
+}
+
+/// Builds a frame with a window of text in a scratch context sharing `imgui`'s fonts and style,
+/// which should give imgui something to draw. `imgui` itself is left alone, so its queued input
+/// and frame count are untouched.
+fn check_test_frame(imgui: &mut Context, diagnostics: &mut Vec<Diagnostic>) {
+    const CHECK: &str = "Test frame";
+    const SIZE: sys::ImVec2 = sys::ImVec2 { x: 320.0, y: 64.0 };
+    let font_global_scale = imgui.io().font_global_scale;
+    let atlas: *mut FontAtlas = imgui.fonts();
+    let atlas: *mut sys::ImFontAtlas = atlas.cast();
+    #[allow(clippy::cast_possible_wrap)]
+    let (vertices, indices) = unsafe {
+        let previous = sys::igGetCurrentContext();
+        let style = *sys::igGetStyle();
+        let font = (*sys::igGetIO()).FontDefault;
+
+        let scratch = sys::igCreateContext(atlas);
+        sys::igSetCurrentContext(scratch);
+        let io = sys::igGetIO();
+        (*io).IniFilename = ptr::null();
+        (*io).LogFilename = ptr::null();
+        (*io).DisplaySize = SIZE;
+        (*io).DeltaTime = 1.0 / 60.0;
+        (*io).FontGlobalScale = font_global_scale;
+        (*io).FontDefault = font;
+        *sys::igGetStyle() = style;
+
+        sys::igNewFrame();
+        let origin = sys::ImVec2 { x: 0.0, y: 0.0 };
+        sys::igSetNextWindowPos(origin, sys::ImGuiCond_Always as _, origin);
+        sys::igSetNextWindowSize(SIZE, sys::ImGuiCond_Always as _);
+        let flags = sys::ImGuiWindowFlags_NoDecoration
+            | sys::ImGuiWindowFlags_NoInputs
+            | sys::ImGuiWindowFlags_NoSavedSettings;
+        sys::igBegin(c"##self_test".as_ptr(), ptr::null_mut(), flags as _);
+        let text = SAMPLE_TEXT.as_bytes().as_ptr_range();
+        sys::igTextUnformatted(text.start.cast(), text.end.cast());
+        sys::igEnd();
+        sys::igRender();
+        let draw_data = sys::igGetDrawData();
+        let counts = ((*draw_data).TotalVtxCount, (*draw_data).TotalIdxCount);
+
+        // restored first, so destroying the scratch context leaves it current
+        sys::igSetCurrentContext(previous);
+        sys::igDestroyContext(scratch);
+        counts
+    };
+
+    if vertices == 0 || indices == 0 {
+        diagnostics.push(error(
+            CHECK,
+            String::from(
+                "A frame with text in it had nothing to draw; check the fonts and UI scale",
+            ),
+        ));
+    }
+}
+
+fn atlas_size(atlas: &mut FontAtlas) -> (GLint, GLint) {
+    let atlas: *mut FontAtlas = atlas;
+    let atlas: *mut sys::ImFontAtlas = atlas.cast();
+    unsafe { ((*atlas).TexWidth, (*atlas).TexHeight) }
+}
+
+fn set_enabled(capability: GLenum, enabled: GLboolean) {
+    unsafe {
+        if enabled == gl::TRUE {
+            gl::Enable(capability);
+        } else {
+            gl::Disable(capability);
+        }
+    }
+}
+
+/// Clears errors left by earlier GL calls, so only the checks' own are seen
+fn drain_errors() {
+    // bounded, as without a context some drivers report an error on every call
+    for _ in 0..16 {
+        if take_error().is_none() {
+            break;
+        }
+    }
+}
+
+fn take_error() -> Option<GLenum> {
+    let error = unsafe { gl::GetError() };
+    (error != gl::NO_ERROR).then_some(error)
+}
+
+fn warning(check: &'static str, message: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        check,
+        message,
+    }
+}
+
+fn error(check: &'static str, message: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Error,
+        check,
+        message,
+    }
+}
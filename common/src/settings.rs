@@ -0,0 +1,240 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+/// Upgrades a namespace's values from one schema version to the next
+pub type Migration = fn(&mut Map<String, Value>);
+
+/// A JSON-backed key/value store for user preferences
+///
+/// Saving only writes the keys this store has changed, re-reading the file for the rest, so
+/// panels and plugins sharing a file don't undo each other's changes. Give each its own
+/// `namespace` to keep their keys apart.
+#[derive(Debug, Default)]
+pub struct Settings {
+    path: Option<PathBuf>,
+    values: Map<String, Value>,
+    /// Keys set or removed since loading
+    changed: BTreeSet<String>,
+}
+
+/// The settings under one key of a `Settings`, e.g. for one panel, with a schema version for
+/// migrating them
+pub struct Namespace<'a> {
+    settings: &'a mut Settings,
+    name: String,
+}
+
+impl Settings {
+    /// Creates an in-memory store which is never written to disk.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads settings from `path`. A missing file yields an empty store which will be
+    /// created on the first `save`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the file exists but could not be read or parsed.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, SettingsError> {
+        let path = path.into();
+        let values = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Settings {
+            path: Some(path),
+            values,
+            changed: BTreeSet::new(),
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the value stored under `key`, or `None` if it is missing or has the wrong shape.
+    #[must_use]
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.values.get(key)?;
+        serde_json::from_value(value.clone())
+            .map_err(|e| warn!(key, error = %e, "Ignoring invalid setting"))
+            .ok()
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if `value` could not be serialized.
+    pub fn set<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), SettingsError> {
+        self.values
+            .insert(String::from(key), serde_json::to_value(value)?);
+        self.changed.insert(String::from(key));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+        self.changed.insert(String::from(key));
+    }
+
+    /// The settings stored under `name`, kept apart from other namespaces and top-level keys
+    pub fn namespace(&mut self, name: &str) -> Namespace<'_> {
+        Namespace {
+            settings: self,
+            name: String::from(name),
+        }
+    }
+
+    /// Writes the settings back to the file they were loaded from. In-memory stores are
+    /// left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the file could not be written.
+    pub fn save(&self) -> Result<(), SettingsError> {
+        if let Some(path) = &self.path {
+            let mut values = match fs::read_to_string(path) {
+                Ok(contents) => serde_json::from_str(&contents)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+                Err(e) => return Err(e.into()),
+            };
+            for key in &self.changed {
+                match self.values.get(key) {
+                    Some(value) => values.insert(key.clone(), value.clone()),
+                    None => values.remove(key),
+                };
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(&values)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl Namespace<'_> {
+    const VERSION_KEY: &'static str = "schema_version";
+
+    /// Returns the value stored under `key`, or `None` if it is missing or has the wrong shape.
+    #[must_use]
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.values()?.get(key)?;
+        serde_json::from_value(value.clone())
+            .map_err(|e| warn!(namespace = self.name, key, error = %e, "Ignoring invalid setting"))
+            .ok()
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if `value` could not be serialized.
+    pub fn set<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), SettingsError> {
+        let value = serde_json::to_value(value)?;
+        self.values_mut().insert(String::from(key), value);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.values_mut().remove(key);
+    }
+
+    /// The schema version the values were last migrated to, 0 if never
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.values()
+            .and_then(|values| values.get(Self::VERSION_KEY))
+            .and_then(Value::as_u64)
+            .unwrap_or_default()
+    }
+
+    /// Runs the migrations after the stored version in order, then stores the version as the
+    /// number of migrations. Append new migrations to the list and never reorder it. Values
+    /// from a newer schema than the list knows are left alone.
+    pub fn migrate(&mut self, migrations: &[Migration]) {
+        let version = usize::try_from(self.version()).unwrap_or(usize::MAX);
+        if version >= migrations.len() {
+            if version > migrations.len() {
+                warn!(
+                    namespace = self.name,
+                    version, "Settings are from a newer version"
+                );
+            }
+            return;
+        }
+        let values = self.values_mut();
+        for migration in &migrations[version..] {
+            migration(values);
+        }
+        values.insert(String::from(Self::VERSION_KEY), migrations.len().into());
+    }
+
+    fn values(&self) -> Option<&Map<String, Value>> {
+        self.settings.values.get(&self.name)?.as_object()
+    }
+
+    fn values_mut(&mut self) -> &mut Map<String, Value> {
+        self.settings.changed.insert(self.name.clone());
+        let value = self
+            .settings
+            .values
+            .entry(self.name.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !value.is_object() {
+            *value = Value::Object(Map::new());
+        }
+        value.as_object_mut().expect("namespace is an object")
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "Unable to access settings: {e}"),
+            SettingsError::Json(e) => write!(f, "Invalid settings: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(value: io::Error) -> Self {
+        SettingsError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for SettingsError {
+    fn from(value: serde_json::Error) -> Self {
+        SettingsError::Json(value)
+    }
+}
@@ -0,0 +1,144 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::time::Instant;
+
+/// Smooths a value sampled once per sim frame so that gauges drawn at display rate move
+/// steadily when the sim's frame rate is low. Between samples the value is extrapolated at its
+/// latest rate of change, for up to `max_extrapolation`, and the output eases towards it over
+/// `time_constant`.
+#[derive(Clone, Debug)]
+pub struct Smoother {
+    time_constant: f32,
+    max_extrapolation: f32,
+    period: Option<f32>,
+    latest: Option<Sample>,
+    /// Units per second of sim time, from the latest two samples
+    rate: f32,
+    output: Option<(Instant, f32)>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    sim_time: f32,
+    received: Instant,
+    value: f32,
+}
+
+impl Smoother {
+    /// Eases towards the extrapolated value with `time_constant` seconds, or follows it exactly
+    /// if zero
+    #[must_use]
+    pub fn new(time_constant: f32) -> Self {
+        Smoother {
+            time_constant: time_constant.max(0.0),
+            max_extrapolation: 0.25,
+            period: None,
+            latest: None,
+            rate: 0.0,
+            output: None,
+        }
+    }
+
+    /// How many seconds past the latest sample to extrapolate, so a paused sim or a long frame
+    /// doesn't send a needle off the scale. Defaults to a quarter of a second.
+    #[must_use]
+    pub fn max_extrapolation(mut self, seconds: f32) -> Self {
+        self.max_extrapolation = seconds.max(0.0);
+        self
+    }
+
+    /// Treats the value as wrapping every `period`, e.g. 360 for a heading, so it moves the
+    /// short way round. Outputs are in `0..period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` isn't positive and finite, as nothing could be wrapped by it.
+    #[must_use]
+    pub fn wrapping(mut self, period: f32) -> Self {
+        assert!(
+            period.is_finite() && period > 0.0,
+            "wrapping period must be positive, not {period}"
+        );
+        self.period = Some(period);
+        self
+    }
+
+    /// Records `value` as read at `sim_time` seconds. Samples from a sim frame already seen,
+    /// i.e. with the same time, are ignored. If the clock went backwards, e.g. when a situation
+    /// was loaded, the rate of change is measured afresh.
+    pub fn sample(&mut self, sim_time: f32, value: f32) {
+        let received = Instant::now();
+        match self.latest {
+            Some(latest) if sim_time > latest.sim_time => {
+                self.rate = self.difference(latest.value, value) / (sim_time - latest.sim_time);
+            }
+            Some(latest) if sim_time < latest.sim_time => self.rate = 0.0,
+            Some(_) => return,
+            None => self.rate = 0.0,
+        }
+        self.latest = Some(Sample {
+            sim_time,
+            received,
+            value,
+        });
+    }
+
+    /// The smoothed value now, or zero before the first sample
+    pub fn value(&mut self) -> f32 {
+        let Some(latest) = self.latest else {
+            return 0.0;
+        };
+        let now = Instant::now();
+        let elapsed = now
+            .duration_since(latest.received)
+            .as_secs_f32()
+            .min(self.max_extrapolation);
+        let target = latest.value + self.rate * elapsed;
+        let value = match self.output {
+            Some((updated, output)) if self.time_constant > 0.0 => {
+                let dt = now.duration_since(updated).as_secs_f32();
+                let blend = 1.0 - (-dt / self.time_constant).exp();
+                output + self.difference(output, target) * blend
+            }
+            _ => target,
+        };
+        let value = self.wrap(value);
+        self.output = Some((now, value));
+        value
+    }
+
+    /// Forgets the samples, e.g. after the user's aircraft is reloaded, so the next one is
+    /// shown at once rather than eased towards
+    pub fn reset(&mut self) {
+        self.latest = None;
+        self.rate = 0.0;
+        self.output = None;
+    }
+
+    /// `to - from`, the short way round if wrapping
+    fn difference(&self, from: f32, to: f32) -> f32 {
+        let difference = to - from;
+        match self.period {
+            Some(period) => {
+                let difference = difference.rem_euclid(period);
+                if difference > period / 2.0 {
+                    difference - period
+                } else {
+                    difference
+                }
+            }
+            None => difference,
+        }
+    }
+
+    fn wrap(&self, value: f32) -> f32 {
+        match self.period {
+            Some(period) => value.rem_euclid(period),
+            None => value,
+        }
+    }
+}
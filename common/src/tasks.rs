@@ -0,0 +1,222 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use imgui::{Condition, ProgressBar, Ui, WindowFlags};
+
+const TASK_LIST_POPUP: &str = "##task_list";
+
+/// Shared flag a job checks to find out it should stop early
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Registry of running background jobs. Cloning gives another handle to the same registry.
+///
+/// The `System` draws a progress bar along the bottom of the window while any task is running;
+/// clicking it lists every task with a button to cancel it.
+#[derive(Clone, Default)]
+pub struct Tasks {
+    tasks: Arc<Mutex<Vec<Task>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+struct Task {
+    id: u64,
+    name: String,
+    status: String,
+    progress: Option<f32>,
+    cancellation: CancellationToken,
+}
+
+/// Held by a job to report progress. The task is removed from the registry when this is dropped.
+pub struct TaskHandle {
+    id: u64,
+    tasks: Tasks,
+    cancellation: CancellationToken,
+}
+
+impl Tasks {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a task, which runs until the returned handle is dropped.
+    #[must_use]
+    pub fn start(&self, name: &str) -> TaskHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancellation = CancellationToken::new();
+        self.lock().push(Task {
+            id,
+            name: String::from(name),
+            status: String::new(),
+            progress: None,
+            cancellation: cancellation.clone(),
+        });
+        TaskHandle {
+            id,
+            tasks: self.clone(),
+            cancellation,
+        }
+    }
+
+    /// Runs `job` as a task on a new thread.
+    pub fn spawn<F>(&self, name: &str, job: F) -> JoinHandle<()>
+    where
+        F: FnOnce(&TaskHandle) + Send + 'static,
+    {
+        let handle = self.start(name);
+        thread::spawn(move || job(&handle))
+    }
+
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        !self.lock().is_empty()
+    }
+
+    /// Label and progress of the first running task
+    pub(crate) fn current(&self) -> Option<(String, Option<f32>)> {
+        self.lock()
+            .first()
+            .map(|task| (task.label(), task.progress))
+    }
+
+    /// Draws the progress bar along the bottom of the display, if any tasks are running.
+    pub fn draw(&self, ui: &Ui) {
+        let tasks = self.lock();
+        let Some(first) = tasks.first() else {
+            return;
+        };
+
+        let [width, height] = ui.io().display_size;
+        let bar_height = ui.frame_height_with_spacing();
+        let summary = if tasks.len() > 1 {
+            format!("{} (+{} more)", first.label(), tasks.len() - 1)
+        } else {
+            first.label()
+        };
+        let fraction = first.fraction(ui);
+        let mut cancelled = None;
+
+        ui.window("##tasks")
+            .position([0.0, height - bar_height], Condition::Always)
+            .size([width, bar_height], Condition::Always)
+            .flags(
+                WindowFlags::NO_DECORATION
+                    | WindowFlags::NO_MOVE
+                    | WindowFlags::NO_SAVED_SETTINGS
+                    | WindowFlags::NO_FOCUS_ON_APPEARING,
+            )
+            .build(|| {
+                ProgressBar::new(fraction)
+                    .overlay_text(&summary)
+                    .size([-f32::MIN_POSITIVE, 0.0])
+                    .build(ui);
+                if ui.is_item_clicked() {
+                    ui.open_popup(TASK_LIST_POPUP);
+                }
+                ui.popup(TASK_LIST_POPUP, || {
+                    for task in tasks.iter() {
+                        let _id = ui.push_id_usize(usize::try_from(task.id).unwrap_or_default());
+                        ui.text(task.label());
+                        ProgressBar::new(task.fraction(ui))
+                            .size([200.0, 0.0])
+                            .build(ui);
+                        ui.same_line();
+                        if ui.small_button("Cancel") {
+                            cancelled = Some(task.cancellation.clone());
+                        }
+                    }
+                });
+            });
+
+        if let Some(cancellation) = cancelled {
+            cancellation.cancel();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Task>> {
+        // a job panicking mid-update leaves nothing inconsistent, so ignore poisoning
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Task {
+    fn label(&self) -> String {
+        if self.status.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}: {}", self.name, self.status)
+        }
+    }
+
+    /// Tasks without progress animate to show they are still alive
+    fn fraction(&self, ui: &Ui) -> f32 {
+        #[allow(clippy::cast_possible_truncation)]
+        self.progress.unwrap_or_else(|| ui.time().fract() as f32)
+    }
+}
+
+impl TaskHandle {
+    /// Sets progress from 0.0 to 1.0, or `None` if unknown
+    pub fn set_progress(&self, progress: Option<f32>) {
+        self.update(|task| task.progress = progress.map(|p| p.clamp(0.0, 1.0)));
+    }
+
+    pub fn set_status(&self, status: &str) {
+        self.update(|task| task.status = String::from(status));
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    #[must_use]
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    pub(crate) fn label_and_progress(&self) -> (String, Option<f32>) {
+        self.tasks
+            .lock()
+            .iter()
+            .find(|task| task.id == self.id)
+            .map(|task| (task.label(), task.progress))
+            .unwrap_or_default()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Task)) {
+        if let Some(task) = self.tasks.lock().iter_mut().find(|task| task.id == self.id) {
+            f(task);
+        }
+    }
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        self.tasks.lock().retain(|task| task.id != self.id);
+    }
+}
@@ -0,0 +1,461 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::{fs, io};
+
+use imgui::{Style, StyleColor};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{Settings, SettingsError};
+
+/// The smallest UI scale `System::set_ui_scale` accepts
+pub const MIN_UI_SCALE: f32 = 0.25;
+/// The largest UI scale `System::set_ui_scale` accepts
+pub const MAX_UI_SCALE: f32 = 4.0;
+
+/// `scale` clamped between `MIN_UI_SCALE` and `MAX_UI_SCALE`, or `None` if it's NaN. Used by
+/// Systems, as fonts can't be built at zero, negative or enormous sizes.
+#[must_use]
+pub fn clamp_ui_scale(scale: f32) -> Option<f32> {
+    (!scale.is_nan()).then(|| scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE))
+}
+
+/// The colours and sizes of imgui's widgets, applied with `System::set_theme`
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    Classic,
+    /// White text and borders on black, for bright cockpits and poor eyesight
+    HighContrast,
+    Custom(CustomTheme),
+}
+
+/// A theme of the app's or user's own, e.g. tweaked in imgui's style editor and captured with
+/// `from_style`, or written by hand in TOML or JSON and read with `load`. Colours not given are
+/// taken from the dark theme.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustomTheme {
+    pub name: String,
+    /// RGBA colours keyed by imgui's name for them, e.g. `"WindowBg"`. Unknown names are ignored.
+    pub colors: BTreeMap<String, [f32; 4]>,
+    pub sizes: ThemeSizes,
+    pub vars: ThemeVars,
+}
+
+/// The rounding and border sizes a theme sets, in pixels. Spacing and padding are left alone
+/// as layouts depend on them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeSizes {
+    pub window_rounding: f32,
+    pub child_rounding: f32,
+    pub frame_rounding: f32,
+    pub popup_rounding: f32,
+    pub scrollbar_rounding: f32,
+    pub grab_rounding: f32,
+    pub tab_rounding: f32,
+    pub window_border_size: f32,
+    pub frame_border_size: f32,
+    pub popup_border_size: f32,
+}
+
+/// imgui's other style variables, left as they are unless given. Sizes are in pixels and
+/// alignments from 0 (left or top) to 1 (right or bottom).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeVars {
+    pub alpha: Option<f32>,
+    pub disabled_alpha: Option<f32>,
+    pub window_padding: Option<[f32; 2]>,
+    pub window_min_size: Option<[f32; 2]>,
+    pub window_title_align: Option<[f32; 2]>,
+    pub child_border_size: Option<f32>,
+    pub frame_padding: Option<[f32; 2]>,
+    pub item_spacing: Option<[f32; 2]>,
+    pub item_inner_spacing: Option<[f32; 2]>,
+    pub cell_padding: Option<[f32; 2]>,
+    pub indent_spacing: Option<f32>,
+    pub scrollbar_size: Option<f32>,
+    pub grab_min_size: Option<f32>,
+    pub tab_border_size: Option<f32>,
+    pub button_text_align: Option<[f32; 2]>,
+    pub selectable_text_align: Option<[f32; 2]>,
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    TomlSerialize(toml::ser::Error),
+    /// The file's extension was neither `toml` nor `json`
+    UnknownFormat,
+}
+
+impl Theme {
+    const SETTINGS_KEY: &'static str = "theme";
+
+    /// The themes that come with imgui-support, for offering a choice
+    #[must_use]
+    pub fn built_in() -> [Theme; 4] {
+        [
+            Theme::Dark,
+            Theme::Light,
+            Theme::Classic,
+            Theme::HighContrast,
+        ]
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::Classic => "Classic",
+            Theme::HighContrast => "High contrast",
+            Theme::Custom(custom) => &custom.name,
+        }
+    }
+
+    /// Sets `style`'s colours and sizes. Used by Systems.
+    pub fn apply(&self, style: &mut Style) {
+        match self {
+            Theme::Dark => {
+                style.use_dark_colors();
+                ThemeSizes::default().apply(style);
+            }
+            Theme::Light => {
+                style.use_light_colors();
+                ThemeSizes::default().apply(style);
+            }
+            Theme::Classic => {
+                style.use_classic_colors();
+                ThemeSizes::default().apply(style);
+            }
+            Theme::HighContrast => {
+                style.use_dark_colors();
+                for (color, value) in HIGH_CONTRAST {
+                    style[color] = value;
+                }
+                ThemeSizes {
+                    window_border_size: 1.0,
+                    frame_border_size: 1.0,
+                    popup_border_size: 1.0,
+                    ..ThemeSizes::default()
+                }
+                .apply(style);
+            }
+            Theme::Custom(custom) => custom.apply(style),
+        }
+    }
+
+    /// Resets `style` to `base`, then applies the theme and scales its sizes by `scale`. Used by
+    /// Systems, which keep `base` from before any theme or scale was applied.
+    pub fn apply_scaled(&self, style: &mut Style, base: &Style, scale: f32) {
+        *style = *base;
+        self.apply(style);
+        style.scale_all_sizes(scale);
+    }
+
+    /// Reads the theme from `settings`, falling back to the default if none is stored.
+    #[must_use]
+    pub fn load(settings: &Settings) -> Self {
+        settings.get(Self::SETTINGS_KEY).unwrap_or_default()
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the theme could not be serialized.
+    pub fn store(&self, settings: &mut Settings) -> Result<(), SettingsError> {
+        settings.set(Self::SETTINGS_KEY, self)
+    }
+}
+
+impl CustomTheme {
+    /// Captures all of `style`'s colours and sizes, e.g. from `Ui::clone_style` after editing
+    #[must_use]
+    pub fn from_style(name: &str, style: &Style) -> Self {
+        CustomTheme {
+            name: String::from(name),
+            colors: StyleColor::VARIANTS
+                .iter()
+                .map(|color| (String::from(color.name()), style[*color]))
+                .collect(),
+            sizes: ThemeSizes::from_style(style),
+            vars: ThemeVars::from_style(style),
+        }
+    }
+
+    /// Reads a theme from `path`, in TOML or JSON according to its extension
+    ///
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if the file could not be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, ThemeError> {
+        let contents = fs::read_to_string(path)?;
+        match format(path)? {
+            Format::Toml => Self::from_toml(&contents),
+            Format::Json => Self::from_json(&contents),
+        }
+    }
+
+    /// Writes the theme to `path`, in TOML or JSON according to its extension
+    ///
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if the theme could not be serialized or the file written.
+    pub fn save(&self, path: &Path) -> Result<(), ThemeError> {
+        let contents = match format(path)? {
+            Format::Toml => self.to_toml()?,
+            Format::Json => self.to_json()?,
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if `toml` is not a valid theme.
+    pub fn from_toml(toml: &str) -> Result<Self, ThemeError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if the theme could not be serialized.
+    pub fn to_toml(&self) -> Result<String, ThemeError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if `json` is not a valid theme.
+    pub fn from_json(json: &str) -> Result<Self, ThemeError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if the theme could not be serialized.
+    pub fn to_json(&self) -> Result<String, ThemeError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    fn apply(&self, style: &mut Style) {
+        style.use_dark_colors();
+        for color in StyleColor::VARIANTS {
+            if let Some(value) = self.colors.get(color.name()) {
+                style[color] = *value;
+            }
+        }
+        self.sizes.apply(style);
+        self.vars.apply(style);
+    }
+}
+
+enum Format {
+    Toml,
+    Json,
+}
+
+fn format(path: &Path) -> Result<Format, ThemeError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("toml") => Ok(Format::Toml),
+        Some(extension) if extension.eq_ignore_ascii_case("json") => Ok(Format::Json),
+        _ => Err(ThemeError::UnknownFormat),
+    }
+}
+
+impl Default for CustomTheme {
+    fn default() -> Self {
+        CustomTheme {
+            name: String::from("Custom"),
+            colors: BTreeMap::new(),
+            sizes: ThemeSizes::default(),
+            vars: ThemeVars::default(),
+        }
+    }
+}
+
+impl ThemeSizes {
+    #[must_use]
+    pub fn from_style(style: &Style) -> Self {
+        ThemeSizes {
+            window_rounding: style.window_rounding,
+            child_rounding: style.child_rounding,
+            frame_rounding: style.frame_rounding,
+            popup_rounding: style.popup_rounding,
+            scrollbar_rounding: style.scrollbar_rounding,
+            grab_rounding: style.grab_rounding,
+            tab_rounding: style.tab_rounding,
+            window_border_size: style.window_border_size,
+            frame_border_size: style.frame_border_size,
+            popup_border_size: style.popup_border_size,
+        }
+    }
+
+    fn apply(&self, style: &mut Style) {
+        style.window_rounding = self.window_rounding;
+        style.child_rounding = self.child_rounding;
+        style.frame_rounding = self.frame_rounding;
+        style.popup_rounding = self.popup_rounding;
+        style.scrollbar_rounding = self.scrollbar_rounding;
+        style.grab_rounding = self.grab_rounding;
+        style.tab_rounding = self.tab_rounding;
+        style.window_border_size = self.window_border_size;
+        style.frame_border_size = self.frame_border_size;
+        style.popup_border_size = self.popup_border_size;
+    }
+}
+
+impl ThemeVars {
+    #[must_use]
+    pub fn from_style(style: &Style) -> Self {
+        ThemeVars {
+            alpha: Some(style.alpha),
+            disabled_alpha: Some(style.disabled_alpha),
+            window_padding: Some(style.window_padding),
+            window_min_size: Some(style.window_min_size),
+            window_title_align: Some(style.window_title_align),
+            child_border_size: Some(style.child_border_size),
+            frame_padding: Some(style.frame_padding),
+            item_spacing: Some(style.item_spacing),
+            item_inner_spacing: Some(style.item_inner_spacing),
+            cell_padding: Some(style.cell_padding),
+            indent_spacing: Some(style.indent_spacing),
+            scrollbar_size: Some(style.scrollbar_size),
+            grab_min_size: Some(style.grab_min_size),
+            tab_border_size: Some(style.tab_border_size),
+            button_text_align: Some(style.button_text_align),
+            selectable_text_align: Some(style.selectable_text_align),
+        }
+    }
+
+    fn apply(&self, style: &mut Style) {
+        fn set<T: Copy>(field: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        set(&mut style.alpha, self.alpha);
+        set(&mut style.disabled_alpha, self.disabled_alpha);
+        set(&mut style.window_padding, self.window_padding);
+        set(&mut style.window_min_size, self.window_min_size);
+        set(&mut style.window_title_align, self.window_title_align);
+        set(&mut style.child_border_size, self.child_border_size);
+        set(&mut style.frame_padding, self.frame_padding);
+        set(&mut style.item_spacing, self.item_spacing);
+        set(&mut style.item_inner_spacing, self.item_inner_spacing);
+        set(&mut style.cell_padding, self.cell_padding);
+        set(&mut style.indent_spacing, self.indent_spacing);
+        set(&mut style.scrollbar_size, self.scrollbar_size);
+        set(&mut style.grab_min_size, self.grab_min_size);
+        set(&mut style.tab_border_size, self.tab_border_size);
+        set(&mut style.button_text_align, self.button_text_align);
+        set(&mut style.selectable_text_align, self.selectable_text_align);
+    }
+}
+
+impl Display for ThemeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeError::Io(e) => write!(f, "Unable to access theme: {e}"),
+            ThemeError::Json(e) => write!(f, "Invalid theme: {e}"),
+            ThemeError::Toml(e) => write!(f, "Invalid theme: {e}"),
+            ThemeError::TomlSerialize(e) => write!(f, "Unable to write theme: {e}"),
+            ThemeError::UnknownFormat => write!(f, "Themes must be .toml or .json files"),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl From<io::Error> for ThemeError {
+    fn from(value: io::Error) -> Self {
+        ThemeError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for ThemeError {
+    fn from(value: serde_json::Error) -> Self {
+        ThemeError::Json(value)
+    }
+}
+
+impl From<toml::de::Error> for ThemeError {
+    fn from(value: toml::de::Error) -> Self {
+        ThemeError::Toml(value)
+    }
+}
+
+impl From<toml::ser::Error> for ThemeError {
+    fn from(value: toml::ser::Error) -> Self {
+        ThemeError::TomlSerialize(value)
+    }
+}
+
+/// imgui's sizes, with slightly rounded windows and frames
+impl Default for ThemeSizes {
+    fn default() -> Self {
+        ThemeSizes {
+            window_rounding: 3.0,
+            child_rounding: 0.0,
+            frame_rounding: 2.0,
+            popup_rounding: 0.0,
+            scrollbar_rounding: 9.0,
+            grab_rounding: 0.0,
+            tab_rounding: 4.0,
+            window_border_size: 1.0,
+            frame_border_size: 0.0,
+            popup_border_size: 1.0,
+        }
+    }
+}
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const YELLOW: [f32; 4] = [1.0, 0.9, 0.0, 1.0];
+const GREY: [f32; 4] = [0.25, 0.25, 0.25, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.3, 0.8, 1.0];
+const BRIGHT_BLUE: [f32; 4] = [0.0, 0.5, 1.0, 1.0];
+
+/// Changes from the dark theme's colours for `Theme::HighContrast`
+const HIGH_CONTRAST: [(StyleColor, [f32; 4]); 27] = [
+    (StyleColor::Text, WHITE),
+    (StyleColor::TextDisabled, [0.7, 0.7, 0.7, 1.0]),
+    (StyleColor::WindowBg, BLACK),
+    (StyleColor::ChildBg, BLACK),
+    (StyleColor::PopupBg, BLACK),
+    (StyleColor::Border, WHITE),
+    (StyleColor::FrameBg, BLACK),
+    (StyleColor::FrameBgHovered, GREY),
+    (StyleColor::FrameBgActive, BLUE),
+    (StyleColor::TitleBg, BLACK),
+    (StyleColor::TitleBgActive, BLUE),
+    (StyleColor::MenuBarBg, BLACK),
+    (StyleColor::CheckMark, YELLOW),
+    (StyleColor::SliderGrab, WHITE),
+    (StyleColor::SliderGrabActive, YELLOW),
+    (StyleColor::Button, GREY),
+    (StyleColor::ButtonHovered, BLUE),
+    (StyleColor::ButtonActive, BRIGHT_BLUE),
+    (StyleColor::Header, GREY),
+    (StyleColor::HeaderHovered, BLUE),
+    (StyleColor::HeaderActive, BRIGHT_BLUE),
+    (StyleColor::Separator, WHITE),
+    (StyleColor::Tab, GREY),
+    (StyleColor::TabHovered, BLUE),
+    (StyleColor::TabActive, BLUE),
+    (StyleColor::TextSelectedBg, BLUE),
+    (StyleColor::NavHighlight, YELLOW),
+];
@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{Settings, SettingsError};
+
+const METRES_PER_FOOT: f32 = 0.3048;
+const KPH_PER_KNOT: f32 = 1.852;
+const HPA_PER_INHG: f32 = 33.863_89;
+const KG_PER_LB: f32 = 0.453_592_37;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Dimension {
+    Length,
+    Speed,
+    Pressure,
+    Mass,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Unit {
+    Feet,
+    Metres,
+    Knots,
+    KilometresPerHour,
+    InchesOfMercury,
+    Hectopascals,
+    Kilograms,
+    Pounds,
+}
+
+impl Unit {
+    #[must_use]
+    pub fn dimension(self) -> Dimension {
+        match self {
+            Unit::Feet | Unit::Metres => Dimension::Length,
+            Unit::Knots | Unit::KilometresPerHour => Dimension::Speed,
+            Unit::InchesOfMercury | Unit::Hectopascals => Dimension::Pressure,
+            Unit::Kilograms | Unit::Pounds => Dimension::Mass,
+        }
+    }
+
+    #[must_use]
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Feet => "ft",
+            Unit::Metres => "m",
+            Unit::Knots => "kt",
+            Unit::KilometresPerHour => "km/h",
+            Unit::InchesOfMercury => "inHg",
+            Unit::Hectopascals => "hPa",
+            Unit::Kilograms => "kg",
+            Unit::Pounds => "lb",
+        }
+    }
+
+    /// Number of decimal places conventionally shown for this unit
+    #[must_use]
+    pub fn precision(self) -> usize {
+        match self {
+            Unit::InchesOfMercury => 2,
+            _ => 0,
+        }
+    }
+
+    /// Units sharing a dimension with this one, in display order
+    #[must_use]
+    pub fn alternatives(self) -> &'static [Unit] {
+        match self.dimension() {
+            Dimension::Length => &[Unit::Feet, Unit::Metres],
+            Dimension::Speed => &[Unit::Knots, Unit::KilometresPerHour],
+            Dimension::Pressure => &[Unit::InchesOfMercury, Unit::Hectopascals],
+            Dimension::Mass => &[Unit::Kilograms, Unit::Pounds],
+        }
+    }
+
+    /// Converts `value` from this unit into `to`, or `None` if `to` is of another dimension.
+    #[must_use]
+    pub fn convert(self, value: f32, to: Unit) -> Option<f32> {
+        (self.dimension() == to.dimension()).then(|| to.scale_from_base(self.to_base(value)))
+    }
+
+    fn to_base(self, value: f32) -> f32 {
+        match self {
+            Unit::Feet => value * METRES_PER_FOOT,
+            Unit::Knots => value * KPH_PER_KNOT,
+            Unit::InchesOfMercury => value * HPA_PER_INHG,
+            Unit::Pounds => value * KG_PER_LB,
+            Unit::Metres | Unit::KilometresPerHour | Unit::Hectopascals | Unit::Kilograms => value,
+        }
+    }
+
+    fn scale_from_base(self, value: f32) -> f32 {
+        match self {
+            Unit::Feet => value / METRES_PER_FOOT,
+            Unit::Knots => value / KPH_PER_KNOT,
+            Unit::InchesOfMercury => value / HPA_PER_INHG,
+            Unit::Pounds => value / KG_PER_LB,
+            Unit::Metres | Unit::KilometresPerHour | Unit::Hectopascals | Unit::Kilograms => value,
+        }
+    }
+
+    /// Formats `value`, given in this unit, with the unit symbol appended.
+    #[must_use]
+    pub fn format(self, value: f32) -> String {
+        format!("{value:.*} {}", self.precision(), self.symbol())
+    }
+}
+
+/// The units a user prefers to see for each dimension
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnitPreferences {
+    pub length: Unit,
+    pub speed: Unit,
+    pub pressure: Unit,
+    pub mass: Unit,
+}
+
+impl UnitPreferences {
+    const SETTINGS_KEY: &'static str = "units";
+
+    /// Reads preferences from `settings`, falling back to defaults if none are stored.
+    #[must_use]
+    pub fn load(settings: &Settings) -> Self {
+        settings.get(Self::SETTINGS_KEY).unwrap_or_default()
+    }
+
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the preferences could not be serialized.
+    pub fn store(&self, settings: &mut Settings) -> Result<(), SettingsError> {
+        settings.set(Self::SETTINGS_KEY, self)
+    }
+
+    #[must_use]
+    pub fn preferred(&self, dimension: Dimension) -> Unit {
+        match dimension {
+            Dimension::Length => self.length,
+            Dimension::Speed => self.speed,
+            Dimension::Pressure => self.pressure,
+            Dimension::Mass => self.mass,
+        }
+    }
+
+    pub fn set_preferred(&mut self, unit: Unit) {
+        match unit.dimension() {
+            Dimension::Length => self.length = unit,
+            Dimension::Speed => self.speed = unit,
+            Dimension::Pressure => self.pressure = unit,
+            Dimension::Mass => self.mass = unit,
+        }
+    }
+
+    /// Formats `value`, stored in `unit`, in the preferred unit for its dimension.
+    #[must_use]
+    pub fn format(&self, value: f32, unit: Unit) -> String {
+        let preferred = self.preferred(unit.dimension());
+        unit.convert(value, preferred)
+            .map_or_else(|| unit.format(value), |value| preferred.format(value))
+    }
+}
+
+impl Default for UnitPreferences {
+    fn default() -> Self {
+        UnitPreferences {
+            length: Unit::Feet,
+            speed: Unit::Knots,
+            pressure: Unit::InchesOfMercury,
+            mass: Unit::Kilograms,
+        }
+    }
+}
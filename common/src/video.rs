@@ -0,0 +1,97 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::ffi::c_void;
+
+use gl21 as gl;
+use image::{EncodableLayout, ImageError, RgbaImage};
+use imgui::{Image, TextureId, Ui};
+
+use crate::backend::{create_texture, delete_texture};
+
+/// A texture whose contents are replaced as new frames arrive, e.g. from a camera. It's created
+/// through the current backend when the first frame arrives, and again if the frame size changes.
+#[derive(Default)]
+pub struct VideoTexture {
+    texture: Option<TextureId>,
+    width: u32,
+    height: u32,
+}
+
+impl VideoTexture {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uploads `frame`, outside of drawing the frame it's shown in
+    ///
+    /// # Errors
+    ///
+    /// Returns `ImageError` if the texture could not be created.
+    pub fn update(&mut self, frame: &RgbaImage) -> Result<(), ImageError> {
+        let (width, height) = frame.dimensions();
+        match self.texture {
+            Some(texture) if (width, height) == (self.width, self.height) => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                unsafe {
+                    gl::BindTexture(gl::TEXTURE_2D, texture.id() as _);
+                    gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+                    gl::TexSubImage2D(
+                        gl::TEXTURE_2D,
+                        0,
+                        0,
+                        0,
+                        width as _,
+                        height as _,
+                        gl::RGBA,
+                        gl::UNSIGNED_BYTE,
+                        frame.as_bytes().as_ptr().cast::<c_void>(),
+                    );
+                }
+            }
+            _ => {
+                if let Some(texture) = self.texture.take() {
+                    delete_texture(texture);
+                }
+                self.texture = Some(create_texture(frame)?);
+                self.width = width;
+                self.height = height;
+            }
+        }
+        Ok(())
+    }
+
+    /// The texture, once a frame has arrived
+    #[must_use]
+    pub fn texture_id(&self) -> Option<TextureId> {
+        self.texture
+    }
+
+    /// The latest frame's width and height
+    #[must_use]
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.texture.map(|_| (self.width, self.height))
+    }
+
+    /// Draws the latest frame `width` pixels wide, keeping its aspect ratio. Draws nothing
+    /// before the first frame.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn draw(&self, ui: &Ui, width: f32) {
+        if let Some(texture) = self.texture {
+            let height = width * self.height as f32 / self.width.max(1) as f32;
+            Image::new(texture, [width, height]).build(ui);
+        }
+    }
+}
+
+impl Drop for VideoTexture {
+    fn drop(&mut self) {
+        if let Some(texture) = self.texture.take() {
+            delete_texture(texture);
+        }
+    }
+}
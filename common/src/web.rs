@@ -0,0 +1,176 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+//! Runs an `App` in a canvas on a web page, drawn with WebGL2, e.g. so a panel can be used from
+//! a tablet's browser. Built for `wasm32-unknown-unknown` with the `wasm` feature.
+//!
+//! The canvas's size is left to the page's CSS; the `System` fills it at the device's pixel
+//! ratio. There's no clock `std::time::Instant` can read in the browser, so the app should
+//! avoid the helpers built on it, such as jobs, kinetic scrolling, smoothing and the recorder.
+
+mod keymap;
+mod platform;
+mod renderer;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use imgui::{Context, StyleColor};
+use tracing::warn;
+use wasm_bindgen::prelude::{Closure, JsCast, JsValue};
+use web_sys::{HtmlCanvasElement, WebGl2RenderingContext, Window};
+
+use crate::backend::{dispatch_event, Backend, Capabilities, FrameDriver};
+use crate::events::Event;
+use crate::tasks::Tasks;
+use crate::web::platform::{EventQueue, Platform};
+use crate::web::renderer::Renderer;
+use crate::App;
+
+pub use crate::web::renderer::{create_texture, delete_texture};
+
+#[derive(Debug)]
+pub enum WebError {
+    /// Not running in a page with a document, e.g. in a worker
+    NoDocument,
+    /// No canvas has the id given to `init`
+    NoCanvas(String),
+    /// The browser or device doesn't support WebGL2
+    NoWebGl2,
+    /// The shaders failed to compile or link, with the driver's log
+    Shader(String),
+    Js(JsValue),
+}
+
+impl From<JsValue> for WebError {
+    fn from(error: JsValue) -> Self {
+        WebError::Js(error)
+    }
+}
+
+/// The next frame's callback, which keeps itself alive by asking for the frame after
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+pub struct System {
+    imgui: Context,
+    platform: Platform,
+    renderer: Renderer,
+    driver: FrameDriver,
+    events: EventQueue,
+    display_size: [u32; 2],
+    app: Box<dyn App>,
+}
+
+/// Hosts `app` in the canvas with id `canvas_id`. Call `System::run` to start drawing it.
+///
+/// # Errors
+///
+/// Returns `WebError` if there's no such canvas or it can't be drawn in with WebGL2.
+pub fn init<A: App + 'static>(canvas_id: &str, app: A) -> Result<System, WebError> {
+    let window = web_sys::window().ok_or(WebError::NoDocument)?;
+    let canvas = window
+        .document()
+        .ok_or(WebError::NoDocument)?
+        .get_element_by_id(canvas_id)
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .ok_or_else(|| WebError::NoCanvas(canvas_id.to_owned()))?;
+    let gl = canvas
+        .get_context("webgl2")?
+        .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok())
+        .ok_or(WebError::NoWebGl2)?;
+
+    let mut imgui = Context::create();
+    // there's no file system to keep window positions in
+    imgui.set_ini_filename(None);
+    let events = EventQueue::default();
+    let platform = Platform::new(&mut imgui, window, canvas, &events)?;
+    let renderer = Renderer::new(&mut imgui, gl)?;
+
+    Backend::register(
+        Backend::Web,
+        Capabilities::default(),
+        create_texture,
+        delete_texture,
+    );
+
+    Ok(System {
+        imgui,
+        platform,
+        renderer,
+        driver: FrameDriver::new(Tasks::new(), None),
+        events,
+        display_size: [0, 0],
+        app: Box::new(app),
+    })
+}
+
+impl System {
+    #[must_use]
+    pub fn tasks(&self) -> &Tasks {
+        self.driver.tasks()
+    }
+
+    /// The padding around the app's contents, zero by default
+    pub fn set_window_padding(&mut self, padding: [f32; 2]) {
+        self.driver.set_window_padding(padding);
+    }
+
+    /// Draws a frame each time the browser is about to repaint, for as long as the page is open
+    pub fn run(mut self) {
+        let window = self.platform.window().clone();
+        let callback = FrameCallback::default();
+        let next = callback.clone();
+        let next_window = window.clone();
+        *callback.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            self.frame();
+            request_frame(&next_window, &next);
+        }) as Box<dyn FnMut()>));
+        request_frame(&window, &callback);
+    }
+
+    fn frame(&mut self) {
+        let [width, height] = self.platform.display_size();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let display_size = [width as u32, height as u32];
+        if display_size != self.display_size {
+            self.display_size = display_size;
+            self.app.handle_event(
+                Event::Resize(display_size[0], display_size[1]),
+                self.driver.input(),
+            );
+        }
+        for event in self.events.take() {
+            dispatch_event(
+                &mut *self.app,
+                &mut self.platform,
+                self.imgui.io_mut(),
+                event,
+                self.driver.input(),
+            );
+        }
+
+        let delta = self.platform.advance();
+        self.imgui.io_mut().update_delta_time(delta);
+        self.app.update(delta.as_secs_f32());
+
+        self.renderer
+            .set_clear_color(self.imgui.style()[StyleColor::WindowBg]);
+        self.driver.frame(
+            &mut self.imgui,
+            &mut self.platform,
+            &mut self.renderer,
+            &*self.app,
+        );
+    }
+}
+
+fn request_frame(window: &Window, callback: &FrameCallback) {
+    if let Some(callback) = callback.borrow().as_ref() {
+        if let Err(error) = window.request_animation_frame(callback.as_ref().unchecked_ref()) {
+            warn!(?error, "Unable to request a frame");
+        }
+    }
+}
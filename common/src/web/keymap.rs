@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::Key;
+
+/// Maps a `KeyboardEvent`'s `code`, which names the physical key whatever the layout, to imgui's
+pub fn to_imgui_key(code: &str) -> Option<Key> {
+    match code {
+        "Tab" => Some(Key::Tab),
+        "ArrowLeft" => Some(Key::LeftArrow),
+        "ArrowRight" => Some(Key::RightArrow),
+        "ArrowUp" => Some(Key::UpArrow),
+        "ArrowDown" => Some(Key::DownArrow),
+        "PageUp" => Some(Key::PageUp),
+        "PageDown" => Some(Key::PageDown),
+        "Home" => Some(Key::Home),
+        "End" => Some(Key::End),
+        "Insert" => Some(Key::Insert),
+        "Delete" => Some(Key::Delete),
+        "Backspace" => Some(Key::Backspace),
+        "Space" => Some(Key::Space),
+        "Enter" => Some(Key::Enter),
+        "Escape" => Some(Key::Escape),
+
+        "Digit0" => Some(Key::Alpha0),
+        "Digit1" => Some(Key::Alpha1),
+        "Digit2" => Some(Key::Alpha2),
+        "Digit3" => Some(Key::Alpha3),
+        "Digit4" => Some(Key::Alpha4),
+        "Digit5" => Some(Key::Alpha5),
+        "Digit6" => Some(Key::Alpha6),
+        "Digit7" => Some(Key::Alpha7),
+        "Digit8" => Some(Key::Alpha8),
+        "Digit9" => Some(Key::Alpha9),
+
+        "KeyA" => Some(Key::A),
+        "KeyB" => Some(Key::B),
+        "KeyC" => Some(Key::C),
+        "KeyD" => Some(Key::D),
+        "KeyE" => Some(Key::E),
+        "KeyF" => Some(Key::F),
+        "KeyG" => Some(Key::G),
+        "KeyH" => Some(Key::H),
+        "KeyI" => Some(Key::I),
+        "KeyJ" => Some(Key::J),
+        "KeyK" => Some(Key::K),
+        "KeyL" => Some(Key::L),
+        "KeyM" => Some(Key::M),
+        "KeyN" => Some(Key::N),
+        "KeyO" => Some(Key::O),
+        "KeyP" => Some(Key::P),
+        "KeyQ" => Some(Key::Q),
+        "KeyR" => Some(Key::R),
+        "KeyS" => Some(Key::S),
+        "KeyT" => Some(Key::T),
+        "KeyU" => Some(Key::U),
+        "KeyV" => Some(Key::V),
+        "KeyW" => Some(Key::W),
+        "KeyX" => Some(Key::X),
+        "KeyY" => Some(Key::Y),
+        "KeyZ" => Some(Key::Z),
+
+        "F1" => Some(Key::F1),
+        "F2" => Some(Key::F2),
+        "F3" => Some(Key::F3),
+        "F4" => Some(Key::F4),
+        "F5" => Some(Key::F5),
+        "F6" => Some(Key::F6),
+        "F7" => Some(Key::F7),
+        "F8" => Some(Key::F8),
+        "F9" => Some(Key::F9),
+        "F10" => Some(Key::F10),
+        "F11" => Some(Key::F11),
+        "F12" => Some(Key::F12),
+
+        "Quote" => Some(Key::Apostrophe),
+        "Comma" => Some(Key::Comma),
+        "Minus" => Some(Key::Minus),
+        "Period" => Some(Key::Period),
+        "Slash" => Some(Key::Slash),
+        "Semicolon" => Some(Key::Semicolon),
+        "Equal" => Some(Key::Equal),
+        "BracketLeft" => Some(Key::LeftBracket),
+        "Backslash" => Some(Key::Backslash),
+        "BracketRight" => Some(Key::RightBracket),
+        "Backquote" => Some(Key::GraveAccent),
+
+        "Numpad0" => Some(Key::Keypad0),
+        "Numpad1" => Some(Key::Keypad1),
+        "Numpad2" => Some(Key::Keypad2),
+        "Numpad3" => Some(Key::Keypad3),
+        "Numpad4" => Some(Key::Keypad4),
+        "Numpad5" => Some(Key::Keypad5),
+        "Numpad6" => Some(Key::Keypad6),
+        "Numpad7" => Some(Key::Keypad7),
+        "Numpad8" => Some(Key::Keypad8),
+        "Numpad9" => Some(Key::Keypad9),
+
+        "NumpadDecimal" => Some(Key::KeypadDecimal),
+        "NumpadDivide" => Some(Key::KeypadDivide),
+        "NumpadMultiply" => Some(Key::KeypadMultiply),
+        "NumpadSubtract" => Some(Key::KeypadSubtract),
+        "NumpadAdd" => Some(Key::KeypadAdd),
+        "NumpadEnter" => Some(Key::KeypadEnter),
+        "NumpadEqual" => Some(Key::KeypadEqual),
+        _ => None,
+    }
+}
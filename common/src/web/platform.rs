@@ -0,0 +1,278 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use imgui::{Context, Io};
+use tracing::warn;
+use wasm_bindgen::prelude::{Closure, JsCast};
+use web_sys::{HtmlCanvasElement, KeyboardEvent, Performance, PointerEvent, WheelEvent, Window};
+
+use crate::backend::{self, PlatformBackend};
+use crate::events::{Action, Event, Modifiers, MouseButton};
+use crate::web::keymap::to_imgui_key;
+use crate::web::WebError;
+
+/// The canvas's input, waiting for the next frame
+pub type EventQueue = Rc<RefCell<Vec<Event>>>;
+
+type Listener = Closure<dyn FnMut(web_sys::Event)>;
+
+pub struct Platform {
+    window: Window,
+    canvas: HtmlCanvasElement,
+    performance: Performance,
+    /// When the last frame started, in milliseconds since the page loaded
+    last_frame: Option<f64>,
+    /// The canvas's event listeners, which the browser can only call while they're alive
+    listeners: Vec<(&'static str, Listener)>,
+}
+
+impl Platform {
+    /// Makes the canvas focusable and listens for its input, translated into `Event`s in `events`
+    pub fn new(
+        imgui: &mut Context,
+        window: Window,
+        canvas: HtmlCanvasElement,
+        events: &EventQueue,
+    ) -> Result<Platform, WebError> {
+        imgui.set_platform_name(Some(format!(
+            "imgui-web-platform {}",
+            env!("CARGO_PKG_VERSION")
+        )));
+        let performance = window.performance().ok_or(WebError::NoDocument)?;
+
+        canvas.set_tab_index(0);
+        // otherwise dragging with a finger scrolls or zooms the page rather than reaching imgui
+        canvas.style().set_property("touch-action", "none")?;
+
+        let mut platform = Platform {
+            window,
+            canvas,
+            performance,
+            last_frame: None,
+            listeners: Vec::new(),
+        };
+        platform.listen_for_pointer(events)?;
+        platform.listen_for_keys(events)?;
+
+        let queue = events.clone();
+        platform.listen("wheel", move |event| {
+            if let Some(event) = event.dyn_ref::<WheelEvent>() {
+                event.prevent_default();
+                queue.borrow_mut().push(scroll(event));
+            }
+        })?;
+        let queue = events.clone();
+        platform.listen("focus", move |_| {
+            queue.borrow_mut().push(Event::Focus(true));
+        })?;
+        let queue = events.clone();
+        platform.listen("blur", move |_| {
+            queue.borrow_mut().push(Event::Focus(false));
+        })?;
+        // so right clicks reach imgui rather than opening the browser's menu
+        platform.listen("contextmenu", web_sys::Event::prevent_default)?;
+        Ok(platform)
+    }
+
+    #[must_use]
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// The canvas's size on the page, in CSS pixels
+    #[must_use]
+    pub fn display_size(&self) -> [f32; 2] {
+        #[allow(clippy::cast_precision_loss)]
+        [
+            self.canvas.client_width() as f32,
+            self.canvas.client_height() as f32,
+        ]
+    }
+
+    /// The time since the last call, or a sixtieth of a second the first time
+    pub fn advance(&mut self) -> Duration {
+        let now = self.performance.now();
+        let delta = self
+            .last_frame
+            .map_or(1.0 / 60.0, |last| (now - last).max(0.0) / 1000.0);
+        self.last_frame = Some(now);
+        Duration::from_secs_f64(delta)
+    }
+
+    fn listen_for_pointer(&mut self, events: &EventQueue) -> Result<(), WebError> {
+        let queue = events.clone();
+        self.listen("pointermove", move |event| {
+            if let Some(event) = event.dyn_ref::<PointerEvent>() {
+                queue.borrow_mut().push(cursor_pos(event));
+            }
+        })?;
+
+        let queue = events.clone();
+        let canvas = self.canvas.clone();
+        self.listen("pointerdown", move |event| {
+            let Some(event) = event.dyn_ref::<PointerEvent>() else {
+                return;
+            };
+            // keep receiving the pointer's moves and release if it leaves the canvas mid-drag
+            if let Err(error) = canvas.set_pointer_capture(event.pointer_id()) {
+                warn!(?error, "Unable to capture the pointer");
+            }
+            if let Err(error) = canvas.focus() {
+                warn!(?error, "Unable to focus the canvas");
+            }
+            // a touch has no moves before it lands
+            let mut queue = queue.borrow_mut();
+            queue.push(cursor_pos(event));
+            if let Some(button) = mouse_button(event) {
+                queue.push(Event::MouseButton(button, Action::Press));
+            }
+        })?;
+
+        let queue = events.clone();
+        self.listen("pointerup", move |event| {
+            let Some(event) = event.dyn_ref::<PointerEvent>() else {
+                return;
+            };
+            let mut queue = queue.borrow_mut();
+            queue.push(cursor_pos(event));
+            if let Some(button) = mouse_button(event) {
+                queue.push(Event::MouseButton(button, Action::Release));
+            }
+        })
+    }
+
+    fn listen_for_keys(&mut self, events: &EventQueue) -> Result<(), WebError> {
+        let queue = events.clone();
+        self.listen("keydown", move |event| {
+            if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                let key = key_event(event, Action::Press);
+                // keep Tab, Backspace, arrows and space from moving focus or scrolling the page,
+                // leaving the browser's shortcuts alone
+                if let Event::Key(Some(_), _, _, modifiers) = &key {
+                    if !modifiers.control && !modifiers.super_key {
+                        event.prevent_default();
+                    }
+                }
+                queue.borrow_mut().push(key);
+            }
+        })?;
+
+        let queue = events.clone();
+        self.listen("keyup", move |event| {
+            if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                queue.borrow_mut().push(key_event(event, Action::Release));
+            }
+        })
+    }
+
+    fn listen(
+        &mut self,
+        kind: &'static str,
+        mut handler: impl FnMut(&web_sys::Event) + 'static,
+    ) -> Result<(), WebError> {
+        let listener = Closure::wrap(
+            Box::new(move |event: web_sys::Event| handler(&event)) as Box<dyn FnMut(_)>
+        );
+        self.canvas
+            .add_event_listener_with_callback(kind, listener.as_ref().unchecked_ref())?;
+        self.listeners.push((kind, listener));
+        Ok(())
+    }
+}
+
+impl PlatformBackend for Platform {
+    /// Sizes the canvas's drawing buffer to match its size on the page at the device's pixel
+    /// ratio, and tells imgui
+    fn prepare_frame(&mut self, io: &mut Io) {
+        #[allow(clippy::cast_possible_truncation)]
+        let ratio = self.window.device_pixel_ratio() as f32;
+        let [width, height] = self.display_size();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (buffer_width, buffer_height) = (
+            (width * ratio).round() as u32,
+            (height * ratio).round() as u32,
+        );
+        if self.canvas.width() != buffer_width {
+            self.canvas.set_width(buffer_width);
+        }
+        if self.canvas.height() != buffer_height {
+            self.canvas.set_height(buffer_height);
+        }
+        io.display_size = [width, height];
+        io.display_framebuffer_scale = [ratio, ratio];
+    }
+
+    fn handle_event(&mut self, io: &mut Io, event: Event) {
+        backend::send_input(io, event);
+    }
+}
+
+impl Drop for Platform {
+    fn drop(&mut self) {
+        for (kind, listener) in &self.listeners {
+            let callback = listener.as_ref().unchecked_ref();
+            if let Err(error) = self
+                .canvas
+                .remove_event_listener_with_callback(kind, callback)
+            {
+                warn!(?error, kind, "Unable to remove an event listener");
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn cursor_pos(event: &PointerEvent) -> Event {
+    Event::CursorPos(event.offset_x() as f32, event.offset_y() as f32)
+}
+
+/// Touches and pens in contact report the left button
+fn mouse_button(event: &PointerEvent) -> Option<MouseButton> {
+    match event.button() {
+        0 => Some(MouseButton::Left),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+/// Wheels report pixels, lines or pages; a wheel click is taken to be 100 pixels or 3 lines
+#[allow(clippy::cast_possible_truncation)]
+fn scroll(event: &WheelEvent) -> Event {
+    let scale = match event.delta_mode() {
+        WheelEvent::DOM_DELTA_PIXEL => 1.0 / 100.0,
+        WheelEvent::DOM_DELTA_LINE => 1.0 / 3.0,
+        _ => 1.0,
+    };
+    Event::Scroll(
+        (-event.delta_x() * scale) as f32,
+        (-event.delta_y() * scale) as f32,
+    )
+}
+
+/// `key` is the character typed, or the key's name, e.g. "Enter", if it doesn't type one
+fn key_event(event: &KeyboardEvent, action: Action) -> Event {
+    let key = event.key();
+    let mut chars = key.chars();
+    let ch = match (chars.next(), chars.next()) {
+        (Some(ch), None) => ch,
+        _ => '\0',
+    };
+    Event::Key(
+        to_imgui_key(&event.code()),
+        ch,
+        action,
+        Modifiers {
+            control: event.ctrl_key(),
+            option: event.alt_key(),
+            shift: event.shift_key(),
+            super_key: event.meta_key(),
+        },
+    )
+}
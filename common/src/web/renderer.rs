@@ -0,0 +1,390 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::{mem, slice};
+
+use image::{ImageError, RgbaImage};
+use imgui::{Context, DrawCmd, DrawCmdParams, DrawData, DrawIdx, DrawVert, TextureId};
+use tracing::{debug, warn};
+use web_sys::{
+    WebGl2RenderingContext as Gl, WebGlBuffer, WebGlProgram, WebGlShader, WebGlTexture,
+    WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::backend::RendererBackend;
+use crate::metrics::{self, RenderStats};
+use crate::renderer_common::{
+    add_font_styles, clipped_out, configure_imgui, FontStyles, DEFAULT_FONT_SIZE,
+};
+use crate::web::WebError;
+
+const VERTEX_SHADER: &str = r"#version 300 es
+uniform mat4 projection;
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+out vec2 frag_uv;
+out vec4 frag_color;
+void main() {
+    frag_uv = uv;
+    frag_color = color;
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_SHADER: &str = r"#version 300 es
+precision mediump float;
+uniform sampler2D tex;
+in vec2 frag_uv;
+in vec4 frag_color;
+out vec4 out_color;
+void main() {
+    out_color = frag_color * texture(tex, frag_uv);
+}
+";
+
+const INDEX_TYPE: u32 = if mem::size_of::<DrawIdx>() == 2 {
+    Gl::UNSIGNED_SHORT
+} else {
+    Gl::UNSIGNED_INT
+};
+
+thread_local! {
+    /// The textures imgui draws with, by the id it knows each by, once a `System` has a context
+    static TEXTURES: RefCell<Option<Textures>> = const { RefCell::new(None) };
+}
+
+struct Textures {
+    gl: Gl,
+    next_id: usize,
+    by_id: HashMap<usize, WebGlTexture>,
+}
+
+impl Textures {
+    fn create(&mut self, width: u32, height: u32, pixels: &[u8]) -> TextureId {
+        let texture = self.gl.create_texture();
+        self.gl.bind_texture(Gl::TEXTURE_2D, texture.as_ref());
+        #[allow(clippy::cast_possible_wrap)]
+        for (parameter, value) in [
+            (Gl::TEXTURE_MIN_FILTER, Gl::LINEAR),
+            (Gl::TEXTURE_MAG_FILTER, Gl::LINEAR),
+            (Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE),
+            (Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE),
+        ] {
+            self.gl
+                .tex_parameteri(Gl::TEXTURE_2D, parameter, value as i32);
+        }
+        self.gl.pixel_storei(Gl::UNPACK_ALIGNMENT, 1);
+        #[allow(clippy::cast_possible_wrap)]
+        let uploaded = self
+            .gl
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                Gl::TEXTURE_2D,
+                0,
+                Gl::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                Gl::RGBA,
+                Gl::UNSIGNED_BYTE,
+                Some(pixels),
+            );
+        if let Err(error) = uploaded {
+            warn!(?error, width, height, "Unable to upload texture");
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        if let Some(texture) = texture {
+            self.by_id.insert(id, texture);
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        metrics::track_texture(id as u32, width, height);
+        TextureId::new(id)
+    }
+
+    fn delete(&mut self, texture_id: TextureId) {
+        debug!(id = texture_id.id(), "Deallocating texture");
+        #[allow(clippy::cast_possible_truncation)]
+        metrics::untrack_texture(texture_id.id() as u32);
+        self.gl
+            .delete_texture(self.by_id.remove(&texture_id.id()).as_ref());
+    }
+}
+
+/// Creates a texture from `image` in the canvas's context, for `Backend::register`; apps shared
+/// between backends use `backend::create_texture`
+///
+/// # Errors
+///
+/// Never fails; the signature matches the other backends'.
+///
+/// # Panics
+///
+/// Panics if no web `System` has been created.
+pub fn create_texture(image: &RgbaImage) -> Result<TextureId, ImageError> {
+    let (width, height) = image.dimensions();
+    Ok(with_textures(|textures| {
+        textures.create(width, height, image.as_raw())
+    }))
+}
+
+/// Deletes a texture from `create_texture`
+///
+/// # Panics
+///
+/// Panics if no web `System` has been created.
+pub fn delete_texture(texture_id: TextureId) {
+    with_textures(|textures| textures.delete(texture_id));
+}
+
+fn with_textures<T>(f: impl FnOnce(&mut Textures) -> T) -> T {
+    TEXTURES
+        .with_borrow_mut(|textures| f(textures.as_mut().expect("No web System has been created")))
+}
+
+pub struct Renderer {
+    gl: Gl,
+    program: WebGlProgram,
+    projection: Option<WebGlUniformLocation>,
+    texture: Option<WebGlUniformLocation>,
+    vertex_array: Option<WebGlVertexArrayObject>,
+    vertex_buffer: Option<WebGlBuffer>,
+    index_buffer: Option<WebGlBuffer>,
+    clear_color: [f32; 4],
+}
+
+impl Renderer {
+    /// Compiles the shaders and uploads the font atlas into `gl`, which textures are then
+    /// created in
+    pub fn new(imgui: &mut Context, gl: Gl) -> Result<Self, WebError> {
+        configure_imgui(imgui, "web");
+        let program = link_program(&gl)?;
+
+        let vertex_array = gl.create_vertex_array();
+        let vertex_buffer = gl.create_buffer();
+        let index_buffer = gl.create_buffer();
+        gl.bind_vertex_array(vertex_array.as_ref());
+        gl.bind_buffer(Gl::ARRAY_BUFFER, vertex_buffer.as_ref());
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let stride = mem::size_of::<DrawVert>() as i32;
+        for (location, size, kind, normalized, offset) in [
+            (0, 2, Gl::FLOAT, false, 0),
+            (1, 2, Gl::FLOAT, false, 8),
+            (2, 4, Gl::UNSIGNED_BYTE, true, 16),
+        ] {
+            gl.enable_vertex_attrib_array(location);
+            gl.vertex_attrib_pointer_with_i32(location, size, kind, normalized, stride, offset);
+        }
+        gl.bind_vertex_array(None);
+
+        TEXTURES.set(Some(Textures {
+            gl: gl.clone(),
+            next_id: 1,
+            by_id: HashMap::new(),
+        }));
+        let atlas = imgui.fonts();
+        add_font_styles(atlas, DEFAULT_FONT_SIZE, &FontStyles::default());
+        let texture = atlas.build_rgba32_texture();
+        let font_texture =
+            with_textures(|textures| textures.create(texture.width, texture.height, texture.data));
+        atlas.tex_id = font_texture;
+
+        Ok(Renderer {
+            projection: gl.get_uniform_location(&program, "projection"),
+            texture: gl.get_uniform_location(&program, "tex"),
+            gl,
+            program,
+            vertex_array,
+            vertex_buffer,
+            index_buffer,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+        })
+    }
+
+    /// The colour the canvas is cleared to before each frame
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.clear_color = color;
+    }
+
+    fn setup_render_state(&self, draw_data: &DrawData, fb_width: i32, fb_height: i32) {
+        let gl = &self.gl;
+        gl.viewport(0, 0, fb_width, fb_height);
+        gl.enable(Gl::BLEND);
+        gl.blend_equation(Gl::FUNC_ADD);
+        gl.blend_func_separate(
+            Gl::SRC_ALPHA,
+            Gl::ONE_MINUS_SRC_ALPHA,
+            Gl::ONE,
+            Gl::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.disable(Gl::CULL_FACE);
+        gl.disable(Gl::DEPTH_TEST);
+        gl.enable(Gl::SCISSOR_TEST);
+
+        let [left, top] = draw_data.display_pos;
+        let right = left + draw_data.display_size[0];
+        let bottom = top + draw_data.display_size[1];
+        #[rustfmt::skip]
+        let projection = [
+            2.0 / (right - left), 0.0, 0.0, 0.0,
+            0.0, 2.0 / (top - bottom), 0.0, 0.0,
+            0.0, 0.0, -1.0, 0.0,
+            (right + left) / (left - right), (top + bottom) / (bottom - top), 0.0, 1.0,
+        ];
+        gl.use_program(Some(&self.program));
+        gl.uniform1i(self.texture.as_ref(), 0);
+        gl.uniform_matrix4fv_with_f32_array(self.projection.as_ref(), false, &projection);
+        gl.bind_vertex_array(self.vertex_array.as_ref());
+        gl.active_texture(Gl::TEXTURE0);
+    }
+}
+
+impl RendererBackend for Renderer {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn render(&mut self, draw_data: &DrawData) -> RenderStats {
+        let gl = &self.gl;
+        let [width, height] = draw_data.display_size;
+        let [scale_x, scale_y] = draw_data.framebuffer_scale;
+        let fb_width = (width * scale_x) as i32;
+        let fb_height = (height * scale_y) as i32;
+
+        let [red, green, blue, alpha] = self.clear_color;
+        gl.disable(Gl::SCISSOR_TEST);
+        gl.clear_color(red, green, blue, alpha);
+        gl.clear(Gl::COLOR_BUFFER_BIT);
+
+        let mut stats = RenderStats::default();
+        if fb_width <= 0 || fb_height <= 0 {
+            return stats;
+        }
+        self.setup_render_state(draw_data, fb_width, fb_height);
+
+        let [left, top] = draw_data.display_pos;
+        let mut bound_texture = None;
+        TEXTURES.with_borrow(|textures| {
+            let textures = &textures
+                .as_ref()
+                .expect("No web System has been created")
+                .by_id;
+            for draw_list in draw_data.draw_lists() {
+                let vertices = draw_list.vtx_buffer();
+                let indices = draw_list.idx_buffer();
+                stats.draw_lists += 1;
+                stats.vertices += vertices.len();
+                stats.indices += indices.len();
+
+                gl.bind_buffer(Gl::ARRAY_BUFFER, self.vertex_buffer.as_ref());
+                gl.buffer_data_with_u8_array(Gl::ARRAY_BUFFER, as_bytes(vertices), Gl::STREAM_DRAW);
+                gl.bind_buffer(Gl::ELEMENT_ARRAY_BUFFER, self.index_buffer.as_ref());
+                gl.buffer_data_with_u8_array(
+                    Gl::ELEMENT_ARRAY_BUFFER,
+                    as_bytes(indices),
+                    Gl::STREAM_DRAW,
+                );
+
+                for cmd in draw_list.commands() {
+                    match cmd {
+                        DrawCmd::Elements {
+                            count,
+                            cmd_params:
+                                DrawCmdParams {
+                                    clip_rect,
+                                    texture_id,
+                                    idx_offset,
+                                    ..
+                                },
+                        } => {
+                            stats.commands += 1;
+                            if clipped_out(draw_data, clip_rect) {
+                                stats.scissor_rejects += 1;
+                                continue;
+                            }
+                            if bound_texture != Some(texture_id) {
+                                gl.bind_texture(Gl::TEXTURE_2D, textures.get(&texture_id.id()));
+                                bound_texture = Some(texture_id);
+                                stats.texture_binds += 1;
+                            }
+                            let [x, y, z, w] = clip_rect;
+                            gl.scissor(
+                                ((x - left) * scale_x) as i32,
+                                ((height - (w - top)) * scale_y) as i32,
+                                ((z - x) * scale_x) as i32,
+                                ((w - y) * scale_y) as i32,
+                            );
+                            gl.draw_elements_with_i32(
+                                Gl::TRIANGLES,
+                                count as i32,
+                                INDEX_TYPE,
+                                (idx_offset * mem::size_of::<DrawIdx>()) as i32,
+                            );
+                            stats.draw_calls += 1;
+                        }
+                        DrawCmd::ResetRenderState => {
+                            self.setup_render_state(draw_data, fb_width, fb_height);
+                            bound_texture = None;
+                        }
+                        DrawCmd::RawCallback { .. } => {
+                            unimplemented!("Haven't implemented user callbacks yet");
+                        }
+                    }
+                }
+            }
+        });
+        gl.bind_vertex_array(None);
+        stats
+    }
+}
+
+/// The bytes of imgui's vertex and index buffers, which have no padding
+fn as_bytes<T: Copy>(data: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr().cast::<u8>(), mem::size_of_val(data)) }
+}
+
+fn link_program(gl: &Gl) -> Result<WebGlProgram, WebError> {
+    let vertex = compile_shader(gl, Gl::VERTEX_SHADER, VERTEX_SHADER)?;
+    let fragment = compile_shader(gl, Gl::FRAGMENT_SHADER, FRAGMENT_SHADER)?;
+    let program = gl
+        .create_program()
+        .ok_or_else(|| WebError::Shader("Unable to create program".to_owned()))?;
+    gl.attach_shader(&program, &vertex);
+    gl.attach_shader(&program, &fragment);
+    gl.link_program(&program);
+    gl.delete_shader(Some(&vertex));
+    gl.delete_shader(Some(&fragment));
+    if gl
+        .get_program_parameter(&program, Gl::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(program)
+    } else {
+        Err(WebError::Shader(
+            gl.get_program_info_log(&program).unwrap_or_default(),
+        ))
+    }
+}
+
+fn compile_shader(gl: &Gl, kind: u32, source: &str) -> Result<WebGlShader, WebError> {
+    let shader = gl
+        .create_shader(kind)
+        .ok_or_else(|| WebError::Shader("Unable to create shader".to_owned()))?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+    if gl
+        .get_shader_parameter(&shader, Gl::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(shader)
+    } else {
+        Err(WebError::Shader(
+            gl.get_shader_info_log(&shader).unwrap_or_default(),
+        ))
+    }
+}
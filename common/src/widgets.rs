@@ -0,0 +1,40 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+pub use crate::widgets::about::AboutWindow;
+pub use crate::widgets::console::Console;
+pub use crate::widgets::context_menu::context_menu_for;
+pub use crate::widgets::coord::CoordInput;
+pub use crate::widgets::file_browser::{BrowseMode, FileBrowser};
+pub use crate::widgets::flight_plan::{FlightPlanTable, Waypoint};
+pub use crate::widgets::link::link;
+pub use crate::widgets::log_console::{LogBuffer, LogConsole, LogLayer, LogRecord};
+pub use crate::widgets::qr_code::qr_code;
+pub use crate::widgets::quantity::QuantityInput;
+pub use crate::widgets::radio::{parse_squawk, Band, FrequencyInput, SquawkInput};
+pub use crate::widgets::search_combo::{
+    fuzzy_filter, fuzzy_score, ItemSource, SearchCombo, StaticSource, ThreadedSource,
+};
+pub use crate::widgets::selectable_text::SelectableText;
+pub use crate::widgets::time::{
+    format_duration, format_time, parse_duration, parse_time, utc_now, utc_today, DatePicker,
+    DurationPicker, TimePicker, TimeReference, SECONDS_PER_DAY,
+};
+
+mod about;
+mod console;
+mod context_menu;
+mod coord;
+mod file_browser;
+mod flight_plan;
+mod link;
+mod log_console;
+mod qr_code;
+mod quantity;
+mod radio;
+mod search_combo;
+mod selectable_text;
+mod time;
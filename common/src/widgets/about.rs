@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::ffi::{c_char, CStr};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use gl21 as gl;
+use imgui::{Condition, TableFlags, Ui};
+use tracing::warn;
+
+use crate::platform_utils::open_url;
+
+/// A window showing what a support request needs: the app's and this crate's versions, the GL
+/// renderer, the loaded fonts and the app's enabled features, with buttons to copy it all as
+/// text and to open the log file.
+pub struct AboutWindow {
+    title: String,
+    open: bool,
+    app_name: String,
+    app_version: String,
+    features: Vec<String>,
+    log_path: Option<PathBuf>,
+}
+
+impl AboutWindow {
+    /// About `app_name` at `app_version`, e.g. `env!("CARGO_PKG_VERSION")`, in a window titled
+    /// `title`, which is also its id. Hidden until opened.
+    #[must_use]
+    pub fn new(title: &str, app_name: &str, app_version: &str) -> Self {
+        AboutWindow {
+            title: String::from(title),
+            open: false,
+            app_name: String::from(app_name),
+            app_version: String::from(app_version),
+            features: Vec::new(),
+            log_path: None,
+        }
+    }
+
+    /// Lists `feature` as enabled, e.g. if `cfg!(feature = "...")`
+    #[must_use]
+    pub fn feature(mut self, feature: &str) -> Self {
+        self.features.push(String::from(feature));
+        self
+    }
+
+    /// Shows an "Open log" button which opens `path` with the system's viewer
+    #[must_use]
+    pub fn log_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.log_path = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Opens or closes the window, e.g. from a Help menu
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// The window's details as plain text, as copied by "Copy diagnostics". Needs the GL context
+    /// current, as it is while drawing.
+    #[must_use]
+    pub fn diagnostics(&self, ui: &Ui) -> String {
+        let mut text = String::new();
+        for (label, value) in self.details(ui) {
+            let _ = writeln!(text, "{label}: {value}");
+        }
+        text
+    }
+
+    /// Draws the window if it's open
+    pub fn build(&mut self, ui: &Ui) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        ui.window(&self.title)
+            .size([480.0, 0.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| self.draw_contents(ui));
+        self.open &= open;
+    }
+
+    fn draw_contents(&self, ui: &Ui) {
+        let details = self.details(ui);
+        let flags = TableFlags::BORDERS_INNER_V | TableFlags::SIZING_STRETCH_PROP;
+        if let Some(_table) = ui.begin_table_with_flags("##about", 2, flags) {
+            for (label, value) in &details {
+                ui.table_next_row();
+                ui.table_next_column();
+                ui.text_disabled(label);
+                ui.table_next_column();
+                ui.text_wrapped(value);
+            }
+        }
+        ui.separator();
+
+        if ui.button("Copy diagnostics") {
+            ui.set_clipboard_text(self.diagnostics(ui));
+        }
+        if let Some(path) = &self.log_path {
+            ui.same_line();
+            if ui.button("Open log") {
+                if let Err(e) = open_url(&path.to_string_lossy()) {
+                    warn!(path = %path.display(), error = %e, "Unable to open log");
+                }
+            }
+        }
+    }
+
+    fn details(&self, ui: &Ui) -> Vec<(&'static str, String)> {
+        let features = if self.features.is_empty() {
+            String::from("none")
+        } else {
+            self.features.join(", ")
+        };
+        vec![
+            ("App", format!("{} {}", self.app_name, self.app_version)),
+            ("imgui-support", String::from(env!("CARGO_PKG_VERSION"))),
+            ("Dear ImGui", String::from(imgui::dear_imgui_version())),
+            (
+                "Platform",
+                format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            ),
+            ("Renderer", gl_string(gl::RENDERER)),
+            ("GL vendor", gl_string(gl::VENDOR)),
+            ("GL version", gl_string(gl::VERSION)),
+            ("Fonts", font_names(ui).join(", ")),
+            ("Features", features),
+        ]
+    }
+}
+
+fn gl_string(name: gl::types::GLenum) -> String {
+    let string = unsafe { gl::GetString(name) };
+    if string.is_null() {
+        return String::from("unknown");
+    }
+    unsafe { CStr::from_ptr(string.cast::<c_char>()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn font_names(ui: &Ui) -> Vec<String> {
+    let atlas = ui.fonts();
+    atlas
+        .fonts()
+        .into_iter()
+        .filter_map(|id| atlas.get_font(id))
+        .map(|font| {
+            let font: *const imgui::Font = font;
+            let name = unsafe { imgui::sys::ImFont_GetDebugName(font.cast_mut().cast()) };
+            if name.is_null() {
+                String::from("unnamed")
+            } else {
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        })
+        .collect()
+}
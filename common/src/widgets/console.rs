@@ -0,0 +1,306 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::BTreeMap;
+
+use imgui::{
+    Condition, FocusedWidget, HistoryDirection, InputTextCallback, InputTextCallbackHandler,
+    StyleColor, TextCallbackData, Ui,
+};
+
+type Handler = Box<dyn FnMut(&[&str]) -> Result<String, String>>;
+
+/// Lines kept in the scrollback before the oldest are dropped
+const SCROLLBACK: usize = 1000;
+
+const BUILTINS: [&str; 3] = ["clear", "help", "history"];
+
+/// A developer console in its own window: type a command and its arguments, separated by spaces
+/// or quoted, and the registered handler's output is printed below the command. Up and down
+/// recall earlier commands and tab completes command names.
+///
+/// `help`, `clear` and `history` are built in.
+pub struct Console {
+    title: String,
+    open: bool,
+    commands: BTreeMap<String, Handler>,
+    input: String,
+    history: Vec<String>,
+    /// Which history entry is in the input line, `None` while typing a new one
+    history_position: Option<usize>,
+    scrollback: Vec<(Kind, String)>,
+    scroll_to_bottom: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Command,
+    Output,
+    Error,
+}
+
+/// Handles the input line's history and completion keys
+struct LineEditor<'a> {
+    history: &'a [String],
+    position: Option<usize>,
+    names: Vec<&'a str>,
+    /// Candidates to print when the completion was ambiguous
+    candidates: Vec<String>,
+}
+
+impl Console {
+    /// A console titled `title`, which is also its window's id. Hidden until opened.
+    #[must_use]
+    pub fn new(title: &str) -> Self {
+        Console {
+            title: String::from(title),
+            open: false,
+            commands: BTreeMap::new(),
+            input: String::new(),
+            history: Vec::new(),
+            history_position: None,
+            scrollback: Vec::new(),
+            scroll_to_bottom: false,
+        }
+    }
+
+    /// Runs `handler` with the arguments when `name` is entered, printing the text it returns,
+    /// or its error in red. Replaces any handler already registered for `name`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        handler: impl FnMut(&[&str]) -> Result<String, String> + 'static,
+    ) {
+        self.commands.insert(String::from(name), Box::new(handler));
+    }
+
+    pub fn unregister(&mut self, name: &str) {
+        self.commands.remove(name);
+    }
+
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    /// Opens or closes the console, e.g. from a hotkey
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Prints `text` in the scrollback, e.g. from a command's side effect
+    pub fn print(&mut self, text: &str) {
+        self.push(Kind::Output, text);
+    }
+
+    /// Runs `line` as if it had been typed, adding it to the history
+    pub fn execute(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.push(Kind::Command, &format!("> {line}"));
+        self.history.retain(|entry| entry != line);
+        self.history.push(String::from(line));
+
+        let words = split_words(line);
+        let args: Vec<&str> = words.iter().map(String::as_str).collect();
+        let Some((name, args)) = args.split_first() else {
+            return;
+        };
+        match *name {
+            "clear" => self.scrollback.clear(),
+            "help" => {
+                let names: Vec<&str> = self.names();
+                self.push(Kind::Output, &names.join("  "));
+            }
+            "history" => {
+                let history = self.history.join("\n");
+                self.push(Kind::Output, &history);
+            }
+            _ => {
+                let result = match self.commands.get_mut(*name) {
+                    Some(handler) => handler(args),
+                    None => Err(format!("Unknown command '{name}', try 'help'")),
+                };
+                match result {
+                    Ok(output) => self.push(Kind::Output, &output),
+                    Err(error) => self.push(Kind::Error, &error),
+                }
+            }
+        }
+    }
+
+    /// Draws the console's window if it's open
+    pub fn build(&mut self, ui: &Ui) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        let title = self.title.clone();
+        ui.window(&title)
+            .size([560.0, 360.0], Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| self.draw_contents(ui));
+        self.open &= open;
+    }
+
+    fn draw_contents(&mut self, ui: &Ui) {
+        let footer = ui.frame_height_with_spacing() + ui.clone_style().item_spacing[1];
+        ui.child_window("##scrollback")
+            .size([0.0, -footer])
+            .horizontal_scrollbar(true)
+            .build(|| {
+                for (kind, text) in &self.scrollback {
+                    match kind {
+                        Kind::Command => {
+                            ui.text_colored(ui.style_color(StyleColor::TextDisabled), text);
+                        }
+                        Kind::Output => ui.text(text),
+                        Kind::Error => ui.text_colored([1.0, 0.4, 0.4, 1.0], text),
+                    }
+                }
+                if self.scroll_to_bottom {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                    self.scroll_to_bottom = false;
+                }
+            });
+        ui.separator();
+
+        let mut editor = LineEditor {
+            history: &self.history,
+            position: self.history_position,
+            names: self.commands.keys().map(String::as_str).collect(),
+            candidates: Vec::new(),
+        };
+        editor.names.extend(BUILTINS);
+        ui.set_next_item_width(-1.0);
+        let entered = ui
+            .input_text("##input", &mut self.input)
+            .hint("Enter a command, or 'help'")
+            .enter_returns_true(true)
+            .callback(
+                InputTextCallback::HISTORY | InputTextCallback::COMPLETION,
+                &mut editor,
+            )
+            .build();
+        self.history_position = editor.position;
+        let candidates = editor.candidates;
+        if !candidates.is_empty() {
+            self.push(Kind::Output, &candidates.join("  "));
+        }
+        if ui.is_window_appearing() || entered {
+            ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+        }
+        if entered {
+            let line = std::mem::take(&mut self.input);
+            self.history_position = None;
+            self.execute(&line);
+        }
+    }
+
+    fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.commands.keys().map(String::as_str).collect();
+        names.extend(BUILTINS);
+        names.sort_unstable();
+        names
+    }
+
+    fn push(&mut self, kind: Kind, text: &str) {
+        for line in text.lines() {
+            self.scrollback.push((kind, String::from(line)));
+        }
+        let excess = self.scrollback.len().saturating_sub(SCROLLBACK);
+        self.scrollback.drain(..excess);
+        self.scroll_to_bottom = true;
+    }
+}
+
+impl InputTextCallbackHandler for &mut LineEditor<'_> {
+    fn on_history(&mut self, direction: HistoryDirection, mut data: TextCallbackData) {
+        let last = self.history.len().checked_sub(1);
+        self.position = match (direction, self.position) {
+            (HistoryDirection::Up, None) => last,
+            (HistoryDirection::Up, Some(position)) => Some(position.saturating_sub(1)),
+            (HistoryDirection::Down, Some(position)) if Some(position) != last => {
+                Some(position + 1)
+            }
+            (HistoryDirection::Down, _) => None,
+        };
+        data.clear();
+        if let Some(position) = self.position {
+            data.push_str(&self.history[position]);
+        }
+    }
+
+    /// Completes the command name, as far as the matching names agree
+    fn on_completion(&mut self, mut data: TextCallbackData) {
+        let typed = String::from(data.str());
+        // only the command is completed, not its arguments
+        if typed.contains(char::is_whitespace) {
+            return;
+        }
+        let mut matches: Vec<&str> = self
+            .names
+            .iter()
+            .copied()
+            .filter(|name| name.starts_with(&typed))
+            .collect();
+        matches.sort_unstable();
+        let Some(first) = matches.first() else {
+            return;
+        };
+        let common = matches.iter().fold(*first, |common, name| {
+            let length = common
+                .char_indices()
+                .zip(name.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(common.len().min(name.len()), |((index, _), _)| index);
+            &common[..length]
+        });
+        data.clear();
+        data.push_str(common);
+        if matches.len() == 1 {
+            data.push_str(" ");
+        } else {
+            self.candidates = matches.iter().map(|name| String::from(*name)).collect();
+        }
+    }
+}
+
+/// Splits `line` at whitespace, keeping words in double quotes together
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_word = false;
+    for character in line.chars() {
+        match character {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
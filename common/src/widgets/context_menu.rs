@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::{sys, ItemHoveredFlags, MouseButton, Ui};
+
+use crate::events::LongPress;
+
+/// Opens the popup `id` as a context menu for the last item when it's right-clicked or
+/// long-pressed, calling `build` to fill it in while it's open. Returns whether it's open.
+///
+/// Long presses are read from imgui's mouse state with `LongPress::default()`, so touchscreens
+/// get the menu in either System whether or not `Event::LongPress` is turned on. The menu opens
+/// at the cursor, extending away from the nearest edges of the window so it stays inside.
+pub fn context_menu_for(ui: &Ui, id: &str, build: impl FnOnce()) -> bool {
+    if ui.is_item_clicked_with_button(MouseButton::Right)
+        || is_item_long_pressed(ui, &LongPress::default())
+    {
+        ui.open_popup(id);
+        let [x, y] = ui.io().mouse_pos;
+        let [width, height] = ui.io().display_size;
+        let pivot = sys::ImVec2 {
+            x: if x > width / 2.0 { 1.0 } else { 0.0 },
+            y: if y > height / 2.0 { 1.0 } else { 0.0 },
+        };
+        #[allow(clippy::cast_possible_wrap)]
+        unsafe {
+            sys::igSetNextWindowPos(sys::ImVec2 { x, y }, sys::ImGuiCond_Always as _, pivot);
+        }
+    }
+    match ui.begin_popup(id) {
+        Some(_popup) => {
+            build();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether the left button went down on the last item and has just been held there for the
+/// long press's duration
+fn is_item_long_pressed(ui: &Ui, long_press: &LongPress) -> bool {
+    let io = unsafe { &*sys::igGetIO() };
+    let threshold = long_press.duration.as_secs_f32();
+    // Only the frame the threshold is crossed counts
+    if io.MouseDownDuration[0] < threshold || io.MouseDownDurationPrev[0] >= threshold {
+        return false;
+    }
+    if ui.is_mouse_dragging_with_threshold(MouseButton::Left, long_press.tolerance) {
+        return false;
+    }
+    let sys::ImVec2 { x, y } = io.MouseClickedPos[0];
+    let ([left, top], [right, bottom]) = (ui.item_rect_min(), ui.item_rect_max());
+    (left..right).contains(&x)
+        && (top..bottom).contains(&y)
+        && ui.is_item_hovered_with_flags(ItemHoveredFlags::ALLOW_WHEN_BLOCKED_BY_ACTIVE_ITEM)
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::Ui;
+
+use crate::geo::{CoordFormat, LatLon};
+
+/// Text entry for a latitude/longitude pair.
+///
+/// Any supported format is accepted on entry; the value is displayed in the chosen format.
+#[must_use]
+pub struct CoordInput<'a> {
+    label: &'a str,
+    format: CoordFormat,
+}
+
+impl<'a> CoordInput<'a> {
+    pub fn new(label: &'a str) -> Self {
+        CoordInput {
+            label,
+            format: CoordFormat::default(),
+        }
+    }
+
+    pub fn format(mut self, format: CoordFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Returns true if `coord` was changed. Invalid entries are discarded.
+    pub fn build(self, ui: &Ui, coord: &mut LatLon) -> bool {
+        let mut text = coord.format(self.format);
+        if ui
+            .input_text(self.label, &mut text)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            if let Some(parsed) = LatLon::parse(&text) {
+                let changed = parsed != *coord;
+                *coord = parsed;
+                return changed;
+            }
+        }
+        if ui.is_item_hovered() && !ui.is_item_active() {
+            ui.tooltip_text("N51 28.65 W0 27.68, 51°28'39\"N 0°27'41\"W or 51.4775 -0.4614");
+        }
+        false
+    }
+}
@@ -0,0 +1,348 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use imgui::{MouseButton, SelectableFlags, StyleColor, Ui};
+
+/// Whether a `FileBrowser` chooses a file or a folder
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BrowseMode {
+    OpenFile,
+    SelectFolder,
+}
+
+/// A file and folder chooser drawn with imgui, for use where native dialogs aren't available,
+/// e.g. inside X-Plane. Draw it in a window or popup of its own, and hold on to it between
+/// frames.
+///
+/// Folders are entered by double-clicking, and files chosen by double-clicking or with the
+/// Open button. Favourites are listed beside the folder with the home folder and, on Windows,
+/// the drives. Store `favorite_paths` in the app's settings to keep them between sessions.
+pub struct FileBrowser {
+    id: String,
+    mode: BrowseMode,
+    directory: PathBuf,
+    path_input: String,
+    entries: Vec<Entry>,
+    error: Option<String>,
+    filters: Vec<(String, Vec<String>)>,
+    filter: usize,
+    favorites: Vec<PathBuf>,
+    selected: Option<PathBuf>,
+    show_hidden: bool,
+    /// The home folder and the drives or root, found once as checking drives can be slow
+    places: Vec<(String, PathBuf)>,
+}
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+}
+
+impl FileBrowser {
+    /// Starts in the home folder
+    #[must_use]
+    pub fn new(id: &str, mode: BrowseMode) -> Self {
+        let mut browser = FileBrowser {
+            id: String::from(id),
+            mode,
+            directory: PathBuf::new(),
+            path_input: String::new(),
+            entries: Vec::new(),
+            error: None,
+            filters: Vec::new(),
+            filter: 0,
+            favorites: Vec::new(),
+            selected: None,
+            show_hidden: false,
+            places: home()
+                .map(|home| (String::from("Home"), home))
+                .into_iter()
+                .chain(
+                    roots()
+                        .into_iter()
+                        .map(|root| (root.display().to_string(), root)),
+                )
+                .collect(),
+        };
+        browser.navigate(&home().unwrap_or_else(|| PathBuf::from("/")));
+        browser
+    }
+
+    /// Starts in `directory` instead of the home folder
+    #[must_use]
+    pub fn directory(mut self, directory: &Path) -> Self {
+        self.navigate(directory);
+        self
+    }
+
+    /// Adds a choice of file types, e.g. `("Flight plans", &["fms", "fpl"])`. Extensions are
+    /// matched ignoring case. The first filter added is chosen initially; with none, all files
+    /// are shown.
+    #[must_use]
+    pub fn filter(mut self, label: &str, extensions: &[&str]) -> Self {
+        let extensions = extensions.iter().map(|e| e.to_lowercase()).collect();
+        self.filters.push((String::from(label), extensions));
+        self
+    }
+
+    #[must_use]
+    pub fn favorites(mut self, favorites: Vec<PathBuf>) -> Self {
+        self.favorites = favorites;
+        self
+    }
+
+    /// The favourite folders, including those the user added or removed
+    #[must_use]
+    pub fn favorite_paths(&self) -> &[PathBuf] {
+        &self.favorites
+    }
+
+    /// The folder being shown
+    #[must_use]
+    pub fn current_directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Shows `directory`'s contents, keeping the current folder if it can't be read
+    pub fn navigate(&mut self, directory: &Path) {
+        match read_entries(directory) {
+            Ok(entries) => {
+                self.directory = directory.to_path_buf();
+                self.entries = entries;
+                self.error = None;
+                self.selected = None;
+            }
+            Err(e) => self.error = Some(format!("{}: {e}", directory.display())),
+        }
+        self.path_input = self.directory.display().to_string();
+    }
+
+    /// Draws the browser filling the available space. Returns the chosen path once the user
+    /// confirms it.
+    pub fn build(&mut self, ui: &Ui) -> Option<PathBuf> {
+        let _id = ui.push_id(&self.id);
+        let mut chosen = None;
+        let mut target = None;
+
+        if ui.button("Up") {
+            target = self.directory.parent().map(Path::to_path_buf);
+        }
+        ui.same_line();
+        let favorite = self.favorites.contains(&self.directory);
+        if ui.button(if favorite { "Unstar" } else { "Star" }) {
+            if favorite {
+                self.favorites.retain(|path| *path != self.directory);
+            } else {
+                self.favorites.push(self.directory.clone());
+            }
+        }
+        ui.same_line();
+        ui.set_next_item_width(-1.0);
+        if ui
+            .input_text("##path", &mut self.path_input)
+            .enter_returns_true(true)
+            .build()
+        {
+            target = Some(PathBuf::from(&self.path_input));
+        }
+        if let Some(error) = &self.error {
+            ui.text_colored([1.0, 0.4, 0.4, 1.0], error);
+        }
+
+        let footer = ui.frame_height_with_spacing() + ui.clone_style().item_spacing[1];
+        ui.child_window("##places")
+            .size([160.0, -footer])
+            .border(true)
+            .build(|| {
+                target = self.draw_places(ui).or(target.take());
+            });
+        ui.same_line();
+        ui.child_window("##entries")
+            .size([0.0, -footer])
+            .border(true)
+            .build(|| {
+                let (entered, confirmed) = self.draw_entries(ui);
+                target = entered.or(target.take());
+                chosen = confirmed.or(chosen.take());
+            });
+
+        if !self.filters.is_empty() {
+            ui.set_next_item_width(160.0);
+            let labels: Vec<&str> = self
+                .filters
+                .iter()
+                .map(|(label, _)| label.as_str())
+                .collect();
+            ui.combo_simple_string("##filter", &mut self.filter, &labels);
+            ui.same_line();
+        }
+        ui.checkbox("Hidden", &mut self.show_hidden);
+        ui.same_line();
+        let (label, choice) = match self.mode {
+            BrowseMode::OpenFile => ("Open", self.selected.clone()),
+            BrowseMode::SelectFolder => (
+                "Select",
+                self.selected
+                    .clone()
+                    .or_else(|| Some(self.directory.clone())),
+            ),
+        };
+        let _disabled = choice.is_none().then(|| ui.begin_disabled(true));
+        if ui.button(label) {
+            chosen = choice;
+        }
+
+        if let Some(target) = target {
+            self.navigate(&target);
+        }
+        chosen
+    }
+
+    fn draw_places(&self, ui: &Ui) -> Option<PathBuf> {
+        let mut target = None;
+        let mut place = |label: &str, path: &Path| {
+            if ui
+                .selectable_config(label)
+                .selected(path == self.directory)
+                .build()
+            {
+                target = Some(path.to_path_buf());
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(path.display().to_string());
+            }
+        };
+        for (label, path) in &self.places {
+            place(label, path);
+        }
+        if !self.favorites.is_empty() {
+            ui.separator();
+            ui.text_disabled("Favourites");
+            for (index, path) in self.favorites.iter().enumerate() {
+                let name = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                place(&format!("{name}##favorite{index}"), path);
+            }
+        }
+        target
+    }
+
+    /// Returns a folder to enter and a path the user has confirmed
+    fn draw_entries(&mut self, ui: &Ui) -> (Option<PathBuf>, Option<PathBuf>) {
+        let mut entered = None;
+        let mut confirmed = None;
+        let size_column = ui.content_region_avail()[0] - 80.0;
+        let extensions = self
+            .filters
+            .get(self.filter)
+            .map(|(_, extensions)| extensions);
+        for entry in &self.entries {
+            if !self.show_hidden && entry.name.starts_with('.') {
+                continue;
+            }
+            let shown = entry.is_dir
+                || (self.mode == BrowseMode::OpenFile
+                    && match extensions {
+                        Some(extensions) => matches(&entry.path, extensions),
+                        None => true,
+                    });
+            if !shown {
+                continue;
+            }
+
+            let label = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            let selected = self.selected.as_ref() == Some(&entry.path);
+            if ui
+                .selectable_config(&label)
+                .selected(selected)
+                .flags(SelectableFlags::ALLOW_DOUBLE_CLICK)
+                .build()
+            {
+                self.selected = Some(entry.path.clone());
+                if ui.is_mouse_double_clicked(MouseButton::Left) {
+                    if entry.is_dir {
+                        entered = Some(entry.path.clone());
+                    } else {
+                        confirmed = Some(entry.path.clone());
+                    }
+                }
+            }
+            if !entry.is_dir {
+                ui.same_line_with_pos(size_column);
+                ui.text_colored(
+                    ui.style_color(StyleColor::TextDisabled),
+                    format_size(entry.size),
+                );
+            }
+        }
+        (entered, confirmed)
+    }
+}
+
+/// Folders first, then files, each in name order ignoring case
+fn read_entries(directory: &Path) -> std::io::Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = fs::read_dir(directory)?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let path = entry.path();
+            // follows links, so a link to a folder can be entered
+            let metadata = fs::metadata(&path).ok();
+            Entry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                is_dir: metadata.as_ref().is_some_and(fs::Metadata::is_dir),
+                size: metadata.map_or(0, |metadata| metadata.len()),
+                path,
+            }
+        })
+        .collect();
+    entries.sort_by_cached_key(|entry| (!entry.is_dir, entry.name.to_lowercase()));
+    Ok(entries)
+}
+
+fn matches(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| extensions.contains(&extension))
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// The drives on Windows, or the root folder elsewhere
+fn roots() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        (b'A'..=b'Z')
+            .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+            .filter(|drive| drive.exists())
+            .collect()
+    } else {
+        vec![PathBuf::from("/")]
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
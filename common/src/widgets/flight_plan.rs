@@ -0,0 +1,188 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::{DragDropFlags, TableFlags, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::RichCopy;
+use crate::geo::{CoordFormat, LatLon};
+use crate::widgets::{context_menu_for, CoordInput};
+
+const ROW_PAYLOAD: &str = "FLIGHT_PLAN_ROW";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub ident: String,
+    pub position: LatLon,
+}
+
+impl Waypoint {
+    #[must_use]
+    pub fn new(ident: &str, position: LatLon) -> Self {
+        Waypoint {
+            ident: String::from(ident),
+            position,
+        }
+    }
+}
+
+/// Table of waypoints with inline editing, drag-to-reorder and per-leg distance and bearing.
+#[must_use]
+pub struct FlightPlanTable<'a> {
+    id: &'a str,
+    format: CoordFormat,
+    removable: bool,
+}
+
+impl<'a> FlightPlanTable<'a> {
+    pub fn new(id: &'a str) -> Self {
+        FlightPlanTable {
+            id,
+            format: CoordFormat::default(),
+            removable: true,
+        }
+    }
+
+    pub fn format(mut self, format: CoordFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Shows a button on each row to delete the waypoint (default true)
+    pub fn removable(mut self, removable: bool) -> Self {
+        self.removable = removable;
+        self
+    }
+
+    /// The plan as a table to copy, with the legs as shown. Right-clicking or long-pressing a
+    /// row's number in the table copies it.
+    #[must_use]
+    pub fn rich_copy(&self, waypoints: &[Waypoint]) -> RichCopy {
+        let mut total = 0.0;
+        let rows: Vec<Vec<String>> = waypoints
+            .iter()
+            .enumerate()
+            .map(|(index, waypoint)| {
+                let mut row = vec![
+                    format!("{}", index + 1),
+                    waypoint.ident.clone(),
+                    waypoint.position.format(self.format),
+                ];
+                if index > 0 {
+                    let from = &waypoints[index - 1].position;
+                    let distance = from.distance_nm(&waypoint.position);
+                    total += distance;
+                    row.push(format!("{distance:.1}"));
+                    row.push(format!("{:03.0}", from.bearing_to(&waypoint.position)));
+                    row.push(format!("{total:.1}"));
+                }
+                row
+            })
+            .collect();
+        RichCopy::table(&["#", "Ident", "Position", "Dist", "Brg", "Total"], &rows)
+    }
+
+    /// Returns true if the plan was edited, reordered or had a waypoint removed.
+    pub fn build(self, ui: &Ui, waypoints: &mut Vec<Waypoint>) -> bool {
+        // Identifies this plan in the drag payload so rows can't be dropped into another table
+        let plan = waypoints.as_ptr() as usize;
+        let mut changed = false;
+        let mut moved = None;
+        let mut removed = None;
+
+        let columns = ["#", "Ident", "Position", "Dist", "Brg", "Total", ""];
+        let count = if self.removable { 7 } else { 6 };
+        let flags = TableFlags::BORDERS_INNER_V | TableFlags::ROW_BG | TableFlags::SIZING_FIXED_FIT;
+        let Some(_table) = ui.begin_table_with_flags(self.id, count, flags) else {
+            return false;
+        };
+        for column in &columns[..count] {
+            ui.table_setup_column(column);
+        }
+        ui.table_headers_row();
+
+        let mut total = 0.0;
+        for index in 0..waypoints.len() {
+            let _id = ui.push_id_usize(index);
+            ui.table_next_row();
+
+            ui.table_next_column();
+            ui.selectable(format!("{}", index + 1));
+            context_menu_for(ui, "##copy", || {
+                if ui.menu_item("Copy flight plan") {
+                    self.rich_copy(waypoints).copy(ui);
+                }
+            });
+            if let Some(tooltip) = ui
+                .drag_drop_source_config(ROW_PAYLOAD)
+                .begin_payload((plan, index))
+            {
+                ui.text(&waypoints[index].ident);
+                tooltip.end();
+            }
+            if let Some(target) = ui.drag_drop_target() {
+                if let Some(Ok(payload)) =
+                    target.accept_payload::<(usize, usize), _>(ROW_PAYLOAD, DragDropFlags::empty())
+                {
+                    if payload.data.0 == plan {
+                        moved = Some((payload.data.1, index));
+                    }
+                }
+                target.pop();
+            }
+
+            ui.table_next_column();
+            ui.set_next_item_width(80.0);
+            changed |= ui
+                .input_text("##ident", &mut waypoints[index].ident)
+                .build();
+
+            ui.table_next_column();
+            ui.set_next_item_width(200.0);
+            changed |= CoordInput::new("##position")
+                .format(self.format)
+                .build(ui, &mut waypoints[index].position);
+
+            if index > 0 {
+                let from = &waypoints[index - 1].position;
+                let to = &waypoints[index].position;
+                let distance = from.distance_nm(to);
+                total += distance;
+
+                ui.table_next_column();
+                ui.text(format!("{distance:.1}"));
+                ui.table_next_column();
+                ui.text(format!("{:03.0}", from.bearing_to(to)));
+                ui.table_next_column();
+                ui.text(format!("{total:.1}"));
+            } else {
+                ui.table_next_column();
+                ui.table_next_column();
+                ui.table_next_column();
+            }
+
+            if self.removable {
+                ui.table_next_column();
+                if ui.small_button("x") {
+                    removed = Some(index);
+                }
+            }
+        }
+
+        if let Some((from, to)) = moved {
+            if from != to && from < waypoints.len() {
+                let waypoint = waypoints.remove(from);
+                waypoints.insert(to, waypoint);
+                changed = true;
+            }
+        }
+        if let Some(index) = removed {
+            waypoints.remove(index);
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+
+use imgui::{MouseCursor, StyleColor, Ui};
+use tracing::warn;
+
+use crate::platform_utils::open_url;
+
+/// Links opened this session, shown in the visited colour
+static VISITED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Draws `label` as a hyperlink to `url`, opening it with `platform_utils::open_url` when
+/// clicked. Returns true if it was clicked.
+///
+/// The colours come from the style's button colours so links follow the theme, dimmed once a
+/// link has been opened this session. Hovering underlines the link and shows `url` as a tooltip.
+/// Text after `##` in `label` isn't shown, as with other widgets.
+pub fn link(ui: &Ui, label: &str, url: &str) -> bool {
+    let text = label.split("##").next().unwrap_or_default();
+    let size = ui.calc_text_size(text);
+    let [x, y] = ui.cursor_screen_pos();
+
+    let clicked = {
+        let _id = ui.push_id(url);
+        ui.invisible_button(label, [size[0].max(1.0), size[1]])
+    };
+    let hovered = ui.is_item_hovered();
+    let visited = VISITED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(url);
+
+    let colour = if ui.is_item_active() {
+        ui.style_color(StyleColor::Text)
+    } else if hovered {
+        ui.style_color(StyleColor::ButtonHovered)
+    } else if visited {
+        mix(
+            ui.style_color(StyleColor::ButtonActive),
+            ui.style_color(StyleColor::TextDisabled),
+        )
+    } else {
+        ui.style_color(StyleColor::ButtonActive)
+    };
+    let draw_list = ui.get_window_draw_list();
+    draw_list.add_text([x, y], colour, text);
+    if hovered {
+        draw_list
+            .add_line([x, y + size[1]], [x + size[0], y + size[1]], colour)
+            .build();
+        ui.set_mouse_cursor(Some(MouseCursor::Hand));
+        ui.tooltip_text(url);
+    }
+
+    if clicked {
+        VISITED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(String::from(url));
+        if let Err(e) = open_url(url) {
+            warn!(url, error = %e, "Unable to open link");
+        }
+    }
+    clicked
+}
+
+fn mix(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        f32::midpoint(a[0], b[0]),
+        f32::midpoint(a[1], b[1]),
+        f32::midpoint(a[2], b[2]),
+        a[3],
+    ]
+}
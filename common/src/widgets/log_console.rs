@@ -0,0 +1,254 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Write};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use imgui::{ListClipper, StyleColor, Ui};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::clipboard::RichCopy;
+use crate::widgets::time::{format_time, utc_now};
+
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+/// A tracing event as captured by `LogBuffer`
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    /// Seconds since midnight UTC
+    pub time: u32,
+    pub level: Level,
+    pub target: String,
+    /// The message followed by any other fields as `name=value`
+    pub message: String,
+}
+
+/// The most recent tracing events, shown by a `LogConsole`. Cloning gives another handle.
+///
+/// Install its `layer` alongside the app's own, e.g.
+/// `tracing_subscriber::registry().with(buffer.layer())`. Events arrive from any thread.
+#[derive(Clone)]
+pub struct LogBuffer {
+    inner: Arc<Mutex<VecDeque<LogRecord>>>,
+    capacity: usize,
+}
+
+/// Captures events into a `LogBuffer`, dropping the oldest once it's full
+pub struct LogLayer {
+    buffer: LogBuffer,
+}
+
+/// Shows a `LogBuffer`'s events, newest at the bottom, filtered by level and a search of their
+/// target and message. The shown events can be copied to the clipboard.
+pub struct LogConsole {
+    id: String,
+    buffer: LogBuffer,
+    level: Level,
+    query: String,
+    follow: bool,
+}
+
+impl LogBuffer {
+    /// Keeps the last `capacity` events
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        LogBuffer {
+            inner: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity: capacity.max(1),
+        }
+    }
+
+    #[must_use]
+    pub fn layer(&self) -> LogLayer {
+        LogLayer {
+            buffer: self.clone(),
+        }
+    }
+
+    pub fn push(&self, record: LogRecord) {
+        let mut records = self.lock();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<LogRecord>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        self.buffer.push(LogRecord {
+            time: utc_now(),
+            level: *metadata.level(),
+            target: String::from(metadata.target()),
+            message: visitor.finish(),
+        });
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.message.is_empty() {
+            String::from(self.fields.trim_start())
+        } else {
+            self.message + &self.fields
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:5} {}: {}",
+            format_time(self.time, true),
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+impl LogConsole {
+    /// Shows events at `Level::INFO` and above until another level is chosen
+    #[must_use]
+    pub fn new(id: &str, buffer: LogBuffer) -> Self {
+        LogConsole {
+            id: String::from(id),
+            buffer,
+            level: Level::INFO,
+            query: String::new(),
+            follow: true,
+        }
+    }
+
+    /// Draws the filters and buttons, with the events below filling the available space
+    pub fn build(&mut self, ui: &Ui) {
+        let _id = ui.push_id(&self.id);
+
+        ui.set_next_item_width(80.0);
+        if let Some(_combo) = ui.begin_combo("##level", self.level.as_str()) {
+            for level in LEVELS {
+                if ui
+                    .selectable_config(level.as_str())
+                    .selected(level == self.level)
+                    .build()
+                {
+                    self.level = level;
+                }
+            }
+        }
+        ui.same_line();
+        ui.set_next_item_width(-170.0);
+        ui.input_text("##query", &mut self.query)
+            .hint("Search")
+            .build();
+
+        let shown = self.filtered();
+        ui.same_line();
+        if ui.button("Copy") {
+            let lines: Vec<String> = shown.iter().map(ToString::to_string).collect();
+            RichCopy::text(&lines.join("\n")).copy(ui);
+        }
+        ui.same_line();
+        if ui.button("Clear") {
+            self.buffer.clear();
+        }
+        ui.same_line();
+        ui.checkbox("Follow", &mut self.follow);
+
+        ui.child_window("##records")
+            .horizontal_scrollbar(true)
+            .build(|| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let clipper = ListClipper::new(shown.len() as i32);
+                for index in clipper.begin(ui).iter() {
+                    #[allow(clippy::cast_sign_loss)]
+                    let record = &shown[index as usize];
+                    let colour = match record.level {
+                        Level::ERROR => [1.0, 0.4, 0.4, 1.0],
+                        Level::WARN => [1.0, 0.8, 0.3, 1.0],
+                        Level::INFO => ui.style_color(StyleColor::Text),
+                        _ => ui.style_color(StyleColor::TextDisabled),
+                    };
+                    ui.text_colored(colour, record.to_string());
+                }
+                if self.follow {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+            });
+    }
+
+    /// The events at or above the chosen level that match the search, copied so the buffer
+    /// isn't locked while they're drawn
+    fn filtered(&self) -> Vec<LogRecord> {
+        let query = self.query.to_lowercase();
+        self.buffer
+            .lock()
+            .iter()
+            .filter(|record| record.level <= self.level)
+            .filter(|record| {
+                query.is_empty()
+                    || record.message.to_lowercase().contains(&query)
+                    || record.target.to_lowercase().contains(&query)
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::RefCell;
+
+use gl21 as gl;
+use image::{Rgba, RgbaImage};
+use imgui::{Image, TextureId, Ui};
+use qrcode::{Color, QrCode};
+use tracing::warn;
+
+use crate::backend::{create_texture, delete_texture};
+
+/// Pixels per module in the texture, which is drawn scaled without smoothing
+const MODULE_PIXELS: u32 = 4;
+/// The blank border, in modules, scanners need around the code
+const QUIET_ZONE: u32 = 4;
+/// Codes kept as textures, the least recently drawn being dropped first
+const CACHE_SIZE: usize = 8;
+
+thread_local! {
+    static CACHE: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Entry {
+    data: String,
+    /// `None` if `data` couldn't be encoded
+    texture: Option<TextureId>,
+    last_drawn: i32,
+}
+
+/// Draws `data` as a QR code `size` pixels square, e.g. a pairing code for a companion app.
+///
+/// The code is encoded into a texture the first time it's drawn, through the current backend,
+/// and kept while it's drawn regularly. Data too long for a QR code is shown as a message
+/// instead.
+pub fn qr_code(ui: &Ui, data: &str, size: f32) {
+    let frame = ui.frame_count();
+    let texture = CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(entry) = cache.iter_mut().find(|entry| entry.data == data) {
+            entry.last_drawn = frame;
+            return entry.texture;
+        }
+        if cache.len() == CACHE_SIZE {
+            let oldest = (0..cache.len())
+                .min_by_key(|index| cache[*index].last_drawn)
+                .unwrap_or_default();
+            if let Some(texture) = cache.swap_remove(oldest).texture {
+                delete_texture(texture);
+            }
+        }
+        let texture = encode(data);
+        cache.push(Entry {
+            data: String::from(data),
+            texture,
+            last_drawn: frame,
+        });
+        texture
+    });
+
+    match texture {
+        Some(texture) => Image::new(texture, [size, size]).build(ui),
+        None => ui.text_disabled("Too much data for a QR code"),
+    }
+}
+
+fn encode(data: &str) -> Option<TextureId> {
+    let code = match QrCode::new(data) {
+        Ok(code) => code,
+        Err(e) => {
+            warn!(error = %e, "Unable to encode QR code");
+            return None;
+        }
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let modules = code.width() as u32;
+    let colors = code.to_colors();
+    let side = (modules + QUIET_ZONE * 2) * MODULE_PIXELS;
+    let image = RgbaImage::from_fn(side, side, |x, y| {
+        let (column, row) = (x / MODULE_PIXELS, y / MODULE_PIXELS);
+        let dark = (QUIET_ZONE..QUIET_ZONE + modules).contains(&column)
+            && (QUIET_ZONE..QUIET_ZONE + modules).contains(&row)
+            && colors[((row - QUIET_ZONE) * modules + column - QUIET_ZONE) as usize] == Color::Dark;
+        if dark {
+            Rgba([0, 0, 0, 255])
+        } else {
+            Rgba([255, 255, 255, 255])
+        }
+    });
+    match create_texture(&image) {
+        Ok(texture) => {
+            // the texture is still bound; smoothing would blur the modules' edges
+            #[allow(clippy::cast_possible_wrap)]
+            unsafe {
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as _);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as _);
+            }
+            Some(texture)
+        }
+        Err(e) => {
+            warn!(error = %e, "Unable to create QR code texture");
+            None
+        }
+    }
+}
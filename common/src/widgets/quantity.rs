@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::Ui;
+
+use crate::format::{self, Locale};
+use crate::units::{Unit, UnitPreferences};
+
+/// Numeric input for a physical quantity.
+///
+/// The bound value is stored in a fixed unit chosen by the caller, and is shown and edited in
+/// whichever unit the user prefers for that dimension. Decimals are written with the current
+/// `Locale`'s separator.
+#[must_use]
+pub struct QuantityInput<'a> {
+    label: &'a str,
+    unit: Unit,
+    preferences: &'a mut UnitPreferences,
+    step: Option<f32>,
+    unit_selector: bool,
+}
+
+impl<'a> QuantityInput<'a> {
+    pub fn new(label: &'a str, unit: Unit, preferences: &'a mut UnitPreferences) -> Self {
+        QuantityInput {
+            label,
+            unit,
+            preferences,
+            step: None,
+            unit_selector: false,
+        }
+    }
+
+    /// Step applied by the +/- buttons, in the displayed unit
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Show a combo next to the input that changes the user's preferred unit
+    pub fn unit_selector(mut self, unit_selector: bool) -> Self {
+        self.unit_selector = unit_selector;
+        self
+    }
+
+    /// Returns true if `value` was changed.
+    pub fn build(self, ui: &Ui, value: &mut f32) -> bool {
+        let preferred = self.preferences.preferred(self.unit.dimension());
+        let (display_unit, mut display_value) = match self.unit.convert(*value, preferred) {
+            Some(converted) => (preferred, converted),
+            None => (self.unit, *value),
+        };
+        let locale = format::current();
+
+        let _id = ui.push_id(self.label);
+        // imgui's own numeric inputs only understand a decimal point
+        let changed = if locale.decimal_separator == '.' {
+            let format = format!("%.{}f {}", display_unit.precision(), display_unit.symbol());
+            let mut input = ui.input_float(self.label, &mut display_value);
+            if let Some(step) = self.step {
+                input = input.step(step);
+            }
+            input.display_format(&format).build()
+        } else {
+            self.build_localised(ui, &mut display_value, display_unit, &locale)
+        };
+        if changed {
+            if let Some(converted) = display_unit.convert(display_value, self.unit) {
+                *value = converted;
+            }
+        }
+
+        if self.unit_selector {
+            ui.same_line();
+            let width = ui.calc_text_size("km/h")[0] + ui.frame_height() * 2.0;
+            ui.set_next_item_width(width);
+            if let Some(_combo) = ui.begin_combo("##unit", display_unit.symbol()) {
+                for &unit in display_unit.alternatives() {
+                    if ui
+                        .selectable_config(unit.symbol())
+                        .selected(unit == display_unit)
+                        .build()
+                    {
+                        self.preferences.set_preferred(unit);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// A text field laid out like imgui's, with the step buttons between it and the label
+    fn build_localised(&self, ui: &Ui, value: &mut f32, unit: Unit, locale: &Locale) -> bool {
+        let number = locale.format_number(f64::from(*value), unit.precision(), false);
+        let mut text = format!("{number} {}", unit.symbol());
+        let button_size = ui.frame_height();
+        let spacing = ui.clone_style().item_inner_spacing[0];
+        let buttons_width = if self.step.is_some() {
+            (button_size + spacing) * 2.0
+        } else {
+            0.0
+        };
+
+        let mut changed = false;
+        ui.set_next_item_width((ui.calc_item_width() - buttons_width).max(1.0));
+        if ui
+            .input_text("##value", &mut text)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            let number = text.trim().trim_end_matches(unit.symbol());
+            #[allow(clippy::cast_possible_truncation)]
+            if let Some(parsed) = locale.parse_number(number) {
+                *value = parsed as f32;
+                changed = true;
+            }
+        }
+        if let Some(step) = self.step {
+            for (label, delta) in [("-", -step), ("+", step)] {
+                ui.same_line_with_spacing(0.0, spacing);
+                if ui.button_with_size(label, [button_size, button_size]) {
+                    *value += delta;
+                    changed = true;
+                }
+            }
+        }
+        let label = self.label.split("##").next().unwrap_or_default();
+        if !label.is_empty() {
+            ui.same_line_with_spacing(0.0, spacing);
+            ui.text(label);
+        }
+        changed
+    }
+}
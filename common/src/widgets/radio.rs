@@ -0,0 +1,246 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::{InputTextCallback, InputTextCallbackHandler, Ui};
+
+/// Frequency band and channel spacing for a `FrequencyInput`. Frequencies are in kHz.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Band {
+    /// VHF COM, 118.000-136.990 MHz with 8.33 kHz channel names
+    Com833,
+    /// VHF COM, 118.000-136.975 MHz with 25 kHz spacing
+    Com25,
+    /// VOR/ILS, 108.00-117.95 MHz with 50 kHz spacing
+    Nav,
+}
+
+impl Band {
+    #[must_use]
+    pub fn range(self) -> (u32, u32) {
+        match self {
+            Band::Com833 => (118_000, 136_990),
+            Band::Com25 => (118_000, 136_975),
+            Band::Nav => (108_000, 117_950),
+        }
+    }
+
+    fn step(self) -> u32 {
+        match self {
+            Band::Com833 => 5,
+            Band::Com25 => 25,
+            Band::Nav => 50,
+        }
+    }
+
+    fn decimals(self) -> usize {
+        match self {
+            Band::Com833 | Band::Com25 => 3,
+            Band::Nav => 2,
+        }
+    }
+
+    /// Returns true if `khz` is a valid channel in this band.
+    ///
+    /// 8.33 kHz channel names are the 25 kHz channels plus those ending in 05, 10, 15, 30, 35,
+    /// 40, 55, 60, 65, 80, 85 or 90.
+    #[must_use]
+    pub fn is_valid(self, khz: u32) -> bool {
+        let (min, max) = self.range();
+        self.on_grid(khz) && (min..=max).contains(&khz)
+    }
+
+    fn on_grid(self, khz: u32) -> bool {
+        match self {
+            Band::Com833 => khz.is_multiple_of(5) && khz % 25 != 20,
+            Band::Com25 | Band::Nav => khz.is_multiple_of(self.step()),
+        }
+    }
+
+    /// Moves `channels` channels up or down from `khz`, wrapping within the kHz part of the
+    /// frequency as a radio tuning knob does.
+    #[must_use]
+    pub fn tune(self, khz: u32, channels: i32) -> u32 {
+        let mhz = khz / 1000 * 1000;
+        let mut khz = khz;
+        for _ in 0..channels.unsigned_abs() {
+            loop {
+                khz = if channels > 0 {
+                    if khz + self.step() >= mhz + 1000 {
+                        mhz
+                    } else {
+                        khz + self.step()
+                    }
+                } else if khz < mhz + self.step() {
+                    mhz + 1000 - self.step()
+                } else {
+                    khz - self.step()
+                };
+                if self.on_grid(khz) {
+                    break;
+                }
+            }
+        }
+        khz
+    }
+
+    /// Moves `mhz` whole MHz up or down from `khz`, wrapping within the band.
+    #[must_use]
+    pub fn tune_mhz(self, khz: u32, mhz: i32) -> u32 {
+        let (min, max) = self.range();
+        let span = i64::from(max / 1000 - min / 1000 + 1);
+        let current = i64::from(khz / 1000 - min / 1000);
+        let tuned = (current + i64::from(mhz)).rem_euclid(span);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let tuned = (tuned as u32 + min / 1000) * 1000 + khz % 1000;
+        tuned.min(max)
+    }
+
+    #[must_use]
+    pub fn format(self, khz: u32) -> String {
+        let decimals = self.decimals();
+        let fraction = khz % 1000 / 10u32.pow(3 - u32::try_from(decimals).unwrap_or(3));
+        format!("{}.{fraction:0decimals$}", khz / 1000)
+    }
+
+    /// Parses a frequency in MHz such as "121.5" or "121.505", returning kHz if it is valid in
+    /// this band.
+    #[must_use]
+    pub fn parse(self, text: &str) -> Option<u32> {
+        let (whole, fraction) = text.trim().split_once('.').unwrap_or((text.trim(), ""));
+        if fraction.len() > 3 {
+            return None;
+        }
+        let whole: u32 = whole.parse().ok()?;
+        let fraction: u32 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{fraction:0<3}").parse().ok()?
+        };
+        let khz = whole.checked_mul(1000)?.checked_add(fraction)?;
+        self.is_valid(khz).then_some(khz)
+    }
+}
+
+/// Text entry for a radio frequency with validation and scroll-to-tune.
+///
+/// Scrolling over the field tunes by one channel, or by 1 MHz with shift held.
+#[must_use]
+pub struct FrequencyInput<'a> {
+    label: &'a str,
+    band: Band,
+}
+
+impl<'a> FrequencyInput<'a> {
+    pub fn new(label: &'a str, band: Band) -> Self {
+        FrequencyInput { label, band }
+    }
+
+    /// Returns true if `khz` was changed. Invalid entries are discarded.
+    pub fn build(self, ui: &Ui, khz: &mut u32) -> bool {
+        let mut text = self.band.format(*khz);
+        let mut changed = false;
+        if ui
+            .input_text(self.label, &mut text)
+            .chars_decimal(true)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            if let Some(parsed) = self.band.parse(&text) {
+                changed = parsed != *khz;
+                *khz = parsed;
+            }
+        }
+
+        if let Some(steps) = scroll_steps(ui) {
+            let tuned = if ui.io().key_shift {
+                self.band.tune_mhz(*khz, steps)
+            } else {
+                self.band.tune(*khz, steps)
+            };
+            changed |= tuned != *khz;
+            *khz = tuned;
+        }
+        changed
+    }
+}
+
+/// Text entry for a four digit transponder code, accepting only octal digits.
+///
+/// Codes are held as their decimal representation, e.g. 7700. Scrolling over the field steps
+/// the code up or down.
+#[must_use]
+pub struct SquawkInput<'a> {
+    label: &'a str,
+}
+
+impl<'a> SquawkInput<'a> {
+    pub fn new(label: &'a str) -> Self {
+        SquawkInput { label }
+    }
+
+    /// Returns true if `code` was changed.
+    pub fn build(self, ui: &Ui, code: &mut u16) -> bool {
+        let mut text = format!("{:04}", *code);
+        let mut changed = false;
+        if ui
+            .input_text(self.label, &mut text)
+            .callback(InputTextCallback::CHAR_FILTER, OctalFilter)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            if let Some(parsed) = parse_squawk(&text) {
+                changed = parsed != *code;
+                *code = parsed;
+            }
+        }
+
+        if let Some(steps) = scroll_steps(ui) {
+            let octal = squawk_to_octal(*code) + steps;
+            let tuned = octal_to_squawk(octal.rem_euclid(0o10000));
+            changed |= tuned != *code;
+            *code = tuned;
+        }
+        changed
+    }
+}
+
+/// Returns the code if `text` is one to four octal digits.
+#[must_use]
+pub fn parse_squawk(text: &str) -> Option<u16> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > 4 || !text.chars().all(|c| ('0'..='7').contains(&c)) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn squawk_to_octal(code: u16) -> i32 {
+    i32::from_str_radix(&code.to_string(), 8).unwrap_or(0)
+}
+
+fn octal_to_squawk(octal: i32) -> u16 {
+    format!("{octal:o}").parse().unwrap_or(0)
+}
+
+fn scroll_steps(ui: &Ui) -> Option<i32> {
+    let wheel = ui.io().mouse_wheel;
+    if ui.is_item_hovered() && !ui.is_item_active() && wheel != 0.0 {
+        #[allow(clippy::cast_possible_truncation)]
+        Some(wheel.signum() as i32)
+    } else {
+        None
+    }
+}
+
+struct OctalFilter;
+
+impl InputTextCallbackHandler for OctalFilter {
+    fn char_filter(&mut self, c: char) -> Option<char> {
+        ('0'..='7').contains(&c).then_some(c)
+    }
+}
@@ -0,0 +1,255 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cmp::Reverse;
+use std::f32::consts::TAU;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use imgui::{ListClipper, StyleColor, Ui};
+
+/// Supplies the items shown by a `SearchCombo`
+pub trait ItemSource {
+    /// Starts a lookup for `query`, superseding any lookup still in progress
+    fn request(&mut self, query: &str);
+
+    /// Returns the results of the latest lookup once they are available
+    fn poll(&mut self) -> Option<Vec<String>>;
+}
+
+/// A fixed list of items, fuzzy filtered on the calling thread
+pub struct StaticSource {
+    items: Vec<String>,
+    results: Option<Vec<String>>,
+}
+
+impl StaticSource {
+    #[must_use]
+    pub fn new(items: Vec<String>) -> Self {
+        StaticSource {
+            items,
+            results: None,
+        }
+    }
+}
+
+impl ItemSource for StaticSource {
+    fn request(&mut self, query: &str) {
+        self.results = Some(fuzzy_filter(query, &self.items));
+    }
+
+    fn poll(&mut self) -> Option<Vec<String>> {
+        self.results.take()
+    }
+}
+
+/// Runs lookups on a background thread, e.g. for navdata queries. Queries made while a lookup is
+/// running are dropped in favour of the latest, and results from superseded lookups are
+/// discarded.
+pub struct ThreadedSource {
+    queries: Sender<(u64, String)>,
+    results: Receiver<(u64, Vec<String>)>,
+    /// The ID of the latest query
+    latest: u64,
+}
+
+impl ThreadedSource {
+    pub fn new(lookup: impl Fn(&str) -> Vec<String> + Send + 'static) -> Self {
+        let (queries, requests) = channel::<(u64, String)>();
+        let (sender, results) = channel();
+        // stops once the source is dropped and its sender with it
+        thread::spawn(move || {
+            while let Ok(request) = requests.recv() {
+                let (id, query) = requests.try_iter().last().unwrap_or(request);
+                if sender.send((id, lookup(&query))).is_err() {
+                    break;
+                }
+            }
+        });
+        ThreadedSource {
+            queries,
+            results,
+            latest: 0,
+        }
+    }
+}
+
+impl ItemSource for ThreadedSource {
+    fn request(&mut self, query: &str) {
+        self.latest += 1;
+        // the worker only stops once this source is dropped
+        let _ = self.queries.send((self.latest, String::from(query)));
+    }
+
+    fn poll(&mut self) -> Option<Vec<String>> {
+        self.results
+            .try_iter()
+            .filter(|(id, _)| *id == self.latest)
+            .last()
+            .map(|(_, results)| results)
+    }
+}
+
+/// Combo box with a search field, for lists too long to scroll through (airports, navaids etc.).
+///
+/// Unlike the other widgets this holds state between frames, so keep it alongside the app's data.
+pub struct SearchCombo<S: ItemSource> {
+    label: String,
+    source: S,
+    query: String,
+    results: Vec<String>,
+    loading: bool,
+}
+
+impl<S: ItemSource> SearchCombo<S> {
+    pub fn new(label: &str, source: S) -> Self {
+        SearchCombo {
+            label: String::from(label),
+            source,
+            query: String::new(),
+            results: Vec::new(),
+            loading: false,
+        }
+    }
+
+    /// Returns true if an item was selected.
+    pub fn build(&mut self, ui: &Ui, selected: &mut String) -> bool {
+        let Some(_combo) = ui.begin_combo(&self.label, &*selected) else {
+            return false;
+        };
+
+        if ui.is_window_appearing() {
+            self.query.clear();
+            self.refresh();
+            ui.set_keyboard_focus_here();
+        }
+        if ui
+            .input_text("##query", &mut self.query)
+            .hint("Search")
+            .build()
+        {
+            self.refresh();
+        }
+
+        if let Some(results) = self.source.poll() {
+            self.results = results;
+            self.loading = false;
+        }
+        if self.loading {
+            spinner(ui, ui.text_line_height() / 2.0);
+            ui.same_line();
+            ui.text_disabled("Searching...");
+        }
+
+        let mut changed = false;
+        ui.child_window("##results")
+            .size([0.0, ui.text_line_height_with_spacing() * 10.0])
+            .build(|| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let clipper = ListClipper::new(self.results.len() as i32);
+                for index in clipper.begin(ui).iter() {
+                    #[allow(clippy::cast_sign_loss)]
+                    let item = &self.results[index as usize];
+                    if ui
+                        .selectable_config(item)
+                        .selected(item == selected)
+                        .build()
+                    {
+                        selected.clone_from(item);
+                        changed = true;
+                        ui.close_current_popup();
+                    }
+                }
+            });
+        changed
+    }
+
+    fn refresh(&mut self) {
+        self.source.request(&self.query);
+        self.loading = true;
+    }
+}
+
+/// Returns the items matching `query`, best match first. An empty query matches everything.
+#[must_use]
+pub fn fuzzy_filter(query: &str, items: &[String]) -> Vec<String> {
+    let mut scored: Vec<_> = items
+        .iter()
+        .filter_map(|item| fuzzy_score(query, item).map(|score| (score, item)))
+        .collect();
+    // stable sort keeps equally scored items in their original order
+    scored.sort_by_key(|(score, _)| Reverse(*score));
+    scored.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+/// Case-insensitive subsequence match, favouring substrings, prefixes, word starts and
+/// consecutive runs. Returns `None` if `candidate` doesn't contain every character of `query` in
+/// order.
+#[must_use]
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<u32> {
+    let query = query.to_lowercase();
+    let candidate = candidate.to_lowercase();
+
+    let mut score = 0;
+    let mut wanted = query.chars().peekable();
+    let mut previous: Option<char> = None;
+    let mut run = 0;
+    for ch in candidate.chars() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if ch == next {
+            wanted.next();
+            run += 1;
+            score += run;
+            if !previous.is_some_and(char::is_alphanumeric) {
+                score += 5;
+            }
+        } else {
+            run = 0;
+        }
+        previous = Some(ch);
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+
+    if let Some(index) = candidate.find(&query) {
+        #[allow(clippy::cast_possible_truncation)]
+        let length = query.chars().count() as u32;
+        score += 10 * length;
+        if index == 0 {
+            score += 20;
+        } else if candidate[..index].ends_with(|c: char| !c.is_alphanumeric()) {
+            score += 10;
+        }
+    }
+    Some(score)
+}
+
+fn spinner(ui: &Ui, radius: f32) {
+    const DOTS: u8 = 8;
+    let [x, y] = ui.cursor_screen_pos();
+    let centre = [x + radius, y + radius];
+    let colour = ui.style_color(StyleColor::Text);
+    #[allow(clippy::cast_possible_truncation)]
+    let phase = (ui.time() * 8.0) as f32;
+    let draw_list = ui.get_window_draw_list();
+    for dot in 0..DOTS {
+        let angle = f32::from(dot) / f32::from(DOTS) * TAU;
+        let fade = (phase - f32::from(dot)).rem_euclid(f32::from(DOTS)) / f32::from(DOTS);
+        let position = [
+            centre[0] + angle.cos() * radius * 0.7,
+            centre[1] + angle.sin() * radius * 0.7,
+        ];
+        let colour = [colour[0], colour[1], colour[2], colour[3] * (1.0 - fade)];
+        draw_list
+            .add_circle(position, radius * 0.2, colour)
+            .filled(true)
+            .build();
+    }
+    ui.dummy([radius * 2.0, radius * 2.0]);
+}
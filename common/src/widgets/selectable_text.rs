@@ -0,0 +1,251 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::{Key, MouseButton, MouseCursor, StyleColor, Ui};
+
+use crate::clipboard::RichCopy;
+use crate::widgets::context_menu_for;
+
+const MENU_POPUP: &str = "##selectable_text_menu";
+
+/// Read-only text, wrapped to the available width, that can be selected by dragging with the
+/// mouse and copied, e.g. a METAR or lines from a log. imgui's own text can't be selected.
+///
+/// Double-click selects a word. Ctrl+C (Cmd+C on macOS) copies the selection and Ctrl+A selects
+/// everything while the text has focus, and right-clicking or a long press shows the same as a
+/// menu. The selection is kept between frames, so hold on to the widget rather than creating it
+/// each frame.
+pub struct SelectableText {
+    id: String,
+    /// Byte offsets where the selection was started and where it now ends
+    selection: Option<(usize, usize)>,
+    focused: bool,
+}
+
+/// A wrapped line, as the byte offset and x position of each character boundary from its start
+/// to its end
+struct Line {
+    stops: Vec<(usize, f32)>,
+}
+
+impl SelectableText {
+    #[must_use]
+    pub fn new(id: &str) -> Self {
+        SelectableText {
+            id: String::from(id),
+            selection: None,
+            focused: false,
+        }
+    }
+
+    /// The selected part of `text`, if any
+    #[must_use]
+    pub fn selected<'t>(&self, text: &'t str) -> Option<&'t str> {
+        let (start, end) = self.range()?;
+        text.get(start..end).filter(|selected| !selected.is_empty())
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Draws `text` at the cursor, filling the available width
+    pub fn build(&mut self, ui: &Ui, text: &str) {
+        // the text may have changed since the selection was made
+        if self.selection.is_some_and(|(anchor, cursor)| {
+            !text.is_char_boundary(anchor) || !text.is_char_boundary(cursor)
+        }) {
+            self.selection = None;
+        }
+
+        let width = ui.content_region_avail()[0].max(1.0);
+        let line_height = ui.text_line_height();
+        let lines = layout(ui, text, width);
+        #[allow(clippy::cast_precision_loss)]
+        let height = line_height * lines.len() as f32;
+
+        let origin = ui.cursor_screen_pos();
+        ui.invisible_button(&self.id, [width, height]);
+        let hovered = ui.is_item_hovered();
+        let hit = || {
+            let [x, y] = ui.io().mouse_pos;
+            hit_test(&lines, line_height, [x - origin[0], y - origin[1]])
+        };
+
+        if hovered {
+            ui.set_mouse_cursor(Some(MouseCursor::TextInput));
+        }
+        if ui.is_item_clicked() {
+            self.focused = true;
+            let offset = hit();
+            self.selection = if ui.is_mouse_double_clicked(MouseButton::Left) {
+                Some(word_at(text, offset))
+            } else if ui.io().key_shift {
+                Some((self.selection.map_or(offset, |(anchor, _)| anchor), offset))
+            } else {
+                Some((offset, offset))
+            };
+        } else if ui.is_item_active() && ui.is_mouse_dragging(MouseButton::Left) {
+            if let Some((_, cursor)) = &mut self.selection {
+                *cursor = hit();
+            }
+        } else if !hovered && ui.is_mouse_clicked(MouseButton::Left) {
+            self.focused = false;
+        }
+
+        let io = ui.io();
+        let shortcut = if io.config_mac_os_behaviors {
+            io.key_super
+        } else {
+            io.key_ctrl
+        };
+        if self.focused && shortcut && ui.is_key_pressed(Key::C) {
+            self.copy_selection(ui, text);
+        }
+        if self.focused && shortcut && ui.is_key_pressed(Key::A) {
+            self.selection = Some((0, text.len()));
+        }
+
+        self.draw(ui, text, &lines, origin);
+        self.draw_menu(ui, text);
+    }
+
+    fn range(&self) -> Option<(usize, usize)> {
+        self.selection
+            .map(|(anchor, cursor)| (anchor.min(cursor), anchor.max(cursor)))
+    }
+
+    fn copy_selection(&self, ui: &Ui, text: &str) {
+        if let Some(selected) = self.selected(text) {
+            RichCopy::text(selected).copy(ui);
+        }
+    }
+
+    fn draw(&self, ui: &Ui, text: &str, lines: &[Line], origin: [f32; 2]) {
+        let draw_list = ui.get_window_draw_list();
+        let line_height = ui.text_line_height();
+        let highlight = ui.style_color(StyleColor::TextSelectedBg);
+        let colour = ui.style_color(StyleColor::Text);
+        let range = self.range().filter(|(start, end)| start < end);
+        let mut y = origin[1];
+        for line in lines {
+            let (line_start, line_end) = line.range();
+            if let Some((start, end)) = range {
+                let (start, end) = (start.max(line_start), end.min(line_end));
+                if start < end {
+                    draw_list
+                        .add_rect(
+                            [origin[0] + line.x_at(start), y],
+                            [origin[0] + line.x_at(end), y + line_height],
+                            highlight,
+                        )
+                        .filled(true)
+                        .build();
+                }
+            }
+            draw_list.add_text([origin[0], y], colour, &text[line_start..line_end]);
+            y += line_height;
+        }
+    }
+
+    /// Opens on right-click or long press over the text, the last item drawn
+    fn draw_menu(&mut self, ui: &Ui, text: &str) {
+        let _id = ui.push_id(&self.id);
+        context_menu_for(ui, MENU_POPUP, || {
+            self.focused = true;
+            if ui
+                .menu_item_config("Copy")
+                .enabled(self.selected(text).is_some())
+                .build()
+            {
+                self.copy_selection(ui, text);
+            }
+            if ui.menu_item("Copy all") {
+                RichCopy::text(text).copy(ui);
+            }
+            if ui.menu_item("Select all") {
+                self.selection = Some((0, text.len()));
+            }
+        });
+    }
+}
+
+impl Line {
+    fn starting_at(offset: usize) -> Self {
+        Line {
+            stops: vec![(offset, 0.0)],
+        }
+    }
+
+    fn range(&self) -> (usize, usize) {
+        (self.stops[0].0, self.stops[self.stops.len() - 1].0)
+    }
+
+    fn x_at(&self, offset: usize) -> f32 {
+        self.stops
+            .iter()
+            .find(|(stop, _)| *stop >= offset)
+            .or(self.stops.last())
+            .map_or(0.0, |(_, x)| *x)
+    }
+}
+
+/// Breaks `text` into lines at newlines, and after the last space that fits in `width`, or
+/// mid-word if a word is wider than that
+fn layout(ui: &Ui, text: &str, width: f32) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for paragraph in text.split('\n') {
+        let mut line = Line::starting_at(offset);
+        // the stop just after the line's last space, where it can break
+        let mut last_space = None;
+        for (index, character) in paragraph.char_indices() {
+            let advance = ui.calc_text_size(character.encode_utf8(&mut [0; 4]))[0];
+            let mut x = line.stops[line.stops.len() - 1].1 + advance;
+            if x > width && line.stops.len() > 1 {
+                let split = last_space.take().unwrap_or(line.stops.len() - 1);
+                let mut rest = line.stops.split_off(split);
+                let (break_offset, break_x) = rest[0];
+                line.stops.push((break_offset, break_x));
+                lines.push(line);
+                for (_, stop_x) in &mut rest {
+                    *stop_x -= break_x;
+                }
+                line = Line { stops: rest };
+                x = line.stops[line.stops.len() - 1].1 + advance;
+            }
+            line.stops.push((offset + index + character.len_utf8(), x));
+            if character == ' ' {
+                last_space = Some(line.stops.len() - 1);
+            }
+        }
+        lines.push(line);
+        offset += paragraph.len() + 1;
+    }
+    lines
+}
+
+/// The character boundary nearest `position`, relative to the text's top left
+fn hit_test(lines: &[Line], line_height: f32, position: [f32; 2]) -> usize {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let row = (position[1] / line_height).max(0.0) as usize;
+    let line = &lines[row.min(lines.len() - 1)];
+    line.stops
+        .iter()
+        .min_by(|(_, a), (_, b)| (a - position[0]).abs().total_cmp(&(b - position[0]).abs()))
+        .map_or(0, |(offset, _)| *offset)
+}
+
+/// The bounds of the word containing `offset`
+fn word_at(text: &str, offset: usize) -> (usize, usize) {
+    let start = text[..offset]
+        .rfind(char::is_whitespace)
+        .map_or(0, |index| index + 1);
+    let end = text[offset..]
+        .find(char::is_whitespace)
+        .map_or(text.len(), |index| offset + index);
+    (start, end)
+}
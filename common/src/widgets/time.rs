@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use imgui::Ui;
+
+use crate::format;
+
+pub const SECONDS_PER_DAY: u32 = 86_400;
+
+/// The clock a time of day is expressed in
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimeReference {
+    Zulu,
+    Local,
+}
+
+impl TimeReference {
+    #[must_use]
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeReference::Zulu => "Z",
+            TimeReference::Local => "L",
+        }
+    }
+}
+
+/// Returns the current UTC time as seconds since midnight.
+#[must_use]
+pub fn utc_now() -> u32 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    u32::try_from(elapsed % u64::from(SECONDS_PER_DAY)).unwrap_or_default()
+}
+
+/// Returns today's UTC date as year, month and day.
+#[must_use]
+pub fn utc_today() -> (i32, u32, u32) {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = i64::try_from(elapsed / u64::from(SECONDS_PER_DAY)).unwrap_or_default();
+    civil_from_days(days)
+}
+
+/// Formats seconds since midnight as `HH:MM` or `HH:MM:SS`.
+#[must_use]
+pub fn format_time(time: u32, seconds: bool) -> String {
+    let time = time % SECONDS_PER_DAY;
+    let (h, m, s) = (time / 3600, time / 60 % 60, time % 60);
+    if seconds {
+        format!("{h:02}:{m:02}:{s:02}")
+    } else {
+        format!("{h:02}:{m:02}")
+    }
+}
+
+/// Parses `HH:MM`, `HH:MM:SS` or `HHMM` into seconds since midnight.
+#[must_use]
+pub fn parse_time(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let parts: Vec<&str> = if text.contains(':') {
+        text.split(':').collect()
+    } else if text.len() == 4 && text.is_ascii() {
+        vec![&text[..2], &text[2..]]
+    } else {
+        return None;
+    };
+    let (h, m, s): (u32, u32, u32) = match parts[..] {
+        [h, m] => (h.parse().ok()?, m.parse().ok()?, 0),
+        [h, m, s] => (h.parse().ok()?, m.parse().ok()?, s.parse().ok()?),
+        _ => return None,
+    };
+    (h < 24 && m < 60 && s < 60).then(|| h * 3600 + m * 60 + s)
+}
+
+/// Formats a duration as `H:MM` or `H:MM:SS`, with hours allowed to exceed 24.
+#[must_use]
+pub fn format_duration(duration: Duration, seconds: bool) -> String {
+    let total = duration.as_secs();
+    let (h, m, s) = (total / 3600, total / 60 % 60, total % 60);
+    if seconds {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{h}:{m:02}")
+    }
+}
+
+/// Parses `H:MM` or `H:MM:SS` into a duration.
+#[must_use]
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    let (h, m, s): (u64, u64, u64) = match parts[..] {
+        [h, m] => (h.parse().ok()?, m.parse().ok()?, 0),
+        [h, m, s] => (h.parse().ok()?, m.parse().ok()?, s.parse().ok()?),
+        _ => return None,
+    };
+    if m >= 60 || s >= 60 {
+        return None;
+    }
+    let seconds = h.checked_mul(3600)?.checked_add(m * 60 + s)?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Text entry for a time of day, held as seconds since midnight
+#[must_use]
+pub struct TimePicker<'a> {
+    label: &'a str,
+    reference: TimeReference,
+    seconds: bool,
+}
+
+impl<'a> TimePicker<'a> {
+    pub fn new(label: &'a str, reference: TimeReference) -> Self {
+        TimePicker {
+            label,
+            reference,
+            seconds: false,
+        }
+    }
+
+    pub fn seconds(mut self, seconds: bool) -> Self {
+        self.seconds = seconds;
+        self
+    }
+
+    /// Returns true if `time` was changed. Invalid entries are discarded.
+    pub fn build(self, ui: &Ui, time: &mut u32) -> bool {
+        let mut text = format!(
+            "{}{}",
+            format_time(*time, self.seconds),
+            self.reference.suffix()
+        );
+        if ui
+            .input_text(self.label, &mut text)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            let text = text.trim_end_matches(['Z', 'z', 'L', 'l']);
+            if let Some(parsed) = parse_time(text) {
+                let changed = parsed != *time;
+                *time = parsed;
+                return changed;
+            }
+        }
+        false
+    }
+}
+
+/// Text entry for a duration such as a block time or timer
+#[must_use]
+pub struct DurationPicker<'a> {
+    label: &'a str,
+    seconds: bool,
+}
+
+impl<'a> DurationPicker<'a> {
+    pub fn new(label: &'a str) -> Self {
+        DurationPicker {
+            label,
+            seconds: false,
+        }
+    }
+
+    pub fn seconds(mut self, seconds: bool) -> Self {
+        self.seconds = seconds;
+        self
+    }
+
+    /// Returns true if `duration` was changed. Invalid entries are discarded.
+    pub fn build(self, ui: &Ui, duration: &mut Duration) -> bool {
+        let mut text = format_duration(*duration, self.seconds);
+        if ui
+            .input_text(self.label, &mut text)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            if let Some(parsed) = parse_duration(&text) {
+                let changed = parsed != *duration;
+                *duration = parsed;
+                return changed;
+            }
+        }
+        false
+    }
+}
+
+/// Text entry for a calendar date, held as year, month and day and written in the current
+/// `Locale`'s order
+#[must_use]
+pub struct DatePicker<'a> {
+    label: &'a str,
+}
+
+impl<'a> DatePicker<'a> {
+    pub fn new(label: &'a str) -> Self {
+        DatePicker { label }
+    }
+
+    /// Returns true if `date` was changed. Invalid entries are discarded.
+    pub fn build(self, ui: &Ui, date: &mut (i32, u32, u32)) -> bool {
+        let locale = format::current();
+        let (year, month, day) = *date;
+        let mut text = locale.format_date(year, month, day);
+        if ui
+            .input_text(self.label, &mut text)
+            .enter_returns_true(true)
+            .auto_select_all(true)
+            .build()
+        {
+            if let Some(parsed) = locale.parse_date(&text) {
+                let changed = parsed != *date;
+                *date = parsed;
+                return changed;
+            }
+        }
+        false
+    }
+}
+
+/// The year, month and day `days` after 1970-01-01, in the proleptic Gregorian calendar
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month as u32, day as u32)
+}
@@ -0,0 +1,57 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+//! An `App` which runs both as a desktop tool (`dual-desktop`) and as an X-Plane plugin
+//! (`dual-plugin`). It depends only on `imgui-support`, and checks what the backend it finds
+//! itself in can do at runtime rather than with `cfg`.
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+
+use std::path::PathBuf;
+
+use imgui::Ui;
+use imgui_support::backend::Backend;
+use imgui_support::events::{Event, InputState};
+use imgui_support::App;
+
+#[derive(Default)]
+pub struct Dual {
+    dropped: Vec<PathBuf>,
+}
+
+impl App for Dual {
+    fn draw_ui(&self, ui: &Ui) {
+        let host = match Backend::current() {
+            Some(Backend::Standalone) => "a desktop window",
+            Some(Backend::XPlane) => "X-Plane",
+            Some(Backend::Web) => "a browser",
+            Some(Backend::Custom) | None => "a custom backend",
+        };
+        ui.text(format!("Running in {host}"));
+
+        let capabilities = Backend::capabilities();
+        if capabilities.file_drop {
+            ui.separator();
+            ui.text("Drop files on the window:");
+            for path in &self.dropped {
+                ui.bullet_text(path.display().to_string());
+            }
+        }
+        if capabilities.vr {
+            ui.separator();
+            ui.text_wrapped("Put on a headset to see this window in VR");
+        }
+    }
+
+    fn handle_event(&mut self, event: Event, _input: &InputState) -> bool {
+        if let Event::FileDrop(paths) = event {
+            self.dropped = paths;
+            return true;
+        }
+        false
+    }
+}
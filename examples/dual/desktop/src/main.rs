@@ -0,0 +1,19 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+//! Runs the `dual` app as a desktop tool
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+
+use dual_app::Dual;
+
+fn main() {
+    let glfw = glfw::init(glfw::fail_on_errors).expect("Unable to initialise GLFW");
+    let mut system =
+        imgui_support_standalone::init(glfw, "Dual", 100, 100, 400, 300, Dual::default());
+    system.main_loop();
+}
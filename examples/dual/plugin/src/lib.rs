@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+//! Runs the `dual` app as an X-Plane plugin. Copy the built library to
+//! `Resources/plugins/dual/lin_x64/dual.xpl`, or `mac_x64` or `win_x64`.
+
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use imgui_support_xplane::System;
+use xplm::plugin::{Plugin, PluginInfo};
+use xplm::xplane_plugin;
+
+use dual_app::Dual;
+
+struct DualPlugin {
+    _system: System,
+}
+
+impl Plugin for DualPlugin {
+    type Error = Infallible;
+
+    fn start() -> Result<Self, Self::Error> {
+        let app = Rc::new(RefCell::new(Dual::default()));
+        let mut system = imgui_support_xplane::init("Dual", 100, 400, 400, 300, app);
+        system.set_visible(true);
+        Ok(DualPlugin { _system: system })
+    }
+
+    fn info(&self) -> PluginInfo {
+        PluginInfo {
+            name: String::from("Dual"),
+            signature: String::from("com.github.ddunwoody.imgui-support.dual"),
+            description: String::from("The same App as the dual-desktop tool"),
+        }
+    }
+}
+
+xplane_plugin!(DualPlugin);
@@ -0,0 +1,217 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use image::{DynamicImage, RgbImage, RgbaImage};
+use imgui_support::video::VideoTexture;
+use nokhwa::pixel_format::RgbFormat;
+use nokhwa::utils::{CameraIndex, RequestedFormat, RequestedFormatType};
+use nokhwa::Camera;
+use rqrr::PreparedImage;
+use tracing::warn;
+
+/// Decoding is much slower than capturing, so only a few frames a second are decoded
+const DECODE_INTERVAL: Duration = Duration::from_millis(250);
+/// A code still in view is sent again after this long
+const REPEAT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Starts and stops the System's QR scanner, which sends each code it reads to the app as
+/// `Event::User`. Clone it to give the app a handle.
+#[derive(Clone, Default)]
+pub struct QrScannerHandle {
+    scanner: Rc<RefCell<Option<QrScanner>>>,
+}
+
+impl QrScannerHandle {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts reading QR codes from the camera at `index`, the first being 0. Replaces any
+    /// scanner already running, stopping it first so it lets go of the camera.
+    pub fn start(&self, index: u32) {
+        self.stop();
+        *self.scanner.borrow_mut() = Some(QrScanner::start(index));
+    }
+
+    pub fn stop(&self) {
+        self.scanner.take();
+    }
+
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.scanner.borrow().is_some()
+    }
+
+    /// The camera's view while the scanner is running, e.g. to help the user aim it
+    #[must_use]
+    pub fn preview(&self) -> Option<Ref<'_, VideoTexture>> {
+        Ref::filter_map(self.scanner.borrow(), |scanner| {
+            scanner.as_ref().map(QrScanner::texture)
+        })
+        .ok()
+    }
+
+    /// Uploads the latest frame and returns the codes read since the last call. Used by the
+    /// System, which passes them to the app.
+    pub(crate) fn poll(&self) -> Vec<String> {
+        self.scanner
+            .borrow_mut()
+            .as_mut()
+            .map(QrScanner::poll)
+            .unwrap_or_default()
+    }
+}
+
+/// Captures frames from a camera on a background thread, showing them in a `VideoTexture` and
+/// decoding any QR codes in them.
+struct QrScanner {
+    texture: VideoTexture,
+    latest: Arc<Mutex<Option<RgbaImage>>>,
+    codes: Receiver<String>,
+    last_code: Option<(String, Instant)>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl QrScanner {
+    /// Starts capturing from the camera at `index`, the first being 0
+    fn start(index: u32) -> QrScanner {
+        let latest = Arc::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, codes) = channel();
+        let thread = {
+            let latest = Arc::clone(&latest);
+            let stop = Arc::clone(&stop);
+            thread::Builder::new()
+                .name(String::from("qr-scanner"))
+                .spawn(move || capture(index, &latest, &sender, &stop))
+                .map_err(|e| warn!(error = %e, "Unable to start QR scanner"))
+                .ok()
+        };
+        QrScanner {
+            texture: VideoTexture::new(),
+            latest,
+            codes,
+            last_code: None,
+            stop,
+            thread,
+        }
+    }
+
+    fn texture(&self) -> &VideoTexture {
+        &self.texture
+    }
+
+    /// Uploads the latest frame and returns the codes decoded since the last call, leaving out
+    /// a code that's still in view
+    fn poll(&mut self) -> Vec<String> {
+        let frame = self
+            .latest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(frame) = frame {
+            if let Err(e) = self.texture.update(&frame) {
+                warn!(error = %e, "Unable to show camera frame");
+            }
+        }
+
+        let mut codes = Vec::new();
+        for code in self.codes.try_iter() {
+            let repeated = self
+                .last_code
+                .as_ref()
+                .is_some_and(|(last, seen)| *last == code && seen.elapsed() < REPEAT_INTERVAL);
+            if !repeated {
+                codes.push(code.clone());
+                self.last_code = Some((code, Instant::now()));
+            }
+        }
+        codes
+    }
+}
+
+impl Drop for QrScanner {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn capture(
+    index: u32,
+    latest: &Mutex<Option<RgbaImage>>,
+    codes: &Sender<String>,
+    stop: &AtomicBool,
+) {
+    let format = RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
+    let mut camera = match Camera::new(CameraIndex::Index(index), format) {
+        Ok(camera) => camera,
+        Err(e) => {
+            warn!(index, error = %e, "Unable to open camera");
+            return;
+        }
+    };
+    if let Err(e) = camera.open_stream() {
+        warn!(index, error = %e, "Unable to start camera");
+        return;
+    }
+
+    let mut last_decode: Option<Instant> = None;
+    while !stop.load(Ordering::Relaxed) {
+        let frame = match camera
+            .frame()
+            .and_then(|buffer| buffer.decode_image::<RgbFormat>())
+        {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!(index, error = %e, "Unable to capture from camera");
+                break;
+            }
+        };
+        if last_decode.is_none_or(|last| last.elapsed() >= DECODE_INTERVAL) {
+            last_decode = Some(Instant::now());
+            for code in decode(&frame) {
+                if codes.send(code).is_err() {
+                    return;
+                }
+            }
+        }
+        *latest.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some(DynamicImage::ImageRgb8(frame).into_rgba8());
+    }
+    if let Err(e) = camera.stop_stream() {
+        warn!(index, error = %e, "Unable to stop camera");
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn decode(frame: &RgbImage) -> Vec<String> {
+    let (width, height) = frame.dimensions();
+    let mut prepared =
+        PreparedImage::prepare_from_greyscale(width as usize, height as usize, |x, y| {
+            let [r, g, b] = frame.get_pixel(x as u32, y as u32).0;
+            // Rec. 601 luma
+            ((u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114) / 1000) as u8
+        });
+    prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content)
+        .collect()
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::ffi::{CStr, CString};
+
+use glfw::{ffi, Context, Window};
+use imgui::ClipboardBackend;
+
+/// Connects imgui's clipboard to the system's through GLFW, so text copied in imgui, e.g. with
+/// `RichCopy`, pastes into other apps. GLFW's clipboard only holds text.
+pub struct GlfwClipboard {
+    window: *mut ffi::GLFWwindow,
+}
+
+impl GlfwClipboard {
+    /// The window must outlive imgui's clipboard calls, which only happen during a frame
+    pub fn new(window: &Window) -> Self {
+        GlfwClipboard {
+            window: window.window_ptr(),
+        }
+    }
+}
+
+impl ClipboardBackend for GlfwClipboard {
+    fn get(&mut self) -> Option<String> {
+        let text = unsafe { ffi::glfwGetClipboardString(self.window) };
+        (!text.is_null()).then(|| {
+            unsafe { CStr::from_ptr(text) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    fn set(&mut self, value: &str) {
+        // imgui's strings can't contain NULs, so this only fails for text set by the app
+        if let Ok(value) = CString::new(value) {
+            unsafe { ffi::glfwSetClipboardString(self.window, value.as_ptr()) };
+        }
+    }
+}
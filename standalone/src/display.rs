@@ -0,0 +1,79 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use glfw::{Glfw, Window, WindowMode};
+use tracing::warn;
+
+use imgui_support::geometry::Rect;
+
+/// How the window occupies the screen. Monitors are numbered as in `System::monitors`, where the
+/// first is usually the primary one.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DisplayMode {
+    /// A normal window at the position and size it was built with, or was last left at
+    #[default]
+    Windowed,
+    /// Takes over the monitor, switching it to the video mode nearest the window's size
+    Fullscreen { monitor: usize },
+    /// An undecorated window covering the monitor at its current video mode, which other windows
+    /// can still be switched to quickly
+    Borderless { monitor: usize },
+}
+
+/// Puts `window` in `mode`, restoring it to `windowed` in windowed mode
+pub(crate) fn apply(glfw: &mut Glfw, window: &mut Window, mode: DisplayMode, windowed: Rect) {
+    let (x, y) = (windowed.left, windowed.top.min(windowed.bottom));
+    let (width, height) = (windowed.width(), windowed.height());
+    match mode {
+        DisplayMode::Windowed => {
+            window.set_decorated(true);
+            window.set_monitor(WindowMode::Windowed, x, y, width, height, None);
+        }
+        DisplayMode::Fullscreen { monitor } => glfw.with_connected_monitors(|_, monitors| {
+            let Some(monitor_ref) = monitors.get(monitor) else {
+                warn!(monitor, "No such monitor for fullscreen");
+                return;
+            };
+            let mode = WindowMode::FullScreen(monitor_ref);
+            window.set_monitor(mode, 0, 0, width, height, None);
+        }),
+        DisplayMode::Borderless { monitor } => glfw.with_connected_monitors(|_, monitors| {
+            let Some(monitor_ref) = monitors.get(monitor) else {
+                warn!(monitor, "No such monitor for borderless fullscreen");
+                return;
+            };
+            let Some(video_mode) = monitor_ref.get_video_mode() else {
+                warn!(monitor, "Unable to read monitor's video mode");
+                return;
+            };
+            let (x, y) = monitor_ref.get_pos();
+            window.set_decorated(false);
+            window.set_monitor(
+                WindowMode::Windowed,
+                x,
+                y,
+                video_mode.width,
+                video_mode.height,
+                None,
+            );
+        }),
+    }
+}
+
+/// The connected monitors' names, in the order `DisplayMode` numbers them
+pub(crate) fn monitor_names(glfw: &mut Glfw) -> Vec<String> {
+    glfw.with_connected_monitors(|_, monitors| {
+        monitors
+            .iter()
+            .enumerate()
+            .map(|(index, monitor)| {
+                monitor
+                    .get_name()
+                    .unwrap_or_else(|| format!("Monitor {}", index + 1))
+            })
+            .collect()
+    })
+}
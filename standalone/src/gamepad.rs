@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use glfw::{Action, GamepadAxis, GamepadButton, Glfw, JoystickId};
+use imgui::Key;
+
+use imgui_support::navigation::GamepadState;
+
+const BUTTONS: [(GamepadButton, Key); 14] = [
+    (GamepadButton::ButtonStart, Key::GamepadStart),
+    (GamepadButton::ButtonBack, Key::GamepadBack),
+    (GamepadButton::ButtonX, Key::GamepadFaceLeft),
+    (GamepadButton::ButtonB, Key::GamepadFaceRight),
+    (GamepadButton::ButtonY, Key::GamepadFaceUp),
+    (GamepadButton::ButtonA, Key::GamepadFaceDown),
+    (GamepadButton::ButtonDpadLeft, Key::GamepadDpadLeft),
+    (GamepadButton::ButtonDpadRight, Key::GamepadDpadRight),
+    (GamepadButton::ButtonDpadUp, Key::GamepadDpadUp),
+    (GamepadButton::ButtonDpadDown, Key::GamepadDpadDown),
+    (GamepadButton::ButtonLeftBumper, Key::GamepadL1),
+    (GamepadButton::ButtonRightBumper, Key::GamepadR1),
+    (GamepadButton::ButtonLeftThumb, Key::GamepadL3),
+    (GamepadButton::ButtonRightThumb, Key::GamepadR3),
+];
+
+/// Reads the first connected joystick that GLFW recognises as a gamepad.
+pub fn gamepad_state(glfw: &Glfw) -> Option<GamepadState> {
+    let state = (0..16)
+        .filter_map(JoystickId::from_i32)
+        .map(|id| glfw.get_joystick(id))
+        .find(glfw::Joystick::is_gamepad)?
+        .get_gamepad_state()?;
+
+    let buttons = BUTTONS
+        .iter()
+        .filter(|(button, _)| state.get_button_state(*button) == Action::Press)
+        .map(|(_, key)| *key)
+        .collect();
+
+    // GLFW triggers rest at -1, so halfway from there to 1 maps them onto 0 to 1
+    let trigger = |axis| f32::midpoint(state.get_axis(axis), 1.0);
+
+    Some(GamepadState {
+        buttons,
+        left_stick: [
+            state.get_axis(GamepadAxis::AxisLeftX),
+            state.get_axis(GamepadAxis::AxisLeftY),
+        ],
+        right_stick: [
+            state.get_axis(GamepadAxis::AxisRightX),
+            state.get_axis(GamepadAxis::AxisRightY),
+        ],
+        left_trigger: trigger(GamepadAxis::AxisLeftTrigger),
+        right_trigger: trigger(GamepadAxis::AxisRightTrigger),
+    })
+}
@@ -8,84 +8,658 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::missing_panics_doc)]
 
+#[cfg(feature = "camera")]
+use std::cell::Ref;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Receiver;
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use gl21 as gl;
-use glfw::{Context, Glfw, Window, WindowEvent};
+use glfw::{Context, Glfw, PixelImage, Window, WindowEvent, WindowHint};
 use image::{ImageError, RgbaImage};
-use imgui::{Condition, TextureId, WindowFlags};
-use imgui_support::events::{Action, Event, Modifiers, MouseButton};
+#[cfg(feature = "docking")]
+use imgui::ConfigFlags;
+use imgui::{DrawData, Style, TextureId, Ui};
+use imgui_support::allocator::{self, ContextMemory};
+use imgui_support::backend::{Backend, Capabilities, FrameDriver, FrameHooks, HostWindow};
+use imgui_support::close::CloseHandle;
+use imgui_support::dialog::{ModalDialog, Modals};
+use imgui_support::draw_data::{DrawDataExport, OwnedDrawData};
+use imgui_support::events::{
+    Action, Event, InputState, LongPress, LongPressDetector, Modifiers, MotionCoalescer,
+    MouseButton,
+};
+use imgui_support::focus::Focus;
+use imgui_support::fonts::{FontRebuilder, Fonts};
+use imgui_support::format::{self, Locale};
+use imgui_support::geometry::{Placement, Rect};
+use imgui_support::hotkeys::{HotkeyAction, Hotkeys};
+use imgui_support::jobs::{ChunkedJobs, FRAME_BUDGET};
+use imgui_support::kinetic::KineticScroll;
+use imgui_support::layout::Breakpoints;
+use imgui_support::loading::LoadingScreen;
+use imgui_support::menu::AppMenu;
+use imgui_support::metrics::{Metrics, MetricsOverlay, PerfOverlay, RenderStats};
+use imgui_support::navigation::{update_gamepad, AnalogTuning, Navigation};
+use imgui_support::phases::{FramePhase, PhaseHooks};
+use imgui_support::profiles::Profiles;
+use imgui_support::recorder::{MacroRecorder, Macros};
+use imgui_support::scroll::ScrollPositions;
+use imgui_support::self_test::{self, Diagnostic};
+use imgui_support::tasks::Tasks;
+use imgui_support::theme::{clamp_ui_scale, CustomTheme, Theme, ThemeError};
+#[cfg(feature = "camera")]
+use imgui_support::video::VideoTexture;
 
-use imgui_support::App;
+use imgui_support::{set_persistence, App};
+use tracing::warn;
 
+#[cfg(feature = "camera")]
+pub use crate::camera::QrScannerHandle;
+use crate::clipboard::GlfwClipboard;
+pub use crate::display::DisplayMode;
+use crate::gamepad::gamepad_state;
 use crate::keymap::to_imgui_key;
-use crate::platform::Platform;
-use crate::renderer::{bind_texture, render, Renderer};
+use crate::platform::{Platform, WindowPlatform};
+use crate::renderer::{bind_texture, rebind_texture, Renderer};
+use crate::utils::primary_monitor_bounds;
 pub use crate::utils::get_screen_bounds;
+#[cfg(feature = "docking")]
+use crate::viewports::Viewports;
 
+#[cfg(feature = "camera")]
+mod camera;
+mod clipboard;
+mod display;
+mod gamepad;
 mod keymap;
 mod platform;
+mod profile;
 mod renderer;
 mod utils;
+#[cfg(feature = "docking")]
+mod viewports;
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct System {
     glfw: Glfw,
     window: Window,
     events: Receiver<(f64, WindowEvent)>,
     imgui: imgui::Context,
     platform: Platform,
-    _renderer: Renderer,
+    renderer: Renderer,
+    fonts: FontRebuilder,
     last_frame_time: Instant,
+    navigation: Navigation,
+    analog_tuning: AnalogTuning,
+    kinetic_scroll: KineticScroll,
+    long_press: LongPressDetector,
+    focus: Focus,
+    close: CloseHandle,
+    docking: bool,
+    driver: FrameDriver,
+    jobs: ChunkedJobs,
+    breakpoints: Option<Breakpoints>,
+    menus: Vec<AppMenu>,
+    hotkeys: Hotkeys,
+    profiles: Option<Profiles>,
+    recorder: MacroRecorder<WindowEvent>,
+    scroll_positions: ScrollPositions,
+    draw_data_export: Option<DrawDataExport>,
+    metrics: Metrics,
+    last_frame_stats: RenderStats,
+    metrics_overlay: bool,
+    perf_overlay: bool,
+    imgui_metrics: bool,
+    memory: ContextMemory,
+    /// What the scissor check found, once the first frame has run it
+    scissor_check: Option<Vec<Diagnostic>>,
+    modals: Vec<ModalDialog>,
+    modal_queue: Modals,
+    phase_hooks: PhaseHooks,
+    theme: Theme,
+    /// imgui's style before any theme or scale was applied
+    base_style: Style,
+    ui_scale: f32,
+    locale: Locale,
+    window_padding: [f32; 2],
+    background: Background,
+    coalesce_events: bool,
+    /// The shortest time between frames, if the frame rate is capped
+    frame_interval: Option<Duration>,
+    display_mode: DisplayMode,
+    /// Where the window goes when it's windowed again
+    windowed_rect: Rect,
+    sleep_when_idle: bool,
+    auto_resize: bool,
+    /// The smallest the window may be, as width and height
+    min_size: Option<(u32, u32)>,
+    max_size: Option<(u32, u32)>,
+    #[cfg(feature = "camera")]
+    qr_scanner: QrScannerHandle,
+    #[cfg(feature = "docking")]
+    viewports: Option<Viewports>,
     app: Box<dyn App>,
 }
 
+/// What the window shows behind the app's UI
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    /// An opaque RGBA colour. The default is dark grey.
+    Color([f32; 4]),
+    /// See-through wherever the UI doesn't draw, for overlays. Needs a window system which
+    /// supports transparent windows, and can only be chosen when the System is built.
+    Transparent,
+    /// Left as it is, e.g. for an app which draws its own scene in `App::render_background`
+    None,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Color([0.2, 0.2, 0.2, 1.0])
+    }
+}
+
+/// What the main window draws around the app, for `FrameDriver`, and what the user chose
+#[allow(clippy::struct_excessive_bools)]
+struct WindowFrame<'a> {
+    app: &'a mut dyn App,
+    menus: &'a [AppMenu],
+    docking: bool,
+    auto_resize: bool,
+    padding: [f32; 2],
+    jobs: &'a ChunkedJobs,
+    modals: &'a mut Vec<ModalDialog>,
+    metrics: &'a mut Metrics,
+    metrics_overlay: bool,
+    perf_overlay: bool,
+    imgui_metrics: &'a mut bool,
+    phase_hooks: &'a PhaseHooks,
+    scissor_check: &'a mut Option<Vec<Diagnostic>>,
+    background: Background,
+    draw_data_export: Option<&'a DrawDataExport>,
+    /// The height of the menu bar
+    top: f32,
+    content_size: Option<[f32; 2]>,
+    chosen: Option<String>,
+}
+
+impl WindowFrame<'_> {
+    /// Probes the bottom left corner of the window's own framebuffer, which is cleared or drawn
+    /// over next
+    fn check_scissor(&mut self) {
+        let diagnostics = self_test::check_scissor(0, 0);
+        for diagnostic in &diagnostics {
+            warn!(%diagnostic, "Self test failed");
+        }
+        *self.scissor_check = Some(diagnostics);
+    }
+
+    fn clear_background(&self) {
+        let clear_color = match self.background {
+            Background::Color(color) => Some(color),
+            Background::Transparent => Some([0.0, 0.0, 0.0, 0.0]),
+            Background::None => None,
+        };
+        if let Some([red, green, blue, alpha]) = clear_color {
+            unsafe {
+                gl::ClearColor(red, green, blue, alpha);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+        }
+    }
+}
+
+impl FrameHooks for WindowFrame<'_> {
+    fn ready(&self) -> bool {
+        self.app.ready()
+    }
+
+    fn host_window(
+        &mut self,
+        ui: &Ui,
+        display_size: [f32; 2],
+        loading: bool,
+    ) -> Option<HostWindow<'_>> {
+        if !loading && !self.menus.is_empty() {
+            if let Some(_menu_bar) = ui.begin_main_menu_bar() {
+                for menu in self.menus {
+                    self.chosen = menu.draw(ui).or(self.chosen.take());
+                }
+                self.top = ui.window_size()[1];
+            }
+        }
+        if self.docking && !loading {
+            return None;
+        }
+        Some(HostWindow {
+            title: "ImGui Window",
+            position: [0.0, self.top],
+            size: [display_size[0], display_size[1] - self.top],
+            padding: self.padding,
+        })
+    }
+
+    fn draw_app(&mut self, ui: &Ui) {
+        if self.docking {
+            #[cfg(feature = "docking")]
+            ui.dockspace_over_main_viewport();
+            self.app.draw_ui(ui);
+        } else if self.auto_resize {
+            ui.group(|| self.app.draw_ui(ui));
+            let [width, height] = ui.item_rect_size();
+            let [padding_x, padding_y] = self.padding;
+            self.content_size =
+                Some([width + padding_x * 2.0, height + padding_y * 2.0 + self.top]);
+        } else {
+            self.app.draw_ui(ui);
+        }
+    }
+
+    fn draw_overlays(&mut self, ui: &Ui, loading: bool) {
+        if !loading {
+            self.jobs.draw(ui);
+            if let Some(modal) = self.modals.first_mut() {
+                modal.draw_popup(ui);
+            }
+            if self.metrics_overlay {
+                MetricsOverlay::draw(ui, self.metrics);
+            }
+            if self.perf_overlay {
+                PerfOverlay::draw(ui, self.metrics);
+            }
+            if *self.imgui_metrics {
+                ui.show_metrics_window(self.imgui_metrics);
+            }
+        }
+        self.phase_hooks.run(FramePhase::Build);
+    }
+
+    fn before_render(&mut self) {
+        if self.scissor_check.is_none() {
+            self.check_scissor();
+        }
+        self.clear_background();
+        self.app.render_background();
+    }
+
+    fn inspect(&mut self, draw_data: &DrawData) {
+        self.metrics.update(draw_data);
+        if let Some(export) = self.draw_data_export {
+            export.publish(draw_data);
+        }
+    }
+}
+
+/// The OpenGL context and framebuffer the window is created with. Fields left as `None` keep
+/// GLFW's defaults.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ContextConfig {
+    /// Samples per pixel for multisample anti-aliasing, e.g. 4
+    pub samples: Option<u32>,
+    /// The lowest GL version to accept, as major and minor. The renderer uses GL 2.1's fixed
+    /// function pipeline, so a core profile won't draw the UI.
+    pub version: Option<(u32, u32)>,
+    /// Leaves out deprecated features, which on macOS is needed for versions above 2.1
+    pub forward_compat: bool,
+    pub depth_bits: Option<u32>,
+    pub stencil_bits: Option<u32>,
+}
+
+impl ContextConfig {
+    fn hint(&self, glfw: &mut Glfw) {
+        if let Some(samples) = self.samples {
+            glfw.window_hint(WindowHint::Samples(Some(samples)));
+        }
+        if let Some((major, minor)) = self.version {
+            glfw.window_hint(WindowHint::ContextVersion(major, minor));
+        }
+        if self.forward_compat {
+            glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
+        }
+        if let Some(bits) = self.depth_bits {
+            glfw.window_hint(WindowHint::DepthBits(Some(bits)));
+        }
+        if let Some(bits) = self.stencil_bits {
+            glfw.window_hint(WindowHint::StencilBits(Some(bits)));
+        }
+    }
+}
+
+/// Configures and creates a `System`
+#[allow(clippy::struct_excessive_bools)]
 #[must_use]
-pub fn init<A: App + 'static>(
-    mut glfw: Glfw,
+pub struct SystemBuilder {
     title: &'static str,
-    x: u32,
-    y: u32,
+    placement: Placement,
     width: u32,
     height: u32,
-    app: A,
-) -> System {
-    // Create a windowed mode window and its OpenGL context
-    let (mut window, events) = glfw
-        .create_window(width, height, title, glfw::WindowMode::Windowed)
-        .expect("Failed to create GLFW window.");
+    persistence: Option<PathBuf>,
+    docking: bool,
+    viewports: bool,
+    loading_screen: Option<LoadingScreen>,
+    breakpoints: Option<Breakpoints>,
+    menus: Vec<AppMenu>,
+    hotkeys: Hotkeys,
+    profiles: Option<PathBuf>,
+    export_draw_data: bool,
+    metrics_overlay: bool,
+    memory_budget: Option<usize>,
+    theme: Theme,
+    window_padding: [f32; 2],
+    background: Background,
+    max_fps: Option<f32>,
+    sleep_when_idle: bool,
+    display_mode: DisplayMode,
+    always_on_top: bool,
+    context: ContextConfig,
+}
 
+impl SystemBuilder {
+    /// A `width` by `height` window `x` pixels right of and `y` pixels down from the top left of
+    /// the screen. Use `placement` to place it relative to another corner or the centre.
     #[allow(clippy::cast_possible_wrap)]
-    {
-        window.set_pos(x as _, y as _);
+    pub fn new(title: &'static str, x: u32, y: u32, width: u32, height: u32) -> Self {
+        SystemBuilder {
+            title,
+            placement: Placement::Absolute {
+                x: x as i32,
+                y: y as i32,
+            },
+            width,
+            height,
+            persistence: None,
+            docking: false,
+            viewports: false,
+            loading_screen: None,
+            breakpoints: None,
+            menus: Vec::new(),
+            hotkeys: Hotkeys::new(),
+            profiles: None,
+            export_draw_data: false,
+            metrics_overlay: false,
+            memory_budget: None,
+            theme: Theme::default(),
+            window_padding: [0.0, 0.0],
+            background: Background::default(),
+            max_fps: None,
+            sleep_when_idle: false,
+            display_mode: DisplayMode::Windowed,
+            always_on_top: false,
+            context: ContextConfig::default(),
+        }
+    }
+
+    /// Where the window starts, worked out from the screen's bounds, replacing the position
+    /// given to `new`
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Shows `loading_screen` until `App::ready` returns true
+    pub fn loading_screen(mut self, loading_screen: LoadingScreen) -> Self {
+        self.loading_screen = Some(loading_screen);
+        self
+    }
+
+    /// Sends `Event::SizeClass` as the window's width crosses `breakpoints`
+    pub fn breakpoints(mut self, breakpoints: Breakpoints) -> Self {
+        self.breakpoints = Some(breakpoints);
+        self
+    }
+
+    /// Draws `menus` in a menu bar along the top of the window. GLFW has no native menus.
+    pub fn menus(mut self, menus: Vec<AppMenu>) -> Self {
+        self.menus = menus;
+        self
+    }
+
+    /// Shortcuts matched against key presses before the app or imgui sees them, so they work
+    /// while a text field has focus
+    pub fn hotkeys(mut self, hotkeys: Hotkeys) -> Self {
+        self.hotkeys = hotkeys;
+        self
+    }
+
+    /// Stores named layouts in the settings file at `path`, see `System::profiles`
+    pub fn profiles(mut self, path: impl Into<PathBuf>) -> Self {
+        self.profiles = Some(path.into());
+        self
+    }
+
+    /// Keeps a copy of each frame's draw data for `System::draw_data_export`. Disabled by
+    /// default, as copying it every frame isn't free.
+    pub fn export_draw_data(mut self, export_draw_data: bool) -> Self {
+        self.export_draw_data = export_draw_data;
+        self
+    }
+
+    /// Shows `System::metrics` in a small window in the top right corner
+    pub fn metrics_overlay(mut self, metrics_overlay: bool) -> Self {
+        self.metrics_overlay = metrics_overlay;
+        self
+    }
+
+    /// Warns when imgui's memory for the window exceeds `bytes`, see `System::memory`
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Styles imgui with `theme` instead of `Theme::Dark`
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// The padding between the window's edges and the app's contents. Defaults to none, so the
+    /// app can draw right up to the edges. Unused with docking, where the app has its own windows.
+    pub fn window_padding(mut self, padding: [f32; 2]) -> Self {
+        self.window_padding = padding;
+        self
+    }
+
+    /// Defaults to dark grey
+    pub fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Draws at most `fps` frames a second. Uncapped by default.
+    pub fn max_fps(mut self, fps: f32) -> Self {
+        self.max_fps = Some(fps);
+        self
+    }
+
+    /// Waits for input before drawing another frame unless something is animating, e.g. the
+    /// app's `App::wants_redraw` or a running job. Otherwise, and by default, a frame is drawn at
+    /// least ten times a second for apps which change without input.
+    pub fn sleep_when_idle(mut self, sleep_when_idle: bool) -> Self {
+        self.sleep_when_idle = sleep_when_idle;
+        self
+    }
+
+    /// Defaults to `DisplayMode::Windowed`, at the position and size given to `new`
+    pub fn display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    /// Keeps the window above other windows. Off by default.
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    /// Requests a GL context and framebuffer other than GLFW's defaults, e.g. with 4x MSAA
+    pub fn context(mut self, context: ContextConfig) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Persists imgui window state to the given ini file. Disabled by default.
+    pub fn persistence(mut self, ini_path: impl Into<PathBuf>) -> Self {
+        self.persistence = Some(ini_path.into());
+        self
     }
 
-    // Make the window's context current
-    window.make_current();
-    window.set_all_polling(true);
+    /// Lets the app's windows dock into a dockspace covering the whole window. Instead of drawing
+    /// into a full-window host, `App::draw_ui` creates its own imgui windows.
+    #[cfg(feature = "docking")]
+    pub fn docking(mut self, docking: bool) -> Self {
+        self.docking = docking;
+        self
+    }
+
+    /// Lets imgui windows be dragged out of the main window onto the desktop and other monitors,
+    /// each becoming an OS window of its own. Input in those windows goes to imgui; the app's
+    /// `handle_event` only sees the main window's.
+    #[cfg(feature = "docking")]
+    pub fn viewports(mut self, viewports: bool) -> Self {
+        self.viewports = viewports;
+        self
+    }
+
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn build<A: App + 'static>(self, mut glfw: Glfw, app: A) -> System {
+        glfw.window_hint(WindowHint::TransparentFramebuffer(
+            self.background == Background::Transparent,
+        ));
+        self.context.hint(&mut glfw);
+        // Create a windowed mode window and its OpenGL context
+        let (mut window, events) = glfw
+            .create_window(
+                self.width,
+                self.height,
+                self.title,
+                glfw::WindowMode::Windowed,
+            )
+            .expect("Failed to create GLFW window.");
+
+        // with no monitor, e.g. over some remote sessions, placement is relative to the window
+        #[allow(clippy::cast_possible_wrap)]
+        let screen = primary_monitor_bounds(&mut glfw)
+            .unwrap_or_else(|| Rect::new(0, 0, self.width as i32, self.height as i32));
+        let windowed_rect = self.placement.rect(&screen, self.width, self.height);
+        window.set_pos(windowed_rect.left, windowed_rect.top);
+        window.set_floating(self.always_on_top);
+        if self.display_mode != DisplayMode::Windowed {
+            display::apply(&mut glfw, &mut window, self.display_mode, windowed_rect);
+        }
+
+        // Make the window's context current
+        window.make_current();
+        window.set_all_polling(true);
+
+        let (mut imgui, memory) = allocator::create_context();
+        memory.set_budget(self.memory_budget);
+        set_persistence(&mut imgui, self.persistence.as_deref());
+        imgui.set_clipboard_backend(GlfwClipboard::new(&window));
+        #[cfg(feature = "docking")]
+        imgui
+            .io_mut()
+            .config_flags
+            .set(ConfigFlags::DOCKING_ENABLE, self.docking);
 
-    let mut imgui = imgui::Context::create();
-    imgui.set_ini_filename(None);
-    imgui.set_log_filename(None);
+        let mut platform = Platform::init(&mut imgui);
 
-    let mut platform = Platform::init(&mut imgui);
+        platform.attach_window(imgui.io_mut(), &window);
 
-    platform.attach_window(imgui.io_mut(), &window);
+        let renderer = Renderer::new(&mut imgui);
+        #[cfg(feature = "docking")]
+        let viewports = self.viewports.then(|| Viewports::install(&mut imgui));
+        let base_style = *imgui.style();
+        self.theme.apply(imgui.style_mut());
 
-    let renderer = Renderer::new(&mut imgui);
+        Backend::register(
+            Backend::Standalone,
+            Capabilities {
+                docking: self.docking,
+                menu_bar: true,
+                file_drop: true,
+                close_veto: true,
+                pop_out: self.viewports,
+                ..Capabilities::default()
+            },
+            create_texture,
+            imgui_support::deallocate_texture,
+        );
 
-    System {
-        glfw,
-        window,
-        events,
-        imgui,
-        platform,
-        _renderer: renderer,
-        last_frame_time: Instant::now(),
-        app: Box::new(app),
+        let tasks = Tasks::new();
+        System {
+            glfw,
+            window,
+            events,
+            imgui,
+            platform,
+            renderer,
+            fonts: FontRebuilder::new(Fonts::new()),
+            last_frame_time: Instant::now(),
+            navigation: Navigation::default(),
+            analog_tuning: AnalogTuning::default(),
+            kinetic_scroll: KineticScroll::new(),
+            long_press: LongPressDetector::default(),
+            focus: Focus::new(),
+            close: CloseHandle::new(),
+            docking: self.docking,
+            jobs: ChunkedJobs::new(tasks.clone()),
+            driver: FrameDriver::new(tasks, self.loading_screen),
+            breakpoints: self.breakpoints,
+            menus: self.menus,
+            hotkeys: self.hotkeys,
+            profiles: self.profiles.map(Profiles::new),
+            recorder: MacroRecorder::new(Macros::new()),
+            scroll_positions: ScrollPositions::alongside(self.persistence.as_deref()),
+            draw_data_export: self.export_draw_data.then(DrawDataExport::new),
+            metrics: Metrics::default(),
+            last_frame_stats: RenderStats::default(),
+            metrics_overlay: self.metrics_overlay,
+            perf_overlay: false,
+            imgui_metrics: false,
+            memory,
+            scissor_check: None,
+            modals: Vec::new(),
+            modal_queue: Modals::new(),
+            phase_hooks: PhaseHooks::new(),
+            theme: self.theme,
+            base_style,
+            ui_scale: 1.0,
+            locale: Locale::from_os(),
+            window_padding: self.window_padding,
+            background: self.background,
+            coalesce_events: true,
+            frame_interval: self.max_fps.and_then(frame_interval),
+            display_mode: self.display_mode,
+            windowed_rect,
+            sleep_when_idle: self.sleep_when_idle,
+            auto_resize: false,
+            min_size: None,
+            max_size: None,
+            #[cfg(feature = "camera")]
+            qr_scanner: QrScannerHandle::new(),
+            #[cfg(feature = "docking")]
+            viewports,
+            app: Box::new(app),
+        }
     }
 }
 
+#[must_use]
+pub fn init<A: App + 'static>(
+    glfw: Glfw,
+    title: &'static str,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    app: A,
+) -> System {
+    SystemBuilder::new(title, x, y, width, height).build(glfw, app)
+}
+
 /// # Errors
 ///
 /// Returns `ImageError` if the image could not be loaded.
@@ -95,56 +669,682 @@ pub fn create_texture(image: &RgbaImage) -> Result<TextureId, ImageError> {
 }
 
 impl System {
+    /// Enables imgui keyboard and/or gamepad navigation. Gamepad input is read from the first
+    /// joystick GLFW recognises as a gamepad.
+    pub fn set_navigation(&mut self, navigation: Navigation) {
+        self.navigation = navigation;
+    }
+
+    /// The response of the gamepad's sticks and triggers
+    #[must_use]
+    pub fn analog_tuning(&self) -> &AnalogTuning {
+        &self.analog_tuning
+    }
+
+    /// Changes the gamepad's axis responses, e.g. after `AnalogTuning::edit` or once loaded from
+    /// settings
+    pub fn set_analog_tuning(&mut self, tuning: AnalogTuning) {
+        self.analog_tuning = tuning;
+    }
+
+    /// Lets scrolling carry on after a trackpad's fingers lift, slowing to a stop. Off by
+    /// default.
+    pub fn set_kinetic_scrolling(&mut self, enabled: bool) {
+        self.kinetic_scroll.set_enabled(enabled);
+    }
+
+    /// For drawing overscroll feedback with `KineticScroll::draw_overscroll`. Clone it to give
+    /// the app a handle.
+    #[must_use]
+    pub fn kinetic_scroll(&self) -> &KineticScroll {
+        &self.kinetic_scroll
+    }
+
+    /// Sends the app `Event::LongPress` when the left button or a finger is held still, or stops
+    /// if `None`. Off by default.
+    pub fn set_long_press(&mut self, long_press: Option<LongPress>) {
+        self.long_press.set_config(long_press);
+    }
+
+    /// Whether imgui wanted the mouse, keyboard or text input as of the last frame, as passed to
+    /// `App::handle_event`
+    #[must_use]
+    pub fn input_state(&self) -> &InputState {
+        self.driver.input()
+    }
+
+    /// Moves focus between widgets without a keyboard. Clone it to give the app, or a hotkey, a
+    /// handle.
+    #[must_use]
+    pub fn focus(&self) -> &Focus {
+        &self.focus
+    }
+
+    /// Closes the window after the current frame, as `request_close` does. Clone it to give the
+    /// app a handle.
+    #[must_use]
+    pub fn close_handle(&self) -> &CloseHandle {
+        &self.close
+    }
+
+    /// Changes the font size and styles without stalling a frame. Clone it to give the app a
+    /// handle.
+    #[must_use]
+    pub fn fonts(&self) -> &Fonts {
+        self.fonts.fonts()
+    }
+
+    /// Background jobs registered here are shown in a progress bar at the bottom of the window.
+    #[must_use]
+    pub fn tasks(&self) -> &Tasks {
+        self.driver.tasks()
+    }
+
+    /// Long operations on the main thread, run a few milliseconds each frame behind a modal
+    /// progress dialog. Clone it to give the app a handle.
+    #[must_use]
+    pub fn jobs(&self) -> &ChunkedJobs {
+        &self.jobs
+    }
+
+    /// Saves and switches between named layouts of the window and imgui's windows, if enabled
+    /// with `SystemBuilder::profiles`. Clone it to give the app a handle.
+    #[must_use]
+    pub fn profiles(&self) -> Option<&Profiles> {
+        self.profiles.as_ref()
+    }
+
+    /// Records and replays input. Clone it to give the app, or a hotkey, a handle.
+    #[must_use]
+    pub fn macros(&self) -> &Macros {
+        self.recorder.macros()
+    }
+
+    /// The draw data of the latest frame, if enabled with `SystemBuilder::export_draw_data`
+    #[must_use]
+    pub fn export_draw_data(&self) -> Option<OwnedDrawData> {
+        self.draw_data_export.as_ref()?.latest()
+    }
+
+    /// A handle to each frame's draw data, e.g. for a thread forwarding the UI elsewhere while
+    /// `main_loop` runs
+    #[must_use]
+    pub fn draw_data_export(&self) -> Option<&DrawDataExport> {
+        self.draw_data_export.as_ref()
+    }
+
+    /// Restores the scroll positions of named child regions drawn through it, stored alongside
+    /// the ini file when persistence is enabled
+    #[must_use]
+    pub fn scroll_positions(&self) -> &ScrollPositions {
+        &self.scroll_positions
+    }
+
+    /// Draw data sizes, texture memory and allocation counts as of the latest frame
+    #[must_use]
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// What the renderer did to draw the latest frame
+    #[must_use]
+    pub fn last_frame_stats(&self) -> RenderStats {
+        self.last_frame_stats
+    }
+
+    /// Shows the frame rate, a graph of frame times and the cost of drawing them in the top left
+    /// corner
+    pub fn show_perf_overlay(&mut self, show: bool) {
+        self.perf_overlay = show;
+    }
+
+    /// Shows imgui's metrics and debugger window, for inspecting windows, draw lists and the
+    /// like. Ignored in release builds.
+    pub fn show_imgui_metrics(&mut self, show: bool) {
+        self.imgui_metrics = show && cfg!(debug_assertions);
+    }
+
+    /// Checks the GL context and fonts by drawing a hidden test frame, returning any problems
+    /// found, e.g. to run once at startup and report before the user sees a broken window.
+    /// Scissoring is checked as the first frame is drawn, so is only included after it.
+    #[must_use]
+    pub fn self_test(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = self_test::run(&mut self.imgui);
+        diagnostics.extend(self.scissor_check.iter().flatten().cloned());
+        diagnostics
+    }
+
+    /// The memory imgui has allocated for the window
+    #[must_use]
+    pub fn memory(&self) -> &ContextMemory {
+        &self.memory
+    }
+
+    /// Runs `hook` at `phase` of every frame, e.g. to draw custom GL before the frame is presented
+    pub fn on_phase(&mut self, phase: FramePhase, hook: impl FnMut() + 'static) {
+        self.phase_hooks.add(phase, hook);
+    }
+
+    /// Adds hooks as `on_phase` does. Clone it to give the app a handle.
+    #[must_use]
+    pub fn phase_hooks(&self) -> &PhaseHooks {
+        &self.phase_hooks
+    }
+
+    /// Passes the app at most one cursor move and one scroll between other events, the last
+    /// position and the summed scroll, rather than every one GLFW reports. On by default; turn it
+    /// off where every position matters, e.g. for drawing.
+    pub fn set_coalesce_events(&mut self, coalesce: bool) {
+        self.coalesce_events = coalesce;
+    }
+
+    /// Changes the cap set with `SystemBuilder::max_fps`, or removes it if `None`
+    pub fn set_max_fps(&mut self, fps: Option<f32>) {
+        self.frame_interval = fps.and_then(frame_interval);
+    }
+
+    /// Changes the mode set with `SystemBuilder::sleep_when_idle`
+    pub fn set_sleep_when_idle(&mut self, sleep_when_idle: bool) {
+        self.sleep_when_idle = sleep_when_idle;
+    }
+
+    /// Restyles imgui with `theme` from the next frame
+    pub fn set_theme(&mut self, theme: Theme) {
+        theme.apply_scaled(self.imgui.style_mut(), &self.base_style, self.ui_scale);
+        self.theme = theme;
+    }
+
+    /// Restyles imgui with the custom theme in the TOML or JSON file at `path`, so a style can be
+    /// edited without rebuilding the app
+    ///
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if the file could not be read or parsed, keeping the current theme.
+    pub fn load_style(&mut self, path: impl AsRef<Path>) -> Result<(), ThemeError> {
+        let theme = CustomTheme::load(path.as_ref())?;
+        self.set_theme(Theme::Custom(theme));
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Scales the whole UI by `scale`, e.g. 1.5 to 2 for 4K displays. The fonts are rebuilt at
+    /// the scaled size, so they stay sharp, and the theme's sizes are scaled to match. The scale
+    /// is clamped between `MIN_UI_SCALE` and `MAX_UI_SCALE`; NaN is ignored.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        let Some(scale) = clamp_ui_scale(scale) else {
+            warn!(scale, "Ignoring invalid UI scale");
+            return;
+        };
+        self.ui_scale = scale;
+        self.fonts.fonts().set_scale(scale);
+        self.theme
+            .apply_scaled(self.imgui.style_mut(), &self.base_style, scale);
+    }
+
+    #[must_use]
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// How widgets write numbers and dates, taken from the OS unless set
+    #[must_use]
+    pub fn locale(&self) -> &Locale {
+        &self.locale
+    }
+
+    /// Changes how widgets write numbers and dates, e.g. to a `Locale::load`ed choice
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Changes the background set with `SystemBuilder::background`. The window can only be
+    /// see-through if it was built with `Background::Transparent`; otherwise that shows black.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    #[must_use]
+    pub fn display_mode(&self) -> DisplayMode {
+        self.display_mode
+    }
+
+    /// Switches between windowed, fullscreen and borderless fullscreen, remembering where the
+    /// window was when leaving windowed mode so it can return there
+    pub fn set_display_mode(&mut self, display_mode: DisplayMode) {
+        if self.display_mode == DisplayMode::Windowed {
+            let (x, y) = self.window.get_pos();
+            let (width, height) = self.window.get_size();
+            self.windowed_rect = Rect::new(x, y, x + width, y + height);
+        }
+        display::apply(
+            &mut self.glfw,
+            &mut self.window,
+            display_mode,
+            self.windowed_rect,
+        );
+        self.display_mode = display_mode;
+    }
+
+    /// The connected monitors' names, numbered as in `DisplayMode`, e.g. for choosing one in a
+    /// settings window
+    pub fn monitors(&mut self) -> Vec<String> {
+        display::monitor_names(&mut self.glfw)
+    }
+
+    /// Stops the user resizing the window beyond `min` or `max`, as width and height, if given
+    pub fn set_size_limits(&mut self, min: Option<(u32, u32)>, max: Option<(u32, u32)>) {
+        self.min_size = min;
+        self.max_size = max;
+        self.window.set_size_limits(
+            min.map(|(width, _)| width),
+            min.map(|(_, height)| height),
+            max.map(|(width, _)| width),
+            max.map(|(_, height)| height),
+        );
+    }
+
+    /// Resizes the window to the size of the app's content as it changes, within the size
+    /// limits, while it's windowed. Suits content with a natural size, such as hints or
+    /// readouts, rather than content which fills the space it's given. Ignored with docking.
+    pub fn set_auto_resize(&mut self, auto_resize: bool) {
+        self.auto_resize = auto_resize;
+    }
+
+    /// Keeps the window above other windows, or stops doing so
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.window.set_floating(always_on_top);
+    }
+
+    /// Replaces the title the window was created with
+    pub fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Shows `icon` in the title bar and task switcher. Ignored on macOS, where the icon comes
+    /// from the app bundle, and on Wayland.
+    pub fn set_icon(&mut self, icon: &RgbaImage) {
+        let (width, height) = icon.dimensions();
+        let pixels = icon
+            .pixels()
+            .map(|pixel| u32::from_ne_bytes(pixel.0))
+            .collect();
+        self.window.set_icon_from_pixels(vec![PixelImage {
+            width,
+            height,
+            pixels,
+        }]);
+    }
+
+    /// Flashes the window's taskbar entry, or bounces its dock icon, until the user looks at it,
+    /// e.g. when a long job finishes in the background
+    pub fn request_attention(&mut self) {
+        self.window.request_attention();
+    }
+
+    /// Changes the padding set with `SystemBuilder::window_padding`
+    pub fn set_window_padding(&mut self, padding: [f32; 2]) {
+        self.window_padding = padding;
+    }
+
+    /// Starts reading QR codes from the camera at `index`, the first being 0, sending each to the
+    /// app as `Event::User`. Replaces any scanner already running.
+    #[cfg(feature = "camera")]
+    pub fn start_qr_scanner(&mut self, index: u32) {
+        self.qr_scanner.start(index);
+    }
+
+    #[cfg(feature = "camera")]
+    pub fn stop_qr_scanner(&mut self) {
+        self.qr_scanner.stop();
+    }
+
+    /// The camera's view while the QR scanner is running, e.g. to help the user aim it
+    #[cfg(feature = "camera")]
+    #[must_use]
+    pub fn qr_scanner_preview(&self) -> Option<Ref<'_, VideoTexture>> {
+        self.qr_scanner.preview()
+    }
+
+    /// Starts, stops and previews the QR scanner as the methods above do. Clone it to give the
+    /// app a handle.
+    #[cfg(feature = "camera")]
+    #[must_use]
+    pub fn qr_scanner(&self) -> &QrScannerHandle {
+        &self.qr_scanner
+    }
+
+    /// Shows a message with a row of buttons in an imgui modal popup, then calls `on_result` with
+    /// the index of the button pressed. Dialogs shown while another is open wait their turn.
+    pub fn show_modal(
+        &mut self,
+        title: &str,
+        body: &str,
+        buttons: &[&str],
+        on_result: impl FnOnce(usize) + 'static,
+    ) {
+        self.modal_queue.show(title, body, buttons, on_result);
+    }
+
+    /// Shows modals as `show_modal` does. Clone it to give the app a handle.
+    #[must_use]
+    pub fn modals(&self) -> &Modals {
+        &self.modal_queue
+    }
+
+    /// Runs frames until the window is closed, by the user or `request_close`
     pub fn main_loop(&mut self) {
-        let System {
-            glfw,
-            window,
-            events,
-            platform,
-            mut last_frame_time,
+        while !self.window.should_close() {
+            let frame_start = Instant::now();
+            self.wait_for_events();
+            self.frame();
+            self.limit_frame_rate(frame_start);
+        }
+    }
+
+    /// Runs one frame without waiting for input or capping the frame rate, for apps which drive
+    /// the loop from their own runtime. Returns false once the window should close, without
+    /// running a frame.
+    pub fn step(&mut self) -> bool {
+        if self.window.should_close() {
+            return false;
+        }
+        self.glfw.poll_events();
+        self.frame();
+        true
+    }
+
+    /// Ends `main_loop` after the current frame, or makes `step` return false
+    pub fn request_close(&mut self) {
+        self.window.set_should_close(true);
+    }
+
+    /// Handles the pending events, then draws and presents a frame
+    fn frame(&mut self) {
+        self.handle_events();
+        self.phase_hooks.run(FramePhase::Input);
+        self.prepare_frame();
+        self.phase_hooks.run(FramePhase::Update);
+        self.modals.extend(self.modal_queue.take());
+
+        let mut hooks = WindowFrame {
+            app: &mut *self.app,
+            menus: &self.menus,
+            docking: self.docking,
+            auto_resize: self.auto_resize,
+            padding: self.window_padding,
+            jobs: &self.jobs,
+            modals: &mut self.modals,
+            metrics: &mut self.metrics,
+            metrics_overlay: self.metrics_overlay,
+            perf_overlay: self.perf_overlay,
+            imgui_metrics: &mut self.imgui_metrics,
+            phase_hooks: &self.phase_hooks,
+            scissor_check: &mut self.scissor_check,
+            background: self.background,
+            draw_data_export: self.draw_data_export.as_ref(),
+            top: 0.0,
+            content_size: None,
+            chosen: None,
+        };
+        format::set_current(self.locale);
+        self.last_frame_stats = self.driver.frame_with(
+            &mut self.imgui,
+            &mut WindowPlatform {
+                platform: &self.platform,
+                window: &self.window,
+            },
+            &mut self.renderer,
+            &mut hooks,
+        );
+        let WindowFrame {
+            content_size,
+            chosen,
             ..
-        } = self;
-        while !window.should_close() {
-            glfw.wait_events_timeout(0.1);
-            for (_timestamp, event) in events.try_iter() {
-                let mut consumed = false;
-                if let Some(app_event) = from_event(&event) {
-                    consumed = self.app.handle_event(app_event);
-                }
-                if !consumed {
-                    platform.handle_event(self.imgui.io_mut(), window, &event);
-                }
+        } = hooks;
+        self.app.render_foreground();
+        self.scroll_positions.save_if_due();
+        self.phase_hooks.run(FramePhase::Render);
+
+        if let Some(id) = chosen {
+            self.app.handle_event(Event::Menu(id), self.driver.input());
+        }
+        self.modals.retain(|modal| !modal.is_finished());
+
+        self.phase_hooks.run(FramePhase::Present);
+        self.present();
+        if self.close.take() {
+            self.window.set_should_close(true);
+        }
+        if let Some(size) = content_size {
+            self.resize_to_content(size);
+        }
+    }
+
+    /// Draws imgui's windows outside the main one, if any, then shows the frame
+    fn present(&mut self) {
+        #[cfg(feature = "docking")]
+        if let Some(viewports) = &self.viewports {
+            viewports.render(&mut self.imgui, &mut self.window);
+        }
+        // Swap front and back buffers
+        self.window.swap_buffers();
+    }
+
+    /// Resizes the window to `size`, within the size limits, unless it fills a monitor
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn resize_to_content(&mut self, [width, height]: [f32; 2]) {
+        if self.display_mode != DisplayMode::Windowed {
+            return;
+        }
+        let mut size = (width.ceil() as u32, height.ceil() as u32);
+        if let Some((max_width, max_height)) = self.max_size {
+            size = (size.0.min(max_width), size.1.min(max_height));
+        }
+        if let Some((min_width, min_height)) = self.min_size {
+            size = (size.0.max(min_width), size.1.max(min_height));
+        }
+        #[allow(clippy::cast_possible_wrap)]
+        let size = (size.0 as i32, size.1 as i32);
+        if self.window.get_size() != size {
+            self.window.set_size(size.0, size.1);
+        }
+    }
+
+    /// Waits for input, unless something is animating and the next frame is wanted at once
+    fn wait_for_events(&mut self) {
+        // a loading screen is up until the app says it's ready, which it's only asked each frame,
+        // and GLFW raises no events for gamepads, which are read each frame
+        if self.jobs.is_busy()
+            || self.driver.tasks().is_busy()
+            || self.driver.is_loading()
+            || self.fonts.fonts().is_rebuilding()
+            || self.navigation.gamepad
+            || self.is_scanning()
+            || self.kinetic_scroll.is_moving()
+            || self.long_press.is_pending()
+            || self.app.wants_redraw()
+        {
+            self.glfw.poll_events();
+        } else if self.sleep_when_idle {
+            self.glfw.wait_events();
+        } else {
+            self.glfw.wait_events_timeout(0.1);
+        }
+    }
+
+    /// Sleeps out the rest of the frame which began at `frame_start` if the frame rate is capped
+    fn limit_frame_rate(&self, frame_start: Instant) {
+        if let Some(interval) = self.frame_interval {
+            if let Some(remaining) = interval.checked_sub(frame_start.elapsed()) {
+                thread::sleep(remaining);
             }
+        }
+    }
 
-            let now = Instant::now();
-            self.imgui.io_mut().update_delta_time(now - last_frame_time);
-            last_frame_time = now;
+    /// Carries out requests made since the last frame and updates the app and imgui's input
+    fn prepare_frame(&mut self) {
+        self.jobs.run_for(FRAME_BUDGET);
+        profile::handle_request(self.profiles.as_ref(), &mut self.window, &mut self.imgui);
 
-            self.imgui.style_mut().window_padding = [0.0, 0.0];
-            let display_size = self.imgui.io().display_size;
+        if self.renderer.restore_if_lost(&mut self.imgui) {
+            self.app
+                .handle_event(Event::GraphicsReset, self.driver.input());
+        }
+        #[cfg(feature = "camera")]
+        for code in self.qr_scanner.poll() {
+            self.app
+                .handle_event(Event::User(code), self.driver.input());
+        }
+        if let Some(texture) = self
+            .fonts
+            .step(&mut self.imgui, bind_texture, rebind_texture)
+        {
+            self.renderer.replace_font_texture(texture);
+        }
 
-            let ui = self.imgui.new_frame();
-            ui.window("ImGui Window")
-                .position([0.0, 0.0], Condition::Always)
-                .size(display_size, Condition::Always)
-                .flags(
-                    WindowFlags::NO_BACKGROUND
-                        | WindowFlags::NO_DECORATION
-                        | WindowFlags::NO_INPUTS,
-                )
-                .build(|| self.app.draw_ui(ui));
+        let now = Instant::now();
+        let delta = now - self.last_frame_time;
+        self.imgui.io_mut().update_delta_time(delta);
+        self.last_frame_time = now;
+        self.app.update(delta.as_secs_f32());
 
-            unsafe {
-                gl::ClearColor(0.2, 0.2, 0.2, 1.0);
-                gl::Clear(gl::COLOR_BUFFER_BIT);
+        #[allow(clippy::cast_precision_loss)]
+        let width = self.window.get_size().0 as f32;
+        if let Some(class) = self.breakpoints.as_mut().and_then(|b| b.update(width)) {
+            self.app
+                .handle_event(Event::SizeClass(class), self.driver.input());
+        }
+
+        let io = self.imgui.io_mut();
+        self.kinetic_scroll.apply(io);
+        self.navigation.apply(io);
+        if self.navigation.gamepad {
+            update_gamepad(io, gamepad_state(&self.glfw).as_ref(), &self.analog_tuning);
+        }
+        self.focus.apply(&mut self.imgui);
+        #[cfg(feature = "docking")]
+        if let Some(viewports) = &self.viewports {
+            viewports.prepare_frame(&mut self.imgui, &mut self.glfw, &self.window);
+        }
+    }
+
+    /// Whether frames are drawn continuously to show the camera
+    #[cfg(feature = "camera")]
+    fn is_scanning(&self) -> bool {
+        self.qr_scanner.is_running()
+    }
+
+    #[cfg(not(feature = "camera"))]
+    #[allow(clippy::unused_self)]
+    fn is_scanning(&self) -> bool {
+        false
+    }
+
+    fn handle_events(&mut self) {
+        let mut window_events: Vec<WindowEvent> =
+            self.events.try_iter().map(|(_, event)| event).collect();
+        for event in window_events.iter().filter(|event| is_input(event)) {
+            self.recorder.record(event);
+        }
+        window_events.extend(self.recorder.update());
+        if self.coalesce_events {
+            window_events = coalesce(window_events);
+        }
+
+        for event in window_events {
+            let mut consumed = false;
+            if let Some(app_event) = from_event(&event) {
+                self.long_press.observe(&app_event);
+                consumed = match hotkey_action(&mut self.hotkeys, &app_event) {
+                    Some(action) => {
+                        if let Some(hotkey_event) = action.trigger() {
+                            self.app.handle_event(hotkey_event, self.driver.input());
+                        }
+                        true
+                    }
+                    None => self.app.handle_event(app_event, self.driver.input()),
+                };
             }
+            let io = self.imgui.io_mut();
+            match event {
+                // Consuming the close request vetoes it, as does the app's hook
+                WindowEvent::Close => {
+                    let close = !consumed && self.app.on_close_requested();
+                    self.window.set_should_close(close);
+                }
+                // imgui must track the window size and modifiers whatever the app does with the
+                // event
+                WindowEvent::Size(..) | WindowEvent::Focus(_) => {
+                    self.platform.handle_event(io, &self.window, &event);
+                }
+                // Held scrolls reach imgui at the start of the next frame
+                #[allow(clippy::cast_possible_truncation)]
+                WindowEvent::Scroll(x, y)
+                    if !consumed && self.kinetic_scroll.hold(x as f32, y as f32) => {}
+                _ if !consumed => self.platform.handle_event(io, &self.window, &event),
+                _ => {}
+            }
+        }
+        if let Some(long_press) = self.long_press.poll() {
+            self.app.handle_event(long_press, self.driver.input());
+        }
+        #[cfg(feature = "docking")]
+        if let Some(viewports) = &self.viewports {
+            viewports.handle_events(&mut self.imgui, &self.platform);
+        }
+    }
+}
 
-            render(&mut self.imgui);
+impl Drop for System {
+    fn drop(&mut self) {
+        self.app.on_exit();
+    }
+}
 
-            // Swap front and back buffers
-            window.swap_buffers();
+/// Events which macros record, leaving out changes to the window itself
+fn is_input(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::Key(..)
+            | WindowEvent::Char(_)
+            | WindowEvent::CursorPos(..)
+            | WindowEvent::MouseButton(..)
+            | WindowEvent::Scroll(..)
+    )
+}
+
+/// Replaces each run of cursor moves and scrolls with the last position then the summed scroll
+fn coalesce(events: Vec<WindowEvent>) -> Vec<WindowEvent> {
+    let mut motion = MotionCoalescer::default();
+    let mut coalesced = Vec::with_capacity(events.len());
+    for event in events {
+        if !from_event(&event).is_some_and(|app_event| motion.hold(&app_event)) {
+            coalesced.extend(motion.flush().iter().filter_map(to_window_event));
+            coalesced.push(event);
         }
     }
+    coalesced.extend(motion.flush().iter().filter_map(to_window_event));
+    coalesced
+}
+
+/// The GLFW event for a cursor move or scroll let go by a `MotionCoalescer`
+fn to_window_event(event: &Event) -> Option<WindowEvent> {
+    match *event {
+        Event::CursorPos(x, y) => Some(WindowEvent::CursorPos(x.into(), y.into())),
+        Event::Scroll(x, y) => Some(WindowEvent::Scroll(x.into(), y.into())),
+        _ => None,
+    }
+}
+
+/// The time between frames at `fps`, or `None` if that isn't a usable rate
+fn frame_interval(fps: f32) -> Option<Duration> {
+    (fps > 0.0)
+        .then(|| Duration::try_from_secs_f32(fps.recip()).ok())
+        .flatten()
 }
 
 fn from_event(event: &WindowEvent) -> Option<Event> {
@@ -163,8 +1363,8 @@ fn from_event(event: &WindowEvent) -> Option<Event> {
                 None
             }
         }
-        WindowEvent::CursorPos(x, y) => Some(Event::CursorPos(x as _, y as _)),
-        WindowEvent::Scroll(x, y) => Some(Event::Scroll(x as _, y as _)),
+        WindowEvent::CursorPos(x, y) => Some(Event::CursorPos(x as f32, y as f32)),
+        WindowEvent::Scroll(x, y) => Some(Event::Scroll(x as f32, y as f32)),
         WindowEvent::Key(key, _scancode, action, modifiers) => match to_common_action(action) {
             Some(action) => {
                 let key = to_imgui_key(key);
@@ -172,11 +1372,24 @@ fn from_event(event: &WindowEvent) -> Option<Event> {
                     control: modifiers & glfw::Modifiers::Control != glfw::Modifiers::empty(),
                     option: modifiers & glfw::Modifiers::Alt != glfw::Modifiers::empty(),
                     shift: modifiers & glfw::Modifiers::Shift != glfw::Modifiers::empty(),
+                    super_key: modifiers & glfw::Modifiers::Super != glfw::Modifiers::empty(),
                 };
                 Some(Event::Key(key, '\u{0}', action, modifiers))
             }
             None => None,
         },
+        WindowEvent::FileDrop(ref paths) => Some(Event::FileDrop(paths.clone())),
+        WindowEvent::Focus(focused) => Some(Event::Focus(focused)),
+        #[allow(clippy::cast_sign_loss)]
+        WindowEvent::Size(width, height) => Some(Event::Resize(width as _, height as _)),
+        WindowEvent::Close => Some(Event::CloseRequested),
+        _ => None,
+    }
+}
+
+fn hotkey_action<'a>(hotkeys: &'a mut Hotkeys, event: &Event) -> Option<&'a mut HotkeyAction> {
+    match event {
+        Event::Key(Some(key), _, Action::Press, modifiers) => hotkeys.matching(*key, modifiers),
         _ => None,
     }
 }
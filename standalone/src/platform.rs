@@ -25,7 +25,11 @@
 
 use crate::keymap::to_imgui_key;
 use glfw::{Action, Window, WindowEvent};
+#[cfg(feature = "docking")]
+use imgui::ConfigFlags;
 use imgui::{Context, Io, Key, MouseButton};
+use imgui_support::backend::{self, PlatformBackend};
+use imgui_support::events::Event;
 
 pub struct Platform;
 
@@ -48,7 +52,24 @@ impl Platform {
     ///
     /// * framebuffer scale (i.e. DPI factor) is set
     /// * display size is set
+    /// * on Windows, the IME's candidate window follows imgui's text cursor
     pub fn attach_window(&mut self, io: &mut Io, window: &Window) {
+        self.prepare_frame(io, window);
+
+        // GLFW has no preedit callbacks, so on every platform composition happens in the input
+        // method's own window and only committed text arrives, as `WindowEvent::Char`; imgui
+        // never draws the text being composed. Giving imgui the native handle lets its default
+        // Win32 IME handler move that window to the text cursor. GLFW exposes no equivalent for
+        // macOS's text input client or X11's input context, so there the window stays wherever
+        // the input method puts it.
+        #[cfg(target_os = "windows")]
+        unsafe {
+            (*imgui::sys::igGetMainViewport()).PlatformHandleRaw = window.get_win32_window();
+        }
+    }
+
+    /// Updates the framebuffer scale and display size from the window, before each frame
+    pub fn prepare_frame(&self, io: &mut Io, window: &Window) {
         let (scale_factor_x, _scale_factor_y) = window.get_content_scale();
         let hidpi_factor = scale_factor_x.round();
         io.display_framebuffer_scale = [hidpi_factor, hidpi_factor];
@@ -60,7 +81,7 @@ impl Platform {
     ///
     /// * keyboard state is updated
     /// * mouse state is updated
-    pub fn handle_event(&self, io: &mut Io, _window: &Window, event: &WindowEvent) {
+    pub fn handle_event(&self, io: &mut Io, window: &Window, event: &WindowEvent) {
         match *event {
             WindowEvent::Key(key, _scancode, action, _modifiers) => {
                 let pressed = match action {
@@ -90,6 +111,28 @@ impl Platform {
                     }
                 }
             }
+            WindowEvent::Focus(focused) => {
+                // Modifiers pressed or released while another window had focus never reached
+                // us, so take their state from GLFW rather than leaving one stuck down
+                let held = |left, right| {
+                    focused
+                        && (window.get_key(left) != Action::Release
+                            || window.get_key(right) != Action::Release)
+                };
+                io.add_key_event(
+                    Key::ModShift,
+                    held(glfw::Key::LeftShift, glfw::Key::RightShift),
+                );
+                io.add_key_event(
+                    Key::ModCtrl,
+                    held(glfw::Key::LeftControl, glfw::Key::RightControl),
+                );
+                io.add_key_event(Key::ModAlt, held(glfw::Key::LeftAlt, glfw::Key::RightAlt));
+                io.add_key_event(
+                    Key::ModSuper,
+                    held(glfw::Key::LeftSuper, glfw::Key::RightSuper),
+                );
+            }
             WindowEvent::Size(width, height) => {
                 io.display_size = [width as _, height as _];
             }
@@ -100,6 +143,14 @@ impl Platform {
                 }
             }
             WindowEvent::CursorPos(x, y) => {
+                // With multi-viewports imgui works in desktop coordinates, not the window's
+                #[cfg(feature = "docking")]
+                let (x, y) = if io.config_flags.contains(ConfigFlags::VIEWPORTS_ENABLE) {
+                    let (left, top) = window.get_pos();
+                    (x + left as f64, y + top as f64)
+                } else {
+                    (x, y)
+                };
                 io.add_mouse_pos_event([x as _, y as _]);
             }
             WindowEvent::Scroll(x, y) => {
@@ -119,3 +170,29 @@ impl Platform {
         }
     }
 }
+
+/// The main window's platform for `FrameDriver`. GLFW's own events go to
+/// `Platform::handle_event`, which sees more of them; `handle_event` here takes the app's.
+pub struct WindowPlatform<'a> {
+    pub platform: &'a Platform,
+    pub window: &'a Window,
+}
+
+impl PlatformBackend for WindowPlatform<'_> {
+    fn prepare_frame(&mut self, io: &mut Io) {
+        self.platform.prepare_frame(io, self.window);
+    }
+
+    fn handle_event(&mut self, io: &mut Io, event: Event) {
+        // With multi-viewports imgui works in desktop coordinates, not the window's
+        #[cfg(feature = "docking")]
+        let event = match event {
+            Event::CursorPos(x, y) if io.config_flags.contains(ConfigFlags::VIEWPORTS_ENABLE) => {
+                let (left, top) = self.window.get_pos();
+                Event::CursorPos(x + left as f32, y + top as f32)
+            }
+            event => event,
+        };
+        backend::send_input(io, event);
+    }
+}
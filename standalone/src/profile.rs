@@ -0,0 +1,58 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use glfw::Window;
+use imgui::Context;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use imgui_support::profiles::{ProfileRequest, Profiles};
+
+/// The GLFW window's placement and imgui's window state, including docking
+#[derive(Serialize, Deserialize)]
+struct Profile {
+    position: (i32, i32),
+    size: (i32, i32),
+    imgui_ini: String,
+}
+
+impl Profile {
+    fn capture(window: &Window, imgui: &mut Context) -> Self {
+        let mut imgui_ini = String::new();
+        imgui.save_ini_settings(&mut imgui_ini);
+        Profile {
+            position: window.get_pos(),
+            size: window.get_size(),
+            imgui_ini,
+        }
+    }
+
+    fn apply(&self, window: &mut Window, imgui: &mut Context) {
+        window.set_pos(self.position.0, self.position.1);
+        window.set_size(self.size.0, self.size.1);
+        imgui.load_ini_settings(&self.imgui_ini);
+    }
+}
+
+/// Carries out any request made through `Profiles`. Must be called between frames.
+pub fn handle_request(profiles: Option<&Profiles>, window: &mut Window, imgui: &mut Context) {
+    let Some((profiles, request)) =
+        profiles.and_then(|profiles| Some((profiles, profiles.take_request()?)))
+    else {
+        return;
+    };
+    let result = match &request {
+        ProfileRequest::Save(name) => profiles.store(name, &Profile::capture(window, imgui)),
+        ProfileRequest::Apply(name) => profiles.load::<Profile>(name).map(|profile| {
+            if let Some(profile) = profile {
+                profile.apply(window, imgui);
+            }
+        }),
+    };
+    if let Err(e) = result {
+        warn!(?request, error = %e, "Unable to update profile");
+    }
+}
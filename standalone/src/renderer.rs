@@ -8,10 +8,13 @@ use std::mem;
 
 use gl::types::GLuint;
 use gl21 as gl;
-use imgui::{Context, DrawIdx};
+use imgui::{Context, DrawData, DrawIdx};
 
+use imgui_support::backend::RendererBackend;
+use imgui_support::metrics::RenderStats;
 use imgui_support::renderer_common::{
-    add_fonts, configure_imgui, render as common_render, return_param, FontStyles,
+    add_fonts, configure_imgui, delete_texture, render as common_render, return_param,
+    texture_lost, upload_font_texture, FontStyles, DEFAULT_FONT_SIZE,
 };
 
 pub struct Renderer {
@@ -22,20 +25,51 @@ impl Renderer {
     pub fn new(imgui: &mut Context) -> Self {
         configure_imgui(imgui, "standalone");
         let font_texture = bind_texture();
-        add_fonts(font_texture, imgui.fonts(), 14.0, &FontStyles::default());
+        add_fonts(
+            font_texture,
+            imgui.fonts(),
+            DEFAULT_FONT_SIZE,
+            &FontStyles::default(),
+        );
         Self { font_texture }
     }
+
+    /// Switches to a font texture built by a `FontRebuilder`, deleting the old one
+    pub fn replace_font_texture(&mut self, font_texture: GLuint) {
+        delete_texture(self.font_texture);
+        self.font_texture = font_texture;
+    }
+
+    /// Recreates the font texture and those from `create_texture` if the context was reset,
+    /// returning true if it was.
+    pub fn restore_if_lost(&mut self, imgui: &mut Context) -> bool {
+        if !texture_lost(self.font_texture) {
+            return false;
+        }
+        imgui_support::restore_textures();
+        self.font_texture = bind_texture();
+        upload_font_texture(self.font_texture, imgui.fonts());
+        true
+    }
 }
 
-pub fn render(ctx: &mut Context) {
-    let [width, height] = ctx.io().display_size;
-    let [scale_w, scale_h] = ctx.io().display_framebuffer_scale;
+impl RendererBackend for Renderer {
+    fn render(&mut self, draw_data: &DrawData) -> RenderStats {
+        render_draw_data(draw_data)
+    }
+}
+
+/// Renders `draw_data` into the current context's framebuffer, which is that of the window it
+/// was built for
+pub fn render_draw_data(draw_data: &DrawData) -> RenderStats {
+    let [width, height] = draw_data.display_size;
+    let [scale_w, scale_h] = draw_data.framebuffer_scale;
+    // clip rects are in imgui's coordinates, which are the desktop's with multi-viewports
+    let [offset_x, offset_y] = draw_data.display_pos;
 
     let fb_width = width * scale_w;
     let fb_height = height * scale_h;
 
-    let draw_data = ctx.render();
-
     setup_render_state(
         fb_width,
         fb_height,
@@ -43,13 +77,19 @@ pub fn render(ctx: &mut Context) {
         draw_data.display_pos,
     );
 
-    common_render(
+    let stats = common_render(
         draw_data,
-        |count, clip_rect, texture_id, idx_buffer, idx_offset| {
-            let [x, y, z, w] = clip_rect;
-            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        |texture_id| {
+            #[allow(clippy::cast_possible_truncation)]
             unsafe {
                 gl::BindTexture(gl::TEXTURE_2D, texture_id.id() as _);
+            }
+        },
+        |count, clip_rect, idx_buffer, idx_offset| {
+            let [x, y] = [clip_rect[0] - offset_x, clip_rect[1] - offset_y];
+            let [z, w] = [clip_rect[2] - offset_x, clip_rect[3] - offset_y];
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            unsafe {
                 gl::Scissor(
                     (x * scale_w) as _,
                     (fb_height - w * scale_h) as _,
@@ -72,6 +112,7 @@ pub fn render(ctx: &mut Context) {
     );
 
     restore_render_state();
+    stats
 }
 
 fn setup_render_state(
@@ -134,9 +175,7 @@ fn restore_render_state() {
 
 impl Drop for Renderer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteTextures(1, &self.font_texture);
-        }
+        delete_texture(self.font_texture);
     }
 }
 
@@ -147,3 +186,9 @@ pub(crate) fn bind_texture() -> GLuint {
         texture
     }
 }
+
+pub(crate) fn rebind_texture(texture: u32) {
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+    }
+}
@@ -10,12 +10,14 @@ use imgui_support::geometry::Rect;
 
 #[must_use]
 pub fn get_screen_bounds(glfw: &mut Glfw) -> Rect {
+    primary_monitor_bounds(glfw).expect("Failed to get primary monitor's video mode")
+}
+
+/// The primary monitor's bounds, or `None` if there's no monitor, e.g. in a headless session
+pub fn primary_monitor_bounds(glfw: &mut Glfw) -> Option<Rect> {
     #[allow(clippy::cast_possible_wrap)]
     glfw.with_primary_monitor(|_, m| {
-        let mode = m
-            .expect("Failed to get primary monitor")
-            .get_video_mode()
-            .expect("Failed to get video mode");
-        Rect::new(0, 0, mode.width as _, mode.height as _)
+        let mode = m?.get_video_mode()?;
+        Some(Rect::new(0, 0, mode.width as _, mode.height as _))
     })
 }
@@ -0,0 +1,314 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ptr;
+use std::rc::Rc;
+
+use gl21 as gl;
+use glfw::{Context as _, Glfw, GlfwReceiver, PWindow, Window, WindowEvent, WindowHint};
+use imgui::{
+    BackendFlags, ConfigFlags, Context, Id, PlatformMonitor, PlatformViewportBackend,
+    RendererViewportBackend, Viewport, ViewportFlags,
+};
+use tracing::warn;
+
+use crate::platform::Platform;
+use crate::renderer::render_draw_data;
+
+/// Lets imgui windows be dragged out of the main window onto the desktop and other monitors, each
+/// in an OS window of its own sharing the main window's GL context
+pub struct Viewports {
+    windows: Windows,
+    main: Rc<Cell<MainWindow>>,
+    share: Rc<Cell<*const Window>>,
+}
+
+/// The OS windows of imgui's viewports, by viewport
+type Windows = Rc<RefCell<HashMap<Id, ViewportWindow>>>;
+
+struct ViewportWindow {
+    window: PWindow,
+    events: GlfwReceiver<(f64, WindowEvent)>,
+}
+
+/// The main window as of the start of the frame, which imgui asks after while the System has it
+#[derive(Clone, Copy, Default)]
+struct MainWindow {
+    pos: [f32; 2],
+    size: [f32; 2],
+    focused: bool,
+    minimized: bool,
+}
+
+impl Viewports {
+    /// Turns on imgui's viewports, with GLFW creating their windows and GL drawing them
+    pub fn install(imgui: &mut Context) -> Self {
+        let windows = Windows::default();
+        let main = Rc::new(Cell::new(MainWindow::default()));
+        let share = Rc::new(Cell::new(ptr::null()));
+        imgui.set_platform_backend(GlfwViewports {
+            windows: windows.clone(),
+            main: main.clone(),
+            share: share.clone(),
+        });
+        imgui.set_renderer_backend(GlViewports);
+        let io = imgui.io_mut();
+        io.config_flags.insert(ConfigFlags::VIEWPORTS_ENABLE);
+        io.backend_flags
+            .insert(BackendFlags::PLATFORM_HAS_VIEWPORTS | BackendFlags::RENDERER_HAS_VIEWPORTS);
+        Viewports {
+            windows,
+            main,
+            share,
+        }
+    }
+
+    /// Tells imgui where the monitors and main window are, before it starts a frame
+    pub fn prepare_frame(&self, imgui: &mut Context, glfw: &mut Glfw, window: &Window) {
+        let (x, y) = window.get_pos();
+        let (width, height) = window.get_size();
+        #[allow(clippy::cast_precision_loss)]
+        self.main.set(MainWindow {
+            pos: [x as f32, y as f32],
+            size: [width as f32, height as f32],
+            focused: window.is_focused(),
+            minimized: window.is_iconified(),
+        });
+        let monitors = glfw.with_connected_monitors(|_, monitors| {
+            monitors
+                .iter()
+                .filter_map(|monitor| {
+                    let mode = monitor.get_video_mode()?;
+                    let (x, y) = monitor.get_pos();
+                    let (work_x, work_y, work_width, work_height) = monitor.get_workarea();
+                    #[allow(clippy::cast_precision_loss)]
+                    Some(PlatformMonitor {
+                        main_pos: [x as f32, y as f32],
+                        main_size: [mode.width as f32, mode.height as f32],
+                        work_pos: [work_x as f32, work_y as f32],
+                        work_size: [work_width as f32, work_height as f32],
+                        dpi_scale: monitor.get_content_scale().0,
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        imgui
+            .platform_io_mut()
+            .monitors
+            .replace_from_slice(&monitors);
+    }
+
+    /// Passes input in the viewports' windows to imgui, and their moves, resizes and close
+    /// requests to their viewports. The app's `handle_event` only sees the main window's events.
+    pub fn handle_events(&self, imgui: &mut Context, platform: &Platform) {
+        for (id, viewport_window) in self.windows.borrow().iter() {
+            for (_, event) in glfw::flush_messages(&viewport_window.events) {
+                let request = |imgui: &mut Context, set: fn(&mut Viewport)| {
+                    if let Some(viewport) = imgui.viewport_by_id_mut(*id) {
+                        set(viewport);
+                    }
+                };
+                match event {
+                    WindowEvent::Close => {
+                        request(imgui, |viewport| viewport.platform_request_close = true);
+                    }
+                    WindowEvent::Pos(..) => {
+                        request(imgui, |viewport| viewport.platform_request_move = true);
+                    }
+                    WindowEvent::Size(..) => {
+                        request(imgui, |viewport| viewport.platform_request_resize = true);
+                    }
+                    _ => platform.handle_event(imgui.io_mut(), &viewport_window.window, &event),
+                }
+            }
+        }
+    }
+
+    /// Creates, moves and draws the viewports' windows after the frame is rendered, leaving
+    /// `window`'s context current again
+    pub fn render(&self, imgui: &mut Context, window: &mut Window) {
+        // imgui only creates windows while updating them, so the main window outlives its use
+        self.share.set(ptr::from_ref(window));
+        imgui.update_platform_windows();
+        self.share.set(ptr::null());
+        imgui.render_platform_windows_default();
+        window.make_current();
+    }
+}
+
+/// Creates and manages a GLFW window for each of imgui's viewports
+struct GlfwViewports {
+    windows: Windows,
+    main: Rc<Cell<MainWindow>>,
+    /// The main window, whose GL context new windows share, while imgui is updating them
+    share: Rc<Cell<*const Window>>,
+}
+
+impl GlfwViewports {
+    /// Calls `f` with the viewport's window, or returns `None` for the main window's viewport
+    fn with_window<T>(&self, viewport: &Viewport, f: impl FnOnce(&mut Window) -> T) -> Option<T> {
+        let mut windows = self.windows.borrow_mut();
+        windows
+            .get_mut(&viewport.id)
+            .map(|viewport_window| f(&mut viewport_window.window))
+    }
+}
+
+impl PlatformViewportBackend for GlfwViewports {
+    fn create_window(&mut self, viewport: &mut Viewport) {
+        let share = self.share.get();
+        assert!(
+            !share.is_null(),
+            "viewport created outside Viewports::render"
+        );
+        let share = unsafe { &*share };
+        let flags = viewport.flags;
+        let mut glfw = share.glfw.clone();
+        glfw.window_hint(WindowHint::Visible(false));
+        glfw.window_hint(WindowHint::FocusOnShow(
+            !flags.contains(ViewportFlags::NO_FOCUS_ON_APPEARING),
+        ));
+        glfw.window_hint(WindowHint::Decorated(
+            !flags.contains(ViewportFlags::NO_DECORATION),
+        ));
+        glfw.window_hint(WindowHint::Floating(
+            flags.contains(ViewportFlags::TOP_MOST),
+        ));
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let created = share.create_shared(
+            viewport.size[0] as u32,
+            viewport.size[1] as u32,
+            "",
+            glfw::WindowMode::Windowed,
+        );
+        // leave the hints as GLFW's defaults for any other windows
+        glfw.window_hint(WindowHint::Visible(true));
+        glfw.window_hint(WindowHint::FocusOnShow(true));
+        glfw.window_hint(WindowHint::Decorated(true));
+        glfw.window_hint(WindowHint::Floating(false));
+
+        let Some((mut window, events)) = created else {
+            warn!("Failed to create a window for an imgui viewport");
+            return;
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        window.set_pos(viewport.pos[0] as i32, viewport.pos[1] as i32);
+        window.set_all_polling(true);
+        viewport.platform_handle = window.window_ptr().cast();
+        self.windows
+            .borrow_mut()
+            .insert(viewport.id, ViewportWindow { window, events });
+    }
+
+    fn destroy_window(&mut self, viewport: &mut Viewport) {
+        self.windows.borrow_mut().remove(&viewport.id);
+        viewport.platform_handle = ptr::null_mut();
+    }
+
+    fn show_window(&mut self, viewport: &mut Viewport) {
+        self.with_window(viewport, Window::show);
+    }
+
+    fn set_window_pos(&mut self, viewport: &mut Viewport, pos: [f32; 2]) {
+        #[allow(clippy::cast_possible_truncation)]
+        self.with_window(viewport, |window| {
+            window.set_pos(pos[0] as i32, pos[1] as i32);
+        });
+    }
+
+    fn get_window_pos(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+        #[allow(clippy::cast_precision_loss)]
+        self.with_window(viewport, |window| {
+            let (x, y) = window.get_pos();
+            [x as f32, y as f32]
+        })
+        .unwrap_or_else(|| self.main.get().pos)
+    }
+
+    fn set_window_size(&mut self, viewport: &mut Viewport, size: [f32; 2]) {
+        #[allow(clippy::cast_possible_truncation)]
+        self.with_window(viewport, |window| {
+            window.set_size(size[0] as i32, size[1] as i32);
+        });
+    }
+
+    fn get_window_size(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+        #[allow(clippy::cast_precision_loss)]
+        self.with_window(viewport, |window| {
+            let (width, height) = window.get_size();
+            [width as f32, height as f32]
+        })
+        .unwrap_or_else(|| self.main.get().size)
+    }
+
+    fn set_window_focus(&mut self, viewport: &mut Viewport) {
+        self.with_window(viewport, Window::focus);
+    }
+
+    fn get_window_focus(&mut self, viewport: &mut Viewport) -> bool {
+        self.with_window(viewport, |window| window.is_focused())
+            .unwrap_or_else(|| self.main.get().focused)
+    }
+
+    fn get_window_minimized(&mut self, viewport: &mut Viewport) -> bool {
+        self.with_window(viewport, |window| window.is_iconified())
+            .unwrap_or_else(|| self.main.get().minimized)
+    }
+
+    fn set_window_title(&mut self, viewport: &mut Viewport, title: &str) {
+        self.with_window(viewport, |window| window.set_title(title));
+    }
+
+    fn set_window_alpha(&mut self, viewport: &mut Viewport, alpha: f32) {
+        self.with_window(viewport, |window| window.set_opacity(alpha));
+    }
+
+    fn update_window(&mut self, _viewport: &mut Viewport) {}
+
+    fn render_window(&mut self, viewport: &mut Viewport) {
+        self.with_window(viewport, Window::make_current);
+    }
+
+    fn swap_buffers(&mut self, viewport: &mut Viewport) {
+        self.with_window(viewport, Window::swap_buffers);
+    }
+
+    fn create_vk_surface(
+        &mut self,
+        _viewport: &mut Viewport,
+        _instance: u64,
+        _out_surface: &mut u64,
+    ) -> i32 {
+        // VK_ERROR_EXTENSION_NOT_PRESENT, as the windows are drawn with GL
+        -7
+    }
+}
+
+/// Draws each viewport into its window's framebuffer, once `GlfwViewports` has made its context
+/// current
+struct GlViewports;
+
+impl RendererViewportBackend for GlViewports {
+    fn create_window(&mut self, _viewport: &mut Viewport) {}
+
+    fn destroy_window(&mut self, _viewport: &mut Viewport) {}
+
+    fn set_window_size(&mut self, _viewport: &mut Viewport, _size: [f32; 2]) {}
+
+    fn render_window(&mut self, viewport: &mut Viewport) {
+        if !viewport.flags.contains(ViewportFlags::NO_RENDERER_CLEAR) {
+            unsafe {
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+            }
+        }
+        render_draw_data(viewport.draw_data());
+    }
+
+    fn swap_buffers(&mut self, _viewport: &mut Viewport) {}
+}
@@ -0,0 +1,218 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::time::Duration;
+
+use imgui::Ui;
+use xplm::data::borrowed::{DataRef, FindError};
+use xplm::data::{DataRead, DataReadWrite, ReadWrite};
+
+use imgui_support::smoothing::Smoother;
+use imgui_support::widgets::{
+    Band, FrequencyInput, SECONDS_PER_DAY, SquawkInput, TimePicker, TimeReference,
+};
+
+/// Binds a radio frequency dataref to a `FrequencyInput`
+pub struct FrequencyBinding {
+    dataref: DataRef<i32, ReadWrite>,
+    band: Band,
+    khz_per_unit: u32,
+}
+
+impl FrequencyBinding {
+    /// Binds to the 8.33 kHz frequency of COM radio `index` (1 or 2).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FindError` if the dataref could not be found or is not writable.
+    pub fn com(index: u8) -> Result<Self, FindError> {
+        Self::new(
+            &format!("sim/cockpit2/radios/actuators/com{index}_frequency_hz_833"),
+            Band::Com833,
+            1,
+        )
+    }
+
+    /// Binds to the frequency of NAV radio `index` (1 or 2).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FindError` if the dataref could not be found or is not writable.
+    pub fn nav(index: u8) -> Result<Self, FindError> {
+        Self::new(
+            &format!("sim/cockpit2/radios/actuators/nav{index}_frequency_hz"),
+            Band::Nav,
+            10,
+        )
+    }
+
+    /// Binds to an arbitrary integer dataref holding a frequency in units of `khz_per_unit`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FindError` if the dataref could not be found or is not writable.
+    pub fn new(name: &str, band: Band, khz_per_unit: u32) -> Result<Self, FindError> {
+        Ok(FrequencyBinding {
+            dataref: DataRef::find(name)?.writeable()?,
+            band,
+            khz_per_unit,
+        })
+    }
+
+    #[must_use]
+    pub fn khz(&self) -> u32 {
+        self.dataref.get().unsigned_abs() * self.khz_per_unit
+    }
+
+    pub fn set_khz(&mut self, khz: u32) {
+        self.dataref
+            .set((khz / self.khz_per_unit).try_into().unwrap_or(i32::MAX));
+    }
+
+    /// Draws a `FrequencyInput` for the bound radio, writing any change back to the sim.
+    pub fn input(&mut self, ui: &Ui, label: &str) -> bool {
+        let mut khz = self.khz();
+        let changed = FrequencyInput::new(label, self.band).build(ui, &mut khz);
+        if changed {
+            self.set_khz(khz);
+        }
+        changed
+    }
+}
+
+/// Binds the transponder code dataref to a `SquawkInput`
+pub struct SquawkBinding {
+    dataref: DataRef<i32, ReadWrite>,
+}
+
+impl SquawkBinding {
+    /// # Errors
+    ///
+    /// Returns `FindError` if the dataref could not be found or is not writable.
+    pub fn new() -> Result<Self, FindError> {
+        Ok(SquawkBinding {
+            dataref: DataRef::find("sim/cockpit2/radios/actuators/transponder_code")?
+                .writeable()?,
+        })
+    }
+
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        self.dataref.get().try_into().unwrap_or_default()
+    }
+
+    pub fn set_code(&mut self, code: u16) {
+        self.dataref.set(code.into());
+    }
+
+    /// Draws a `SquawkInput` for the transponder, writing any change back to the sim.
+    pub fn input(&mut self, ui: &Ui, label: &str) -> bool {
+        let mut code = self.code();
+        let changed = SquawkInput::new(label).build(ui, &mut code);
+        if changed {
+            self.set_code(code);
+        }
+        changed
+    }
+}
+
+/// The simulator's clock, for displaying and editing sim time
+pub struct SimClock {
+    zulu: DataRef<f32, ReadWrite>,
+    local: DataRef<f32>,
+    flight_time: DataRef<f32>,
+}
+
+impl SimClock {
+    /// # Errors
+    ///
+    /// Returns `FindError` if the time datarefs could not be found.
+    pub fn new() -> Result<Self, FindError> {
+        Ok(SimClock {
+            zulu: DataRef::find("sim/time/zulu_time_sec")?.writeable()?,
+            local: DataRef::find("sim/time/local_time_sec")?,
+            flight_time: DataRef::find("sim/time/total_flight_time_sec")?,
+        })
+    }
+
+    /// Sim time of day in `reference`, as seconds since midnight
+    #[must_use]
+    pub fn time(&self, reference: TimeReference) -> u32 {
+        let seconds = match reference {
+            TimeReference::Zulu => self.zulu.get(),
+            TimeReference::Local => self.local.get(),
+        };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let seconds = seconds.max(0.0) as u32;
+        seconds % SECONDS_PER_DAY
+    }
+
+    /// Sets the sim time of day, given as seconds since midnight in `reference`.
+    pub fn set_time(&mut self, reference: TimeReference, time: u32) {
+        let zulu = match reference {
+            TimeReference::Zulu => time,
+            TimeReference::Local => {
+                let offset = self.time(TimeReference::Local) + SECONDS_PER_DAY
+                    - self.time(TimeReference::Zulu);
+                (time + SECONDS_PER_DAY - offset % SECONDS_PER_DAY) % SECONDS_PER_DAY
+            }
+        };
+        #[allow(clippy::cast_precision_loss)]
+        self.zulu.set(zulu as f32);
+    }
+
+    #[must_use]
+    pub fn flight_time(&self) -> Duration {
+        Duration::from_secs_f32(self.flight_time.get().max(0.0))
+    }
+
+    /// Draws a `TimePicker` for the sim clock, writing any change back to the sim.
+    pub fn time_picker(&mut self, ui: &Ui, label: &str, reference: TimeReference) -> bool {
+        let mut time = self.time(reference);
+        let changed = TimePicker::new(label, reference).build(ui, &mut time);
+        if changed {
+            self.set_time(reference, time);
+        }
+        changed
+    }
+}
+
+/// A float dataref read through a `Smoother`, for needles and tapes drawn every frame
+pub struct SmoothedDataref {
+    dataref: DataRef<f32>,
+    clock: DataRef<f32>,
+    smoother: Smoother,
+}
+
+impl SmoothedDataref {
+    /// # Errors
+    ///
+    /// Returns `FindError` if the dataref could not be found.
+    pub fn new(name: &str, smoother: Smoother) -> Result<Self, FindError> {
+        Ok(SmoothedDataref {
+            dataref: DataRef::find(name)?,
+            clock: DataRef::find("sim/time/total_running_time_sec")?,
+            smoother,
+        })
+    }
+
+    /// Samples the dataref and returns the smoothed value. Call it each frame the value is
+    /// drawn.
+    pub fn value(&mut self) -> f32 {
+        self.smoother.sample(self.clock.get(), self.dataref.get());
+        self.smoother.value()
+    }
+
+    /// The latest value from the sim, unsmoothed
+    #[must_use]
+    pub fn raw(&self) -> f32 {
+        self.dataref.get()
+    }
+
+    pub fn reset(&mut self) {
+        self.smoother.reset();
+    }
+}
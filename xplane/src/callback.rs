@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::any::{type_name, TypeId};
+use std::ffi::c_void;
+use std::marker::PhantomPinned;
+use std::ops::Deref;
+use std::pin::Pin;
+
+/// Owns a value given to X-Plane as a callback's refcon, e.g. for a flight loop, draw callback
+/// or command handler, so apps needn't cast raw pointers themselves.
+///
+/// The value stays at the same address until the box is dropped, so unregister the callback
+/// first, typically in the `Drop` of whatever owns both. Forgetting the box leaks the value
+/// rather than leaving X-Plane with a dangling pointer. Callbacks only get shared access, so
+/// keep state they change in a `Cell` or `RefCell`.
+pub struct CallbackBox<T: 'static> {
+    inner: Pin<Box<Tagged<T>>>,
+}
+
+/// The type comes first so `from_refcon` can check it whatever `T` is
+#[repr(C)]
+struct Tagged<T> {
+    type_id: TypeId,
+    value: T,
+    _pinned: PhantomPinned,
+}
+
+impl<T: 'static> CallbackBox<T> {
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        CallbackBox {
+            inner: Box::pin(Tagged {
+                type_id: TypeId::of::<T>(),
+                value,
+                _pinned: PhantomPinned,
+            }),
+        }
+    }
+
+    /// The pointer to register the callback with
+    #[must_use]
+    pub fn refcon(&self) -> *mut c_void {
+        let tagged: *const Tagged<T> = &*self.inner;
+        tagged.cast_mut().cast()
+    }
+
+    /// The value behind `refcon`, for use in the callback
+    ///
+    /// # Safety
+    ///
+    /// `refcon` must have come from `refcon` on a `CallbackBox<T>` which is still alive, and the
+    /// reference mustn't be kept beyond the callback. A box of another type is caught in debug
+    /// builds.
+    #[must_use]
+    pub unsafe fn from_refcon<'a>(refcon: *mut c_void) -> &'a T {
+        let tagged = &*refcon.cast::<Tagged<T>>();
+        debug_assert!(
+            tagged.type_id == TypeId::of::<T>(),
+            "refcon is not a CallbackBox<{}>",
+            type_name::<T>()
+        );
+        &tagged.value
+    }
+}
+
+impl<T: 'static> Deref for CallbackBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner.value
+    }
+}
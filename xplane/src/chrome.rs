@@ -0,0 +1,278 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::{Condition, MouseCursor, StyleColor, StyleVar, Ui, WindowFlags};
+
+use imgui_support::geometry::Rect;
+
+use crate::ui::{Decoration, ResizingLimits};
+use crate::utils::get_mouse_location;
+
+/// The smallest the grip resizes a window to, so the title bar's buttons stay usable
+const MIN_SIZE: i32 = 64;
+
+/// What the user did with the chrome this frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChromeAction {
+    /// The close button was clicked
+    Close,
+    /// The title bar or grip is being dragged, to the given global desktop bounds
+    SetGeometry(Rect),
+}
+
+/// A title bar with pin and close buttons, and for resizable windows a grip in the bottom right
+/// corner, drawn with imgui for self-decorated windows, which X-Plane leaves bare. Dragging the
+/// title bar moves the window.
+pub struct Chrome {
+    resizable: bool,
+    /// Stops the window being moved or resized
+    pinned: bool,
+    drag: Option<Drag>,
+}
+
+/// A move or resize in progress
+struct Drag {
+    resize: bool,
+    /// Where the mouse and window were when it began, in global desktop boxels
+    mouse: (i32, i32),
+    geometry: Rect,
+    /// Where it last moved the window to, which may since have been snapped elsewhere
+    target: Rect,
+}
+
+impl Chrome {
+    pub fn new(resizable: bool) -> Self {
+        Chrome {
+            resizable,
+            pinned: false,
+            drag: None,
+        }
+    }
+
+    /// Chrome for a self-decorated window, with a grip if it's resizable
+    pub fn for_decoration(decoration: &Decoration) -> Option<Self> {
+        match decoration {
+            Decoration::SelfDecorated => Some(Chrome::new(false)),
+            Decoration::SelfDecoratedResizable => Some(Chrome::new(true)),
+            Decoration::None | Decoration::RoundRectangle => None,
+        }
+    }
+
+    pub fn pinned(&self) -> bool {
+        self.pinned
+    }
+
+    pub fn set_pinned(&mut self, pinned: bool) {
+        self.pinned = pinned;
+    }
+
+    /// The height of the title bar, which the window's contents go below
+    pub fn title_bar_height(ui: &Ui) -> f32 {
+        ui.frame_height() + ui.clone_style().frame_padding[1] * 2.0
+    }
+
+    /// Draws the chrome over a floating window at `geometry`, whose contents are `display_size`,
+    /// returning what the user did with it. Draw it after the window's contents, so it's on top.
+    pub fn draw(
+        &mut self,
+        ui: &Ui,
+        title: &str,
+        geometry: Rect,
+        display_size: [f32; 2],
+        limits: Option<&ResizingLimits>,
+    ) -> Option<ChromeAction> {
+        let _rounding = ui.push_style_var(StyleVar::WindowRounding(0.0));
+        let _padding = ui.push_style_var(StyleVar::WindowPadding([0.0, 0.0]));
+        let _border = ui.push_style_var(StyleVar::WindowBorderSize(0.0));
+        let flags = WindowFlags::NO_DECORATION
+            | WindowFlags::NO_SAVED_SETTINGS
+            | WindowFlags::NO_SCROLL_WITH_MOUSE;
+
+        let (closed, moving) = self.draw_title_bar(ui, title, display_size, flags);
+        let resizing = self.resizable && self.draw_grip(ui, display_size, flags);
+        if closed {
+            self.drag = None;
+            return Some(ChromeAction::Close);
+        }
+
+        let mouse = get_mouse_location();
+        if !(moving || resizing) || self.pinned {
+            self.drag = None;
+        } else if self.drag.is_none() {
+            self.drag = Some(Drag {
+                resize: resizing,
+                mouse,
+                geometry,
+                target: geometry,
+            });
+        }
+        let drag = self.drag.as_mut()?;
+        let (dx, dy) = (mouse.0 - drag.mouse.0, mouse.1 - drag.mouse.1);
+        let start = drag.geometry;
+        let target = if drag.resize {
+            resized(start, dx, dy, limits)
+        } else {
+            Rect::new(
+                start.left + dx,
+                start.top + dy,
+                start.right + dx,
+                start.bottom + dy,
+            )
+        };
+        // only once the mouse moves, so the window isn't dragged back from where it was snapped
+        if target == drag.target {
+            return None;
+        }
+        drag.target = target;
+        Some(ChromeAction::SetGeometry(target))
+    }
+
+    /// Returns whether the close button was clicked and whether the bar is being dragged
+    fn draw_title_bar(
+        &mut self,
+        ui: &Ui,
+        title: &str,
+        display_size: [f32; 2],
+        flags: WindowFlags,
+    ) -> (bool, bool) {
+        let height = Self::title_bar_height(ui);
+        let mut closed = false;
+        let mut moving = false;
+        let _background = ui.push_style_color(
+            StyleColor::WindowBg,
+            ui.style_color(StyleColor::TitleBgActive),
+        );
+        ui.window("##title_bar")
+            .position([0.0, 0.0], Condition::Always)
+            .size([display_size[0], height], Condition::Always)
+            .flags(flags)
+            .build(|| {
+                let buttons_width = height * 2.0;
+                ui.invisible_button(
+                    "##drag",
+                    [(display_size[0] - buttons_width).max(1.0), height],
+                );
+                moving = ui.is_item_active();
+
+                let text_height = ui.text_line_height();
+                let padding = ui.clone_style().frame_padding[0];
+                let colour = ui.style_color(StyleColor::Text);
+                ui.get_window_draw_list().add_text(
+                    [padding, (height - text_height) / 2.0],
+                    colour,
+                    title,
+                );
+
+                ui.set_cursor_pos([display_size[0] - buttons_width, 0.0]);
+                if title_button(ui, "##pin", height, self.pinned) {
+                    self.pinned = !self.pinned;
+                }
+                let [x, y] = ui.item_rect_min();
+                let radius = height * 0.15;
+                ui.get_window_draw_list()
+                    .add_circle([x + height / 2.0, y + height / 2.0], radius, colour)
+                    .filled(self.pinned)
+                    .build();
+
+                ui.set_cursor_pos([display_size[0] - height, 0.0]);
+                closed = title_button(ui, "##close", height, false);
+                let [x, y] = ui.item_rect_min();
+                let inset = height * 0.3;
+                let draw_list = ui.get_window_draw_list();
+                draw_list
+                    .add_line(
+                        [x + inset, y + inset],
+                        [x + height - inset, y + height - inset],
+                        colour,
+                    )
+                    .build();
+                draw_list
+                    .add_line(
+                        [x + height - inset, y + inset],
+                        [x + inset, y + height - inset],
+                        colour,
+                    )
+                    .build();
+            });
+        (closed, moving)
+    }
+
+    /// Returns whether the grip is being dragged
+    fn draw_grip(&self, ui: &Ui, display_size: [f32; 2], flags: WindowFlags) -> bool {
+        let size = ui.frame_height();
+        let mut resizing = false;
+        ui.window("##resize_grip")
+            .position(
+                [display_size[0] - size, display_size[1] - size],
+                Condition::Always,
+            )
+            .size([size, size], Condition::Always)
+            .flags(flags | WindowFlags::NO_BACKGROUND)
+            .build(|| {
+                ui.invisible_button("##grip", [size, size]);
+                resizing = ui.is_item_active();
+                let hovered = ui.is_item_hovered();
+                if (hovered || resizing) && !self.pinned {
+                    ui.set_mouse_cursor(Some(MouseCursor::ResizeNWSE));
+                }
+                let colour = ui.style_color(if resizing {
+                    StyleColor::ResizeGripActive
+                } else if hovered {
+                    StyleColor::ResizeGripHovered
+                } else {
+                    StyleColor::ResizeGrip
+                });
+                let [left, top] = ui.item_rect_min();
+                let [right, bottom] = ui.item_rect_max();
+                ui.get_window_draw_list()
+                    .add_triangle([right, top], [right, bottom], [left, bottom], colour)
+                    .filled(true)
+                    .build();
+            });
+        resizing
+    }
+}
+
+/// A square button the height of the title bar, highlighted when hovered or `on`
+fn title_button(ui: &Ui, id: &str, size: f32, on: bool) -> bool {
+    let clicked = ui.invisible_button(id, [size, size]);
+    let colour = if ui.is_item_active() {
+        Some(StyleColor::ButtonActive)
+    } else if ui.is_item_hovered() {
+        Some(StyleColor::ButtonHovered)
+    } else {
+        on.then_some(StyleColor::Button)
+    };
+    if let Some(colour) = colour {
+        let colour = ui.style_color(colour);
+        ui.get_window_draw_list()
+            .add_rect(ui.item_rect_min(), ui.item_rect_max(), colour)
+            .filled(true)
+            .build();
+    }
+    clicked
+}
+
+/// `start` with its bottom right corner moved by `dx` and `dy` boxels, within `limits`
+fn resized(start: Rect, dx: i32, dy: i32, limits: Option<&ResizingLimits>) -> Rect {
+    let (min_width, min_height, max_width, max_height) =
+        limits.map_or((MIN_SIZE, MIN_SIZE, i32::MAX, i32::MAX), |l| {
+            (
+                l.min_width.max(MIN_SIZE),
+                l.min_height.max(MIN_SIZE),
+                l.max_width,
+                l.max_height,
+            )
+        });
+    let width = (start.right - start.left + dx).clamp(min_width, max_width.max(min_width));
+    let height = (start.top - start.bottom - dy).clamp(min_height, max_height.max(min_height));
+    Rect::new(
+        start.left,
+        start.top,
+        start.left + width,
+        start.top - height,
+    )
+}
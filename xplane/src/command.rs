@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_wrap)]
+
+use std::cell::RefCell;
+use std::ffi::{c_int, c_void, CString};
+
+use xplm_sys::{
+    xplm_CommandBegin, XPLMCommandPhase, XPLMCommandRef, XPLMCreateCommand,
+    XPLMRegisterCommandHandler, XPLMUnregisterCommandHandler,
+};
+
+use crate::callback::CallbackBox;
+use crate::ui::Window;
+
+type Handler = Box<dyn FnMut()>;
+
+/// An XPLM command with a handler run each time the command begins. The handler is unregistered
+/// when this is dropped; the command itself lives until X-Plane exits.
+pub struct Command {
+    command_ref: XPLMCommandRef,
+    handler: CallbackBox<RefCell<Handler>>,
+}
+
+impl Command {
+    /// Creates the command `name`, or reuses it if it already exists
+    pub fn register(name: &str, description: &str, handler: impl FnMut() + 'static) -> Command {
+        let name_c = CString::new(name).expect("Could not create string from {name}");
+        let description_c =
+            CString::new(description).expect("Could not create string from {description}");
+        let handler: CallbackBox<RefCell<Handler>> =
+            CallbackBox::new(RefCell::new(Box::new(handler)));
+
+        unsafe {
+            let command_ref = XPLMCreateCommand(name_c.as_ptr(), description_c.as_ptr());
+            XPLMRegisterCommandHandler(command_ref, Some(handle_command), 1, handler.refcon());
+            Command {
+                command_ref,
+                handler,
+            }
+        }
+    }
+
+    /// Shows a hidden window or one behind other windows, or hides it if it's already in front.
+    ///
+    /// `window` must outlive the command.
+    pub fn toggle_window(name: &str, description: &str, window: &mut Window) -> Command {
+        let window: *mut Window = window;
+        Command::register(name, description, move || unsafe {
+            let window = &mut *window;
+            if !window.visible() {
+                window.set_visible(true);
+                window.bring_to_front();
+            } else if window.is_in_front() {
+                window.set_visible(false);
+            } else {
+                window.bring_to_front();
+            }
+        })
+    }
+}
+
+impl Drop for Command {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMUnregisterCommandHandler(
+                self.command_ref,
+                Some(handle_command),
+                1,
+                self.handler.refcon(),
+            );
+        }
+    }
+}
+
+unsafe extern "C" fn handle_command(
+    _command_ref: XPLMCommandRef,
+    phase: XPLMCommandPhase,
+    refcon: *mut c_void,
+) -> c_int {
+    if phase == xplm_CommandBegin as XPLMCommandPhase {
+        let handler = CallbackBox::<RefCell<Handler>>::from_refcon(refcon);
+        // a handler which runs its own command isn't run again from inside itself
+        if let Ok(mut handler) = handler.try_borrow_mut() {
+            handler();
+        }
+    }
+    // Let other handlers see the command too
+    1
+}
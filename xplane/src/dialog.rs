@@ -0,0 +1,226 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+
+use std::ffi::{c_int, CString};
+use std::ptr::null_mut;
+
+use xplm_sys::{
+    xplmFont_Proportional, XPLMDrawString, XPLMDrawTranslucentDarkBox, XPLMFontID,
+    XPLMGetFontDimensions, XPLMMeasureString, XPLMSetGraphicsState,
+};
+
+use imgui_support::dialog::ModalDialog;
+use imgui_support::events::{Action, Event, MouseButton};
+use imgui_support::geometry::Rect;
+
+use crate::ui::{Decoration, Delegate, Layer, PositioningMode, Ref, Window};
+
+const FONT: XPLMFontID = xplmFont_Proportional as XPLMFontID;
+const WIDTH: i32 = 400;
+const PADDING: i32 = 12;
+const BUTTON_PADDING: i32 = 6;
+const TEXT_COLOR: [f32; 3] = [0.9, 0.9, 0.9];
+
+/// A `ModalDialog` in its own window on X-Plane's modal layer, which dims the sim and blocks
+/// input to other windows until a button is pressed. The window hides itself when the dialog
+/// finishes; dropping this destroys it.
+///
+/// It's drawn with XPLM's text and box drawing rather than imgui, as an imgui context can't be
+/// active alongside the `System`'s.
+pub struct Dialog {
+    window: Ref,
+}
+
+struct DialogDelegate {
+    dialog: ModalDialog,
+    lines: Vec<CString>,
+    labels: Vec<(CString, i32)>,
+    line_height: i32,
+    cursor: (f32, f32),
+    pressed: Option<usize>,
+}
+
+impl Dialog {
+    pub fn show(dialog: ModalDialog) -> Dialog {
+        let line_height = font_height() + 4;
+        let lines = wrap(dialog.body(), WIDTH - 2 * PADDING)
+            .into_iter()
+            .map(to_c_string)
+            .collect::<Vec<_>>();
+        let labels = dialog
+            .buttons()
+            .iter()
+            .map(|label| {
+                (
+                    to_c_string(label.as_str()),
+                    measure(label) + 2 * BUTTON_PADDING,
+                )
+            })
+            .collect();
+        let height = PADDING * 3 + lines.len() as i32 * line_height + line_height + BUTTON_PADDING;
+        let title = String::from(dialog.title());
+
+        let mut window = Window::create(
+            &title,
+            Rect::new(0, height, WIDTH, 0),
+            Decoration::RoundRectangle,
+            Layer::Modal,
+            PositioningMode::CenterOnMonitor,
+            DialogDelegate {
+                dialog,
+                lines,
+                labels,
+                line_height,
+                cursor: (f32::MIN, f32::MIN),
+                pressed: None,
+            },
+        );
+        window.set_visible(true);
+        window.bring_to_front();
+        Dialog { window }
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        !self.window.visible()
+    }
+}
+
+impl DialogDelegate {
+    /// The buttons' rects in the window's draw coordinates, right aligned along the bottom
+    fn button_rects(&self, geometry: &Rect) -> Vec<Rect> {
+        let bottom = geometry.bottom + PADDING;
+        let top = bottom + self.line_height + BUTTON_PADDING;
+        let mut right = geometry.right - PADDING;
+        let mut rects: Vec<Rect> = self
+            .labels
+            .iter()
+            .rev()
+            .map(|(_, width)| {
+                let rect = Rect::new(right - width, top, right, bottom);
+                right -= width + PADDING / 2;
+                rect
+            })
+            .collect();
+        rects.reverse();
+        rects
+    }
+}
+
+impl Delegate for DialogDelegate {
+    fn draw(&mut self, window: &mut Window) {
+        if let Some(index) = self.pressed.take() {
+            self.dialog.finish(index);
+            window.set_visible(false);
+            return;
+        }
+
+        let geometry = window.draw_geometry();
+        unsafe {
+            XPLMSetGraphicsState(0, 0, 0, 0, 1, 0, 0);
+        }
+        for (i, line) in self.lines.iter().enumerate() {
+            let y = geometry.top - PADDING - (i as i32 + 1) * self.line_height;
+            draw_string(line, geometry.left + PADDING, y);
+        }
+
+        let (cursor_x, cursor_y) = self.cursor;
+        for (rect, (label, width)) in self.button_rects(&geometry).iter().zip(&self.labels) {
+            let hovered = contains(rect, cursor_x, cursor_y);
+            unsafe {
+                // a second box over the first highlights the hovered button
+                for _ in 0..=usize::from(hovered) {
+                    XPLMDrawTranslucentDarkBox(rect.left, rect.top, rect.right, rect.bottom);
+                }
+            }
+            let x = rect.left + (width - measure_c(label)) / 2;
+            draw_string(label, x, rect.bottom + BUTTON_PADDING);
+        }
+    }
+
+    fn handle_event(&mut self, window: &Window, event: Event) -> bool {
+        match event {
+            Event::CursorPos(x, y) => self.cursor = (x, y),
+            Event::MouseButton(MouseButton::Left, Action::Press) => {
+                let (x, y) = self.cursor;
+                self.pressed = self
+                    .button_rects(&window.draw_geometry())
+                    .iter()
+                    .position(|rect| contains(rect, x, y))
+                    .or(self.pressed);
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn contains(rect: &Rect, x: f32, y: f32) -> bool {
+    x >= rect.left as f32
+        && x <= rect.right as f32
+        && y >= rect.bottom as f32
+        && y <= rect.top as f32
+}
+
+/// Splits `text` into lines no wider than `width`, keeping explicit line breaks
+fn wrap(text: &str, width: i32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                String::from(word)
+            } else {
+                format!("{line} {word}")
+            };
+            if measure(&candidate) > width && !line.is_empty() {
+                lines.push(std::mem::replace(&mut line, String::from(word)));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn to_c_string(text: impl Into<Vec<u8>>) -> CString {
+    CString::new(text).unwrap_or_default()
+}
+
+fn measure(text: &str) -> i32 {
+    measure_c(&to_c_string(text))
+}
+
+fn measure_c(text: &CString) -> i32 {
+    let length = c_int::try_from(text.as_bytes().len()).unwrap_or(c_int::MAX);
+    unsafe { XPLMMeasureString(FONT, text.as_ptr(), length).ceil() as i32 }
+}
+
+fn font_height() -> i32 {
+    let mut height = 0;
+    unsafe {
+        XPLMGetFontDimensions(FONT, null_mut(), &mut height, null_mut());
+    }
+    height
+}
+
+fn draw_string(text: &CString, x: i32, y: i32) {
+    let mut color = TEXT_COLOR;
+    unsafe {
+        XPLMDrawString(
+            color.as_mut_ptr(),
+            x,
+            y,
+            text.as_ptr().cast_mut(),
+            null_mut(),
+            FONT,
+        );
+    }
+}
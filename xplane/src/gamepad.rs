@@ -0,0 +1,72 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use imgui::Key;
+use xplm::data::ArrayRead;
+use xplm::data::borrowed::{DataRef, FindError};
+
+use imgui_support::navigation::GamepadState;
+
+/// Maps X-Plane joystick buttons and axes onto imgui's gamepad layout.
+///
+/// Indices refer to `sim/joystick/joystick_button_values` and
+/// `sim/joystick/joystick_axis_values`, as shown in X-Plane's joystick settings.
+#[derive(Clone, Debug, Default)]
+pub struct JoystickMapping {
+    pub buttons: Vec<(usize, Key)>,
+    /// X and Y axis indices for the left stick
+    pub left_stick: Option<(usize, usize)>,
+    /// X and Y axis indices for the right stick
+    pub right_stick: Option<(usize, usize)>,
+    pub left_trigger: Option<usize>,
+    pub right_trigger: Option<usize>,
+}
+
+pub(crate) struct Joystick {
+    buttons: DataRef<[i32]>,
+    axes: DataRef<[f32]>,
+}
+
+impl Joystick {
+    pub(crate) fn new() -> Result<Self, FindError> {
+        Ok(Joystick {
+            buttons: DataRef::find("sim/joystick/joystick_button_values")?,
+            axes: DataRef::find("sim/joystick/joystick_axis_values")?,
+        })
+    }
+
+    pub(crate) fn state(&self, mapping: &JoystickMapping) -> GamepadState {
+        let mut buttons = vec![0; self.buttons.len()];
+        self.buttons.get(&mut buttons);
+        let mut axes = vec![0.0; self.axes.len()];
+        self.axes.get(&mut axes);
+
+        // X-Plane reports axes in 0..1
+        let centred = |index: usize| axes.get(index).map_or(0.0, |value| value * 2.0 - 1.0);
+        let trigger = |index: Option<usize>| {
+            index
+                .and_then(|index| axes.get(index))
+                .copied()
+                .unwrap_or_default()
+        };
+        let stick = |indices: Option<(usize, usize)>| {
+            indices.map_or([0.0, 0.0], |(x, y)| [centred(x), centred(y)])
+        };
+
+        GamepadState {
+            buttons: mapping
+                .buttons
+                .iter()
+                .filter(|(index, _)| buttons.get(*index).is_some_and(|value| *value != 0))
+                .map(|(_, key)| *key)
+                .collect(),
+            left_stick: stick(mapping.left_stick),
+            right_stick: stick(mapping.right_stick),
+            left_trigger: trigger(mapping.left_trigger),
+            right_trigger: trigger(mapping.right_trigger),
+        }
+    }
+}
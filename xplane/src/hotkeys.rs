@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_wrap)]
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CString};
+
+use xplm_sys::{
+    xplm_ControlFlag, xplm_DownFlag, xplm_OptionAltFlag, xplm_ShiftFlag, xplm_UpFlag, XPLMHotKeyID,
+    XPLMKeyFlags, XPLMRegisterHotKey, XPLMRegisterKeySniffer, XPLMUnregisterHotKey,
+    XPLMUnregisterKeySniffer,
+};
+
+use imgui_support::events::Modifiers;
+use imgui_support::hotkeys::{HotkeyAction, Hotkeys};
+
+use crate::callback::CallbackBox;
+use crate::ui::keymap::from_imgui_key;
+use crate::ui::Window;
+
+/// What a key sniffer returns to let X-Plane pass a key on, or to consume it
+const PASS_THROUGH: c_int = 1;
+const CONSUME: c_int = 0;
+
+/// `Hotkeys` registered with X-Plane, unregistered when dropped. Keys with no X-Plane virtual key
+/// code are skipped.
+pub struct Registration {
+    ids: Vec<XPLMHotKeyID>,
+    _entries: Vec<CallbackBox<RefCell<Entry>>>,
+}
+
+struct Entry {
+    window: *mut Window,
+    action: HotkeyAction,
+}
+
+impl Registration {
+    pub fn register(hotkeys: Hotkeys, window: &mut Window) -> Registration {
+        let mut keys = Vec::new();
+        let mut entries = Vec::new();
+        for hotkey in hotkeys.into_hotkeys() {
+            if let Some(virtual_key) = from_imgui_key(hotkey.chord.key) {
+                let description_c = CString::new(hotkey.description.as_str())
+                    .expect("Could not create string from description");
+                keys.push((
+                    virtual_key,
+                    to_flags(&hotkey.chord.modifiers),
+                    description_c,
+                ));
+                entries.push(CallbackBox::new(RefCell::new(Entry {
+                    window,
+                    action: hotkey.action,
+                })));
+            }
+        }
+
+        let ids = keys
+            .iter()
+            .zip(&entries)
+            .map(|((virtual_key, flags, description_c), entry)| unsafe {
+                XPLMRegisterHotKey(
+                    *virtual_key,
+                    *flags,
+                    description_c.as_ptr(),
+                    Some(handle_hotkey),
+                    entry.refcon(),
+                )
+            })
+            .collect();
+
+        Registration {
+            ids,
+            _entries: entries,
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        for id in &self.ids {
+            unsafe {
+                XPLMUnregisterHotKey(*id);
+            }
+        }
+    }
+}
+
+/// `Hotkeys` matched against keys on their way to the sim by a key sniffer, unregistered when
+/// dropped. Keys typed into a window with keyboard focus never reach it, and keys which don't
+/// match a shortcut carry on to the sim, as do keys with no X-Plane virtual key code.
+pub struct Sniffer {
+    state: CallbackBox<RefCell<SnifferState>>,
+}
+
+struct SnifferState {
+    window: *mut Window,
+    shortcuts: Vec<Shortcut>,
+    /// The virtual key of the shortcut last pressed, whose repeats and release are consumed too
+    held: Option<c_char>,
+}
+
+struct Shortcut {
+    virtual_key: c_char,
+    /// The modifier flags which must be held, without the up or down flags
+    modifiers: XPLMKeyFlags,
+    action: HotkeyAction,
+}
+
+impl Sniffer {
+    pub fn register(hotkeys: Hotkeys, window: &mut Window) -> Sniffer {
+        let up_down = (xplm_DownFlag | xplm_UpFlag) as XPLMKeyFlags;
+        let shortcuts = hotkeys
+            .into_hotkeys()
+            .into_iter()
+            .filter_map(|hotkey| {
+                Some(Shortcut {
+                    virtual_key: from_imgui_key(hotkey.chord.key)?,
+                    modifiers: to_flags(&hotkey.chord.modifiers) & !up_down,
+                    action: hotkey.action,
+                })
+            })
+            .collect();
+        let state = CallbackBox::new(RefCell::new(SnifferState {
+            window,
+            shortcuts,
+            held: None,
+        }));
+        unsafe {
+            // after windows, so only keys no window has focus for are seen
+            XPLMRegisterKeySniffer(Some(sniff_key), 0, state.refcon());
+        }
+        Sniffer { state }
+    }
+}
+
+impl Drop for Sniffer {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMUnregisterKeySniffer(Some(sniff_key), 0, self.state.refcon());
+        }
+    }
+}
+
+fn to_flags(modifiers: &Modifiers) -> XPLMKeyFlags {
+    let mut flags = xplm_DownFlag;
+    if modifiers.shift {
+        flags |= xplm_ShiftFlag;
+    }
+    if modifiers.option {
+        flags |= xplm_OptionAltFlag;
+    }
+    // X-Plane reports Cmd as the control flag on macOS
+    if modifiers.control || modifiers.super_key {
+        flags |= xplm_ControlFlag;
+    }
+    flags as XPLMKeyFlags
+}
+
+unsafe extern "C" fn handle_hotkey(refcon: *mut c_void) {
+    let entry = CallbackBox::<RefCell<Entry>>::from_refcon(refcon);
+    let mut entry = entry.borrow_mut();
+    let event = entry.action.trigger();
+    let window = entry.window;
+    // not kept borrowed while the app handles the event
+    drop(entry);
+    if let Some(event) = event {
+        (*window).send_event(event);
+    }
+}
+
+unsafe extern "C" fn sniff_key(
+    _key: c_char,
+    flags: XPLMKeyFlags,
+    virtual_key: c_char,
+    refcon: *mut c_void,
+) -> c_int {
+    let state = CallbackBox::<RefCell<SnifferState>>::from_refcon(refcon);
+    let mut state = state.borrow_mut();
+    if flags & xplm_DownFlag as XPLMKeyFlags == 0 {
+        if state.held != Some(virtual_key) {
+            return PASS_THROUGH;
+        }
+        if flags & xplm_UpFlag as XPLMKeyFlags != 0 {
+            state.held = None;
+        }
+        return CONSUME;
+    }
+
+    let modifiers =
+        flags & (xplm_ShiftFlag | xplm_OptionAltFlag | xplm_ControlFlag) as XPLMKeyFlags;
+    let triggered = state
+        .shortcuts
+        .iter_mut()
+        .find(|shortcut| shortcut.virtual_key == virtual_key && shortcut.modifiers == modifiers)
+        .map(|shortcut| shortcut.action.trigger());
+    let Some(event) = triggered else {
+        return PASS_THROUGH;
+    };
+    state.held = Some(virtual_key);
+    let window = state.window;
+    // not kept borrowed while the app handles the event
+    drop(state);
+    if let Some(event) = event {
+        (*window).send_event(event);
+    }
+    CONSUME
+}
@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::path::Path;
+
+use imgui::{Condition, Ui, WindowFlags};
+use serde::{Deserialize, Serialize};
+use xplm::data::borrowed::DataRef;
+use xplm::data::DataRead;
+
+use imgui_support::geometry::Rect;
+use imgui_support::settings::{Settings, SettingsError};
+
+use crate::ui::{PositioningMode, Window};
+use crate::utils::get_screen_bounds;
+
+pub const SETTINGS_KEY: &str = "window_layout";
+
+/// Where a window was and how it was shown
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Layout {
+    mode: PositioningMode,
+    /// Global desktop bounds, OS window bounds when popped out, or (0, 0, width, height) in VR
+    geometry: Rect,
+    visible: bool,
+}
+
+impl Layout {
+    pub fn capture(window: &Window) -> Self {
+        let (mode, geometry) = window.current_geometry();
+        Layout {
+            mode: mode.clone(),
+            geometry,
+            visible: window.visible(),
+        }
+    }
+
+    /// Free geometry which no longer fits the screen (e.g. a monitor was removed) and VR placement
+    /// while VR is off are skipped, leaving the window where it was created. Windows centred on
+    /// or filling monitors are left for X-Plane to place.
+    pub fn apply(&self, window: &mut Window) {
+        window.set_visible(self.visible);
+        match self.mode {
+            PositioningMode::PopOut => {
+                window.set_positioning_mode(PositioningMode::PopOut);
+                window.set_geometry_os(&self.geometry);
+            }
+            PositioningMode::VR => {
+                if vr_enabled() {
+                    window.set_positioning_mode(PositioningMode::VR);
+                    #[allow(clippy::cast_possible_wrap)]
+                    window.set_geometry_vr(
+                        self.geometry.width() as i32,
+                        self.geometry.height() as i32,
+                    );
+                }
+            }
+            PositioningMode::CenterOnMonitor
+            | PositioningMode::FullScreenOnMonitor
+            | PositioningMode::FullScreenOnAllMonitors => {
+                window.set_positioning_mode(self.mode.clone());
+            }
+            PositioningMode::Free => {
+                window.set_positioning_mode(PositioningMode::Free);
+                if contains(&get_screen_bounds(), &self.geometry) {
+                    window.set_geometry(&self.geometry);
+                }
+            }
+        }
+    }
+}
+
+/// Saves `window`'s layout in the settings file at `path`, alongside any other settings
+pub fn save(window: &Window, path: &Path) -> Result<(), SettingsError> {
+    let mut settings = Settings::load(path)?;
+    settings.set(SETTINGS_KEY, &Layout::capture(window))?;
+    settings.save()
+}
+
+/// Covers the window with a prompt to drag it into place, returning true once the user is done
+pub fn draw_placement_prompt(ui: &Ui, display_size: [f32; 2]) -> bool {
+    const PROMPT: &str = "Drag me where you want this panel";
+    let mut done = false;
+    ui.window("##placement")
+        .position([0.0, 0.0], Condition::Always)
+        .size(display_size, Condition::Always)
+        .bg_alpha(0.75)
+        .focused(true)
+        .flags(WindowFlags::NO_DECORATION | WindowFlags::NO_SAVED_SETTINGS)
+        .build(|| {
+            let text_size = ui.calc_text_size(PROMPT);
+            let button_height = ui.frame_height();
+            let spacing = ui.clone_style().item_spacing[1];
+            let top = (display_size[1] - text_size[1] - spacing - button_height) / 2.0;
+            ui.set_cursor_pos([(display_size[0] - text_size[0]) / 2.0, top.max(0.0)]);
+            ui.text(PROMPT);
+            let button_width =
+                ui.calc_text_size("Done")[0] + ui.clone_style().frame_padding[0] * 2.0;
+            ui.set_cursor_pos([(display_size[0] - button_width) / 2.0, ui.cursor_pos()[1]]);
+            done = ui.button("Done");
+        });
+    done
+}
+
+fn vr_enabled() -> bool {
+    DataRef::<i32>::find("sim/graphics/VR/enabled").is_ok_and(|enabled| enabled.get() != 0)
+}
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    inner.left >= outer.left
+        && inner.right <= outer.right
+        && inner.top <= outer.top
+        && inner.bottom >= outer.bottom
+}
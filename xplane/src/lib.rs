@@ -9,31 +9,580 @@
 #![allow(clippy::missing_panics_doc)]
 
 use std::cell::RefCell;
+use std::ffi::{c_int, c_void};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use image::{ImageError, RgbaImage};
-use imgui::{Condition, Context, TextureId, WindowFlags};
+use imgui::{Context, DrawData, Style, TextureId, Ui};
+use tracing::warn;
 
-use imgui_support::App;
-use imgui_support::events::Event;
-use imgui_support::geometry::Rect;
+use imgui_support::{App, set_persistence};
+use imgui_support::allocator::{self, ContextMemory};
+use imgui_support::backend::{Backend, Capabilities, FrameDriver, FrameHooks, HostWindow};
+use imgui_support::close::CloseHandle;
+use imgui_support::dialog::Modals;
+use imgui_support::draw_data::{DrawDataExport, OwnedDrawData};
+use imgui_support::events::{
+    Action, Event, InputState, LongPress, LongPressDetector, MotionCoalescer, WindowState,
+};
+use imgui_support::focus::Focus;
+use imgui_support::fonts::{FontRebuilder, Fonts};
+use imgui_support::format::{self, Locale};
+use imgui_support::geometry::{Placement, Rect};
+use imgui_support::hotkeys::Hotkeys;
+use imgui_support::jobs::{ChunkedJobs, FRAME_BUDGET};
+use imgui_support::kinetic::KineticScroll;
+use imgui_support::layout::Breakpoints;
+use imgui_support::loading::LoadingScreen;
+use imgui_support::menu::AppMenu;
+use imgui_support::metrics::{Metrics, MetricsOverlay, PerfOverlay, RenderStats};
+use imgui_support::navigation::{AnalogTuning, Navigation, update_gamepad};
+use imgui_support::phases::{FramePhase, PhaseHooks};
+use imgui_support::profiles::Profiles;
+use imgui_support::recorder::{MacroRecorder, Macros};
+use imgui_support::scroll::ScrollPositions;
+use imgui_support::self_test::{self, Diagnostic};
+use imgui_support::settings::{Settings, SettingsError};
+use imgui_support::tasks::Tasks;
+use imgui_support::theme::{clamp_ui_scale, CustomTheme, Theme, ThemeError};
 
-use crate::platform::Platform;
-use crate::renderer::{bind_texture, Renderer};
-use crate::ui::{Decoration, Delegate, Gravity, Layer, PositioningMode, Ref, Window};
-pub use crate::utils::get_screen_bounds;
+use crate::chrome::{Chrome, ChromeAction};
+use crate::command::Command;
+use crate::gamepad::JoystickMapping;
+use crate::layout::{Layout, draw_placement_prompt};
+use crate::menu::Menu;
+use crate::messages::to_sim_event;
+use crate::platform::{Platform, WindowPlatform};
+use crate::renderer::{bind_texture, clamp_render_scale, rebind_texture, Renderer};
+use crate::snap::Snapper;
+use crate::ui::{
+    Decoration, Delegate, Gravity, Layer, PositioningMode, Ref, ResizingLimits, Window,
+};
+use crate::update::UpdateLoop;
+pub use crate::platform::FocusPolicy;
+pub use crate::renderer::{BlendMode, RenderState};
+pub use crate::utils::{
+    centred_on_mouse_monitor, get_all_monitor_bounds, get_all_monitor_bounds_os,
+    get_mouse_location, get_mouse_monitor_bounds, get_screen_bounds,
+};
+use crate::vr::VrFollower;
 
+mod chrome;
+mod command;
+mod dialog;
+mod hotkeys;
+mod layout;
+mod menu;
+mod messages;
+mod offscreen;
 mod platform;
+mod profile;
 mod renderer;
+mod snap;
+mod update;
 mod utils;
+mod vr;
 
+pub mod bindings;
+pub mod callback;
+pub mod gamepad;
 pub mod ui;
+pub mod widgets;
+
+/// The sender of X-Plane's own plugin messages
+const XPLANE_PLUGIN_ID: c_int = 0;
 
 pub struct System {
+    // declared first so they're dropped before the window they toggle
+    menu: Option<Menu>,
+    commands: Vec<Command>,
+    _hotkeys: Option<hotkeys::Registration>,
+    _shortcuts: Option<hotkeys::Sniffer>,
+    _update_loop: UpdateLoop,
+    _vr_follower: Option<VrFollower>,
+    modals: Modals,
     window: Ref,
+    options: Rc<RefCell<Options>>,
+    tasks: Tasks,
+    memory: ContextMemory,
+    app_menus: Vec<AppMenu>,
+}
+
+/// Whether clicks and scrolls on the window reach X-Plane instead, for overlays which only
+/// draw over part of the window
+#[derive(Default)]
+pub enum ClickThrough {
+    /// The window takes every click
+    #[default]
+    Never,
+    /// Clicks pass through where imgui doesn't want the mouse, i.e. outside any imgui window
+    /// or item
+    Unused,
+    /// Clicks pass through where the function, given the mouse position in the window, returns
+    /// false
+    HitTest(Box<dyn Fn([f32; 2]) -> bool>),
+}
+
+/// Settings shared between the `System` and its window delegate, applied every frame
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Default)]
+struct Options {
+    navigation: Navigation,
+    focus: Focus,
+    close: CloseHandle,
+    focus_policy: FocusPolicy,
+    fonts: Fonts,
+    joystick_mapping: Option<JoystickMapping>,
+    analog_tuning: AnalogTuning,
+    kinetic_scroll: KineticScroll,
+    long_press: LongPressDetector,
+    input: InputState,
+    fit_to_content: bool,
+    /// The limits set with `System::set_resizing_limits`, below which fitting to content doesn't
+    /// lower the minimum size
+    resizing_limits: Option<ResizingLimits>,
+    auto_resize: bool,
+    breakpoints: Option<Breakpoints>,
+    profiles: Option<Profiles>,
+    vr_ui_scale: Option<f32>,
+    render_scale: f32,
+    macros: Macros,
+    scroll_positions: ScrollPositions,
+    draw_data_export: Option<DrawDataExport>,
+    click_through: ClickThrough,
+    jobs: ChunkedJobs,
+    metrics: Metrics,
+    render_stats: RenderStats,
+    metrics_overlay: bool,
+    perf_overlay: bool,
+    imgui_metrics: bool,
+    phase_hooks: PhaseHooks,
+    theme: Theme,
+    ui_scale: f32,
+    locale: Locale,
+    /// Whether `theme` or `ui_scale` is still to be applied to imgui's style
+    style_changed: bool,
+    coalesce_events: bool,
+    window_padding: [f32; 2],
+    /// Drawn for self-decorated windows
+    chrome: Option<Chrome>,
+    snap_distance: Option<u32>,
+    /// Where to save the window's layout once the user has placed it, while they're placing it
+    placement: Option<PathBuf>,
 }
 
 impl System {
+    /// Enables imgui keyboard and/or gamepad navigation. Gamepad input is only available once a
+    /// joystick mapping has been set.
+    pub fn set_navigation(&mut self, navigation: Navigation) {
+        self.options.borrow_mut().navigation = navigation;
+    }
+
+    /// Moves focus between widgets without a keyboard, e.g. from a button box. Clone it to give
+    /// the app, or a hotkey, a handle.
+    #[must_use]
+    pub fn focus(&self) -> Focus {
+        self.options.borrow().focus.clone()
+    }
+
+    /// Hides the window after the current frame. Clone it to give the app a handle.
+    #[must_use]
+    pub fn close_handle(&self) -> CloseHandle {
+        self.options.borrow().close.clone()
+    }
+
+    /// When the window takes keyboard focus from the sim. Defaults to `FocusPolicy::WhileWanted`.
+    pub fn set_focus_policy(&mut self, focus_policy: FocusPolicy) {
+        self.options.borrow_mut().focus_policy = focus_policy;
+    }
+
+    /// Changes the font size and styles without stalling the sim. Clone it to give the app a
+    /// handle. The new fonts are built while the window is drawn.
+    #[must_use]
+    pub fn fonts(&self) -> Fonts {
+        self.options.borrow().fonts.clone()
+    }
+
+    pub fn set_joystick_mapping(&mut self, mapping: Option<JoystickMapping>) {
+        self.options.borrow_mut().joystick_mapping = mapping;
+    }
+
+    /// The response of the mapped sticks and triggers
+    #[must_use]
+    pub fn analog_tuning(&self) -> AnalogTuning {
+        self.options.borrow().analog_tuning.clone()
+    }
+
+    /// Changes the mapped axes' responses, e.g. after `AnalogTuning::edit` or once loaded from
+    /// settings
+    pub fn set_analog_tuning(&mut self, tuning: AnalogTuning) {
+        self.options.borrow_mut().analog_tuning = tuning;
+    }
+
+    /// Lets scrolling carry on after a trackpad's fingers lift, slowing to a stop. Off by
+    /// default.
+    pub fn set_kinetic_scrolling(&mut self, enabled: bool) {
+        self.options.borrow().kinetic_scroll.set_enabled(enabled);
+    }
+
+    /// For drawing overscroll feedback with `KineticScroll::draw_overscroll`. Clone it to give
+    /// the app a handle.
+    #[must_use]
+    pub fn kinetic_scroll(&self) -> KineticScroll {
+        self.options.borrow().kinetic_scroll.clone()
+    }
+
+    /// Sends the app `Event::LongPress` when the left button is held still, or stops if `None`.
+    /// Off by default.
+    pub fn set_long_press(&mut self, long_press: Option<LongPress>) {
+        self.options.borrow_mut().long_press.set_config(long_press);
+    }
+
+    /// Whether imgui wanted the mouse, keyboard or text input as of the last frame, as passed to
+    /// `App::handle_event`, e.g. for flight loops or key sniffers deciding whether to act on
+    /// input themselves
+    #[must_use]
+    pub fn input_state(&self) -> InputState {
+        self.options.borrow().input
+    }
+
+    /// Shows the frame rate, a graph of frame times and the cost of drawing them in the top left
+    /// corner of the window
+    pub fn show_perf_overlay(&mut self, show: bool) {
+        self.options.borrow_mut().perf_overlay = show;
+    }
+
+    /// Shows imgui's metrics and debugger window in the window, for inspecting windows, draw
+    /// lists and the like. Ignored in release builds.
+    pub fn show_imgui_metrics(&mut self, show: bool) {
+        self.options.borrow_mut().imgui_metrics = show && cfg!(debug_assertions);
+    }
+
+    /// Checks the GL context and fonts by drawing a hidden test frame, returning any problems
+    /// found, e.g. to run when the plugin is enabled and report before the user sees a broken
+    /// panel. Scissoring is checked as the window's first frame is drawn, so is only included
+    /// after it.
+    #[must_use]
+    pub fn self_test(&mut self) -> Vec<Diagnostic> {
+        self.window.self_test()
+    }
+
+    /// Runs `hook` at `phase` of every frame the window draws, e.g. to draw custom GL before
+    /// the frame is presented
+    pub fn on_phase(&mut self, phase: FramePhase, hook: impl FnMut() + 'static) {
+        self.options.borrow().phase_hooks.add(phase, hook);
+    }
+
+    /// Adds hooks as `on_phase` does. Clone it to give the app a handle.
+    #[must_use]
+    pub fn phase_hooks(&self) -> PhaseHooks {
+        self.options.borrow().phase_hooks.clone()
+    }
+
+    /// Restyles imgui with `theme` from the next frame
+    pub fn set_theme(&mut self, theme: Theme) {
+        let mut options = self.options.borrow_mut();
+        options.theme = theme;
+        options.style_changed = true;
+    }
+
+    /// Restyles imgui with the custom theme in the TOML or JSON file at `path`, so a style can be
+    /// edited without rebuilding the app
+    ///
+    /// # Errors
+    ///
+    /// Returns `ThemeError` if the file could not be read or parsed, keeping the current theme.
+    pub fn load_style(&mut self, path: impl AsRef<Path>) -> Result<(), ThemeError> {
+        let theme = CustomTheme::load(path.as_ref())?;
+        self.set_theme(Theme::Custom(theme));
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn theme(&self) -> Theme {
+        self.options.borrow().theme.clone()
+    }
+
+    /// Scales the whole UI by `scale`, e.g. 1.5 to 2 for 4K displays. The fonts are rebuilt at
+    /// the scaled size, so they stay sharp, and the theme's sizes are scaled to match. In VR,
+    /// `set_vr_ui_scale` applies on top. The scale is clamped between `MIN_UI_SCALE` and
+    /// `MAX_UI_SCALE`; NaN is ignored.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        let Some(scale) = clamp_ui_scale(scale) else {
+            warn!(scale, "Ignoring invalid UI scale");
+            return;
+        };
+        let mut options = self.options.borrow_mut();
+        options.ui_scale = scale;
+        options.style_changed = true;
+        options.fonts.set_scale(scale);
+    }
+
+    #[must_use]
+    pub fn ui_scale(&self) -> f32 {
+        self.options.borrow().ui_scale
+    }
+
+    /// How widgets write numbers and dates, taken from the OS unless set
+    #[must_use]
+    pub fn locale(&self) -> Locale {
+        self.options.borrow().locale
+    }
+
+    /// Changes how widgets write numbers and dates, e.g. to a `Locale::load`ed choice
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.options.borrow_mut().locale = locale;
+    }
+
+    /// Stops a self-decorated window being moved or resized from its chrome, as its pin button
+    /// does
+    pub fn set_pinned(&mut self, pinned: bool) {
+        if let Some(chrome) = &mut self.options.borrow_mut().chrome {
+            chrome.set_pinned(pinned);
+        }
+    }
+
+    /// Snaps the window to the edges of the screen and of other Systems' windows once the user
+    /// has moved it within `distance` boxels of them, or stops if `None`. Off by default.
+    pub fn set_snapping(&mut self, distance: Option<u32>) {
+        self.options.borrow_mut().snap_distance = distance;
+    }
+
+    /// Whether a self-decorated window is pinned in place
+    #[must_use]
+    pub fn pinned(&self) -> bool {
+        self.options
+            .borrow()
+            .chrome
+            .as_ref()
+            .is_some_and(Chrome::pinned)
+    }
+
+    /// The memory imgui has allocated for the window. Clone it to give the app a handle.
+    #[must_use]
+    pub fn memory(&self) -> ContextMemory {
+        self.memory.clone()
+    }
+
+    /// Shows a message with a row of buttons in a window on the modal layer, dimming the sim
+    /// behind it, then calls `on_result` with the index of the button pressed. The window opens
+    /// on the next sim frame.
+    pub fn show_modal(
+        &mut self,
+        title: &str,
+        body: &str,
+        buttons: &[&str],
+        on_result: impl FnOnce(usize) + 'static,
+    ) {
+        self.modals.show(title, body, buttons, on_result);
+    }
+
+    /// Shows modals as `show_modal` does. Clone it to give the app a handle.
+    #[must_use]
+    pub fn modals(&self) -> Modals {
+        self.modals.clone()
+    }
+
+    /// Background jobs registered here are shown in a progress bar at the bottom of the window.
+    #[must_use]
+    pub fn tasks(&self) -> &Tasks {
+        &self.tasks
+    }
+
+    /// Long operations on the sim thread, run a few milliseconds each sim frame behind a modal
+    /// progress dialog. Clone it to give the app a handle.
+    #[must_use]
+    pub fn jobs(&self) -> ChunkedJobs {
+        self.options.borrow().jobs.clone()
+    }
+
+    /// Adds a submenu called `name` to the Plugins menu, with an item to show and hide the window
+    /// followed by the menus passed to `SystemBuilder::menus`. Replaces any menu created
+    /// previously.
+    pub fn create_menu(&mut self, name: &str) {
+        self.menu = None;
+        self.menu = Some(Menu::create(name, &mut self.window, &self.app_menus));
+    }
+
+    /// Creates a command such as `myplugin/ui/toggle` which users can bind to a key or joystick
+    /// button. It shows the window and brings it to the front, or hides it if it's already in
+    /// front.
+    pub fn register_toggle_command(&mut self, name: &str, description: &str) {
+        let command = Command::toggle_window(name, description, &mut self.window);
+        self.commands.push(command);
+    }
+
+    /// Creates a command which calls `handler` each time it's triggered. The handler is
+    /// unregistered when the `System` is dropped.
+    pub fn register_command(
+        &mut self,
+        name: &str,
+        description: &str,
+        handler: impl FnMut() + 'static,
+    ) {
+        self.commands
+            .push(Command::register(name, description, handler));
+    }
+
+    /// Sends X-Plane's messages to the app as `Event::Sim`. Call this from the plugin's
+    /// `XPluginReceiveMessage` with its arguments; messages from other plugins are ignored.
+    pub fn handle_message(&mut self, from: c_int, message: c_int, param: *mut c_void) {
+        if from != XPLANE_PLUGIN_ID {
+            return;
+        }
+        if let Some(sim_event) = to_sim_event(message, param) {
+            self.window.send_event(Event::Sim(sim_event));
+        }
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.window.set_visible(visible);
+    }
+
+    pub fn toggle_visible(&mut self) -> bool {
+        self.window.toggle_visible()
+    }
+
+    pub fn set_click_through(&mut self, click_through: ClickThrough) {
+        self.options.borrow_mut().click_through = click_through;
+    }
+
+    /// Passes the app at most one cursor move and one scroll between other events, the last
+    /// position and the summed scroll, rather than every one X-Plane sends. On by default; turn it
+    /// off where every position matters, e.g. for drawing.
+    pub fn set_coalesce_events(&mut self, coalesce: bool) {
+        self.options.borrow_mut().coalesce_events = coalesce;
+    }
+
+    /// Changes the padding set with `SystemBuilder::window_padding`
+    pub fn set_window_padding(&mut self, padding: [f32; 2]) {
+        self.options.borrow_mut().window_padding = padding;
+    }
+
+    pub fn set_resizing_limits(&mut self, resizing_limits: ResizingLimits) {
+        self.options.borrow_mut().resizing_limits = Some(resizing_limits);
+        self.window.set_resizing_limits(resizing_limits);
+    }
+
+    /// Keeps the window's minimum size at the size of the app's content once it no longer fits,
+    /// so it can't be resized to clip it further. Content which stretches to fill the window
+    /// doesn't hold it at its current size. The limits from `set_resizing_limits` are kept: the
+    /// minimum is never lowered below theirs.
+    pub fn set_fit_to_content(&mut self, fit_to_content: bool) {
+        self.options.borrow_mut().fit_to_content = fit_to_content;
+    }
+
+    /// Resizes the window to the size of the app's content as it changes, within the resizing
+    /// limits, keeping its top left corner in place. Suits content with a natural size, such as
+    /// hints or readouts, rather than content which fills the space it's given.
+    pub fn set_auto_resize(&mut self, auto_resize: bool) {
+        self.options.borrow_mut().auto_resize = auto_resize;
+    }
+
+    /// Renders the window offscreen at `scale` times its resolution on screen, then draws it
+    /// into the window: above 1 to supersample, e.g. for crisper text in VR, or below 1 to
+    /// undersample a heavy window for performance. The scale is clamped between 0.5 and 2; NaN is
+    /// ignored. 1, the default, renders straight into the window.
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let Some(scale) = clamp_render_scale(scale) else {
+            warn!(scale, "Ignoring invalid render scale");
+            return;
+        };
+        self.options.borrow_mut().render_scale = scale;
+    }
+
+    #[must_use]
+    pub fn render_scale(&self) -> f32 {
+        self.options.borrow().render_scale
+    }
+
+    /// Scales fonts by `scale` while the window is in VR, where the default size can be hard to
+    /// read. `None` leaves the scale alone. It's clamped as `set_ui_scale` is.
+    pub fn set_vr_ui_scale(&mut self, scale: Option<f32>) {
+        self.options.borrow_mut().vr_ui_scale = scale.and_then(clamp_ui_scale);
+    }
+
+    /// Stores the window's position, size, pop-out/VR state and visibility in the settings file at
+    /// `path`, alongside any other settings it holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the settings file could not be read or written.
+    pub fn save_layout(&self, path: impl Into<PathBuf>) -> Result<(), SettingsError> {
+        layout::save(&self.window, &path.into())
+    }
+
+    /// Restores the layout stored by `save_layout`, returning false if there was none.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SettingsError` if the settings file could not be read.
+    pub fn restore_layout(&mut self, path: impl Into<PathBuf>) -> Result<bool, SettingsError> {
+        let settings = Settings::load(path)?;
+        let Some(layout) = settings.get::<Layout>(layout::SETTINGS_KEY) else {
+            return Ok(false);
+        };
+        layout.apply(&mut self.window);
+        Ok(true)
+    }
+
+    /// Records and replays input to the window. Clone it to give the app, or a hotkey, a handle.
+    /// Playback happens while the window is drawn.
+    #[must_use]
+    pub fn macros(&self) -> Macros {
+        self.options.borrow().macros.clone()
+    }
+
+    /// The draw data of the latest frame the window drew, if enabled with
+    /// `SystemBuilder::export_draw_data`
+    #[must_use]
+    pub fn export_draw_data(&self) -> Option<OwnedDrawData> {
+        self.options.borrow().draw_data_export.as_ref()?.latest()
+    }
+
+    /// A handle to each frame's draw data, e.g. for a thread forwarding the UI elsewhere
+    #[must_use]
+    pub fn draw_data_export(&self) -> Option<DrawDataExport> {
+        self.options.borrow().draw_data_export.clone()
+    }
+
+    /// Restores the scroll positions of named child regions drawn through it, stored alongside
+    /// the ini file when persistence is enabled. Clone it to give the app a handle.
+    #[must_use]
+    pub fn scroll_positions(&self) -> ScrollPositions {
+        self.options.borrow().scroll_positions.clone()
+    }
+
+    /// Draw data sizes, texture memory and allocation counts as of the last frame the window
+    /// drew
+    #[must_use]
+    pub fn metrics(&self) -> Metrics {
+        self.options.borrow().metrics
+    }
+
+    /// What the renderer did to draw the last frame the window drew
+    #[must_use]
+    pub fn last_frame_stats(&self) -> RenderStats {
+        self.options.borrow().render_stats
+    }
+
+    /// Saves and switches between named window layouts, if enabled with
+    /// `SystemBuilder::profiles`. Requests are carried out the next time the window is drawn.
+    #[must_use]
+    pub fn profiles(&self) -> Option<Profiles> {
+        self.options.borrow().profiles.clone()
+    }
+
+    /// Moves the window, keeping its size, to the middle of the monitor the mouse is on, taking
+    /// it out of pop-out or VR
+    pub fn centre_on_mouse_monitor(&mut self) {
+        let geometry = self.window.geometry();
+        let rect = centred_on_mouse_monitor(geometry.width(), geometry.height());
+        self.window.set_positioning_mode(PositioningMode::Free);
+        self.window.set_geometry(&rect);
+    }
+
     #[must_use]
     pub fn window(&self) -> &Ref {
         &self.window
@@ -45,50 +594,339 @@ impl System {
     }
 }
 
+/// Configures and creates a `System`
 #[must_use]
-pub fn init<A: App + 'static>(
+#[allow(clippy::struct_excessive_bools)]
+pub struct SystemBuilder {
     title: &'static str,
-    x: u32,
-    y: u32,
+    placement: Placement,
     width: u32,
     height: u32,
-    app: Rc<RefCell<A>>,
-) -> System {
-    let mut imgui = Context::create();
-    let platform = Platform::init(&mut imgui).expect("Unable to create platform");
-    let renderer = Renderer::new(&mut imgui).expect("Unable to create renderer");
-    imgui.set_ini_filename(None);
-    imgui.set_log_filename(None);
+    decoration: Decoration,
+    layer: Layer,
+    positioning_mode: PositioningMode,
+    visible: bool,
+    persistence: Option<PathBuf>,
+    loading_screen: Option<LoadingScreen>,
+    breakpoints: Option<Breakpoints>,
+    menus: Vec<AppMenu>,
+    hotkeys: Hotkeys,
+    shortcuts: Hotkeys,
+    profiles: Option<PathBuf>,
+    follow_vr: bool,
+    export_draw_data: bool,
+    metrics_overlay: bool,
+    memory_budget: Option<usize>,
+    theme: Theme,
+    first_run_placement: Option<PathBuf>,
+    window_padding: [f32; 2],
+    render_state: Option<RenderState>,
+    render_scale: f32,
+}
 
-    let bounds = get_screen_bounds();
+impl SystemBuilder {
+    /// A `width` by `height` window `x` pixels right of and `y` pixels down from the top left of
+    /// the screen. Use `placement` to place it relative to another corner or the centre.
     #[allow(clippy::cast_possible_wrap)]
-    let rect = {
-        let left = bounds.left + x as i32;
-        let top = bounds.top - y as i32;
-        let right = left + width as i32;
-        let bottom = top - height as i32;
-        Rect::new(left, top, right, bottom)
-    };
+    pub fn new(title: &'static str, x: u32, y: u32, width: u32, height: u32) -> Self {
+        SystemBuilder {
+            title,
+            placement: Placement::Absolute {
+                x: x as i32,
+                y: y as i32,
+            },
+            width,
+            height,
+            decoration: Decoration::RoundRectangle,
+            layer: Layer::FloatingWindows,
+            positioning_mode: PositioningMode::Free,
+            visible: false,
+            persistence: None,
+            loading_screen: None,
+            breakpoints: None,
+            menus: Vec::new(),
+            hotkeys: Hotkeys::new(),
+            shortcuts: Hotkeys::new(),
+            profiles: None,
+            follow_vr: true,
+            export_draw_data: false,
+            metrics_overlay: false,
+            memory_budget: None,
+            theme: Theme::default(),
+            first_run_placement: None,
+            window_padding: [0.0, 0.0],
+            render_state: None,
+            render_scale: 1.0,
+        }
+    }
 
-    let mut window = Window::create(
-        title,
-        rect,
-        Decoration::RoundRectangle,
-        Layer::FloatingWindows,
-        PositioningMode::Free,
-        WindowDelegate::new(imgui, platform, renderer, app),
-    );
+    /// Styles imgui with `theme` instead of `Theme::Dark`
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
 
-    window.set_visible(false);
+    /// Defaults to `Decoration::RoundRectangle`. Self-decorated windows get a title bar with pin
+    /// and close buttons, drawn with imgui, and resizable ones a grip in the bottom right corner.
+    pub fn decoration(mut self, decoration: Decoration) -> Self {
+        self.decoration = decoration;
+        self
+    }
 
-    window.set_gravity(Gravity {
-        left: 0.0,
-        top: 1.0,
-        right: 1.0,
-        bottom: 0.0,
-    });
+    /// Defaults to `Layer::FloatingWindows`
+    pub fn layer(mut self, layer: Layer) -> Self {
+        self.layer = layer;
+        self
+    }
 
-    System { window }
+    /// Overrides the blending and depth handling, which otherwise come from
+    /// `RenderState::for_layer`
+    pub fn render_state(mut self, render_state: RenderState) -> Self {
+        self.render_state = Some(render_state);
+        self
+    }
+
+    /// Renders the window at `scale` times its resolution on screen, as
+    /// `System::set_render_scale` does. Defaults to 1.
+    pub fn render_scale(mut self, scale: f32) -> Self {
+        self.render_scale = clamp_render_scale(scale).unwrap_or(1.0);
+        self
+    }
+
+    /// Defaults to `PositioningMode::Free`
+    pub fn positioning_mode(mut self, positioning_mode: PositioningMode) -> Self {
+        self.positioning_mode = positioning_mode;
+        self
+    }
+
+    /// Whether the window is shown as soon as it's created. Defaults to false.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Where the window starts, worked out from the screen's bounds, replacing the position
+    /// given to `new`. A layout restored by `first_run_placement` takes precedence.
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Restores the window's layout from the settings file at `path`, as `System::restore_layout`
+    /// does. If none has been saved yet, the window is shown in the middle of the screen with a
+    /// prompt to drag it into place, and its layout is saved once the user is done.
+    pub fn first_run_placement(mut self, path: impl Into<PathBuf>) -> Self {
+        self.first_run_placement = Some(path.into());
+        self
+    }
+
+    /// The padding between the window's edges and the app's contents. Defaults to none, so the
+    /// app can draw right up to the edges.
+    pub fn window_padding(mut self, padding: [f32; 2]) -> Self {
+        self.window_padding = padding;
+        self
+    }
+
+    /// Persists imgui window state to the given ini file, e.g. in X-Plane's
+    /// `Output/preferences` directory. Disabled by default.
+    pub fn persistence(mut self, ini_path: impl Into<PathBuf>) -> Self {
+        self.persistence = Some(ini_path.into());
+        self
+    }
+
+    /// Shows `loading_screen` until `App::ready` returns true
+    pub fn loading_screen(mut self, loading_screen: LoadingScreen) -> Self {
+        self.loading_screen = Some(loading_screen);
+        self
+    }
+
+    /// Sends `Event::SizeClass` as the window's width crosses `breakpoints`, e.g. when it's
+    /// resized or popped out
+    pub fn breakpoints(mut self, breakpoints: Breakpoints) -> Self {
+        self.breakpoints = Some(breakpoints);
+        self
+    }
+
+    /// Moves the window into VR when the user enables it, and back to its previous position when
+    /// they disable it. Defaults to true.
+    pub fn follow_vr(mut self, follow_vr: bool) -> Self {
+        self.follow_vr = follow_vr;
+        self
+    }
+
+    /// Keeps a copy of each frame's draw data for `System::export_draw_data`. Disabled by default,
+    /// as copying it every frame isn't free.
+    pub fn export_draw_data(mut self, export_draw_data: bool) -> Self {
+        self.export_draw_data = export_draw_data;
+        self
+    }
+
+    /// Shows `System::metrics` in a small window in the top right corner of the window
+    pub fn metrics_overlay(mut self, metrics_overlay: bool) -> Self {
+        self.metrics_overlay = metrics_overlay;
+        self
+    }
+
+    /// Warns when imgui's memory for the window exceeds `bytes`, see `System::memory`. Call
+    /// `allocator::set_allocator` first to draw it from the plugin's own allocator.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Menus added to the plugin's submenu by `System::create_menu`
+    pub fn menus(mut self, menus: Vec<AppMenu>) -> Self {
+        self.menus = menus;
+        self
+    }
+
+    /// Registered as X-Plane hot keys, which work whatever has keyboard focus and can be remapped
+    /// in X-Plane's keyboard settings
+    pub fn hotkeys(mut self, hotkeys: Hotkeys) -> Self {
+        self.hotkeys = hotkeys;
+        self
+    }
+
+    /// Matched against keys on their way to the sim by a key sniffer, so they work while the
+    /// window is hidden or lacks keyboard focus, e.g. a bare C to toggle a checklist. Unlike
+    /// `hotkeys` they can't be remapped in X-Plane's keyboard settings. Keys typed into a window
+    /// with keyboard focus, and keys which don't match a shortcut, reach the window or sim as
+    /// usual. None by default.
+    pub fn shortcuts(mut self, shortcuts: Hotkeys) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+
+    /// Stores named layouts in the settings file at `path`, see `System::profiles`
+    pub fn profiles(mut self, path: impl Into<PathBuf>) -> Self {
+        self.profiles = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn build<A: App + 'static>(self, app: Rc<RefCell<A>>) -> System {
+        let (mut imgui, memory) = allocator::create_context();
+        memory.set_budget(self.memory_budget);
+        let platform = Platform::init(&mut imgui).expect("Unable to create platform");
+        let render_state = self
+            .render_state
+            .unwrap_or_else(|| RenderState::for_layer(self.layer));
+        let renderer = Renderer::new(&mut imgui, render_state).expect("Unable to create renderer");
+        set_persistence(&mut imgui, self.persistence.as_deref());
+
+        Backend::register(
+            Backend::XPlane,
+            Capabilities {
+                plugin_menu: true,
+                commands: true,
+                global_hotkeys: true,
+                pop_out: true,
+                vr: true,
+                ..Capabilities::default()
+            },
+            create_texture,
+            imgui_support::deallocate_texture,
+        );
+
+        let rect = self
+            .placement
+            .rect(&get_screen_bounds(), self.width, self.height);
+
+        let tasks = Tasks::new();
+        let jobs = ChunkedJobs::new(tasks.clone());
+        let options = Rc::new(RefCell::new(Options {
+            profiles: self.profiles.map(Profiles::new),
+            breakpoints: self.breakpoints,
+            scroll_positions: ScrollPositions::alongside(self.persistence.as_deref()),
+            jobs: jobs.clone(),
+            draw_data_export: self.export_draw_data.then(DrawDataExport::new),
+            metrics_overlay: self.metrics_overlay,
+            theme: self.theme,
+            ui_scale: 1.0,
+            render_scale: self.render_scale,
+            locale: Locale::from_os(),
+            style_changed: true,
+            coalesce_events: true,
+            window_padding: self.window_padding,
+            chrome: Chrome::for_decoration(&self.decoration),
+            ..Options::default()
+        }));
+        let mut window = Window::create(
+            self.title,
+            rect,
+            self.decoration,
+            self.layer,
+            self.positioning_mode,
+            WindowDelegate::new(
+                imgui,
+                platform,
+                renderer,
+                options.clone(),
+                tasks.clone(),
+                self.loading_screen,
+                app.clone(),
+            ),
+        );
+
+        window.set_visible(self.visible);
+        if let Some(path) = self.first_run_placement {
+            let saved =
+                Settings::load(&path).map(|settings| settings.get::<Layout>(layout::SETTINGS_KEY));
+            match saved {
+                Ok(Some(layout)) => layout.apply(&mut window),
+                Ok(None) => {
+                    window.set_geometry(&centred_on_mouse_monitor(self.width, self.height));
+                    window.set_visible(true);
+                    options.borrow_mut().placement = Some(path);
+                }
+                Err(e) => warn!(error = %e, "Unable to read window layout"),
+            }
+        }
+
+        window.set_gravity(Gravity {
+            left: 0.0,
+            top: 1.0,
+            right: 1.0,
+            bottom: 0.0,
+        });
+
+        let modals = Modals::new();
+        let update_loop = UpdateLoop::create(app, &mut window, jobs, modals.clone(), FRAME_BUDGET);
+        let vr_follower = self.follow_vr.then(|| VrFollower::create(&mut window));
+        let hotkeys = (!self.hotkeys.is_empty())
+            .then(|| hotkeys::Registration::register(self.hotkeys, &mut window));
+        let shortcuts = (!self.shortcuts.is_empty())
+            .then(|| hotkeys::Sniffer::register(self.shortcuts, &mut window));
+
+        System {
+            menu: None,
+            commands: Vec::new(),
+            _hotkeys: hotkeys,
+            _shortcuts: shortcuts,
+            _update_loop: update_loop,
+            _vr_follower: vr_follower,
+            modals,
+            window,
+            options,
+            tasks,
+            memory,
+            app_menus: self.menus,
+        }
+    }
+}
+
+/// Creates a `System` with the default options, its window hidden until `System::set_visible`.
+/// Use `SystemBuilder` to show it straight away or to let the user place it on first run.
+#[must_use]
+pub fn init<A: App + 'static>(
+    title: &'static str,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    app: Rc<RefCell<A>>,
+) -> System {
+    SystemBuilder::new(title, x, y, width, height).build(app)
 }
 
 /// # Errors
@@ -99,11 +937,101 @@ pub fn create_texture(image: &RgbaImage) -> Result<TextureId, ImageError> {
     imgui_support::create_texture(texture_id, image)
 }
 
+/// Raises `window`'s minimum size to the content's `size` when it overflows the window's
+/// `display_size`, and lowers it when the content shrinks, but no lower than the user's `floor`.
+/// Content which stretches to fill the window never overflows it, so it doesn't stop the user
+/// shrinking the window.
+#[allow(clippy::cast_possible_truncation)]
+fn fit_to_content_size(
+    window: &mut Window,
+    size: [f32; 2],
+    display_size: [f32; 2],
+    floor: Option<ResizingLimits>,
+) {
+    let limits = window.resizing_limits().copied();
+    let fit = |content: f32, display: f32, min: Option<i32>, floor: Option<i32>| {
+        let content = content.ceil() as i32;
+        let min = min.unwrap_or(0);
+        if content > display.ceil() as i32 || content < min {
+            content.max(floor.unwrap_or(0))
+        } else {
+            min
+        }
+    };
+    let min_width = fit(
+        size[0],
+        display_size[0],
+        limits.map(|l| l.min_width),
+        floor.map(|l| l.min_width),
+    );
+    let min_height = fit(
+        size[1],
+        display_size[1],
+        limits.map(|l| l.min_height),
+        floor.map(|l| l.min_height),
+    );
+    let (current_width, current_height) = limits.map_or((0, 0), |l| (l.min_width, l.min_height));
+    if min_width == current_width && min_height == current_height {
+        return;
+    }
+    // the user's maximum, which raising the minimum may have pushed up
+    let (max_width, max_height) = floor
+        .or(limits)
+        .map_or((i32::MAX, i32::MAX), |l| (l.max_width, l.max_height));
+    window.set_resizing_limits(ResizingLimits::new(
+        min_width,
+        min_height,
+        max_width.max(min_width),
+        max_height.max(min_height),
+    ));
+}
+
+/// Resizes `window` to `size`, within its resizing limits, keeping its top left corner in place.
+/// Full-screen windows are left alone.
+#[allow(clippy::cast_possible_truncation)]
+fn resize_to_content(window: &mut Window, [width, height]: [f32; 2]) {
+    let (mut width, mut height) = (width.ceil() as i32, height.ceil() as i32);
+    if let Some(limits) = window.resizing_limits() {
+        width = width.clamp(limits.min_width, limits.max_width.max(limits.min_width));
+        height = height.clamp(limits.min_height, limits.max_height.max(limits.min_height));
+    }
+    if window.in_vr() {
+        if window.geometry_vr() != (width, height) {
+            window.set_geometry_vr(width, height);
+        }
+    } else if window.popped_out() {
+        let rect = window.geometry_os();
+        if rect.right - rect.left != width || rect.top - rect.bottom != height {
+            let resized = Rect::new(rect.left, rect.top, rect.left + width, rect.top - height);
+            window.set_geometry_os(&resized);
+        }
+    } else if matches!(window.positioning_mode(), PositioningMode::Free) {
+        let rect = window.geometry();
+        if rect.right - rect.left != width || rect.top - rect.bottom != height {
+            let resized = Rect::new(rect.left, rect.top, rect.left + width, rect.top - height);
+            window.set_geometry(&resized);
+        }
+    }
+}
+
 struct WindowDelegate<A: App> {
     imgui: Context,
     platform: Platform,
     renderer: Renderer,
+    fonts: FontRebuilder,
+    options: Rc<RefCell<Options>>,
+    driver: FrameDriver,
     app: Rc<RefCell<A>>,
+    size: Option<(u32, u32)>,
+    focused: bool,
+    window_state: Option<WindowState>,
+    /// What the scissor check found, once the first frame has run it
+    scissor_check: Option<Vec<Diagnostic>>,
+    snapper: Snapper,
+    recorder: MacroRecorder<Event>,
+    coalescer: MotionCoalescer,
+    /// imgui's style before any theme or scale was applied
+    base_style: Style,
 }
 
 impl<A: App> WindowDelegate<A> {
@@ -111,40 +1039,437 @@ impl<A: App> WindowDelegate<A> {
         imgui: Context,
         platform: Platform,
         renderer: Renderer,
+        options: Rc<RefCell<Options>>,
+        tasks: Tasks,
+        loading_screen: Option<LoadingScreen>,
         app: Rc<RefCell<A>>,
     ) -> WindowDelegate<A> {
+        let recorder = MacroRecorder::new(options.borrow().macros.clone());
+        let fonts = FontRebuilder::new(options.borrow().fonts.clone());
         WindowDelegate {
+            base_style: *imgui.style(),
             imgui,
             platform,
             renderer,
+            fonts,
+            options,
+            driver: FrameDriver::new(tasks, loading_screen),
             app,
+            size: None,
+            focused: false,
+            window_state: None,
+            scissor_check: None,
+            snapper: Snapper::new(),
+            recorder,
+            coalescer: MotionCoalescer::default(),
+        }
+    }
+
+    fn input(&self) -> InputState {
+        self.options.borrow().input
+    }
+
+    fn captures_mouse(&self) -> bool {
+        let io = self.imgui.io();
+        match &self.options.borrow().click_through {
+            ClickThrough::Never => true,
+            ClickThrough::Unused => io.want_capture_mouse,
+            ClickThrough::HitTest(hit_test) => hit_test(io.mouse_pos),
+        }
+    }
+
+    /// Passes `event` to the app, recording it, and to imgui unless the app consumed it
+    fn dispatch(&mut self, window: &Window, event: Event) {
+        if matches!(
+            event,
+            Event::Key(..) | Event::CursorPos(..) | Event::MouseButton(..) | Event::Scroll(..)
+        ) {
+            self.recorder.record(&event);
+        }
+        self.options.borrow_mut().long_press.observe(&event);
+        let consumed = self
+            .app
+            .borrow_mut()
+            .handle_event(event.clone(), &self.input());
+        if consumed {
+            return;
+        }
+        // Held scrolls reach imgui at the start of the next frame
+        if let Event::Scroll(x, y) = event {
+            if self.options.borrow().kinetic_scroll.hold(x, y) {
+                return;
+            }
+        }
+        self.platform
+            .handle_event(self.imgui.io_mut(), window, event);
+    }
+
+    /// X-Plane has no focus or resize callbacks, so changes are detected each frame
+    fn detect_window_changes(&mut self, window: &Window, geometry: &Rect) {
+        let size = (geometry.width(), geometry.height());
+        if self.size != Some(size) {
+            self.size = Some(size);
+            self.app
+                .borrow_mut()
+                .handle_event(Event::Resize(size.0, size.1), &self.input());
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let width = size.0 as f32;
+        let class = {
+            let mut options = self.options.borrow_mut();
+            options.breakpoints.as_mut().and_then(|b| b.update(width))
+        };
+        if let Some(class) = class {
+            self.app
+                .borrow_mut()
+                .handle_event(Event::SizeClass(class), &self.input());
+        }
+
+        let focused = window.has_keyboard_focus();
+        if focused != self.focused {
+            self.focused = focused;
+            // X-Plane or the OS may have kept a modifier's release from us
+            platform::release_keys(self.imgui.io_mut());
+            self.app
+                .borrow_mut()
+                .handle_event(Event::Focus(focused), &self.input());
+        }
+    }
+}
+
+/// What an X-Plane window draws around the app, for `FrameDriver`, and what the user did with it
+#[allow(clippy::struct_excessive_bools)]
+struct WindowFrame<'a, A: App> {
+    app: &'a RefCell<A>,
+    options: &'a RefCell<Options>,
+    renderer: &'a Renderer,
+    scissor_check: &'a mut Option<Vec<Diagnostic>>,
+    phase_hooks: &'a PhaseHooks,
+    title: String,
+    geometry: Rect,
+    in_vr: bool,
+    limits: Option<ResizingLimits>,
+    show_chrome: bool,
+    /// Whether to measure the app's contents, to fit or resize the window to them
+    measure: bool,
+    padding: [f32; 2],
+    top: f32,
+    display_size: [f32; 2],
+    content_size: Option<[f32; 2]>,
+    placed: bool,
+    chrome_action: Option<ChromeAction>,
+}
+
+impl<A: App> FrameHooks for WindowFrame<'_, A> {
+    fn ready(&self) -> bool {
+        self.app.borrow().ready()
+    }
+
+    fn host_window(
+        &mut self,
+        ui: &Ui,
+        display_size: [f32; 2],
+        _loading: bool,
+    ) -> Option<HostWindow<'_>> {
+        self.display_size = display_size;
+        self.top = if self.show_chrome {
+            Chrome::title_bar_height(ui)
+        } else {
+            0.0
+        };
+        Some(HostWindow {
+            title: &self.title,
+            position: [0.0, self.top],
+            size: [display_size[0], display_size[1] - self.top],
+            padding: self.padding,
+        })
+    }
+
+    fn draw_app(&mut self, ui: &Ui) {
+        if self.measure {
+            ui.group(|| self.app.borrow().draw_ui(ui));
+            let [width, height] = ui.item_rect_size();
+            let [padding_x, padding_y] = self.padding;
+            self.content_size =
+                Some([width + padding_x * 2.0, height + padding_y * 2.0 + self.top]);
+        } else {
+            self.app.borrow().draw_ui(ui);
+        }
+    }
+
+    fn draw_overlays(&mut self, ui: &Ui, loading: bool) {
+        if !loading {
+            let (metrics, metrics_overlay, perf_overlay, imgui_metrics, placing) = {
+                let options = self.options.borrow();
+                options.jobs.draw(ui);
+                (
+                    options.metrics,
+                    options.metrics_overlay,
+                    options.perf_overlay,
+                    options.imgui_metrics,
+                    options.placement.is_some(),
+                )
+            };
+            if metrics_overlay {
+                MetricsOverlay::draw(ui, &metrics);
+            }
+            if perf_overlay {
+                PerfOverlay::draw(ui, &metrics);
+            }
+            if imgui_metrics {
+                let mut open = true;
+                ui.show_metrics_window(&mut open);
+                self.options.borrow_mut().imgui_metrics = open;
+            }
+            if placing {
+                self.placed = draw_placement_prompt(ui, self.display_size);
+            }
+        }
+        if self.show_chrome {
+            if let Some(chrome) = &mut self.options.borrow_mut().chrome {
+                self.chrome_action = chrome.draw(
+                    ui,
+                    &self.title,
+                    self.geometry,
+                    self.display_size,
+                    self.limits.as_ref(),
+                );
+            }
+        }
+        self.phase_hooks.run(FramePhase::Build);
+    }
+
+    fn before_render(&mut self) {
+        if self.scissor_check.is_none() {
+            let diagnostics = self.renderer.check_scissor(self.geometry, self.in_vr);
+            for diagnostic in &diagnostics {
+                warn!(%diagnostic, "Self test failed");
+            }
+            *self.scissor_check = Some(diagnostics);
+        }
+        self.app.borrow_mut().render_background();
+    }
+
+    fn inspect(&mut self, draw_data: &DrawData) {
+        let options = &mut *self.options.borrow_mut();
+        options.metrics.update(draw_data);
+        if let Some(export) = &options.draw_data_export {
+            export.publish(draw_data);
         }
     }
 }
 
 impl<A: App + 'static> Delegate for WindowDelegate<A> {
+    #[allow(clippy::too_many_lines)]
     fn draw(&mut self, window: &mut Window) {
-        let geometry = window.geometry();
+        profile::handle_request(self.options.borrow().profiles.as_ref(), window);
 
-        self.platform.prepare_frame(self.imgui.io_mut(), window);
+        let geometry = window.draw_geometry();
+        self.detect_window_changes(window, &geometry);
+        for event in self.recorder.update() {
+            self.handle_event(window, event);
+        }
+        for event in self.coalescer.flush() {
+            self.dispatch(window, event);
+        }
+        let long_press = self.options.borrow_mut().long_press.poll();
+        if let Some(long_press) = long_press {
+            self.app
+                .borrow_mut()
+                .handle_event(long_press, &self.input());
+        }
+        // cloned so hooks can use the System
+        let phase_hooks = self.options.borrow().phase_hooks.clone();
+        phase_hooks.run(FramePhase::Input);
+        if self.renderer.restore_if_lost(&mut self.imgui) {
+            self.app
+                .borrow_mut()
+                .handle_event(Event::GraphicsReset, &self.input());
+        }
+        if let Some(texture) = self
+            .fonts
+            .step(&mut self.imgui, bind_texture, rebind_texture)
+        {
+            self.renderer.replace_font_texture(texture);
+        }
 
-        self.imgui.style_mut().window_padding = [0.0, 0.0];
-        let display_size = self.imgui.io().display_size;
+        let focus_policy = self.options.borrow().focus_policy;
+        self.platform.set_focus_policy(focus_policy);
 
-        let ui = self.imgui.new_frame();
-        #[allow(clippy::cast_precision_loss)]
-        ui.window(window.title())
-            .position([0.0, 0.0], Condition::Always)
-            .size(display_size, Condition::Always)
-            .flags(WindowFlags::NO_BACKGROUND | WindowFlags::NO_DECORATION | WindowFlags::NO_INPUTS)
-            .build(|| self.app.borrow().draw_ui(ui));
-        self.renderer.render(&mut self.imgui, geometry);
-    }
-
-    fn handle_event(&mut self, window: &Window, event: Event) {
-        let consumed = self.app.borrow_mut().handle_event(event.clone());
-        if !consumed {
-            platform::handle_event(self.imgui.io_mut(), window, event);
+        {
+            let mut options = self.options.borrow_mut();
+            if options.style_changed {
+                let style = self.imgui.style_mut();
+                options
+                    .theme
+                    .apply_scaled(style, &self.base_style, options.ui_scale);
+                options.style_changed = false;
+            }
+            let io = self.imgui.io_mut();
+            if let Some(scale) = options.vr_ui_scale {
+                io.font_global_scale = if window.in_vr() { scale } else { 1.0 };
+            }
+            options.kinetic_scroll.apply(io);
+            options.navigation.apply(io);
+            if options.navigation.gamepad {
+                let state = options
+                    .joystick_mapping
+                    .as_ref()
+                    .map(|mapping| self.platform.gamepad_state(mapping));
+                update_gamepad(io, state.as_ref(), &options.analog_tuning);
+            }
+        }
+        let focus = self.options.borrow().focus.clone();
+        focus.apply(&mut self.imgui);
+        phase_hooks.run(FramePhase::Update);
+
+        let (fit_to_content, floor, auto_resize, padding, show_chrome) = {
+            let options = self.options.borrow();
+            (
+                options.fit_to_content,
+                options.resizing_limits,
+                options.auto_resize,
+                options.window_padding,
+                // popped out windows have the OS's decoration, and VR windows X-Plane's
+                options.chrome.is_some()
+                    && matches!(window.positioning_mode(), PositioningMode::Free),
+            )
+        };
+        let mut hooks = WindowFrame {
+            app: &self.app,
+            options: &self.options,
+            renderer: &self.renderer,
+            scissor_check: &mut self.scissor_check,
+            phase_hooks: &phase_hooks,
+            title: window.title().to_owned(),
+            geometry,
+            in_vr: window.in_vr(),
+            limits: window.resizing_limits().copied(),
+            show_chrome,
+            measure: fit_to_content || auto_resize,
+            padding,
+            top: 0.0,
+            display_size: [0.0, 0.0],
+            content_size: None,
+            placed: false,
+            chrome_action: None,
+        };
+        let render_scale = self.options.borrow().render_scale;
+        let mut renderer = self
+            .renderer
+            .for_window(geometry, window.in_vr(), render_scale);
+        // other Systems in the plugin may use another locale
+        format::set_current(self.options.borrow().locale);
+        let render_stats = self.driver.frame_with(
+            &mut self.imgui,
+            &mut WindowPlatform {
+                platform: &mut self.platform,
+                window,
+            },
+            &mut renderer,
+            &mut hooks,
+        );
+        let WindowFrame {
+            display_size,
+            content_size,
+            placed,
+            chrome_action,
+            ..
+        } = hooks;
+        {
+            let mut options = self.options.borrow_mut();
+            options.input = *self.driver.input();
+            options.render_stats = render_stats;
+            options.scroll_positions.save_if_due();
+        }
+        self.app.borrow_mut().render_foreground();
+        phase_hooks.run(FramePhase::Render);
+
+        if let Some(size) = content_size {
+            if fit_to_content {
+                fit_to_content_size(window, size, display_size, floor);
+            }
+            if auto_resize {
+                resize_to_content(window, size);
+            }
+        }
+        match chrome_action {
+            // Consuming the close request vetoes it, as does the app's hook
+            Some(ChromeAction::Close) => {
+                let close = {
+                    let mut app = self.app.borrow_mut();
+                    let consumed = app.handle_event(Event::CloseRequested, &self.input());
+                    !consumed && app.on_close_requested()
+                };
+                if close {
+                    window.set_visible(false);
+                }
+            }
+            Some(ChromeAction::SetGeometry(rect)) => window.set_geometry(&rect),
+            None => {}
+        }
+        if placed {
+            if let Some(path) = self.options.borrow_mut().placement.take() {
+                if let Err(e) = layout::save(window, &path) {
+                    warn!(error = %e, "Unable to save window layout");
+                }
+            }
+        }
+        if self.options.borrow().close.take() {
+            window.set_visible(false);
+        }
+        phase_hooks.run(FramePhase::Present);
+    }
+
+    fn handle_event(&mut self, window: &Window, event: Event) -> bool {
+        // releases always reach imgui, which may have taken the press
+        let passes_through = matches!(
+            event,
+            Event::MouseButton(_, Action::Press) | Event::Scroll(..)
+        ) && !self.captures_mouse();
+        if passes_through {
+            return false;
+        }
+        if self.options.borrow().coalesce_events {
+            if self.coalescer.hold(&event) {
+                return true;
+            }
+            for held in self.coalescer.flush() {
+                self.dispatch(window, held);
+            }
+        }
+        self.dispatch(window, event);
+        true
+    }
+
+    fn self_test(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = self_test::run(&mut self.imgui);
+        diagnostics.extend(self.scissor_check.iter().flatten().cloned());
+        diagnostics
+    }
+
+    /// Checked here rather than when drawing, as hidden windows aren't drawn
+    fn poll(&mut self, window: &mut Window) {
+        let floating =
+            window.visible() && matches!(window.positioning_mode(), PositioningMode::Free);
+        let snap_distance = self.options.borrow().snap_distance;
+        // the chrome's title bar and grip, and anything else in the window, drag with a button
+        // held down in imgui
+        let dragging = self.imgui.io().mouse_down.iter().any(|&down| down);
+        let snapped =
+            self.snapper
+                .update(floating.then(|| window.geometry()), snap_distance, dragging);
+        if let Some(snapped) = snapped {
+            window.set_geometry(&snapped);
+        }
+
+        let state = window.state();
+        if self.window_state != Some(state) {
+            self.window_state = Some(state);
+            self.app
+                .borrow_mut()
+                .handle_event(Event::WindowChanged(state), &self.input());
         }
     }
 }
@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+
+use std::cell::RefCell;
+use std::ffi::{c_float, c_int, c_void, CString};
+use std::mem::size_of;
+use std::ptr::null_mut;
+
+use xplm_sys::{
+    xplm_FlightLoop_Phase_BeforeFlightModel, xplm_Menu_Checked, xplm_Menu_Unchecked,
+    XPLMAppendMenuItem, XPLMAppendMenuSeparator, XPLMCheckMenuItem, XPLMCreateFlightLoop,
+    XPLMCreateFlightLoop_t, XPLMCreateMenu, XPLMDestroyFlightLoop, XPLMDestroyMenu,
+    XPLMFindPluginsMenu, XPLMFlightLoopID, XPLMFlightLoopPhaseType, XPLMMenuCheck, XPLMMenuID,
+    XPLMRemoveMenuItem, XPLMScheduleFlightLoop,
+};
+
+use imgui_support::events::Event;
+use imgui_support::menu::{AppMenu, MenuItem};
+
+use crate::callback::CallbackBox;
+use crate::ui::Window;
+
+/// The window can also be closed from its title bar, so the checkmark is polled
+const SYNC_INTERVAL: c_float = 0.25;
+
+/// A submenu of the Plugins menu with an item that shows and hides a window, checked while the
+/// window is visible, followed by any `AppMenu`s as nested submenus.
+///
+/// Owned by the `System`, which drops it before the window it points to.
+pub struct Menu {
+    id: XPLMMenuID,
+    parent_item: c_int,
+    sync_loop: XPLMFlightLoopID,
+    state: CallbackBox<RefCell<State>>,
+}
+
+struct State {
+    window: *mut Window,
+    menu: XPLMMenuID,
+    checked: Option<bool>,
+    /// Ids of app menu items, referred to by their index plus one so null means the toggle item
+    ids: Vec<String>,
+    submenus: Vec<XPLMMenuID>,
+}
+
+impl Menu {
+    pub fn create(name: &str, window: &mut Window, app_menus: &[AppMenu]) -> Menu {
+        let name_c = CString::new(name).expect("Could not create string from {name}");
+        let item_c = CString::new(window.title()).expect("Could not create string from title");
+
+        let state = CallbackBox::new(RefCell::new(State {
+            window,
+            menu: null_mut(),
+            checked: None,
+            ids: Vec::new(),
+            submenus: Vec::new(),
+        }));
+        let refcon = state.refcon();
+
+        unsafe {
+            let plugins_menu = XPLMFindPluginsMenu();
+            let parent_item = XPLMAppendMenuItem(plugins_menu, name_c.as_ptr(), null_mut(), 0);
+            let id = XPLMCreateMenu(
+                name_c.as_ptr(),
+                plugins_menu,
+                parent_item,
+                Some(handle_menu),
+                refcon,
+            );
+            XPLMAppendMenuItem(id, item_c.as_ptr(), null_mut(), 0);
+            {
+                let mut state = state.borrow_mut();
+                state.menu = id;
+                state.sync();
+
+                if !app_menus.is_empty() {
+                    XPLMAppendMenuSeparator(id);
+                }
+                for app_menu in app_menus {
+                    state.append_submenu(id, app_menu, refcon);
+                }
+            }
+
+            let mut params = XPLMCreateFlightLoop_t {
+                structSize: size_of::<XPLMCreateFlightLoop_t>() as _,
+                phase: xplm_FlightLoop_Phase_BeforeFlightModel as XPLMFlightLoopPhaseType,
+                callbackFunc: Some(sync_checkmark),
+                refcon,
+            };
+            let sync_loop = XPLMCreateFlightLoop(&mut params);
+            XPLMScheduleFlightLoop(sync_loop, SYNC_INTERVAL, 1);
+
+            Menu {
+                id,
+                parent_item,
+                sync_loop,
+                state,
+            }
+        }
+    }
+}
+
+impl Drop for Menu {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMDestroyFlightLoop(self.sync_loop);
+            for submenu in self.state.borrow().submenus.iter().rev() {
+                XPLMDestroyMenu(*submenu);
+            }
+            XPLMDestroyMenu(self.id);
+            XPLMRemoveMenuItem(XPLMFindPluginsMenu(), self.parent_item);
+        }
+    }
+}
+
+impl State {
+    unsafe fn sync(&mut self) {
+        let visible = (*self.window).visible();
+        if self.checked != Some(visible) {
+            let check = if visible {
+                xplm_Menu_Checked
+            } else {
+                xplm_Menu_Unchecked
+            };
+            XPLMCheckMenuItem(self.menu, 0, check as XPLMMenuCheck);
+            self.checked = Some(visible);
+        }
+    }
+
+    unsafe fn append_submenu(
+        &mut self,
+        parent: XPLMMenuID,
+        app_menu: &AppMenu,
+        refcon: *mut c_void,
+    ) {
+        let title_c = CString::new(app_menu.title()).expect("Could not create string from title");
+        let parent_item = XPLMAppendMenuItem(parent, title_c.as_ptr(), null_mut(), 0);
+        let menu = XPLMCreateMenu(
+            title_c.as_ptr(),
+            parent,
+            parent_item,
+            Some(handle_menu),
+            refcon,
+        );
+        self.submenus.push(menu);
+
+        for item in app_menu.items() {
+            match item {
+                MenuItem::Item { label, id } => {
+                    let label_c =
+                        CString::new(label.as_str()).expect("Could not create string from {label}");
+                    self.ids.push(id.clone());
+                    let item_ref = self.ids.len() as *mut c_void;
+                    XPLMAppendMenuItem(menu, label_c.as_ptr(), item_ref, 0);
+                }
+                MenuItem::Separator => XPLMAppendMenuSeparator(menu),
+                MenuItem::Submenu(submenu) => self.append_submenu(menu, submenu, refcon),
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn handle_menu(menu_ref: *mut c_void, item_ref: *mut c_void) {
+    let state = CallbackBox::<RefCell<State>>::from_refcon(menu_ref);
+    let mut state = state.borrow_mut();
+    let window = &mut *state.window;
+    if item_ref.is_null() {
+        window.toggle_visible();
+        state.sync();
+    } else {
+        let id = state.ids[item_ref as usize - 1].clone();
+        // not kept borrowed while the app handles the event
+        drop(state);
+        window.send_event(Event::Menu(id));
+    }
+}
+
+unsafe extern "C" fn sync_checkmark(
+    _elapsed_since_last_call: c_float,
+    _elapsed_since_last_flight_loop: c_float,
+    _counter: c_int,
+    refcon: *mut c_void,
+) -> c_float {
+    let state = CallbackBox::<RefCell<State>>::from_refcon(refcon);
+    state.borrow_mut().sync();
+    SYNC_INTERVAL
+}
@@ -0,0 +1,38 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::ffi::{c_int, c_void};
+
+use xplm_sys::{
+    XPLM_MSG_AIRPLANE_COUNT_CHANGED, XPLM_MSG_AIRPORT_LOADED, XPLM_MSG_ENTERED_VR,
+    XPLM_MSG_EXITING_VR, XPLM_MSG_LIVERY_LOADED, XPLM_MSG_PLANE_CRASHED, XPLM_MSG_PLANE_LOADED,
+    XPLM_MSG_PLANE_UNLOADED, XPLM_MSG_RELEASE_PLANES, XPLM_MSG_SCENERY_LOADED,
+    XPLM_MSG_WILL_WRITE_PREFS,
+};
+
+use imgui_support::events::SimEvent;
+
+/// Translates a message received by `XPluginReceiveMessage`. Messages from other plugins and ones
+/// X-Plane adds later are ignored.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn to_sim_event(message: c_int, param: *mut c_void) -> Option<SimEvent> {
+    // aircraft messages pass the aircraft's index as the parameter
+    let plane = param as isize as i32;
+    match message as u32 {
+        XPLM_MSG_PLANE_CRASHED => Some(SimEvent::PlaneCrashed),
+        XPLM_MSG_PLANE_LOADED => Some(SimEvent::PlaneLoaded(plane)),
+        XPLM_MSG_PLANE_UNLOADED => Some(SimEvent::PlaneUnloaded(plane)),
+        XPLM_MSG_LIVERY_LOADED => Some(SimEvent::LiveryLoaded(plane)),
+        XPLM_MSG_AIRPORT_LOADED => Some(SimEvent::AirportLoaded),
+        XPLM_MSG_SCENERY_LOADED => Some(SimEvent::SceneryLoaded),
+        XPLM_MSG_AIRPLANE_COUNT_CHANGED => Some(SimEvent::AirplaneCountChanged),
+        XPLM_MSG_WILL_WRITE_PREFS => Some(SimEvent::WillWritePrefs),
+        XPLM_MSG_ENTERED_VR => Some(SimEvent::EnteredVr),
+        XPLM_MSG_EXITING_VR => Some(SimEvent::ExitingVr),
+        XPLM_MSG_RELEASE_PLANES => Some(SimEvent::ReleasePlanes),
+        _ => None,
+    }
+}
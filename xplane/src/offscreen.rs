@@ -0,0 +1,216 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_wrap)]
+
+use std::ffi::{c_char, c_void, CString};
+use std::mem;
+
+use gl::types::{GLenum, GLint, GLsizei, GLuint};
+use gl21 as gl;
+use tracing::warn;
+use xplm_sys::XPLMBindTexture2d;
+
+use imgui_support::renderer_common::{delete_texture, return_param};
+
+use crate::renderer::bind_texture;
+
+// GL 3.0 / ARB_framebuffer_object, which the GL 2.1 bindings leave out
+const FRAMEBUFFER: GLenum = 0x8D40;
+const FRAMEBUFFER_BINDING: GLenum = 0x8CA6;
+const FRAMEBUFFER_COMPLETE: GLenum = 0x8CD5;
+const COLOR_ATTACHMENT0: GLenum = 0x8CE0;
+
+type GenFramebuffers = unsafe extern "system" fn(GLsizei, *mut GLuint);
+type DeleteFramebuffers = unsafe extern "system" fn(GLsizei, *const GLuint);
+type BindFramebuffer = unsafe extern "system" fn(GLenum, GLuint);
+type FramebufferTexture2D = unsafe extern "system" fn(GLenum, GLenum, GLenum, GLuint, GLint);
+type CheckFramebufferStatus = unsafe extern "system" fn(GLenum) -> GLenum;
+
+/// A texture the window's UI is rendered into at a scale of its size on screen, then drawn into
+/// the window, for `System::set_render_scale`
+pub struct Offscreen {
+    functions: Functions,
+    framebuffer: GLuint,
+    texture: GLuint,
+    size: [i32; 2],
+    /// A size the driver wouldn't render into, so it isn't tried again every frame
+    unsupported: Option<[i32; 2]>,
+}
+
+/// The framebuffer entry points, looked up at runtime as X-Plane's GL provides them but the
+/// bindings don't
+struct Functions {
+    gen_framebuffers: GenFramebuffers,
+    delete_framebuffers: DeleteFramebuffers,
+    bind_framebuffer: BindFramebuffer,
+    framebuffer_texture_2d: FramebufferTexture2D,
+    check_framebuffer_status: CheckFramebufferStatus,
+}
+
+impl Offscreen {
+    /// Looks up the framebuffer entry points, returning `None` if the driver lacks any. GL
+    /// objects are only created once there's something to render.
+    pub fn load() -> Option<Offscreen> {
+        unsafe {
+            let functions = Functions {
+                gen_framebuffers: mem::transmute::<*const c_void, GenFramebuffers>(proc_address(
+                    "glGenFramebuffers",
+                )?),
+                delete_framebuffers: mem::transmute::<*const c_void, DeleteFramebuffers>(
+                    proc_address("glDeleteFramebuffers")?,
+                ),
+                bind_framebuffer: mem::transmute::<*const c_void, BindFramebuffer>(proc_address(
+                    "glBindFramebuffer",
+                )?),
+                framebuffer_texture_2d: mem::transmute::<*const c_void, FramebufferTexture2D>(
+                    proc_address("glFramebufferTexture2D")?,
+                ),
+                check_framebuffer_status: mem::transmute::<*const c_void, CheckFramebufferStatus>(
+                    proc_address("glCheckFramebufferStatus")?,
+                ),
+            };
+            Some(Offscreen {
+                functions,
+                framebuffer: 0,
+                texture: 0,
+                size: [0, 0],
+                unsupported: None,
+            })
+        }
+    }
+
+    /// Binds the framebuffer, `size` pixels and cleared, with a viewport covering it. Returns
+    /// the framebuffer that was bound before, to pass to `end`, or `None` if the driver won't
+    /// render into one this size.
+    pub fn begin(&mut self, size: [i32; 2]) -> Option<GLuint> {
+        if self.unsupported == Some(size) {
+            return None;
+        }
+        let previous = unsafe { return_param(|x| gl::GetIntegerv(FRAMEBUFFER_BINDING, x)) };
+        #[allow(clippy::cast_sign_loss)]
+        let previous = previous as GLuint;
+        if size != self.size || self.texture == 0 {
+            self.resize(size);
+        }
+        unsafe {
+            (self.functions.bind_framebuffer)(FRAMEBUFFER, self.framebuffer);
+            if (self.functions.check_framebuffer_status)(FRAMEBUFFER) != FRAMEBUFFER_COMPLETE {
+                (self.functions.bind_framebuffer)(FRAMEBUFFER, previous);
+                warn!(
+                    ?size,
+                    "Unable to render offscreen, so the render scale is ignored"
+                );
+                self.unsupported = Some(size);
+                return None;
+            }
+            gl::PushAttrib(gl::VIEWPORT_BIT | gl::COLOR_BUFFER_BIT);
+            gl::Viewport(0, 0, size[0], size[1]);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        Some(previous)
+    }
+
+    /// Rebinds the `previous` framebuffer from `begin`, and its viewport, leaving the texture
+    /// bound to draw into the window
+    pub fn end(&self, previous: GLuint) {
+        unsafe {
+            gl::PopAttrib();
+            (self.functions.bind_framebuffer)(FRAMEBUFFER, previous);
+            XPLMBindTexture2d(self.texture as _, 0);
+        }
+    }
+
+    /// Forgets the framebuffer and texture without deleting them, after a context reset has
+    /// already lost them. They're created again with the next frame.
+    pub fn forget(&mut self) {
+        self.framebuffer = 0;
+        self.texture = 0;
+    }
+
+    fn resize(&mut self, size: [i32; 2]) {
+        self.delete();
+        self.texture = bind_texture();
+        unsafe {
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as _,
+                size[0],
+                size[1],
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            self.framebuffer = return_param(|x| (self.functions.gen_framebuffers)(1, x));
+            let previous = return_param(|x| gl::GetIntegerv(FRAMEBUFFER_BINDING, x));
+            (self.functions.bind_framebuffer)(FRAMEBUFFER, self.framebuffer);
+            (self.functions.framebuffer_texture_2d)(
+                FRAMEBUFFER,
+                COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.texture,
+                0,
+            );
+            #[allow(clippy::cast_sign_loss)]
+            (self.functions.bind_framebuffer)(FRAMEBUFFER, previous as GLuint);
+        }
+        self.size = size;
+    }
+
+    fn delete(&mut self) {
+        if self.framebuffer != 0 {
+            unsafe {
+                (self.functions.delete_framebuffers)(1, &self.framebuffer);
+            }
+            self.framebuffer = 0;
+        }
+        if self.texture != 0 {
+            delete_texture(self.texture);
+            self.texture = 0;
+        }
+    }
+}
+
+impl Drop for Offscreen {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
+
+#[cfg(windows)]
+fn proc_address(name: &str) -> Option<*const c_void> {
+    #[link(name = "opengl32")]
+    extern "system" {
+        fn wglGetProcAddress(name: *const c_char) -> *const c_void;
+    }
+    let name = CString::new(name).ok()?;
+    let address = unsafe { wglGetProcAddress(name.as_ptr()) };
+    (!address.is_null()).then_some(address)
+}
+
+/// X-Plane has already loaded the GL library, so its entry points are among the process's
+/// symbols
+#[cfg(not(windows))]
+fn proc_address(name: &str) -> Option<*const c_void> {
+    #[cfg(target_os = "macos")]
+    const RTLD_DEFAULT: *mut c_void = -2_isize as *mut c_void;
+    #[cfg(not(target_os = "macos"))]
+    const RTLD_DEFAULT: *mut c_void = std::ptr::null_mut();
+
+    extern "C" {
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+    let name = CString::new(name).ok()?;
+    let address = unsafe { dlsym(RTLD_DEFAULT, name.as_ptr()) };
+    (!address.is_null()).then_some(address.cast_const())
+}
@@ -6,18 +6,40 @@
 
 use std::primitive;
 
-use imgui::{Context, Io, Key, MouseButton, sys};
+use imgui::{Context, Io, Key, sys};
 use xplm::data::borrowed::{DataRef, FindError};
 use xplm::data::DataRead;
 
-use imgui_support::events;
-use imgui_support::events::{Action, Event, Modifiers};
+use imgui_support::backend::{self, PlatformBackend};
+use imgui_support::events::{Action, Event};
 use imgui_support::geometry::Rect;
+use imgui_support::navigation::GamepadState;
 
-use crate::ui::Window;
+use crate::gamepad::{Joystick, JoystickMapping};
+use crate::ui::{PositioningMode, Window};
 
 pub struct Platform {
     frame_rate_period: DataRef<f32>,
+    joystick: Joystick,
+    focus_policy: FocusPolicy,
+    /// Whether the last click on the window since the frame began was over imgui's UI
+    clicked: Option<bool>,
+}
+
+/// When the window takes keyboard focus from X-Plane, so keys reach the UI instead of the sim.
+/// X-Plane takes it back when the user clicks elsewhere.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FocusPolicy {
+    /// Holds focus while imgui wants the keyboard, e.g. while a text field is active or while
+    /// keyboard navigation is on, giving it back as soon as it doesn't
+    #[default]
+    WhileWanted,
+    /// A click on the window brings it to the front, taking focus if the click is on imgui's UI
+    /// and giving it back if it isn't
+    OnClick,
+    /// Never takes focus, so keys always reach the sim. Focus taken with
+    /// `Window::take_keyboard_focus` is left alone.
+    Never,
 }
 
 impl Platform {
@@ -32,13 +54,20 @@ impl Platform {
 
         Ok(Platform {
             frame_rate_period: DataRef::find("sim/operation/misc/frame_rate_period")?,
+            joystick: Joystick::new()?,
+            focus_policy: FocusPolicy::default(),
+            clicked: None,
         })
     }
 
-    pub fn prepare_frame(&self, io: &mut Io, window: &mut Window) {
+    pub fn set_focus_policy(&mut self, focus_policy: FocusPolicy) {
+        self.focus_policy = focus_policy;
+    }
+
+    pub fn prepare_frame(&mut self, io: &mut Io, window: &mut Window) {
         io.display_framebuffer_scale = [1.0, 1.0];
 
-        let geometry = window.geometry();
+        let geometry = window.draw_geometry();
         #[allow(clippy::cast_precision_loss)]
         {
             io.display_size = geometry.into();
@@ -52,77 +81,190 @@ impl Platform {
         }
 
         let has_keyboard_focus = window.has_keyboard_focus();
+        let clicked = self.clicked.take();
+        let wants_focus = match self.focus_policy {
+            FocusPolicy::WhileWanted => io.want_capture_keyboard,
+            FocusPolicy::OnClick => {
+                if clicked.is_some() && !window.is_in_front() {
+                    window.bring_to_front();
+                }
+                clicked.unwrap_or(has_keyboard_focus)
+            }
+            FocusPolicy::Never => has_keyboard_focus,
+        };
 
-        if io.want_capture_keyboard && !has_keyboard_focus {
+        if wants_focus && !has_keyboard_focus {
             window.take_keyboard_focus();
-        } else if !io.want_capture_keyboard && has_keyboard_focus {
+        } else if !wants_focus && has_keyboard_focus {
             window.release_keyboard_focus();
-            // lift all keys
-            io.keys_down = [false; sys::ImGuiKey_COUNT as usize];
-            io.add_key_event(Key::ModCtrl, false);
-            io.add_key_event(Key::ModAlt, false);
-            io.add_key_event(Key::ModShift, false);
+            release_keys(io);
+        }
+    }
+
+    pub fn gamepad_state(&self, mapping: &JoystickMapping) -> GamepadState {
+        self.joystick.state(mapping)
+    }
+
+    pub fn handle_event(&mut self, io: &mut Io, window: &Window, event: Event) {
+        if let Event::MouseButton(_, Action::Press) = event {
+            self.clicked = Some(io.want_capture_mouse);
         }
+        handle_event(io, window, event);
     }
 }
 
-pub fn handle_event(io: &mut Io, window: &Window, event: Event) {
-    match event {
-        Event::Key(key, ch, action, modifiers) => {
-            let pressed = action == Action::Press;
-            if let Some(key) = key {
-                io.add_key_event(key, pressed);
-            }
+/// A window's platform for `FrameDriver`, which needs the window to size the display and take
+/// keyboard focus
+pub struct WindowPlatform<'a> {
+    pub platform: &'a mut Platform,
+    pub window: &'a mut Window,
+}
 
-            let Modifiers {
-                control,
-                option,
-                shift,
-            } = modifiers;
+impl PlatformBackend for WindowPlatform<'_> {
+    fn prepare_frame(&mut self, io: &mut Io) {
+        self.platform.prepare_frame(io, self.window);
+    }
 
-            if pressed && !control && !option && ch != '\u{7f}' {
-                io.add_input_character(ch);
-            }
+    fn handle_event(&mut self, io: &mut Io, event: Event) {
+        self.platform.handle_event(io, self.window, event);
+    }
+}
 
-            io.add_key_event(Key::ModCtrl, control);
-            io.add_key_event(Key::ModAlt, option);
-            io.add_key_event(Key::ModShift, shift);
-        }
+/// Lifts all keys, including modifiers, e.g. when keyboard focus moves and their releases won't
+/// reach imgui. Modifiers still held are reasserted by the next key event, which carries them.
+pub fn release_keys(io: &mut Io) {
+    io.keys_down = [false; sys::ImGuiKey_COUNT as usize];
+    io.add_key_event(Key::ModCtrl, false);
+    io.add_key_event(Key::ModAlt, false);
+    io.add_key_event(Key::ModShift, false);
+    io.add_key_event(Key::ModSuper, false);
+}
+
+fn handle_event(io: &mut Io, window: &Window, event: Event) {
+    let event = match event {
         Event::CursorPos(x, y) => {
             let (x, y) = translate_to_imgui_space(window, x, y);
-            io.add_mouse_pos_event([x as _, y as _]);
+            Event::CursorPos(x, y)
         }
-        Event::Scroll(x, y) => {
-            #[allow(clippy::cast_precision_loss)]
-            io.add_mouse_wheel_event([x as _, y as _]);
-        }
-        Event::MouseButton(button, action) => {
-            let button = match button {
-                events::MouseButton::Left => MouseButton::Left,
-                events::MouseButton::Right => MouseButton::Right,
-            };
-            io.add_mouse_button_event(button, action != Action::Release);
-        }
-    }
+        event => event,
+    };
+    backend::send_input(io, event);
+}
+
+/// Uses the geometry `prepare_frame` sizes the display with, so clicks land where they're drawn
+fn translate_to_imgui_space(window: &Window, x: f32, y: f32) -> (f32, f32) {
+    to_imgui_space(window.positioning_mode(), &window.draw_geometry(), x, y)
 }
 
+/// Converts mouse coordinates, whose origin depends on where the window is shown, to imgui's,
+/// which start at the window's top left. `geometry` is the window's draw geometry. Points
+/// outside the window map to `f32::MIN`.
 #[allow(clippy::cast_precision_loss)]
-fn translate_to_imgui_space(window: &Window, x: i32, y: i32) -> (f32, f32) {
-    let Rect {
-        left,
-        top,
-        right,
-        bottom,
-    } = window.geometry();
-
-    let out_x = x - left;
-    if out_x < 0 || out_x > right - left {
+fn to_imgui_space(
+    positioning_mode: &PositioningMode,
+    geometry: &Rect,
+    x: f32,
+    y: f32,
+) -> (f32, f32) {
+    let (out_x, out_y, width, height) = match positioning_mode {
+        // VR windows only have a size, with the mouse relative to their bottom left corner
+        PositioningMode::VR => {
+            let width = (geometry.right - geometry.left) as f32;
+            let height = (geometry.bottom - geometry.top).abs() as f32;
+            (x, height - y, width, height)
+        }
+        // popped out windows too report the mouse in global desktop boxels, with y increasing
+        // upwards
+        _ => (
+            x - geometry.left as f32,
+            geometry.top as f32 - y,
+            (geometry.right - geometry.left) as f32,
+            (geometry.top - geometry.bottom) as f32,
+        ),
+    };
+
+    if out_x < 0.0 || out_x > width || out_y < 0.0 || out_y > height {
         return (primitive::f32::MIN, primitive::f32::MIN);
     }
+    (out_x, out_y)
+}
 
-    let out_y = top - y;
-    if out_y < 0 || out_y > top - bottom {
-        return (primitive::f32::MIN, primitive::f32::MIN);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTSIDE: (f32, f32) = (f32::MIN, f32::MIN);
+
+    fn display_size(geometry: Rect) -> (f32, f32) {
+        let [width, height]: [f32; 2] = geometry.into();
+        (width, height)
+    }
+
+    #[test]
+    fn free_window() {
+        let geometry = Rect::new(100, 500, 400, 300);
+        let mode = PositioningMode::Free;
+        assert_eq!(to_imgui_space(&mode, &geometry, 150.0, 450.0), (50.0, 50.0));
+        assert_eq!(to_imgui_space(&mode, &geometry, 100.0, 500.0), (0.0, 0.0));
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 400.0, 300.0),
+            (300.0, 200.0)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, 99.0, 450.0), OUTSIDE);
+        assert_eq!(to_imgui_space(&mode, &geometry, 150.0, 501.0), OUTSIDE);
+        assert_eq!(to_imgui_space(&mode, &geometry, 401.0, 299.0), OUTSIDE);
+    }
+
+    #[test]
+    fn popped_out_window_maps_into_the_drawn_display() {
+        // on a second monitor, where OS pixels and boxels may differ with scaling
+        let geometry = Rect::new(2000, 900, 2600, 500);
+        let mode = PositioningMode::PopOut;
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 2300.5, 700.0),
+            (300.5, 200.0)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, 2000.0, 900.0), (0.0, 0.0));
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 2600.0, 500.0),
+            display_size(geometry)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, 1999.0, 700.0), OUTSIDE);
+        assert_eq!(to_imgui_space(&mode, &geometry, 2300.0, 499.0), OUTSIDE);
+    }
+
+    #[test]
+    fn vr_window() {
+        // VR windows are drawn from their bottom left corner
+        let geometry = Rect::new(0, 300, 400, 0);
+        let mode = PositioningMode::VR;
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 100.0, 250.0),
+            (100.0, 50.0)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, 0.0, 300.0), (0.0, 0.0));
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 400.0, 0.0),
+            display_size(geometry)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, -1.0, 100.0), OUTSIDE);
+        assert_eq!(to_imgui_space(&mode, &geometry, 100.0, 301.0), OUTSIDE);
+    }
+
+    #[test]
+    fn centred_window() {
+        let geometry = Rect::new(760, 690, 1160, 390);
+        let mode = PositioningMode::CenterOnMonitor;
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 960.0, 540.0),
+            (200.0, 150.0)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, 760.0, 690.0), (0.0, 0.0));
+        assert_eq!(
+            to_imgui_space(&mode, &geometry, 1160.0, 390.0),
+            display_size(geometry)
+        );
+        assert_eq!(to_imgui_space(&mode, &geometry, 1161.0, 540.0), OUTSIDE);
+        assert_eq!(to_imgui_space(&mode, &geometry, 960.0, 389.0), OUTSIDE);
     }
-    (out_x as f32, out_y as f32)
 }
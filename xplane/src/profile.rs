@@ -0,0 +1,32 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use tracing::warn;
+
+use imgui_support::profiles::{ProfileRequest, Profiles};
+
+use crate::layout::Layout;
+use crate::ui::Window;
+
+/// Carries out any request made through `Profiles`, storing the window's `Layout`
+pub fn handle_request(profiles: Option<&Profiles>, window: &mut Window) {
+    let Some((profiles, request)) =
+        profiles.and_then(|profiles| Some((profiles, profiles.take_request()?)))
+    else {
+        return;
+    };
+    let result = match &request {
+        ProfileRequest::Save(name) => profiles.store(name, &Layout::capture(window)),
+        ProfileRequest::Apply(name) => profiles.load::<Layout>(name).map(|layout| {
+            if let Some(layout) = layout {
+                layout.apply(window);
+            }
+        }),
+    };
+    if let Err(e) = result {
+        warn!(?request, error = %e, "Unable to update profile");
+    }
+}
@@ -4,105 +4,358 @@
  * All rights reserved.
  */
 
+use std::cell::RefCell;
 use std::mem;
 
 use gl21 as gl;
 use gl::types::GLuint;
-use imgui::{Context, DrawIdx};
+use imgui::{Context, DrawData, DrawIdx, TextureId};
+use tracing::warn;
 use xplm::data::ArrayRead;
 use xplm::data::borrowed::{DataRef, FindError};
 use xplm_sys::{XPLMBindTexture2d, XPLMGenerateTextureNumbers, XPLMSetGraphicsState};
 
+use imgui_support::backend::RendererBackend;
 use imgui_support::geometry::Rect;
+use imgui_support::metrics::RenderStats;
+use imgui_support::self_test::{self, Diagnostic};
 use imgui_support::renderer_common::{
-    add_fonts, configure_imgui, FontStyles, render, return_param,
+    add_fonts, configure_imgui, DEFAULT_FONT_SIZE, delete_texture, FontStyles, render,
+    return_param, texture_lost, upload_font_texture,
 };
 
+use crate::offscreen::Offscreen;
+use crate::ui::Layer;
+
+/// The smallest and largest multiples of a window's resolution it can be rendered at
+const MIN_RENDER_SCALE: f32 = 0.5;
+const MAX_RENDER_SCALE: f32 = 2.0;
+
+/// `scale` clamped to the render scales offscreen rendering supports, or `None` if it's NaN
+pub(crate) fn clamp_render_scale(scale: f32) -> Option<f32> {
+    (!scale.is_nan()).then(|| scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE))
+}
+
 pub struct Renderer {
     font_texture: GLuint,
+    render_state: RenderState,
+    /// For render scales other than 1, if the driver can render offscreen
+    offscreen: Option<RefCell<Offscreen>>,
     modelview_matrix: DataRef<[f32]>,
     viewport: DataRef<[i32]>,
     projection_matrix: DataRef<[f32]>,
 }
 
+/// How the UI is blended into what X-Plane has drawn, which depends on the window's layer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RenderState {
+    pub blend: BlendMode,
+    /// Discards fully transparent pixels
+    pub alpha_test: bool,
+    /// Hides the UI behind nearer 3-D geometry. Only meaningful for the flight overlay layer.
+    pub depth_test: bool,
+    pub depth_write: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlendMode {
+    /// Blends colour and alpha alike, which suits windows drawn over other windows
+    Alpha,
+    /// Blends colour by alpha but adds to the destination alpha rather than thinning it, so
+    /// translucent UI drawn over the sim's scene doesn't leave see-through patches where X-Plane
+    /// composites the frame
+    SeparateAlpha,
+}
+
+impl RenderState {
+    /// The preset for windows in `layer`. Floating and modal windows are drawn over X-Plane's
+    /// own windows as before; the flight overlay and notifications are drawn over the scene, so
+    /// they keep its alpha and don't drop the faint edges of text.
+    #[must_use]
+    pub fn for_layer(layer: Layer) -> Self {
+        match layer {
+            Layer::FloatingWindows | Layer::Modal => RenderState {
+                blend: BlendMode::Alpha,
+                alpha_test: true,
+                depth_test: false,
+                depth_write: false,
+            },
+            Layer::FlightOverlay | Layer::GrowlNotifications => RenderState {
+                blend: BlendMode::SeparateAlpha,
+                alpha_test: false,
+                depth_test: false,
+                depth_write: false,
+            },
+        }
+    }
+}
+
 impl Renderer {
-    pub fn new(imgui: &mut Context) -> Result<Renderer, FindError> {
+    pub fn new(imgui: &mut Context, render_state: RenderState) -> Result<Renderer, FindError> {
         configure_imgui(imgui, "xplane");
         let font_texture = bind_texture();
-        add_fonts(font_texture, imgui.fonts(), 14.0, &FontStyles::default());
+        add_fonts(
+            font_texture,
+            imgui.fonts(),
+            DEFAULT_FONT_SIZE,
+            &FontStyles::default(),
+        );
+
+        let offscreen = Offscreen::load();
+        if offscreen.is_none() {
+            warn!("Framebuffer objects are unavailable, so the render scale will be ignored");
+        }
 
         Ok(Renderer {
             font_texture,
+            render_state,
+            offscreen: offscreen.map(RefCell::new),
             modelview_matrix: DataRef::find("sim/graphics/view/modelview_matrix")?,
             viewport: DataRef::find("sim/graphics/view/viewport")?,
             projection_matrix: DataRef::find("sim/graphics/view/projection_matrix")?,
         })
     }
 
-    pub fn render(&self, imgui: &mut Context, rect: Rect) {
-        let Rect { left, top, .. } = rect;
-        setup_render_state(left, top);
+    /// Switches to a font texture built by a `FontRebuilder`, deleting the old one
+    pub fn replace_font_texture(&mut self, font_texture: GLuint) {
+        delete_texture(self.font_texture);
+        self.font_texture = font_texture;
+    }
+
+    /// Recreates the font texture and those from `create_texture` if the context was reset,
+    /// returning true if it was.
+    pub fn restore_if_lost(&mut self, imgui: &mut Context) -> bool {
+        if !texture_lost(self.font_texture) {
+            return false;
+        }
+        imgui_support::restore_textures();
+        if let Some(offscreen) = &self.offscreen {
+            offscreen.borrow_mut().forget();
+        }
+        self.font_texture = bind_texture();
+        upload_font_texture(self.font_texture, imgui.fonts());
+        true
+    }
+
+    /// Renders frames into `rect`, from `Window::draw_geometry`, for `FrameDriver`. A `scale`
+    /// other than 1 renders them offscreen at that multiple of the window's size on screen.
+    pub fn for_window(&self, rect: Rect, in_vr: bool, scale: f32) -> WindowRenderer<'_> {
+        WindowRenderer {
+            renderer: self,
+            rect,
+            in_vr,
+            scale,
+        }
+    }
+
+    /// Runs `self_test::check_scissor` on the two pixels in the bottom left corner of `rect`,
+    /// from `Window::draw_geometry`, which X-Plane draws over next frame
+    pub fn check_scissor(&self, rect: Rect, in_vr: bool) -> Vec<Diagnostic> {
+        let (modelview, projection, viewport) = self.transforms(in_vr);
+        let (x, y) = boxels_to_native(rect.left, rect.bottom, modelview, projection, viewport);
+        self_test::check_scissor(x, y)
+    }
+
+    fn transforms(&self, in_vr: bool) -> ([f32; 16], [f32; 16], [i32; 4]) {
+        // VR windows are drawn offscreen, so the sim's view transforms don't apply to them
+        if in_vr {
+            gl_transforms()
+        } else {
+            self.sim_transforms()
+        }
+    }
+
+    fn sim_transforms(&self) -> ([f32; 16], [f32; 16], [i32; 4]) {
         let mut modelview = [0.0; 16];
         let mut projection = [0.0; 16];
         let mut viewport = [0; 4];
-
         self.modelview_matrix.get(&mut modelview);
         self.projection_matrix.get(&mut projection);
         self.viewport.get(&mut viewport);
+        (modelview, projection, viewport)
+    }
+}
+
+/// A `Renderer` drawing into one window's geometry
+pub struct WindowRenderer<'a> {
+    renderer: &'a Renderer,
+    rect: Rect,
+    in_vr: bool,
+    scale: f32,
+}
+
+impl WindowRenderer<'_> {
+    /// Renders into the offscreen texture at `scale` of the window's size on screen, then draws
+    /// the texture into the window. Returns `None`, having drawn nothing, if it can't.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn render_offscreen(&self, draw_data: &DrawData) -> Option<RenderStats> {
+        let mut offscreen = self.renderer.offscreen.as_ref()?.borrow_mut();
+        let [width, height] = draw_data.display_size;
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+        let Rect {
+            left,
+            top,
+            right,
+            bottom,
+        } = self.rect;
+        let (modelview, projection, viewport) = self.renderer.transforms(self.in_vr);
+        let (native_left, native_bottom) =
+            boxels_to_native(left, bottom, modelview, projection, viewport);
+        let (native_right, native_top) =
+            boxels_to_native(right, top, modelview, projection, viewport);
+        let scaled = |native: i32| (native as f32 * self.scale).round().max(1.0) as i32;
+        let size = [
+            scaled(native_right - native_left),
+            scaled(native_top - native_bottom),
+        ];
+        let previous = offscreen.begin(size)?;
 
-        let draw_data = imgui.render();
-        render(
+        let scale_x = size[0] as f32 / width;
+        let scale_y = size[1] as f32 / height;
+        setup_offscreen_state(self.renderer.render_state, draw_data.display_size);
+        let stats = render(
             draw_data,
-            |count, clip_rect, texture_id, idx_buffer, idx_offset| {
-                let [x, y, z, w] = clip_rect;
+            bind_xplm_texture,
+            |count, [x, y, z, w], idx_buffer, idx_offset| {
                 unsafe {
-                    XPLMBindTexture2d(
-                        texture_id
-                            .id()
-                            .try_into()
-                            .unwrap_or_else(|e| panic!("Unable to convert texture ID: {e}")),
-                        0,
-                    );
-                    let (b_left, b_top) = translate_imgui_to_boxel(left, top, x, y);
-                    let (b_right, b_bottom) = translate_imgui_to_boxel(left, top, z, w);
-                    let (n_left, n_top) =
-                        boxels_to_native(b_left, b_top, modelview, projection, viewport);
-                    let (n_right, n_bottom) =
-                        boxels_to_native(b_right, b_bottom, modelview, projection, viewport);
-                    gl::Scissor(n_left, n_bottom, n_right - n_left, n_top - n_bottom);
-                    let idx_size = if mem::size_of::<DrawIdx>() == 2 {
-                        gl::UNSIGNED_SHORT
-                    } else {
-                        gl::UNSIGNED_INT
-                    };
-                    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-                    gl::DrawElements(
-                        gl::TRIANGLES,
-                        count as _,
-                        idx_size,
-                        (idx_buffer.as_ptr() as usize + idx_offset * mem::size_of::<DrawIdx>())
-                            as _,
+                    gl::Scissor(
+                        (x * scale_x) as _,
+                        ((height - w) * scale_y) as _,
+                        ((z - x) * scale_x) as _,
+                        ((w - y) * scale_y) as _,
                     );
                 }
+                draw_elements(count, idx_buffer, idx_offset);
             },
         );
+        restore_offscreen_state();
+        offscreen.end(previous);
+
+        setup_render_state(self.renderer.render_state, left, top);
+        unsafe {
+            // the texture holds colours already multiplied by their alpha
+            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::Color4f(1.0, 1.0, 1.0, 1.0);
+            gl::Begin(gl::QUADS);
+            // the texture's rows run bottom up, imgui's top down
+            gl::TexCoord2f(0.0, 1.0);
+            gl::Vertex2f(0.0, 0.0);
+            gl::TexCoord2f(0.0, 0.0);
+            gl::Vertex2f(0.0, height);
+            gl::TexCoord2f(1.0, 0.0);
+            gl::Vertex2f(width, height);
+            gl::TexCoord2f(1.0, 1.0);
+            gl::Vertex2f(width, 0.0);
+            gl::End();
+        }
         restore_render_state();
+        Some(stats)
+    }
+}
+
+impl RendererBackend for WindowRenderer<'_> {
+    fn render(&mut self, draw_data: &DrawData) -> RenderStats {
+        if (self.scale - 1.0).abs() > f32::EPSILON {
+            if let Some(stats) = self.render_offscreen(draw_data) {
+                return stats;
+            }
+        }
+
+        let Rect { left, top, .. } = self.rect;
+        let (modelview, projection, viewport) = self.renderer.transforms(self.in_vr);
+        setup_render_state(self.renderer.render_state, left, top);
+
+        let stats = render(
+            draw_data,
+            bind_xplm_texture,
+            |count, clip_rect, idx_buffer, idx_offset| {
+                let [x, y, width, height] =
+                    scissor_box(clip_rect, (left, top), modelview, projection, viewport);
+                unsafe {
+                    gl::Scissor(x, y, width, height);
+                }
+                draw_elements(count, idx_buffer, idx_offset);
+            },
+        );
+        restore_render_state();
+        stats
+    }
+}
+
+fn bind_xplm_texture(texture_id: TextureId) {
+    unsafe {
+        XPLMBindTexture2d(
+            texture_id
+                .id()
+                .try_into()
+                .unwrap_or_else(|e| panic!("Unable to convert texture ID: {e}")),
+            0,
+        );
+    }
+}
+
+fn draw_elements(count: usize, idx_buffer: &[DrawIdx], idx_offset: usize) {
+    let idx_size = if mem::size_of::<DrawIdx>() == 2 {
+        gl::UNSIGNED_SHORT
+    } else {
+        gl::UNSIGNED_INT
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    unsafe {
+        gl::DrawElements(
+            gl::TRIANGLES,
+            count as _,
+            idx_size,
+            (idx_buffer.as_ptr() as usize + idx_offset * mem::size_of::<DrawIdx>()) as _,
+        );
+    }
+}
+
+/// The transforms X-Plane set up for the current draw callback. Must be read before
+/// `setup_render_state` changes the projection.
+fn gl_transforms() -> ([f32; 16], [f32; 16], [i32; 4]) {
+    let mut modelview = [0.0; 16];
+    let mut projection = [0.0; 16];
+    let mut viewport = [0; 4];
+    unsafe {
+        gl::GetFloatv(gl::MODELVIEW_MATRIX, modelview.as_mut_ptr());
+        gl::GetFloatv(gl::PROJECTION_MATRIX, projection.as_mut_ptr());
+        gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
     }
+    (modelview, projection, viewport)
 }
 
 impl Drop for Renderer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteTextures(1, &self.font_texture);
-        }
+        delete_texture(self.font_texture);
     }
 }
 
-fn setup_render_state(left: i32, top: i32) {
+fn setup_render_state(state: RenderState, left: i32, top: i32) {
     unsafe {
-        XPLMSetGraphicsState(0, 1, 0, 1, 1, 0, 0);
+        XPLMSetGraphicsState(
+            0,
+            1,
+            0,
+            state.alpha_test.into(),
+            1,
+            state.depth_test.into(),
+            state.depth_write.into(),
+        );
         gl::PushClientAttrib(gl::CLIENT_ALL_ATTRIB_BITS);
         gl::PushAttrib(gl::ENABLE_BIT | gl::COLOR_BUFFER_BIT | gl::TRANSFORM_BIT);
+        match state.blend {
+            BlendMode::Alpha => gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA),
+            BlendMode::SeparateAlpha => gl::BlendFuncSeparate(
+                gl::SRC_ALPHA,
+                gl::ONE_MINUS_SRC_ALPHA,
+                gl::ONE,
+                gl::ONE_MINUS_SRC_ALPHA,
+            ),
+        }
         gl::Disable(gl::CULL_FACE);
         gl::Enable(gl::SCISSOR_TEST);
         gl::EnableClientState(gl::VERTEX_ARRAY);
@@ -118,6 +371,44 @@ fn setup_render_state(left: i32, top: i32) {
     }
 }
 
+/// Sets up drawing into an offscreen texture `display_size` in imgui's coordinates. Alpha is
+/// accumulated rather than thinned, leaving colours multiplied by it for `BlendFunc(ONE, ...)`.
+fn setup_offscreen_state(state: RenderState, [width, height]: [f32; 2]) {
+    unsafe {
+        XPLMSetGraphicsState(0, 1, 0, state.alpha_test.into(), 1, 0, 0);
+        gl::PushClientAttrib(gl::CLIENT_ALL_ATTRIB_BITS);
+        gl::PushAttrib(gl::ENABLE_BIT | gl::COLOR_BUFFER_BIT | gl::TRANSFORM_BIT);
+        gl::BlendFuncSeparate(
+            gl::SRC_ALPHA,
+            gl::ONE_MINUS_SRC_ALPHA,
+            gl::ONE,
+            gl::ONE_MINUS_SRC_ALPHA,
+        );
+        gl::Disable(gl::CULL_FACE);
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::EnableClientState(gl::VERTEX_ARRAY);
+        gl::EnableClientState(gl::TEXTURE_COORD_ARRAY);
+        gl::EnableClientState(gl::COLOR_ARRAY);
+        gl::Enable(gl::TEXTURE_2D);
+
+        gl::MatrixMode(gl::PROJECTION);
+        gl::PushMatrix();
+        gl::LoadIdentity();
+        gl::Ortho(0.0, f64::from(width), f64::from(height), 0.0, -1.0, 1.0);
+        gl::MatrixMode(gl::MODELVIEW);
+        gl::PushMatrix();
+        gl::LoadIdentity();
+    }
+}
+
+fn restore_offscreen_state() {
+    unsafe {
+        gl::MatrixMode(gl::MODELVIEW);
+        gl::PopMatrix();
+    }
+    restore_render_state();
+}
+
 fn restore_render_state() {
     unsafe {
         gl::MatrixMode(gl::PROJECTION);
@@ -131,6 +422,24 @@ fn restore_render_state() {
     }
 }
 
+/// The scissor box, as x, y, width and height in the viewport's pixels, for an imgui clip rect
+/// in a window drawn with its top left corner at `origin` in boxels
+fn scissor_box(
+    clip_rect: [f32; 4],
+    origin: (i32, i32),
+    modelview: [f32; 16],
+    projection: [f32; 16],
+    viewport: [i32; 4],
+) -> [i32; 4] {
+    let [x, y, z, w] = clip_rect;
+    let (left, top) = origin;
+    let (b_left, b_top) = translate_imgui_to_boxel(left, top, x, y);
+    let (b_right, b_bottom) = translate_imgui_to_boxel(left, top, z, w);
+    let (n_left, n_top) = boxels_to_native(b_left, b_top, modelview, projection, viewport);
+    let (n_right, n_bottom) = boxels_to_native(b_right, b_bottom, modelview, projection, viewport);
+    [n_left, n_bottom, n_right - n_left, n_top - n_bottom]
+}
+
 #[allow(clippy::cast_possible_truncation)]
 fn translate_imgui_to_boxel(left: i32, top: i32, x: f32, y: f32) -> (i32, i32) {
     (left + x as i32, top - y as i32)
@@ -152,7 +461,8 @@ fn boxels_to_native(
 
     let out_x = (ndc[0] * 0.5 + 0.5) * viewport[2] as f32 + viewport[0] as f32;
     let out_y = (ndc[1] * 0.5 + 0.5) * viewport[3] as f32 + viewport[1] as f32;
-    (out_x as i32, out_y as i32)
+    // rounded, as the transforms rarely land exactly on a pixel and truncating loses one
+    (out_x.round() as i32, out_y.round() as i32)
 }
 
 fn mult_matrix_vec4f(m: [f32; 16], v: [f32; 4]) -> [f32; 4] {
@@ -172,3 +482,145 @@ pub(crate) fn bind_texture() -> GLuint {
         texture as _
     }
 }
+
+#[allow(clippy::cast_possible_wrap)]
+pub(crate) fn rebind_texture(texture: u32) {
+    unsafe {
+        XPLMBindTexture2d(texture as _, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clamp_render_scale, scissor_box, BlendMode, RenderState};
+    use crate::ui::Layer;
+
+    const IDENTITY: [f32; 16] = [
+        1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ];
+
+    /// A column-major orthographic projection of the given bounds, as `glOrtho` makes
+    fn ortho(left: f32, right: f32, bottom: f32, top: f32) -> [f32; 16] {
+        let mut m = IDENTITY;
+        m[0] = 2.0 / (right - left);
+        m[5] = 2.0 / (top - bottom);
+        m[10] = -1.0;
+        m[12] = -(right + left) / (right - left);
+        m[13] = -(top + bottom) / (top - bottom);
+        m
+    }
+
+    #[test]
+    fn in_sim_window() {
+        let projection = ortho(0.0, 1920.0, 0.0, 1080.0);
+        let viewport = [0, 0, 1920, 1080];
+        let scissor = scissor_box(
+            [10.0, 20.0, 210.0, 120.0],
+            (100, 800),
+            IDENTITY,
+            projection,
+            viewport,
+        );
+        assert_eq!(scissor, [110, 680, 200, 100]);
+    }
+
+    #[test]
+    fn in_sim_window_with_hidpi() {
+        let projection = ortho(0.0, 1920.0, 0.0, 1080.0);
+        let viewport = [0, 0, 3840, 2160];
+        let scissor = scissor_box(
+            [10.0, 20.0, 210.0, 120.0],
+            (100, 800),
+            IDENTITY,
+            projection,
+            viewport,
+        );
+        assert_eq!(scissor, [220, 1360, 400, 200]);
+    }
+
+    #[test]
+    fn popped_out_window() {
+        // drawn in the OS window's own boxels, from its bottom left corner
+        let projection = ortho(0.0, 640.0, 0.0, 480.0);
+        let viewport = [0, 0, 640, 480];
+        let scissor = scissor_box(
+            [0.0, 0.0, 640.0, 240.0],
+            (0, 480),
+            IDENTITY,
+            projection,
+            viewport,
+        );
+        assert_eq!(scissor, [0, 240, 640, 240]);
+    }
+
+    #[test]
+    fn window_on_a_monitor_left_of_the_main_one() {
+        // global desktop boxels run negative to the left of the main monitor, which the sim's
+        // modelview moves onto the monitor being drawn
+        let mut modelview = IDENTITY;
+        modelview[12] = 1920.0;
+        let projection = ortho(0.0, 1920.0, 0.0, 1080.0);
+        let viewport = [0, 0, 1920, 1080];
+        let scissor = scissor_box(
+            [0.0, 0.0, 300.0, 200.0],
+            (-1800, 1000),
+            modelview,
+            projection,
+            viewport,
+        );
+        assert_eq!(scissor, [120, 800, 300, 200]);
+    }
+
+    #[test]
+    fn vr_window() {
+        // drawn offscreen into a texture the size of the window, from its bottom left corner
+        let projection = ortho(0.0, 512.0, 0.0, 384.0);
+        let viewport = [0, 0, 512, 384];
+        let scissor = scissor_box(
+            [16.0, 16.0, 496.0, 368.0],
+            (0, 384),
+            IDENTITY,
+            projection,
+            viewport,
+        );
+        assert_eq!(scissor, [16, 16, 480, 352]);
+    }
+
+    #[test]
+    fn render_scale_is_clamped() {
+        assert_eq!(clamp_render_scale(1.5), Some(1.5));
+        assert_eq!(clamp_render_scale(0.1), Some(0.5));
+        assert_eq!(clamp_render_scale(f32::INFINITY), Some(2.0));
+        assert_eq!(clamp_render_scale(f32::NAN), None);
+    }
+
+    #[test]
+    fn windows_over_windows_blend_alpha() {
+        for layer in [Layer::FloatingWindows, Layer::Modal] {
+            assert_eq!(
+                RenderState::for_layer(layer),
+                RenderState {
+                    blend: BlendMode::Alpha,
+                    alpha_test: true,
+                    depth_test: false,
+                    depth_write: false,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn windows_over_the_scene_keep_its_alpha() {
+        for layer in [Layer::FlightOverlay, Layer::GrowlNotifications] {
+            assert_eq!(
+                RenderState::for_layer(layer),
+                RenderState {
+                    blend: BlendMode::SeparateAlpha,
+                    alpha_test: false,
+                    depth_test: false,
+                    depth_write: false,
+                }
+            );
+        }
+    }
+}
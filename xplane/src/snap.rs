@@ -0,0 +1,178 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use imgui_support::geometry::Rect;
+
+use crate::utils::{get_all_monitor_bounds, get_screen_bounds};
+
+/// Polls a moved window must keep still for, with no mouse button down, before it's snapped. The
+/// wait covers drags by X-Plane's own decoration, whose mouse the window never sees.
+const SETTLE_POLLS: u32 = 10;
+
+thread_local! {
+    /// The floating windows of the plugin's Systems, for snapping to each other
+    static WINDOWS: RefCell<Vec<(u64, Rect)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Snaps a floating window to the edges of the screen and of the plugin's other windows once the
+/// user has moved it near them. Every window is registered, so others can snap to it whether or
+/// not it snaps itself.
+pub struct Snapper {
+    id: u64,
+    /// The window's geometry as of the last poll, and for how many polls it's been the same
+    last: Option<Rect>,
+    still: u32,
+    /// Where the window last settled, to tell moves from resizes
+    settled: Option<Rect>,
+}
+
+impl Snapper {
+    pub fn new() -> Self {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        Snapper {
+            id,
+            last: None,
+            still: 0,
+            settled: None,
+        }
+    }
+
+    /// Called every sim frame with the window's geometry, or `None` while it's hidden, popped out
+    /// or in VR, returning where to snap it to, if anywhere. Nothing is snapped while `dragging`,
+    /// so a window isn't moved from under the cursor when the user pauses mid-drag. Only moves
+    /// are snapped; resizes are left alone.
+    pub fn update(
+        &mut self,
+        geometry: Option<Rect>,
+        distance: Option<u32>,
+        dragging: bool,
+    ) -> Option<Rect> {
+        let Some(geometry) = geometry else {
+            self.unregister();
+            self.last = None;
+            self.settled = None;
+            return None;
+        };
+        WINDOWS.with(|windows| {
+            let mut windows = windows.borrow_mut();
+            match windows.iter_mut().find(|(id, _)| *id == self.id) {
+                Some(window) => window.1 = geometry,
+                None => windows.push((self.id, geometry)),
+            }
+        });
+
+        if dragging || self.last != Some(geometry) {
+            self.last = Some(geometry);
+            self.still = 0;
+            return None;
+        }
+        if self.still >= SETTLE_POLLS {
+            return None;
+        }
+        self.still += 1;
+        if self.still < SETTLE_POLLS {
+            return None;
+        }
+
+        let moved = self.settled.is_some_and(|settled| {
+            settled != geometry
+                && settled.width() == geometry.width()
+                && settled.height() == geometry.height()
+        });
+        self.settled = Some(geometry);
+        let distance = i32::try_from(distance?).unwrap_or(i32::MAX);
+        if !moved {
+            return None;
+        }
+        let snapped = snap(geometry, &screens(), &self.others(), distance);
+        (snapped != geometry).then(|| {
+            self.settled = Some(snapped);
+            snapped
+        })
+    }
+
+    fn others(&self) -> Vec<Rect> {
+        WINDOWS.with(|windows| {
+            windows
+                .borrow()
+                .iter()
+                .filter(|(id, _)| *id != self.id)
+                .map(|(_, geometry)| *geometry)
+                .collect()
+        })
+    }
+
+    fn unregister(&self) {
+        WINDOWS.with(|windows| windows.borrow_mut().retain(|(id, _)| *id != self.id));
+    }
+}
+
+impl Drop for Snapper {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+/// The bounds of each monitor X-Plane is full-screen on, or of its window
+fn screens() -> Vec<Rect> {
+    let monitors: Vec<Rect> = get_all_monitor_bounds()
+        .into_iter()
+        .map(|(_, bounds)| bounds)
+        .collect();
+    if monitors.is_empty() {
+        vec![get_screen_bounds()]
+    } else {
+        monitors
+    }
+}
+
+/// `rect` moved by up to `distance` boxels each way so its edges meet the nearest screen edge,
+/// or the nearest edge of a window alongside it, either butting up to it or lining up with it
+fn snap(rect: Rect, screens: &[Rect], windows: &[Rect], distance: i32) -> Rect {
+    let mut dxs = Vec::new();
+    let mut dys = Vec::new();
+    for screen in screens {
+        dxs.extend([screen.left - rect.left, screen.right - rect.right]);
+        dys.extend([screen.top - rect.top, screen.bottom - rect.bottom]);
+    }
+    for window in windows {
+        if rect.bottom - distance < window.top && window.bottom < rect.top + distance {
+            dxs.extend([
+                window.right - rect.left,
+                window.left - rect.right,
+                window.left - rect.left,
+                window.right - rect.right,
+            ]);
+        }
+        if rect.left - distance < window.right && window.left < rect.right + distance {
+            dys.extend([
+                window.bottom - rect.top,
+                window.top - rect.bottom,
+                window.top - rect.top,
+                window.bottom - rect.bottom,
+            ]);
+        }
+    }
+    let (dx, dy) = (nearest(&dxs, distance), nearest(&dys, distance));
+    Rect::new(
+        rect.left + dx,
+        rect.top + dy,
+        rect.right + dx,
+        rect.bottom + dy,
+    )
+}
+
+fn nearest(deltas: &[i32], distance: i32) -> i32 {
+    deltas
+        .iter()
+        .copied()
+        .filter(|delta| delta.abs() <= distance)
+        .min_by_key(|delta| delta.abs())
+        .unwrap_or(0)
+}
@@ -6,11 +6,13 @@
 
 #![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
 
+use std::cell::UnsafeCell;
 use std::ffi::{c_char, c_int, c_void, CString};
 use std::mem::size_of;
 use std::ops::{Deref, DerefMut};
 use std::ptr::null_mut;
 
+use serde::{Deserialize, Serialize};
 use xplm_sys::{
     xplm_ControlFlag, xplm_CursorDefault, xplm_MouseUp, xplm_OptionAltFlag, xplm_ShiftFlag,
     xplm_UpFlag, xplm_WindowCenterOnMonitor, xplm_WindowDecorationNone,
@@ -30,35 +32,49 @@ use xplm_sys::{
     XPLMTakeKeyboardFocus, XPLMWindowDecoration, XPLMWindowID, XPLMWindowLayer,
 };
 
-use imgui_support::events::{Action, Event, Modifiers, MouseButton};
+use imgui_support::events::{Action, Event, Modifiers, MouseButton, WindowState};
 use imgui_support::geometry::Rect;
+use imgui_support::self_test::Diagnostic;
 
+use crate::callback::CallbackBox;
 use crate::ui::keymap::to_imgui_key;
 
-mod keymap;
+pub(crate) mod keymap;
 
 pub trait Delegate: 'static {
     /// Draws the window contents
     fn draw(&mut self, window: &mut Window);
 
-    fn handle_event(&mut self, window: &Window, event: Event);
+    /// Returns false to let mouse clicks and scrolls through to whatever is behind the window
+    fn handle_event(&mut self, window: &Window, event: Event) -> bool;
+
+    /// Checks the window can be drawn, returning any problems found
+    fn self_test(&mut self) -> Vec<Diagnostic> {
+        Vec::new()
+    }
+
+    /// Called every sim frame, whether or not the window is drawn, to notice changes X-Plane has
+    /// no callback for
+    fn poll(&mut self, _window: &mut Window) {}
 }
 
+/// Owns a window. Its callbacks hand the delegate the window it belongs to, which can't be
+/// borrow checked, hence the `UnsafeCell`.
 pub struct Ref {
-    window: Box<Window>,
+    window: CallbackBox<UnsafeCell<Window>>,
 }
 
 impl Deref for Ref {
     type Target = Window;
 
     fn deref(&self) -> &Self::Target {
-        &self.window
+        unsafe { &*self.window.get() }
     }
 }
 
 impl DerefMut for Ref {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.window
+        unsafe { &mut *self.window.get() }
     }
 }
 
@@ -79,14 +95,15 @@ impl Window {
         positioning_mode: PositioningMode,
         delegate: D,
     ) -> Ref {
-        let mut window_box = Box::new(Window {
-            id: null_mut(),
-            delegate: Box::new(delegate),
-            title: String::from(title),
-            gravity: Gravity::default(),
-            resizing_limits: None,
-        });
-        let window_ptr: *mut Window = &mut *window_box;
+        let mut window = Ref {
+            window: CallbackBox::new(UnsafeCell::new(Window {
+                id: null_mut(),
+                delegate: Box::new(delegate),
+                title: String::from(title),
+                gravity: Gravity::default(),
+                resizing_limits: None,
+            })),
+        };
 
         let Rect {
             left,
@@ -106,19 +123,19 @@ impl Window {
             handleKeyFunc: Some(handle_key),
             handleCursorFunc: Some(handle_cursor),
             handleMouseWheelFunc: Some(handle_mouse_wheel),
-            refcon: window_ptr.cast(),
+            refcon: window.window.refcon(),
             decorateAsFloatingWindow: decoration.into(),
             layer: layer.into(),
             handleRightClickFunc: Some(handle_right_click),
         };
 
-        window_box.id = unsafe {
+        window.id = unsafe {
             let id = XPLMCreateWindowEx(&mut params);
             XPLMSetWindowPositioningMode(id, positioning_mode.into(), -1);
             id
         };
-        set_title(window_box.id, title);
-        Ref { window: window_box }
+        set_title(window.id, title);
+        window
     }
 
     pub fn set_title(&mut self, title: &str) {
@@ -165,6 +182,18 @@ impl Window {
         }
     }
 
+    /// The window's rect in the boxels its draw callback uses: global desktop boxels, or for a VR
+    /// window, boxels from its bottom left corner
+    #[must_use]
+    pub fn draw_geometry(&self) -> Rect {
+        if self.in_vr() {
+            let (width, height) = self.geometry_vr();
+            Rect::new(0, height, width, 0)
+        } else {
+            self.geometry()
+        }
+    }
+
     #[must_use]
     pub fn current_geometry(&self) -> (&PositioningMode, Rect) {
         let positioning_mode = self.positioning_mode();
@@ -206,6 +235,17 @@ impl Window {
         unsafe { XPLMWindowIsInVR(self.id) != 0 }
     }
 
+    /// Where and how the window is shown, with the geometry of its positioning mode
+    #[must_use]
+    pub fn state(&self) -> WindowState {
+        WindowState {
+            geometry: self.current_geometry().1,
+            popped_out: self.popped_out(),
+            in_vr: self.in_vr(),
+            visible: self.visible(),
+        }
+    }
+
     #[must_use]
     pub fn gravity(&self) -> &Gravity {
         &self.gravity
@@ -223,6 +263,11 @@ impl Window {
         }
     }
 
+    #[must_use]
+    pub fn resizing_limits(&self) -> Option<&ResizingLimits> {
+        self.resizing_limits.as_ref()
+    }
+
     pub fn set_resizing_limits(&mut self, resizing_limits: ResizingLimits) {
         unsafe {
             XPLMSetWindowResizingLimits(
@@ -282,6 +327,27 @@ impl Window {
             XPLMBringWindowToFront(self.id);
         }
     }
+
+    /// Runs the delegate's checks, which need X-Plane's GL context, as in any callback
+    pub fn self_test(&mut self) -> Vec<Diagnostic> {
+        self.delegate.self_test()
+    }
+
+    /// Passes `event` to the delegate as though X-Plane had sent it to the window
+    pub fn send_event(&mut self, event: Event) {
+        let window: *mut Window = self;
+        unsafe {
+            (*window).delegate.handle_event(&*window, event);
+        }
+    }
+
+    /// Lets the delegate check for changes, for a flight loop to call every sim frame
+    pub fn poll(&mut self) {
+        let window: *mut Window = self;
+        unsafe {
+            (*window).delegate.poll(&mut *window);
+        }
+    }
 }
 
 fn set_title(id: XPLMWindowID, title: &str) {
@@ -346,7 +412,7 @@ impl From<Decoration> for XPLMWindowDecoration {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Layer {
     FlightOverlay,
     FloatingWindows,
@@ -365,7 +431,7 @@ impl From<Layer> for XPLMWindowLayer {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum PositioningMode {
     Free,
     CenterOnMonitor,
@@ -420,6 +486,7 @@ impl Default for Gravity {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub struct ResizingLimits {
     pub min_width: i32,
     pub min_height: i32,
@@ -439,8 +506,13 @@ impl ResizingLimits {
     }
 }
 
+/// The window behind a callback's refcon
+unsafe fn from_refcon(refcon: *mut c_void) -> *mut Window {
+    CallbackBox::<UnsafeCell<Window>>::from_refcon(refcon).get()
+}
+
 unsafe extern "C" fn draw_window(_window: XPLMWindowID, refcon: *mut c_void) {
-    let window: *mut Window = refcon.cast();
+    let window = from_refcon(refcon);
     (*window).delegate.draw(&mut *window);
 }
 
@@ -451,16 +523,11 @@ unsafe extern "C" fn handle_mouse_click(
     status: XPLMMouseStatus,
     refcon: *mut c_void,
 ) -> c_int {
-    let action = if status == xplm_MouseUp as _ {
-        Action::Release
-    } else {
-        Action::Press
-    };
+    let action = mouse_action(status);
 
     let event = Event::MouseButton(MouseButton::Left, action);
-    let window: *mut Window = refcon.cast();
-    (*window).delegate.handle_event(&*window, event);
-    1
+    let window = from_refcon(refcon);
+    c_int::from((*window).delegate.handle_event(&*window, event))
 }
 
 #[allow(clippy::cast_sign_loss)]
@@ -485,14 +552,26 @@ unsafe extern "C" fn handle_key(
             control: flag_set(flags, xplm_ControlFlag as XPLMKeyFlags),
             option: flag_set(flags, xplm_OptionAltFlag as XPLMKeyFlags),
             shift: flag_set(flags, xplm_ShiftFlag as XPLMKeyFlags),
+            // X-Plane reports Cmd as the control flag on macOS, so imgui's Ctrl shortcuts work
+            super_key: false,
         };
 
         let event = Event::Key(to_imgui_key(virtual_key), ch, action, modifiers);
-        let window: *mut Window = refcon.cast();
+        let window = from_refcon(refcon);
         (*window).delegate.handle_event(&*window, event);
     }
 }
 
+/// The cast names the type because `as _` can't be inferred here: `serde_json` implements
+/// `PartialEq<Value>` for the integer types, giving the comparison more than one candidate.
+fn mouse_action(status: XPLMMouseStatus) -> Action {
+    if status == xplm_MouseUp as XPLMMouseStatus {
+        Action::Release
+    } else {
+        Action::Press
+    }
+}
+
 fn flag_set(flags: XPLMKeyFlags, flag: XPLMKeyFlags) -> bool {
     flags & flag as XPLMKeyFlags != 0
 }
@@ -503,8 +582,9 @@ unsafe extern "C" fn handle_cursor(
     y: c_int,
     refcon: *mut c_void,
 ) -> XPLMCursorStatus {
-    let event = Event::CursorPos(x, y);
-    let window: *mut Window = refcon.cast();
+    #[allow(clippy::cast_precision_loss)]
+    let event = Event::CursorPos(x as f32, y as f32);
+    let window = from_refcon(refcon);
     (*window).delegate.handle_event(&*window, event);
     xplm_CursorDefault as _
 }
@@ -517,11 +597,17 @@ unsafe extern "C" fn handle_mouse_wheel(
     clicks: c_int,
     refcon: *mut c_void,
 ) -> c_int {
-    let (x, y) = if wheel == 0 { (0, clicks) } else { (clicks, 0) };
+    // wheel 1 is the horizontal axis, which X-Plane reports as positive to the right
+    #[allow(clippy::cast_precision_loss)]
+    let clicks = clicks as f32;
+    let (x, y) = if wheel == 0 {
+        (0.0, clicks)
+    } else {
+        (-clicks, 0.0)
+    };
     let event = Event::Scroll(x, y);
-    let window: *mut Window = refcon.cast();
-    (*window).delegate.handle_event(&*window, event);
-    1
+    let window = from_refcon(refcon);
+    c_int::from((*window).delegate.handle_event(&*window, event))
 }
 
 unsafe extern "C" fn handle_right_click(
@@ -531,13 +617,8 @@ unsafe extern "C" fn handle_right_click(
     status: XPLMMouseStatus,
     refcon: *mut c_void,
 ) -> c_int {
-    let action = if status == xplm_MouseUp as _ {
-        Action::Release
-    } else {
-        Action::Press
-    };
+    let action = mouse_action(status);
     let event = Event::MouseButton(MouseButton::Right, action);
-    let window: *mut Window = refcon.cast();
-    (*window).delegate.handle_event(&*window, event);
-    1
+    let window = from_refcon(refcon);
+    c_int::from((*window).delegate.handle_event(&*window, event))
 }
@@ -114,3 +114,11 @@ pub fn to_imgui_key(key: c_char) -> Option<Key> {
         _ => None,
     }
 }
+
+/// The X-Plane virtual key code for `key`, if it has one
+pub fn from_imgui_key(key: Key) -> Option<c_char> {
+    #[allow(clippy::cast_possible_wrap)]
+    (0..=u8::MAX)
+        .map(|code| code as c_char)
+        .find(|&code| to_imgui_key(code) == Some(key))
+}
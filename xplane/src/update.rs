@@ -0,0 +1,102 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+
+use std::cell::RefCell;
+use std::ffi::{c_float, c_int, c_void};
+use std::mem::size_of;
+use std::rc::Rc;
+use std::time::Duration;
+
+use xplm_sys::{
+    xplm_FlightLoop_Phase_AfterFlightModel, XPLMCreateFlightLoop, XPLMCreateFlightLoop_t,
+    XPLMDestroyFlightLoop, XPLMFlightLoopID, XPLMFlightLoopPhaseType, XPLMScheduleFlightLoop,
+};
+
+use imgui_support::dialog::Modals;
+use imgui_support::jobs::ChunkedJobs;
+use imgui_support::App;
+
+use crate::callback::CallbackBox;
+use crate::dialog::Dialog;
+use crate::ui::Window;
+
+/// Negative intervals are in flight loops rather than seconds
+const EVERY_FRAME: c_float = -1.0;
+
+/// Calls `App::update`, polls the window for changes, runs chunked jobs and opens queued modals
+/// after the flight model every sim frame, whether or not the window is drawn.
+///
+/// Owned by the `System`, which drops it before the window it points to.
+pub struct UpdateLoop {
+    id: XPLMFlightLoopID,
+    _state: CallbackBox<State>,
+}
+
+struct State {
+    app: Rc<RefCell<dyn App>>,
+    window: *mut Window,
+    jobs: ChunkedJobs,
+    modals: Modals,
+    /// The open modals' windows, each kept until a button is pressed
+    dialogs: RefCell<Vec<Dialog>>,
+    budget: Duration,
+}
+
+impl UpdateLoop {
+    pub fn create(
+        app: Rc<RefCell<dyn App>>,
+        window: &mut Window,
+        jobs: ChunkedJobs,
+        modals: Modals,
+        budget: Duration,
+    ) -> UpdateLoop {
+        let state = CallbackBox::new(State {
+            app,
+            window,
+            jobs,
+            modals,
+            dialogs: RefCell::new(Vec::new()),
+            budget,
+        });
+        unsafe {
+            let mut params = XPLMCreateFlightLoop_t {
+                structSize: size_of::<XPLMCreateFlightLoop_t>() as _,
+                phase: xplm_FlightLoop_Phase_AfterFlightModel as XPLMFlightLoopPhaseType,
+                callbackFunc: Some(update_app),
+                refcon: state.refcon(),
+            };
+            let id = XPLMCreateFlightLoop(&mut params);
+            XPLMScheduleFlightLoop(id, EVERY_FRAME, 1);
+            UpdateLoop { id, _state: state }
+        }
+    }
+}
+
+impl Drop for UpdateLoop {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMDestroyFlightLoop(self.id);
+        }
+    }
+}
+
+unsafe extern "C" fn update_app(
+    elapsed_since_last_call: c_float,
+    _elapsed_since_last_flight_loop: c_float,
+    _counter: c_int,
+    refcon: *mut c_void,
+) -> c_float {
+    let state = CallbackBox::<State>::from_refcon(refcon);
+    state.app.borrow_mut().update(elapsed_since_last_call);
+    (*state.window).poll();
+    state.jobs.run_for(state.budget);
+    let mut dialogs = state.dialogs.borrow_mut();
+    dialogs.retain(|dialog| !dialog.is_finished());
+    dialogs.extend(state.modals.take().into_iter().map(Dialog::show));
+    EVERY_FRAME
+}
@@ -4,7 +4,12 @@
  * All rights reserved.
  */
 
-use xplm_sys::XPLMGetScreenBoundsGlobal;
+use std::ffi::{c_int, c_void};
+
+use xplm_sys::{
+    XPLMGetAllMonitorBoundsGlobal, XPLMGetAllMonitorBoundsOS, XPLMGetMouseLocationGlobal,
+    XPLMGetScreenBoundsGlobal,
+};
 
 use imgui_support::geometry::Rect;
 
@@ -21,3 +26,66 @@ pub fn get_screen_bounds() -> Rect {
     }
     Rect::new(bounds[0], bounds[1], bounds[2], bounds[3])
 }
+
+/// The index and global desktop bounds of each monitor X-Plane is full-screen on. Monitors
+/// X-Plane only has a window on, or none at all, are left out.
+#[must_use]
+pub fn get_all_monitor_bounds() -> Vec<(i32, Rect)> {
+    let mut monitors: Vec<(i32, Rect)> = Vec::new();
+    let monitors_ptr: *mut Vec<(i32, Rect)> = &mut monitors;
+    unsafe { XPLMGetAllMonitorBoundsGlobal(Some(receive_monitor_bounds), monitors_ptr.cast()) };
+    monitors
+}
+
+/// The index and OS pixel bounds of every monitor, e.g. for placing popped-out windows
+#[must_use]
+pub fn get_all_monitor_bounds_os() -> Vec<(i32, Rect)> {
+    let mut monitors: Vec<(i32, Rect)> = Vec::new();
+    let monitors_ptr: *mut Vec<(i32, Rect)> = &mut monitors;
+    unsafe { XPLMGetAllMonitorBoundsOS(Some(receive_monitor_bounds), monitors_ptr.cast()) };
+    monitors
+}
+
+/// The mouse position in global desktop boxels
+#[must_use]
+pub fn get_mouse_location() -> (i32, i32) {
+    let (mut x, mut y) = (0, 0);
+    unsafe { XPLMGetMouseLocationGlobal(&mut x, &mut y) };
+    (x, y)
+}
+
+/// The global desktop bounds of the monitor the mouse is on, or of the whole desktop if X-Plane
+/// isn't full-screen there
+#[must_use]
+pub fn get_mouse_monitor_bounds() -> Rect {
+    let (x, y) = get_mouse_location();
+    get_all_monitor_bounds()
+        .into_iter()
+        .map(|(_, bounds)| bounds)
+        .find(|bounds| {
+            (bounds.left..bounds.right).contains(&x) && (bounds.bottom..bounds.top).contains(&y)
+        })
+        .unwrap_or_else(get_screen_bounds)
+}
+
+/// A `width` by `height` window in the middle of the monitor the mouse is on
+#[must_use]
+#[allow(clippy::cast_possible_wrap)]
+pub fn centred_on_mouse_monitor(width: u32, height: u32) -> Rect {
+    let bounds = get_mouse_monitor_bounds();
+    let left = bounds.left + (bounds.width().saturating_sub(width) / 2) as i32;
+    let top = bounds.top - (bounds.height().saturating_sub(height) / 2) as i32;
+    Rect::new(left, top, left + width as i32, top - height as i32)
+}
+
+unsafe extern "C" fn receive_monitor_bounds(
+    index: c_int,
+    left: c_int,
+    top: c_int,
+    right: c_int,
+    bottom: c_int,
+    refcon: *mut c_void,
+) {
+    let monitors: *mut Vec<(i32, Rect)> = refcon.cast();
+    (*monitors).push((index, Rect::new(left, top, right, bottom)));
+}
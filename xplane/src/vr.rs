@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+
+use std::cell::RefCell;
+use std::ffi::{c_float, c_int, c_void};
+use std::mem::size_of;
+
+use xplm::data::borrowed::DataRef;
+use xplm::data::DataRead;
+use xplm_sys::{
+    xplm_FlightLoop_Phase_BeforeFlightModel, XPLMCreateFlightLoop, XPLMCreateFlightLoop_t,
+    XPLMDestroyFlightLoop, XPLMFlightLoopID, XPLMFlightLoopPhaseType, XPLMScheduleFlightLoop,
+};
+
+use crate::callback::CallbackBox;
+use crate::layout::Layout;
+use crate::ui::{PositioningMode, Window};
+
+/// VR is switched on from X-Plane's settings, so polling it this often is plenty
+const POLL_INTERVAL: c_float = 0.5;
+
+/// Moves a window into VR when the user enables it and back to where it was when they disable
+/// it. X-Plane doesn't show 2D windows in the headset, so without this they vanish.
+///
+/// Owned by the `System`, which drops it before the window it points to.
+pub struct VrFollower {
+    id: XPLMFlightLoopID,
+    _state: CallbackBox<RefCell<State>>,
+}
+
+struct State {
+    window: *mut Window,
+    vr_enabled: Option<DataRef<i32>>,
+    in_vr: bool,
+    /// Where the window was before it was moved into VR
+    layout: Option<Layout>,
+}
+
+impl VrFollower {
+    pub fn create(window: &mut Window) -> VrFollower {
+        let vr_enabled = DataRef::find("sim/graphics/VR/enabled").ok();
+        let in_vr = vr_enabled
+            .as_ref()
+            .is_some_and(|enabled| enabled.get() != 0);
+        let state = CallbackBox::new(RefCell::new(State {
+            window,
+            vr_enabled,
+            in_vr,
+            layout: None,
+        }));
+        unsafe {
+            let mut params = XPLMCreateFlightLoop_t {
+                structSize: size_of::<XPLMCreateFlightLoop_t>() as _,
+                phase: xplm_FlightLoop_Phase_BeforeFlightModel as XPLMFlightLoopPhaseType,
+                callbackFunc: Some(poll_vr),
+                refcon: state.refcon(),
+            };
+            let id = XPLMCreateFlightLoop(&mut params);
+            XPLMScheduleFlightLoop(id, POLL_INTERVAL, 1);
+            VrFollower { id, _state: state }
+        }
+    }
+}
+
+impl Drop for VrFollower {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMDestroyFlightLoop(self.id);
+        }
+    }
+}
+
+impl State {
+    unsafe fn poll(&mut self) {
+        let window = &mut *self.window;
+        let Some(vr_enabled) = &self.vr_enabled else {
+            return;
+        };
+        let in_vr = vr_enabled.get() != 0;
+        if in_vr == self.in_vr {
+            return;
+        }
+        self.in_vr = in_vr;
+
+        if in_vr {
+            if !window.in_vr() {
+                let layout = Layout::capture(window);
+                let geometry = window.geometry();
+                window.set_positioning_mode(PositioningMode::VR);
+                window.set_geometry_vr(geometry.width() as i32, geometry.height() as i32);
+                self.layout = Some(layout);
+            }
+        } else if let Some(layout) = self.layout.take() {
+            // the user may have shown or hidden the window while in VR
+            let visible = window.visible();
+            layout.apply(window);
+            window.set_visible(visible);
+        }
+    }
+}
+
+unsafe extern "C" fn poll_vr(
+    _elapsed_since_last_call: c_float,
+    _elapsed_since_last_flight_loop: c_float,
+    _counter: c_int,
+    refcon: *mut c_void,
+) -> c_float {
+    let state = CallbackBox::<RefCell<State>>::from_refcon(refcon);
+    state.borrow_mut().poll();
+    POLL_INTERVAL
+}
@@ -0,0 +1,845 @@
+/*
+ * Copyright (c) 2023 David Dunwoody.
+ *
+ * All rights reserved.
+ */
+
+#![allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_wrap,
+    clippy::cast_sign_loss
+)]
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ffi::{c_char, c_float, c_int, c_void, CStr, CString};
+use std::fs;
+use std::mem::size_of;
+use std::path::PathBuf;
+use std::ptr::null_mut;
+use std::rc::Rc;
+use std::time::Instant;
+
+use imgui::{ListClipper, Ui};
+use tracing::warn;
+use xplm_sys::{
+    xplmType_Data, xplmType_Double, xplmType_Float, xplmType_FloatArray, xplmType_Int,
+    xplmType_IntArray, xplm_CommandBegin, xplm_CommandEnd, xplm_FlightLoop_Phase_AfterFlightModel,
+    XPLMCanWriteDataRef, XPLMCommandBegin, XPLMCommandEnd, XPLMCommandOnce, XPLMCommandPhase,
+    XPLMCommandRef, XPLMCreateFlightLoop, XPLMCreateFlightLoop_t, XPLMDataRef, XPLMDataTypeID,
+    XPLMDestroyFlightLoop, XPLMFindCommand, XPLMFindDataRef, XPLMFlightLoopID,
+    XPLMFlightLoopPhaseType, XPLMGetDataRefTypes, XPLMGetDatab, XPLMGetDatad, XPLMGetDataf,
+    XPLMGetDatai, XPLMGetDatavf, XPLMGetDatavi, XPLMGetSystemPath, XPLMRegisterCommandHandler,
+    XPLMScheduleFlightLoop, XPLMSetDatab, XPLMSetDatad, XPLMSetDataf, XPLMSetDatai, XPLMSetDatavf,
+    XPLMSetDatavi, XPLMUnregisterCommandHandler,
+};
+
+use imgui_support::widgets::fuzzy_filter;
+
+use crate::callback::CallbackBox;
+
+/// Elements shown in the list before the rest are elided
+const SUMMARY_ELEMENTS: usize = 4;
+
+/// Asks X-Plane to call a flight loop every frame
+const EVERY_FRAME: c_float = -1.0;
+
+/// Command activations kept in the log before the oldest are dropped
+const LOG_LENGTH: usize = 500;
+
+/// Searches datarefs by name and shows their live values, editing the selected one if it's
+/// writable. A debugging aid for plugin developers.
+///
+/// Names come from X-Plane's `Resources/plugins/DataRefs.txt`, read when the browser is
+/// created, plus any added with `add`, e.g. the plugin's own datarefs.
+pub struct DatarefBrowser {
+    id: String,
+    names: Vec<String>,
+    query: String,
+    matches: Vec<String>,
+    selected: Option<String>,
+    /// Looked up as they're first shown, `None` if X-Plane doesn't know them
+    datarefs: BTreeMap<String, Option<Dataref>>,
+}
+
+#[derive(Clone, Copy)]
+struct Dataref {
+    handle: XPLMDataRef,
+    types: XPLMDataTypeID,
+    writable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Int(i32),
+    Float(f32),
+    Double(f64),
+    IntArray(Vec<i32>),
+    FloatArray(Vec<f32>),
+    Data(Vec<u8>),
+    Unknown,
+}
+
+impl DatarefBrowser {
+    #[must_use]
+    pub fn new(id: &str) -> Self {
+        let names = read_dataref_names();
+        DatarefBrowser {
+            id: String::from(id),
+            matches: names.clone(),
+            names,
+            query: String::new(),
+            selected: None,
+            datarefs: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a dataref missing from `DataRefs.txt`, e.g. one the plugin publishes
+    pub fn add(&mut self, name: &str) {
+        if !self.names.iter().any(|existing| existing == name) {
+            self.names.push(String::from(name));
+            self.matches = fuzzy_filter(&self.query, &self.names);
+        }
+    }
+
+    /// Draws the search field, the matching datarefs and their values, and the selected
+    /// dataref's editor below them
+    pub fn build(&mut self, ui: &Ui) {
+        let _id = ui.push_id(&self.id);
+        ui.set_next_item_width(-1.0);
+        if ui
+            .input_text("##query", &mut self.query)
+            .hint("Search datarefs")
+            .build()
+        {
+            self.matches = fuzzy_filter(&self.query, &self.names);
+        }
+
+        let editor_height = ui.text_line_height_with_spacing() * 6.0;
+        ui.child_window("##datarefs")
+            .size([0.0, -editor_height])
+            .build(|| self.draw_list(ui));
+
+        ui.separator();
+        let selected = self.selected.clone();
+        let dataref = selected.as_deref().and_then(|name| self.lookup(name));
+        ui.child_window("##editor")
+            .build(|| match (&selected, dataref) {
+                (None, _) => ui.text_disabled("Select a dataref to edit it"),
+                (Some(name), None) => {
+                    ui.text(name);
+                    ui.text_disabled("Not found");
+                }
+                (Some(name), Some(dataref)) => {
+                    ui.text(name);
+                    draw_editor(ui, dataref);
+                }
+            });
+    }
+
+    fn draw_list(&mut self, ui: &Ui) {
+        let value_column = ui.content_region_avail()[0] * 0.6;
+        let clipper = ListClipper::new(self.matches.len() as i32);
+        for index in clipper.begin(ui).iter() {
+            let name = self.matches[index as usize].clone();
+            let selected = self.selected.as_ref() == Some(&name);
+            if ui.selectable_config(&name).selected(selected).build() {
+                self.selected = Some(name.clone());
+            }
+            ui.same_line_with_pos(value_column);
+            match self.lookup(&name) {
+                Some(dataref) => ui.text(summary(&dataref.read())),
+                None => ui.text_disabled("not found"),
+            }
+        }
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<Dataref> {
+        *self
+            .datarefs
+            .entry(String::from(name))
+            .or_insert_with(|| Dataref::find(name))
+    }
+}
+
+impl Dataref {
+    fn find(name: &str) -> Option<Dataref> {
+        let name = CString::new(name).ok()?;
+        let handle = unsafe { XPLMFindDataRef(name.as_ptr()) };
+        if handle.is_null() {
+            return None;
+        }
+        Some(Dataref {
+            handle,
+            types: unsafe { XPLMGetDataRefTypes(handle) },
+            writable: unsafe { XPLMCanWriteDataRef(handle) } != 0,
+        })
+    }
+
+    fn has_type(&self, data_type: u32) -> bool {
+        self.types & data_type as XPLMDataTypeID != 0
+    }
+
+    /// Reads the most precise of the types the dataref offers. Arrays are first asked their
+    /// length by passing no buffer.
+    fn read(&self) -> Value {
+        unsafe {
+            if self.has_type(xplmType_Double) {
+                Value::Double(XPLMGetDatad(self.handle))
+            } else if self.has_type(xplmType_Float) {
+                Value::Float(XPLMGetDataf(self.handle))
+            } else if self.has_type(xplmType_Int) {
+                Value::Int(XPLMGetDatai(self.handle))
+            } else if self.has_type(xplmType_FloatArray) {
+                let mut values = vec![0.0; XPLMGetDatavf(self.handle, null_mut(), 0, 0) as usize];
+                XPLMGetDatavf(self.handle, values.as_mut_ptr(), 0, values.len() as c_int);
+                Value::FloatArray(values)
+            } else if self.has_type(xplmType_IntArray) {
+                let mut values = vec![0; XPLMGetDatavi(self.handle, null_mut(), 0, 0) as usize];
+                XPLMGetDatavi(self.handle, values.as_mut_ptr(), 0, values.len() as c_int);
+                Value::IntArray(values)
+            } else if self.has_type(xplmType_Data) {
+                let mut bytes = vec![0u8; XPLMGetDatab(self.handle, null_mut(), 0, 0) as usize];
+                XPLMGetDatab(
+                    self.handle,
+                    bytes.as_mut_ptr().cast(),
+                    0,
+                    bytes.len() as c_int,
+                );
+                Value::Data(bytes)
+            } else {
+                Value::Unknown
+            }
+        }
+    }
+
+    fn write(&self, value: &mut Value) {
+        unsafe {
+            match value {
+                Value::Int(value) => XPLMSetDatai(self.handle, *value),
+                Value::Float(value) => XPLMSetDataf(self.handle, *value),
+                Value::Double(value) => XPLMSetDatad(self.handle, *value),
+                Value::IntArray(values) => {
+                    XPLMSetDatavi(self.handle, values.as_mut_ptr(), 0, values.len() as c_int);
+                }
+                Value::FloatArray(values) => {
+                    XPLMSetDatavf(self.handle, values.as_mut_ptr(), 0, values.len() as c_int);
+                }
+                Value::Data(bytes) => {
+                    XPLMSetDatab(
+                        self.handle,
+                        bytes.as_mut_ptr().cast(),
+                        0,
+                        bytes.len() as c_int,
+                    );
+                }
+                Value::Unknown => {}
+            }
+        }
+    }
+
+    /// Reads a number to plot, from the array element at `index` if given
+    fn sample(&self, index: Option<usize>) -> Option<f32> {
+        unsafe {
+            match index {
+                Some(index) if self.has_type(xplmType_FloatArray) => {
+                    let mut value = 0.0;
+                    let read = XPLMGetDatavf(self.handle, &mut value, index as c_int, 1);
+                    (read == 1).then_some(value)
+                }
+                Some(index) if self.has_type(xplmType_IntArray) => {
+                    let mut value = 0;
+                    let read = XPLMGetDatavi(self.handle, &mut value, index as c_int, 1);
+                    (read == 1).then_some(value as f32)
+                }
+                Some(_) => None,
+                None => match self.read() {
+                    Value::Int(value) => Some(value as f32),
+                    Value::Float(value) => Some(value),
+                    Value::Double(value) => Some(value as f32),
+                    _ => None,
+                },
+            }
+        }
+    }
+}
+
+/// Scrolling line plots of datarefs, sampled every flight loop so fast changes such as an
+/// autopilot oscillating aren't missed between frames of the window. Add a numeric dataref by
+/// name, or an array element as `name[index]`.
+///
+/// Samples are kept in a ring buffer per dataref, so the plots show the last `capacity` flight
+/// loops. Sampling stops when the plot is dropped.
+pub struct DatarefPlot {
+    id: String,
+    height: f32,
+    flight_loop: XPLMFlightLoopID,
+    state: CallbackBox<RefCell<PlotState>>,
+}
+
+struct PlotState {
+    capacity: usize,
+    paused: bool,
+    series: Vec<Series>,
+}
+
+struct Series {
+    name: String,
+    dataref: Dataref,
+    index: Option<usize>,
+    samples: Vec<f32>,
+    /// Where the next sample goes once `samples` is full, i.e. the oldest
+    next: usize,
+}
+
+impl DatarefPlot {
+    /// Plots the last `capacity` samples of each dataref added
+    #[must_use]
+    pub fn new(id: &str, capacity: usize) -> Self {
+        let state = CallbackBox::new(RefCell::new(PlotState {
+            capacity: capacity.max(2),
+            paused: false,
+            series: Vec::new(),
+        }));
+        unsafe {
+            let mut params = XPLMCreateFlightLoop_t {
+                structSize: size_of::<XPLMCreateFlightLoop_t>() as _,
+                phase: xplm_FlightLoop_Phase_AfterFlightModel as XPLMFlightLoopPhaseType,
+                callbackFunc: Some(sample_datarefs),
+                refcon: state.refcon(),
+            };
+            let flight_loop = XPLMCreateFlightLoop(&mut params);
+            XPLMScheduleFlightLoop(flight_loop, EVERY_FRAME, 1);
+            DatarefPlot {
+                id: String::from(id),
+                height: 60.0,
+                flight_loop,
+                state,
+            }
+        }
+    }
+
+    /// Height of each plot (default 60)
+    #[must_use]
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Starts plotting `name`, or `name[index]` for an array element. Returns false if X-Plane
+    /// doesn't know the dataref or it isn't numeric.
+    pub fn add(&mut self, name: &str) -> bool {
+        let (dataref_name, index) = parse_element(name);
+        let Some(dataref) = Dataref::find(dataref_name) else {
+            return false;
+        };
+        if dataref.sample(index).is_none() {
+            return false;
+        }
+        let mut state = self.state.borrow_mut();
+        let capacity = state.capacity;
+        state.series.push(Series {
+            name: String::from(name),
+            dataref,
+            index,
+            samples: Vec::with_capacity(capacity),
+            next: 0,
+        });
+        true
+    }
+
+    /// Stops plotting `name`
+    pub fn remove(&mut self, name: &str) {
+        self.state
+            .borrow_mut()
+            .series
+            .retain(|series| series.name != name);
+    }
+
+    /// Freezes the plots, e.g. to study an oscillation, while the sim carries on
+    pub fn set_paused(&mut self, paused: bool) {
+        self.state.borrow_mut().paused = paused;
+    }
+
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused
+    }
+
+    /// Discards the samples so far
+    pub fn clear(&mut self) {
+        self.state.borrow_mut().clear();
+    }
+
+    /// Draws a plot per dataref, scaled to fit its samples, with its latest value, minimum and
+    /// maximum
+    pub fn build(&mut self, ui: &Ui) {
+        let _id = ui.push_id(&self.id);
+        let mut state = self.state.borrow_mut();
+        ui.checkbox("Pause", &mut state.paused);
+        ui.same_line();
+        if ui.button("Clear") {
+            state.clear();
+        }
+
+        let mut removed = None;
+        for (index, series) in state.series.iter().enumerate() {
+            let _id = ui.push_id_usize(index);
+            ui.text(&series.name);
+            ui.same_line();
+            if ui.small_button("x") {
+                removed = Some(index);
+            }
+            let (min, max) = series
+                .samples
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(min, max), sample| {
+                    (min.min(*sample), max.max(*sample))
+                });
+            let overlay = series.latest().map_or_else(String::new, |latest| {
+                format!("{latest:.3}  [{min:.3}, {max:.3}]")
+            });
+            // a flat line is drawn mid-plot rather than along an edge
+            let margin = ((max - min) * 0.05).max(f32::EPSILON);
+            ui.plot_lines("##plot", &series.samples)
+                .values_offset(series.next)
+                .overlay_text(overlay)
+                .scale_min(min - margin)
+                .scale_max(max + margin)
+                .graph_size([-1.0, self.height])
+                .build();
+        }
+        if let Some(index) = removed {
+            state.series.remove(index);
+        }
+    }
+}
+
+impl Drop for DatarefPlot {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMDestroyFlightLoop(self.flight_loop);
+        }
+    }
+}
+
+impl PlotState {
+    fn clear(&mut self) {
+        for series in &mut self.series {
+            series.samples.clear();
+            series.next = 0;
+        }
+    }
+
+    fn sample(&mut self) {
+        if self.paused {
+            return;
+        }
+        for series in &mut self.series {
+            if let Some(sample) = series.dataref.sample(series.index) {
+                series.push(sample, self.capacity);
+            }
+        }
+    }
+}
+
+impl Series {
+    fn push(&mut self, sample: f32, capacity: usize) {
+        if self.samples.len() < capacity {
+            self.samples.push(sample);
+        } else {
+            self.samples[self.next] = sample;
+            self.next = (self.next + 1) % capacity;
+        }
+    }
+
+    fn latest(&self) -> Option<f32> {
+        // `next` stays at zero until the buffer is full, and returns to it after the last slot
+        match self.next {
+            0 => self.samples.last().copied(),
+            next => self.samples.get(next - 1).copied(),
+        }
+    }
+}
+
+unsafe extern "C" fn sample_datarefs(
+    _elapsed_since_last_call: c_float,
+    _elapsed_since_last_flight_loop: c_float,
+    _counter: c_int,
+    refcon: *mut c_void,
+) -> c_float {
+    CallbackBox::<RefCell<PlotState>>::from_refcon(refcon)
+        .borrow_mut()
+        .sample();
+    EVERY_FRAME
+}
+
+/// Splits `name[index]` into the dataref's name and the element's index
+fn parse_element(name: &str) -> (&str, Option<usize>) {
+    name.strip_suffix(']')
+        .and_then(|rest| rest.split_once('['))
+        .and_then(|(name, index)| Some((name, Some(index.parse().ok()?))))
+        .unwrap_or((name, None))
+}
+
+fn draw_editor(ui: &Ui, dataref: Dataref) {
+    let mut value = dataref.read();
+    if !dataref.writable {
+        ui.text_disabled("Read only");
+    }
+    let _disabled = (!dataref.writable).then(|| ui.begin_disabled(true));
+    let changed = match &mut value {
+        Value::Int(value) => ui.input_int("##value", value).build(),
+        Value::Float(value) => ui.input_float("##value", value).build(),
+        Value::Double(value) => ui.input_scalar("##value", value).build(),
+        Value::IntArray(values) => edit_elements(ui, values, |label, value| {
+            ui.input_int(label, value).build()
+        }),
+        Value::FloatArray(values) => edit_elements(ui, values, |label, value| {
+            ui.input_float(label, value).build()
+        }),
+        Value::Data(bytes) => {
+            let mut text = data_text(bytes);
+            let changed = ui.input_text("##value", &mut text).build();
+            if changed {
+                *bytes = text.into_bytes();
+                bytes.push(0);
+            }
+            changed
+        }
+        Value::Unknown => {
+            ui.text_disabled("Unsupported type");
+            false
+        }
+    };
+    if changed && dataref.writable {
+        dataref.write(&mut value);
+    }
+}
+
+/// Draws an input per element of an array, only for those in view as arrays can be long
+fn edit_elements<T>(ui: &Ui, values: &mut [T], input: impl Fn(&str, &mut T) -> bool) -> bool {
+    let mut changed = false;
+    let clipper = ListClipper::new(values.len() as i32);
+    for index in clipper.begin(ui).iter() {
+        changed |= input(&format!("[{index}]"), &mut values[index as usize]);
+    }
+    changed
+}
+
+fn summary(value: &Value) -> String {
+    fn elements<T: ToString>(values: &[T]) -> String {
+        let shown: Vec<String> = values
+            .iter()
+            .take(SUMMARY_ELEMENTS)
+            .map(ToString::to_string)
+            .collect();
+        let more = if values.len() > SUMMARY_ELEMENTS {
+            ", ..."
+        } else {
+            ""
+        };
+        format!("[{}] {}{more}", values.len(), shown.join(", "))
+    }
+
+    match value {
+        Value::Int(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        Value::Double(value) => value.to_string(),
+        Value::IntArray(values) => elements(values),
+        Value::FloatArray(values) => elements(values),
+        Value::Data(bytes) => format!("\"{}\"", data_text(bytes)),
+        Value::Unknown => String::from("?"),
+    }
+}
+
+/// Byte datarefs mostly hold NUL-terminated strings
+fn data_text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Searches commands by name, running the ones chosen once or holding them down, and logs when
+/// those being watched begin and end, whoever triggers them. A debugging aid alongside the
+/// `DatarefBrowser`.
+///
+/// Names come from X-Plane's `Resources/plugins/Commands.txt`, read when the browser is created,
+/// plus any added with `add`. Held commands are released and watches removed when it's dropped.
+pub struct CommandBrowser {
+    id: String,
+    names: Vec<String>,
+    query: String,
+    matches: Vec<String>,
+    /// Looked up as they're first shown, `None` if X-Plane doesn't know them
+    commands: BTreeMap<String, Option<XPLMCommandRef>>,
+    held: BTreeSet<String>,
+    watches: BTreeMap<String, Watch>,
+    log: Rc<RefCell<VecDeque<Activation>>>,
+    started: Instant,
+}
+
+/// A command handler logging a command's activations, unregistered when dropped
+struct Watch {
+    command_ref: XPLMCommandRef,
+    logger: CallbackBox<Logger>,
+}
+
+struct Logger {
+    name: String,
+    log: Rc<RefCell<VecDeque<Activation>>>,
+    started: Instant,
+}
+
+struct Activation {
+    seconds: f32,
+    name: String,
+    began: bool,
+}
+
+impl CommandBrowser {
+    #[must_use]
+    pub fn new(id: &str) -> Self {
+        let names = read_command_names();
+        CommandBrowser {
+            id: String::from(id),
+            matches: names.clone(),
+            names,
+            query: String::new(),
+            commands: BTreeMap::new(),
+            held: BTreeSet::new(),
+            watches: BTreeMap::new(),
+            log: Rc::default(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Adds a command missing from `Commands.txt`, e.g. one an aircraft or plugin creates
+    pub fn add(&mut self, name: &str) {
+        if !self.names.iter().any(|existing| existing == name) {
+            self.names.push(String::from(name));
+            self.matches = fuzzy_filter(&self.query, &self.names);
+        }
+    }
+
+    /// Starts or stops logging `name`'s activations. Returns false if X-Plane doesn't know it.
+    pub fn watch(&mut self, name: &str, watch: bool) -> bool {
+        let Some(command_ref) = self.lookup(name) else {
+            return false;
+        };
+        if !watch {
+            self.watches.remove(name);
+        } else if !self.watches.contains_key(name) {
+            let watch = Watch::register(command_ref, name, &self.log, self.started);
+            self.watches.insert(String::from(name), watch);
+        }
+        true
+    }
+
+    /// Draws the search field, the matching commands with their buttons, and the log below them
+    pub fn build(&mut self, ui: &Ui) {
+        let _id = ui.push_id(&self.id);
+        ui.set_next_item_width(-1.0);
+        if ui
+            .input_text("##query", &mut self.query)
+            .hint("Search commands")
+            .build()
+        {
+            self.matches = fuzzy_filter(&self.query, &self.names);
+        }
+
+        let log_height = ui.text_line_height_with_spacing() * 8.0;
+        ui.child_window("##commands")
+            .size([0.0, -log_height])
+            .build(|| self.draw_list(ui));
+
+        ui.separator();
+        if ui.small_button("Clear log") {
+            self.log.borrow_mut().clear();
+        }
+        ui.child_window("##log").build(|| {
+            let log = self.log.borrow();
+            let clipper = ListClipper::new(log.len() as i32);
+            for index in clipper.begin(ui).iter() {
+                let activation = &log[index as usize];
+                let phase = if activation.began { "begin" } else { "end" };
+                ui.text(format!(
+                    "{:8.2}  {phase:5}  {}",
+                    activation.seconds, activation.name
+                ));
+            }
+            // follows new entries unless the user has scrolled up
+            if ui.scroll_y() >= ui.scroll_max_y() {
+                ui.set_scroll_here_y_with_ratio(1.0);
+            }
+        });
+    }
+
+    fn draw_list(&mut self, ui: &Ui) {
+        let clipper = ListClipper::new(self.matches.len() as i32);
+        for index in clipper.begin(ui).iter() {
+            let name = self.matches[index as usize].clone();
+            let _id = ui.push_id(&name);
+            let Some(command_ref) = self.lookup(&name) else {
+                ui.text_disabled(format!("{name} (not found)"));
+                continue;
+            };
+
+            if ui.small_button("Once") {
+                unsafe { XPLMCommandOnce(command_ref) };
+            }
+            ui.same_line();
+            let held = self.held.contains(&name);
+            if ui.small_button(if held { "Release" } else { "Hold" }) {
+                if held {
+                    unsafe { XPLMCommandEnd(command_ref) };
+                    self.held.remove(&name);
+                } else {
+                    unsafe { XPLMCommandBegin(command_ref) };
+                    self.held.insert(name.clone());
+                }
+            }
+            ui.same_line();
+            let mut watched = self.watches.contains_key(&name);
+            if ui.checkbox("Log", &mut watched) {
+                self.watch(&name, watched);
+            }
+            ui.same_line();
+            ui.text(&name);
+        }
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<XPLMCommandRef> {
+        *self.commands.entry(String::from(name)).or_insert_with(|| {
+            let name = CString::new(name).ok()?;
+            let command_ref = unsafe { XPLMFindCommand(name.as_ptr()) };
+            (!command_ref.is_null()).then_some(command_ref)
+        })
+    }
+}
+
+impl Drop for CommandBrowser {
+    fn drop(&mut self) {
+        for name in &self.held {
+            if let Some(Some(command_ref)) = self.commands.get(name) {
+                unsafe { XPLMCommandEnd(*command_ref) };
+            }
+        }
+    }
+}
+
+impl Watch {
+    fn register(
+        command_ref: XPLMCommandRef,
+        name: &str,
+        log: &Rc<RefCell<VecDeque<Activation>>>,
+        started: Instant,
+    ) -> Watch {
+        let logger = CallbackBox::new(Logger {
+            name: String::from(name),
+            log: Rc::clone(log),
+            started,
+        });
+        unsafe {
+            XPLMRegisterCommandHandler(command_ref, Some(log_command), 1, logger.refcon());
+        }
+        Watch {
+            command_ref,
+            logger,
+        }
+    }
+}
+
+impl Logger {
+    fn record(&self, began: bool) {
+        let mut log = self.log.borrow_mut();
+        if log.len() == LOG_LENGTH {
+            log.pop_front();
+        }
+        log.push_back(Activation {
+            seconds: self.started.elapsed().as_secs_f32(),
+            name: self.name.clone(),
+            began,
+        });
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        unsafe {
+            XPLMUnregisterCommandHandler(
+                self.command_ref,
+                Some(log_command),
+                1,
+                self.logger.refcon(),
+            );
+        }
+    }
+}
+
+unsafe extern "C" fn log_command(
+    _command_ref: XPLMCommandRef,
+    phase: XPLMCommandPhase,
+    refcon: *mut c_void,
+) -> c_int {
+    let logger = CallbackBox::<Logger>::from_refcon(refcon);
+    // continue phases arrive every frame while held, so only the edges are logged
+    if phase == xplm_CommandBegin as XPLMCommandPhase {
+        logger.record(true);
+    } else if phase == xplm_CommandEnd as XPLMCommandPhase {
+        logger.record(false);
+    }
+    // Let X-Plane and other handlers see the command too
+    1
+}
+
+/// The names in X-Plane's list of its commands, one per line followed by its description
+fn read_command_names() -> Vec<String> {
+    let path = system_path().join("Resources/plugins/Commands.txt");
+    match fs::read_to_string(&path) {
+        Ok(contents) => contents
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .map(String::from)
+            .collect(),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Unable to read command names");
+            Vec::new()
+        }
+    }
+}
+
+/// The names in X-Plane's list of its datarefs, a tab separated file whose first line is a
+/// header
+fn read_dataref_names() -> Vec<String> {
+    let path = system_path().join("Resources/plugins/DataRefs.txt");
+    match fs::read_to_string(&path) {
+        Ok(contents) => contents
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split('\t').next())
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Unable to read dataref names");
+            Vec::new()
+        }
+    }
+}
+
+fn system_path() -> PathBuf {
+    // XPLM's paths are at most 512 bytes
+    let mut buffer = [0 as c_char; 512];
+    unsafe {
+        XPLMGetSystemPath(buffer.as_mut_ptr());
+        PathBuf::from(
+            CStr::from_ptr(buffer.as_ptr())
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}